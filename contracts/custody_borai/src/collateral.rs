@@ -1,5 +1,5 @@
 use crate::error::ContractError;
-use crate::external::handle::{RewardContractExecuteMsg, RewardContractQueryMsg};
+use crate::external::handle::RewardContractExecuteMsg;
 use crate::state::{
     read_borrower_info, read_borrowers, read_config, remove_borrower_info, store_borrower_info,
     BorrowerInfo, Config,
@@ -7,15 +7,15 @@ use crate::state::{
 
 use cosmwasm_bignumber::Uint256;
 use cosmwasm_std::{
-    attr, to_binary, CanonicalAddr, CosmosMsg, Deps, DepsMut, Env, HandleResponse, HumanAddr,
-    MessageInfo, StdResult, WasmMsg,
+    attr, to_binary, Binary, CanonicalAddr, CosmosMsg, Deps, DepsMut, Env, HandleResponse,
+    HumanAddr, MessageInfo, StdResult, WasmMsg,
 };
 use cw20::Cw20HandleMsg;
 use moneymarket::custody::{BorrowerResponse, BorrowersResponse};
 use moneymarket::liquidation::Cw20HookMsg as LiquidationCw20HookMsg;
 
 /// Deposit new collateral
-/// Executor: bAsset token contract
+/// Executor: collateral token contract
 pub fn deposit_collateral(
     deps: DepsMut,
     env: Env,
@@ -32,26 +32,33 @@ pub fn deposit_collateral(
 
     store_borrower_info(deps.storage, &borrower_raw, &borrower_info)?;
 
+    // a market aToken has no reward contract to update
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if let Some(reward_contract) = config.reward_contract {
+        let reward_contract = deps.api.human_address(&reward_contract)?;
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: reward_contract.clone(),
+            send: vec![],
+            msg: to_binary(&RewardContractExecuteMsg::UpdateUserReward {
+                user: borrower.clone(),
+            })?,
+        }));
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: reward_contract,
+            send: vec![],
+            msg: to_binary(&RewardContractExecuteMsg::UpdateUserReward {
+                user: env.contract.address,
+            })?,
+        }));
+    }
+
     let res = HandleResponse {
         attributes: vec![
             attr("action", "deposit_collateral"),
             attr("borrower", borrower.as_str()),
             attr("amount", amount.to_string()),
         ],
-        messages: vec![
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: deps.api.human_address(&config.reward_contract)?,
-                send: vec![],
-                msg: to_binary(&RewardContractExecuteMsg::UpdateUserReward { user: borrower })?,
-            }),
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: deps.api.human_address(&config.reward_contract)?,
-                send: vec![],
-                msg: to_binary(&RewardContractExecuteMsg::UpdateUserReward {
-                    user: env.contract.address.clone(),
-                })?,
-            }),
-        ],
+        messages,
         data: None,
     };
 
@@ -238,6 +245,51 @@ pub fn liquidate_collateral(
     Ok(res)
 }
 
+pub fn send_collateral(
+    deps: DepsMut,
+    info: MessageInfo,
+    borrower: HumanAddr,
+    amount: Uint256,
+    contract: HumanAddr,
+    msg: Binary,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.overseer_contract {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let borrower_raw: CanonicalAddr = deps.api.canonical_address(&borrower)?;
+    let mut borrower_info: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
+    let borrowed_amt = borrower_info.balance - borrower_info.spendable;
+    if amount > borrowed_amt {
+        return Err(ContractError::LiquidationAmountExceedsLocked(
+            borrowed_amt.into(),
+        ));
+    }
+
+    borrower_info.balance = borrower_info.balance - amount;
+    store_borrower_info(deps.storage, &borrower_raw, &borrower_info)?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "send_collateral"),
+            attr("borrower", borrower),
+            attr("contract", contract.clone()),
+            attr("amount", amount),
+        ],
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&config.collateral_token)?,
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Send {
+                contract,
+                amount: amount.into(),
+                msg: Some(msg),
+            })?,
+        })],
+        data: None,
+    })
+}
+
 pub fn query_borrower(deps: Deps, borrower: HumanAddr) -> StdResult<BorrowerResponse> {
     let borrower_raw = deps.api.canonical_address(&borrower)?;
     let borrower_info: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
@@ -7,11 +7,13 @@ use cosmwasm_std::{
 
 use crate::collateral::{
     deposit_collateral, liquidate_collateral, lock_collateral, query_borrower, query_borrowers,
-    unlock_collateral, withdraw_collateral,
+    send_collateral, unlock_collateral, withdraw_collateral,
 };
 use crate::distribution::{distribute_hook, distribute_rewards, swap_to_stable_denom};
 use crate::error::ContractError;
-use crate::state::{read_config, store_config, Config};
+use crate::state::{
+    read_config, read_pause_info, store_config, store_pause_info, Config, PauseInfo,
+};
 
 use cw20::Cw20ReceiveMsg;
 use moneymarket::custody::{
@@ -33,7 +35,10 @@ pub fn init(
         overseer_contract: deps.api.canonical_address(&msg.overseer_contract)?,
         collateral_token: deps.api.canonical_address(&msg.collateral_token)?,
         market_contract: deps.api.canonical_address(&msg.market_contract)?,
-        reward_contract: deps.api.canonical_address(&msg.reward_contract)?,
+        reward_contract: match msg.reward_contract {
+            Some(reward_contract) => Some(deps.api.canonical_address(&reward_contract)?),
+            None => None,
+        },
         liquidation_contract: deps.api.canonical_address(&msg.liquidation_contract)?,
         swap_contract: deps.api.canonical_address(&msg.swap_contract)?,
         stable_addr: deps.api.canonical_address(&msg.stable_addr)?,
@@ -71,6 +76,10 @@ pub fn handle(
             reward_contract,
             swap_contract,
         ),
+        ExecuteMsg::UpdateGuardian { guardian } => update_guardian(deps, info, guardian),
+        ExecuteMsg::UpdatePauseFlags { deposit, withdraw } => {
+            update_pause_flags(deps, info, deposit, withdraw)
+        }
         ExecuteMsg::LockCollateral { borrower, amount } => {
             lock_collateral(deps, info, borrower, amount)
         }
@@ -80,12 +89,21 @@ pub fn handle(
         ExecuteMsg::DistributeRewards {} => distribute_rewards(deps, env, info),
         ExecuteMsg::DistributeHook {} => distribute_hook(deps, env),
         ExecuteMsg::SwapToStableDenom {} => swap_to_stable_denom(deps, env),
-        ExecuteMsg::WithdrawCollateral { amount } => withdraw_collateral(deps, info, amount),
+        ExecuteMsg::WithdrawCollateral { amount } => {
+            assert_not_paused(read_pause_info(deps.storage)?.flags.withdraw, "withdraw")?;
+            withdraw_collateral(deps, info, amount)
+        }
         ExecuteMsg::LiquidateCollateral {
             liquidator,
             borrower,
             amount,
         } => liquidate_collateral(deps, info, liquidator, borrower, amount),
+        ExecuteMsg::SendCollateral {
+            borrower,
+            amount,
+            contract,
+            msg,
+        } => send_collateral(deps, info, borrower, amount, contract, msg),
     }
 }
 
@@ -105,6 +123,7 @@ pub fn receive_cw20(
                 return Err(ContractError::Unauthorized {});
             }
 
+            assert_not_paused(read_pause_info(deps.storage)?.flags.deposit, "deposit")?;
             let cw20_sender_addr = cw20_msg.sender;
             deposit_collateral(deps, env, cw20_sender_addr, cw20_msg.amount.into())
         }
@@ -145,7 +164,7 @@ pub fn update_config(
     }
 
     if let Some(reward_contract) = reward_contract {
-        config.reward_contract = deps.api.canonical_address(&reward_contract)?;
+        config.reward_contract = Some(deps.api.canonical_address(&reward_contract)?);
     }
 
     if let Some(swap_contract) = swap_contract {
@@ -162,6 +181,83 @@ pub fn update_config(
     Ok(res)
 }
 
+pub fn update_guardian(
+    deps: DepsMut,
+    info: MessageInfo,
+    guardian: Option<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut pause_info: PauseInfo = read_pause_info(deps.storage)?;
+    pause_info.guardian = if let Some(guardian) = guardian.as_ref() {
+        Some(deps.api.canonical_address(guardian)?)
+    } else {
+        None
+    };
+    store_pause_info(deps.storage, &pause_info)?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "update_guardian"),
+            attr(
+                "guardian",
+                guardian.map_or_else(|| "none".to_string(), |g| g.to_string()),
+            ),
+        ],
+        messages: vec![],
+        data: None,
+    })
+}
+
+/// The guardian can only pause operations,
+/// the owner can both pause and unpause
+pub fn update_pause_flags(
+    deps: DepsMut,
+    info: MessageInfo,
+    deposit: Option<bool>,
+    withdraw: Option<bool>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut pause_info: PauseInfo = read_pause_info(deps.storage)?;
+
+    let sender_raw = deps.api.canonical_address(&info.sender)?;
+    if sender_raw != config.owner {
+        if pause_info.guardian != Some(sender_raw) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        if [deposit, withdraw].contains(&Some(false)) {
+            return Err(ContractError::Unauthorized {});
+        }
+    }
+
+    if let Some(deposit) = deposit {
+        pause_info.flags.deposit = deposit;
+    }
+
+    if let Some(withdraw) = withdraw {
+        pause_info.flags.withdraw = withdraw;
+    }
+
+    store_pause_info(deps.storage, &pause_info)?;
+    Ok(HandleResponse {
+        attributes: vec![attr("action", "update_pause_flags")],
+        messages: vec![],
+        data: None,
+    })
+}
+
+fn assert_not_paused(paused: bool, operation: &str) -> Result<(), ContractError> {
+    if paused {
+        return Err(ContractError::OperationPaused(operation.to_string()));
+    }
+
+    Ok(())
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -175,6 +271,7 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
 
 pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let config: Config = read_config(deps.storage)?;
+    let pause_info: PauseInfo = read_pause_info(deps.storage)?;
     Ok(ConfigResponse {
         owner: deps.api.human_address(&config.owner)?.to_string(),
         collateral_token: deps
@@ -186,13 +283,22 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
             .human_address(&config.overseer_contract)?
             .to_string(),
         market_contract: deps.api.human_address(&config.market_contract)?.to_string(),
-        reward_contract: deps.api.human_address(&config.reward_contract)?.to_string(),
+        reward_contract: match config.reward_contract {
+            Some(reward_contract) => Some(deps.api.human_address(&reward_contract)?.to_string()),
+            None => None,
+        },
         liquidation_contract: deps
             .api
             .human_address(&config.liquidation_contract)?
             .to_string(),
         stable_addr: deps.api.human_address(&config.stable_addr)?.to_string(),
         basset_info: config.basset_info,
+        guardian: if let Some(guardian) = pause_info.guardian {
+            Some(deps.api.human_address(&guardian)?.to_string())
+        } else {
+            None
+        },
+        pause_flags: pause_info.flags,
     })
 }
 
@@ -1,6 +1,6 @@
 use cosmwasm_bignumber::Uint256;
 use cosmwasm_std::{
-    attr, to_binary, BalanceResponse, BankMsg, BankQuery, Coin, CosmosMsg, Deps, DepsMut, Env,
+    attr, to_binary, BalanceResponse, BankQuery, Coin, CosmosMsg, Deps, DepsMut, Env,
     HandleResponse, HumanAddr, MessageInfo, QueryRequest, StdResult, Uint128, WasmMsg, WasmQuery,
 };
 
@@ -9,7 +9,7 @@ use crate::external::handle::{RewardContractExecuteMsg, RewardContractQueryMsg};
 use crate::state::{read_config, BLunaAccruedRewardsResponse, Config};
 use cw20::Cw20HandleMsg;
 use moneymarket::custody::ExecuteMsg;
-use moneymarket::querier::{deduct_tax, query_all_balances, query_balance};
+use moneymarket::querier::{query_all_balances, query_balance};
 use oraiswap::asset::AssetInfo;
 use oraiswap::router::SwapOperation;
 
@@ -19,7 +19,8 @@ use oraiswap::router::SwapOperation;
 // const REWARDS_THRESHOLD: Uint128 = Uint128::from(1000000u128); fixme
 
 /// Request withdraw reward operation to
-/// reward contract and execute `distribute_hook`
+/// reward contract and execute `distribute_hook`,
+/// nothing to distribute without a reward contract
 /// Executor: overseer
 pub fn distribute_rewards(
     deps: DepsMut,
@@ -31,7 +32,10 @@ pub fn distribute_rewards(
         return Err(ContractError::Unauthorized {});
     }
 
-    let reward_contract = deps.api.human_address(&config.reward_contract)?;
+    let reward_contract = match config.reward_contract {
+        Some(reward_contract) => deps.api.human_address(&reward_contract)?,
+        None => return Ok(HandleResponse::default()),
+    };
 
     // let accrued_rewards =
     //     get_accrued_rewards(deps.as_ref(), reward_contract.clone(), contract_addr)?;
@@ -96,7 +100,7 @@ pub fn distribute_hook(deps: DepsMut, env: Env) -> Result<HandleResponse, Contra
             attr("action", "distribute_rewards"),
             attr("buffer_rewards", reward_amount),
         ],
-        messages: messages,
+        messages,
         data: None,
     };
     Ok(res)
@@ -148,7 +152,7 @@ pub fn swap_to_stable_denom(deps: DepsMut, env: Env) -> Result<HandleResponse, C
 
     let res = HandleResponse {
         attributes: vec![],
-        messages: messages,
+        messages,
         data: None,
     };
     Ok(res)
@@ -24,6 +24,9 @@ pub enum ContractError {
     #[error("Invalid request: \"deposit collateral\" message not included in request")]
     MissingDepositCollateralHook {},
 
+    #[error("Operation paused: {0}")]
+    OperationPaused(String),
+
     #[error("Unlock amount cannot exceed locked amount: {0}")]
     UnlockAmountExceedsLocked(u128),
 
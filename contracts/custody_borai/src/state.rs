@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_bignumber::Uint256;
 use cosmwasm_std::{CanonicalAddr, Deps, Order, StdResult, Storage, Uint128};
 use cosmwasm_storage::{Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
-use moneymarket::custody::{BAssetInfo, BorrowerResponse};
+use moneymarket::custody::{BAssetInfo, BorrowerResponse, PauseFlags};
 
 //BLunaAccruedRewardsResponse the struct that shows the result of accrued_rewards query
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
@@ -13,6 +13,7 @@ pub struct BLunaAccruedRewardsResponse {
 }
 
 const KEY_CONFIG: &[u8] = b"config";
+const KEY_PAUSE_INFO: &[u8] = b"pause_info";
 const PREFIX_BORROWER: &[u8] = b"borrower";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -21,13 +22,19 @@ pub struct Config {
     pub collateral_token: CanonicalAddr,
     pub overseer_contract: CanonicalAddr,
     pub market_contract: CanonicalAddr,
-    pub reward_contract: CanonicalAddr,
+    pub reward_contract: Option<CanonicalAddr>,
     pub liquidation_contract: CanonicalAddr,
     pub swap_contract: CanonicalAddr,
     pub stable_addr: CanonicalAddr,
     pub basset_info: BAssetInfo,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct PauseInfo {
+    pub guardian: Option<CanonicalAddr>,
+    pub flags: PauseFlags,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BorrowerInfo {
     pub balance: Uint256,
@@ -42,6 +49,16 @@ pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
     ReadonlySingleton::new(storage, KEY_CONFIG).load()
 }
 
+pub fn store_pause_info(storage: &mut dyn Storage, data: &PauseInfo) -> StdResult<()> {
+    Singleton::new(storage, KEY_PAUSE_INFO).save(data)
+}
+
+pub fn read_pause_info(storage: &dyn Storage) -> StdResult<PauseInfo> {
+    Ok(ReadonlySingleton::new(storage, KEY_PAUSE_INFO)
+        .may_load()?
+        .unwrap_or_default())
+}
+
 pub fn store_borrower_info(
     storage: &mut dyn Storage,
     borrower: &CanonicalAddr,
@@ -46,6 +46,16 @@ pub fn assert_withdraw_amount(
     Ok(to_withdraw)
 }
 
+pub fn assert_not_paused(paused: bool, operation: &str) -> StdResult<()> {
+    if paused {
+        return Err(StdError::generic_err(format!(
+            "Operation paused: {}",
+            operation
+        )));
+    }
+    Ok(())
+}
+
 pub fn assert_max_slot(max_slot: u8) -> StdResult<()> {
     if max_slot.gt(&MAX_SLOT_CAP) {
         return Err(StdError::generic_err("Max slot exceeds limit"));
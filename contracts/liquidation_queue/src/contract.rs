@@ -1,7 +1,7 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 
-use crate::asserts::{assert_fees, assert_max_slot, assert_max_slot_premium};
+use crate::asserts::{assert_fees, assert_max_slot, assert_max_slot_premium, assert_not_paused};
 use crate::bid::{activate_bids, claim_liquidations, execute_liquidation, retract_bid, submit_bid};
 use crate::querier::query_collateral_whitelist_info;
 use crate::query::{
@@ -9,12 +9,13 @@ use crate::query::{
     query_config, query_liquidation_amount,
 };
 use crate::state::{
-    read_collateral_info, read_config, store_collateral_info, store_config, CollateralInfo, Config,
+    read_collateral_info, read_config, read_pause_info, store_collateral_info, store_config,
+    store_pause_info, CollateralInfo, Config,
 };
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    attr, from_binary, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, 
+    attr, from_binary, to_binary, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, WasmMsg,
     InitResponse, HandleResponse, StdError, StdResult, HumanAddr,
 };
 use cw20::Cw20ReceiveMsg;
@@ -46,7 +47,19 @@ pub fn init(
         },
     )?;
 
-    Ok(InitResponse::default())
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if let Some(init_hook) = msg.init_hook {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: init_hook.contract_addr,
+            msg: init_hook.msg,
+            send: vec![],
+        }));
+    }
+
+    Ok(InitResponse {
+        attributes: vec![],
+        messages,
+    })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -98,11 +111,24 @@ pub fn handle(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> St
             collateral_token,
             bids_idx,
         } => activate_bids(deps, env, info, collateral_token, bids_idx),
-        ExecuteMsg::RetractBid { bid_idx, amount } => retract_bid(deps, env, info, bid_idx, amount),
+        ExecuteMsg::UpdateGuardian { guardian } => update_guardian(deps, info, guardian),
+        ExecuteMsg::UpdatePauseFlags {
+            submit,
+            retract,
+            execute,
+            claim,
+        } => update_pause_flags(deps, info, submit, retract, execute, claim),
+        ExecuteMsg::RetractBid { bid_idx, amount } => {
+            assert_not_paused(read_pause_info(deps.storage)?.flags.retract, "retract")?;
+            retract_bid(deps, env, info, bid_idx, amount)
+        }
         ExecuteMsg::ClaimLiquidations {
             collateral_token,
             bids_idx,
-        } => claim_liquidations(deps, env, info, collateral_token, bids_idx),
+        } => {
+            assert_not_paused(read_pause_info(deps.storage)?.flags.claim, "claim")?;
+            claim_liquidations(deps, env, info, collateral_token, bids_idx)
+        }
     }
 }
 
@@ -119,6 +145,7 @@ pub fn receive_cw20(
             repay_address,
             fee_address,
         } => {
+            assert_not_paused(read_pause_info(deps.storage)?.flags.execute, "execute")?;
             let collateral_token = contract_addr;
             let repay_address = repay_address.unwrap_or_else(|| cw20_msg.sender.clone());
             let fee_address = fee_address.unwrap_or_else(|| cw20_msg.sender.clone());
@@ -135,6 +162,7 @@ pub fn receive_cw20(
             )
         }
         Cw20HookMsg::SubmitBid {collateral_token, premium_slot} => {
+            assert_not_paused(read_pause_info(deps.storage)?.flags.submit, "submit")?;
             submit_bid(deps, env, info, collateral_token, premium_slot, cw20_msg.amount.into())
         }
     }
@@ -294,6 +322,83 @@ pub fn update_collateral_info(
     Ok(res)
 }
 
+pub fn update_guardian(
+    deps: DepsMut,
+    info: MessageInfo,
+    guardian: Option<HumanAddr>,
+) -> StdResult<HandleResponse> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let mut pause_info = read_pause_info(deps.storage)?;
+    pause_info.guardian = match &guardian {
+        Some(guardian) => Some(deps.api.canonical_address(guardian)?),
+        None => None,
+    };
+    store_pause_info(deps.storage, &pause_info)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        attributes: vec![
+            attr("action", "update_guardian"),
+            attr(
+                "guardian",
+                guardian.map_or_else(|| "none".to_string(), |g| g.to_string()),
+            ),
+        ],
+        data: None,
+    })
+}
+
+pub fn update_pause_flags(
+    deps: DepsMut,
+    info: MessageInfo,
+    submit: Option<bool>,
+    retract: Option<bool>,
+    execute: Option<bool>,
+    claim: Option<bool>,
+) -> StdResult<HandleResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut pause_info = read_pause_info(deps.storage)?;
+
+    let sender_raw = deps.api.canonical_address(&info.sender)?;
+    if sender_raw != config.owner {
+        if pause_info.guardian != Some(sender_raw) {
+            return Err(StdError::generic_err("unauthorized"));
+        }
+
+        if [submit, retract, execute, claim].contains(&Some(false)) {
+            return Err(StdError::generic_err("unauthorized"));
+        }
+    }
+
+    if let Some(submit) = submit {
+        pause_info.flags.submit = submit;
+    }
+
+    if let Some(retract) = retract {
+        pause_info.flags.retract = retract;
+    }
+
+    if let Some(execute) = execute {
+        pause_info.flags.execute = execute;
+    }
+
+    if let Some(claim) = claim {
+        pause_info.flags.claim = claim;
+    }
+
+    store_pause_info(deps.storage, &pause_info)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        attributes: vec![attr("action", "update_pause_flags")],
+        data: None,
+    })
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
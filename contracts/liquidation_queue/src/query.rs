@@ -2,7 +2,7 @@ use crate::bid::{calculate_liquidated_collateral, calculate_remaining_bid};
 use crate::querier::{query_collateral_whitelist_info, query_tax_rate_and_cap};
 use crate::state::{
     read_bid, read_bid_pool, read_bid_pools, read_bids_by_user, read_collateral_info, read_config,
-    read_total_bids, Bid, BidPool, CollateralInfo, Config,
+    read_pause_info, read_total_bids, Bid, BidPool, CollateralInfo, Config,
 };
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{CanonicalAddr, Deps, StdResult, Uint128, HumanAddr};
@@ -14,6 +14,7 @@ use moneymarket::tokens::TokensHuman;
 
 pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let config = read_config(deps.storage)?;
+    let pause_info = read_pause_info(deps.storage)?;
     let resp = ConfigResponse {
         owner: deps.api.human_address(&config.owner)?.to_string(),
         oracle_contract: deps.api.human_address(&config.oracle_contract)?.to_string(),
@@ -25,6 +26,11 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         price_timeframe: config.price_timeframe,
         waiting_period: config.waiting_period,
         overseer: deps.api.human_address(&config.overseer)?.to_string(),
+        guardian: match pause_info.guardian {
+            Some(guardian) => Some(deps.api.human_address(&guardian)?.to_string()),
+            None => None,
+        },
+        pause_flags: pause_info.flags,
     };
 
     Ok(resp)
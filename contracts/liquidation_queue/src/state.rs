@@ -1,12 +1,14 @@
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{CanonicalAddr, Order, StdError, StdResult, Storage, Uint128};
 use cosmwasm_storage::{singleton, singleton_read, Bucket, ReadonlyBucket};
+use moneymarket::liquidation_queue::PauseFlags;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::convert::TryInto;
 
 static KEY_CONFIG: &[u8] = b"config";
 static KEY_BID_IDX: &[u8] = b"bid_idx";
+static KEY_PAUSE_INFO: &[u8] = b"pause_info";
 
 static PREFIX_BID: &[u8] = b"bid";
 static PREFIX_BID_BY_USER: &[u8] = b"bid_by_user";
@@ -41,6 +43,22 @@ pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
     singleton_read(storage, KEY_CONFIG).load()
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct PauseInfo {
+    pub guardian: Option<CanonicalAddr>,
+    pub flags: PauseFlags,
+}
+
+pub fn store_pause_info(storage: &mut dyn Storage, pause_info: &PauseInfo) -> StdResult<()> {
+    singleton(storage, KEY_PAUSE_INFO).save(pause_info)
+}
+
+pub fn read_pause_info(storage: &dyn Storage) -> StdResult<PauseInfo> {
+    Ok(singleton_read(storage, KEY_PAUSE_INFO)
+        .may_load()?
+        .unwrap_or_default())
+}
+
 pub fn pop_bid_idx(storage: &mut dyn Storage) -> StdResult<Uint128> {
     let mut idx_store = singleton(storage, KEY_BID_IDX);
     let last_idx: Uint128 = idx_store.load().unwrap_or_else(|_| Uint128::from(1u128));
@@ -1,38 +1,65 @@
 use anchor_token::distributor::ExecuteMsg as FaucetExecuteMsg;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    attr, to_binary, BankMsg, Coin, CosmosMsg, Deps, DepsMut, Env, HandleResponse, HumanAddr,
-    MessageInfo, StdResult, Uint128, WasmMsg,
+    attr, to_binary, Binary, CosmosMsg, Deps, DepsMut, Env, HandleResponse, HumanAddr, MessageInfo,
+    StdResult, WasmMsg,
 };
 use moneymarket::interest_model::BorrowRateResponse;
-use moneymarket::market::{BorrowerInfoResponse, BorrowerInfosResponse};
-use moneymarket::overseer::BorrowLimitResponse;
+use moneymarket::market::{
+    BadDebtWriteOffsResponse, BorrowerInfoResponse, BorrowerInfosResponse,
+    DelegationAllowanceResponse, ExecuteMsg, FeeRecipient, OriginationFeeMode, RateMode,
+};
+use moneymarket::overseer::{BorrowLimitResponse, ExecuteMsg as OverseerExecuteMsg};
 
-use crate::deposit::compute_exchange_rate_raw;
+use crate::deposit::{
+    compute_exchange_rate, compute_exchange_rate_raw, fill_redeem_queue, stable_send_msg,
+    stable_transfer_msg,
+};
 use crate::error::ContractError;
-use crate::querier::{query_borrow_limit, query_borrow_rate, query_target_deposit_rate};
+use crate::querier::{
+    query_borrow_limit, query_borrow_rate, query_collaterals, query_debt_ceilings,
+    query_stable_balance,
+};
 use crate::state::{
-    read_borrower_info, read_borrower_infos, read_config, read_state, store_borrower_info,
-    store_state, BorrowerInfo, Config, State,
+    read_bad_debt_write_offs, read_borrower_cap, read_borrower_info, read_borrower_infos,
+    read_config, read_delegation, read_state, read_term_loan_amounts, remove_delegation,
+    store_bad_debt_write_off, store_borrower_info, store_delegation, store_state, BadDebtWriteOff,
+    BorrowerInfo, Config, Delegation, State,
 };
-use cw20::Cw20HandleMsg;
-use moneymarket::querier::{deduct_tax, query_balance, query_supply};
+use crate::term_loan::{
+    compute_term_interest, open_term_loan, repay_term_loans, write_off_term_loans,
+};
+use cw20::{Cw20HandleMsg, Expiration};
+use moneymarket::querier::{query_asset_balance, query_supply};
+use oraiswap::asset::AssetInfoRaw;
 
 pub const BLOCKS_PER_YEAR: u64 = 6300000;
+#[allow(clippy::too_many_arguments)]
 pub fn borrow_stable(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     borrow_amount: Uint256,
     to: Option<HumanAddr>,
+    on_behalf_of: Option<HumanAddr>,
+    rate_mode: Option<RateMode>,
+    term: Option<u64>,
+    hook_msg: Option<Binary>,
 ) -> Result<HandleResponse, ContractError> {
     let config: Config = read_config(deps.storage)?;
 
+    // Cannot borrow zero amount
+    if borrow_amount.is_zero() {
+        return Err(ContractError::ZeroBorrow {});
+    }
+
     let mut state: State = read_state(deps.storage)?;
 
-    let borrower = info.sender;
+    // the debt lands on on_behalf_of when borrowing as a delegate,
+    // the borrowed stable still goes to the sender
+    let borrower = on_behalf_of.unwrap_or_else(|| info.sender.clone());
     let borrower_raw = deps.api.canonical_address(&borrower)?;
-    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw)?;
 
     // Compute interest
     compute_interest(deps.as_ref(), &config, &mut state, env.block.height, None)?;
@@ -42,55 +69,268 @@ pub fn borrow_stable(
     compute_reward(&mut state, env.block.height);
     compute_borrower_reward(&state, &mut liability);
 
-    let overseer = deps.api.human_address(&config.overseer_contract)?;
-    let borrow_limit_res: BorrowLimitResponse = query_borrow_limit(
+    // a loan has a single rate mode,
+    // term loans are tracked apart from it
+    let rate_mode = rate_mode.unwrap_or(liability.rate_mode);
+    if term.is_none() && rate_mode != liability.rate_mode {
+        if !liability.loan_amount.is_zero() {
+            return Err(ContractError::RateModeMismatch {});
+        }
+
+        liability.rate_mode = rate_mode;
+        liability.stable_rate = Decimal256::zero();
+    }
+
+    let (origination_fee, loan_increase, disbursed_amount) =
+        compute_origination_fee(&config, borrow_amount);
+
+    // the allowance covers the whole debt increase, origination fee included
+    if borrower != info.sender {
+        let delegate_raw = deps.api.canonical_address(&info.sender)?;
+        let mut delegation = read_delegation(deps.storage, &borrower_raw, &delegate_raw)
+            .ok_or(ContractError::DelegationNotFound {})?;
+
+        if delegation.expires.is_expired(&env.block) {
+            return Err(ContractError::DelegationExpired {});
+        }
+
+        if loan_increase > delegation.allowance {
+            return Err(ContractError::DelegationExceeded(
+                delegation.allowance.into(),
+            ));
+        }
+
+        delegation.allowance = delegation.allowance - loan_increase;
+        store_delegation(deps.storage, &borrower_raw, &delegate_raw, &delegation)?;
+    }
+    let (term_loan_amount, _) = read_term_loan_amounts(
+        deps.storage,
+        &borrower_raw,
+        config.term_grace_period,
+        env.block.height,
+    )?;
+    let loan_amount = liability.loan_amount + term_loan_amount;
+    let current_balance = query_stable_balance(deps.as_ref(), &config)?;
+
+    // Assert borrow amount
+    assert_borrow_limits(
         deps.as_ref(),
-        overseer,
+        &env,
+        &config,
+        &state,
+        &borrower,
+        loan_amount,
+        loan_increase,
+        current_balance,
+    )?;
+
+    let mut attributes = vec![
+        attr("action", "borrow_stable"),
+        attr("borrower", borrower.to_string()),
+        attr("delegate", info.sender.to_string()),
+        attr("borrow_amount", borrow_amount),
+        attr("origination_fee", origination_fee),
+    ];
+
+    // the stable rate is priced before the borrow updates liabilities
+    if let Some(term) = term {
+        let stable_rate = compute_stable_rate(deps.as_ref(), &config, &state, current_balance)?;
+        let term_loan = open_term_loan(
+            deps.storage,
+            &mut state,
+            &borrower_raw,
+            loan_increase,
+            stable_rate,
+            env.block.height,
+            term,
+        )?;
+        attributes.push(attr("loan_id", term_loan.loan_id));
+        attributes.push(attr("maturity", term_loan.maturity));
+        attributes.push(attr("interest", term_loan.interest));
+    } else {
+        let stable_rate = if liability.rate_mode == RateMode::Stable {
+            compute_stable_rate(deps.as_ref(), &config, &state, current_balance)?
+        } else {
+            Decimal256::zero()
+        };
+        increase_loan(&mut state, &mut liability, loan_increase, stable_rate);
+    }
+
+    let recipient = to.unwrap_or_else(|| info.sender.clone());
+    let mut messages: Vec<CosmosMsg> = vec![if let Some(hook_msg) = hook_msg {
+        stable_send_msg(
+            deps.as_ref(),
+            &config,
+            recipient,
+            disbursed_amount,
+            hook_msg,
+        )?
+    } else {
+        stable_transfer_msg(deps.as_ref(), &config, recipient, disbursed_amount)?
+    }];
+    if !origination_fee.is_zero() {
+        match config.origination_fee_recipient {
+            FeeRecipient::Reserves => {
+                state.total_reserves += Decimal256::from_uint256(origination_fee);
+            }
+            FeeRecipient::Collector => messages.push(stable_transfer_msg(
+                deps.as_ref(),
+                &config,
+                deps.api.human_address(&config.collector_contract)?,
+                origination_fee,
+            )?),
+        }
+    }
+
+    // the overseer attributes the new loan to the collaterals
+    // and rejects the borrow if a debt ceiling is exceeded
+    messages.push(update_borrower_debt_msg(
+        deps.as_ref(),
+        &config,
         borrower.clone(),
+        true,
+    )?);
+
+    store_state(deps.storage, &state)?;
+    store_borrower_info(deps.storage, &borrower_raw, &liability)?;
+
+    let res = HandleResponse {
+        attributes,
+        messages,
+        data: None,
+    };
+    Ok(res)
+}
+
+pub fn borrow_stable_and_call(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrow_amount: Uint256,
+    contract: HumanAddr,
+    msg: Binary,
+) -> Result<HandleResponse, ContractError> {
+    // a native stable would be sent along with an execution of msg by
+    // the market itself, which is the aToken minter and trusted by the
+    // overseer, so only cw20 stables are sent through a receive hook
+    let config: Config = read_config(deps.storage)?;
+    if let AssetInfoRaw::NativeToken { .. } = config.stable_info {
+        return Err(ContractError::NativeStableCallNotSupported {});
+    }
+
+    let market = env.contract.address.clone();
+    let borrower = info.sender.clone();
+    let mut res = borrow_stable(
+        deps,
+        env,
+        info,
+        borrow_amount,
+        Some(contract.clone()),
+        None,
+        None,
+        None,
+        Some(msg),
+    )?;
+
+    // the hook may change the borrower position,
+    // e.g. by depositing or withdrawing collaterals
+    res.messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: market,
+        send: vec![],
+        msg: to_binary(&ExecuteMsg::AssertBorrowLimit { borrower })?,
+    }));
+    res.attributes.push(attr("contract", contract));
+    Ok(res)
+}
+
+pub fn assert_borrow_limit(
+    deps: Deps,
+    env: Env,
+    info: MessageInfo,
+    borrower: HumanAddr,
+) -> Result<HandleResponse, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let config: Config = read_config(deps.storage)?;
+    let borrower_info = query_borrower_info(deps, env.clone(), borrower.clone(), None)?;
+    let borrow_limit_res: BorrowLimitResponse = query_borrow_limit(
+        deps,
+        deps.api.human_address(&config.overseer_contract)?,
+        borrower,
         Some(env.block.time),
     )?;
 
-    if borrow_limit_res.borrow_limit < borrow_amount + liability.loan_amount {
+    if borrow_limit_res.borrow_limit < borrower_info.loan_amount + borrower_info.term_loan_amount {
         return Err(ContractError::BorrowExceedsLimit(
             borrow_limit_res.borrow_limit.into(),
         ));
     }
-    let query_target = env.contract.address.clone();
-    let current_balance = query_balance(
-        deps.as_ref(),
-        query_target,
-        deps.api.human_address(&config.stable_addr)?,
+
+    Ok(HandleResponse::default())
+}
+
+pub fn approve_delegation(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    delegate: HumanAddr,
+    amount: Uint256,
+    expires: Option<Expiration>,
+) -> Result<HandleResponse, ContractError> {
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::DelegationExpired {});
+    }
+
+    let borrower_raw = deps.api.canonical_address(&info.sender)?;
+    let delegate_raw = deps.api.canonical_address(&delegate)?;
+    store_delegation(
+        deps.storage,
+        &borrower_raw,
+        &delegate_raw,
+        &Delegation {
+            allowance: amount,
+            expires,
+        },
     )?;
 
-    // Assert borrow amount
-    assert_max_borrow_factor(&config, &state, current_balance, borrow_amount)?;
+    Ok(HandleResponse {
+        messages: vec![],
+        attributes: vec![
+            attr("action", "approve_delegation"),
+            attr("borrower", info.sender),
+            attr("delegate", delegate),
+            attr("amount", amount),
+            attr("expires", expires),
+        ],
+        data: None,
+    })
+}
 
-    liability.loan_amount += borrow_amount;
-    state.total_liabilities += Decimal256::from_uint256(borrow_amount);
-    store_state(deps.storage, &state)?;
-    store_borrower_info(deps.storage, &borrower_raw, &liability)?;
+pub fn revoke_delegation(
+    deps: DepsMut,
+    info: MessageInfo,
+    delegate: HumanAddr,
+) -> Result<HandleResponse, ContractError> {
+    let borrower_raw = deps.api.canonical_address(&info.sender)?;
+    let delegate_raw = deps.api.canonical_address(&delegate)?;
+    remove_delegation(deps.storage, &borrower_raw, &delegate_raw);
 
-    let res = HandleResponse {
+    Ok(HandleResponse {
+        messages: vec![],
         attributes: vec![
-            attr("action", "borrow_stable"),
-            attr("borrower", borrower.to_string()),
-            attr("borrow_amount", borrow_amount),
+            attr("action", "revoke_delegation"),
+            attr("borrower", info.sender),
+            attr("delegate", delegate),
         ],
-        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: deps.api.human_address(&config.stable_addr)?,
-            msg: to_binary(&Cw20HandleMsg::Transfer {
-                recipient: to.unwrap_or_else(|| borrower.clone()),
-                amount: deduct_tax(deps.as_ref(), borrow_amount.into())?,
-            })?,
-            send: vec![],
-        })],
         data: None,
-    };
-    Ok(res)
+    })
 }
 
 pub fn repay_stable_from_liquidation(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     borrower: HumanAddr,
@@ -101,19 +341,68 @@ pub fn repay_stable_from_liquidation(
         return Err(ContractError::Unauthorized {});
     }
 
-    let cur_balance: Uint256 = query_balance(
+    let cur_balance: Uint256 = query_stable_balance(deps.as_ref(), &config)?;
+
+    let amount: Uint256 = cur_balance - prev_balance;
+    if amount.is_zero() {
+        return Err(ContractError::ZeroRepay {});
+    }
+
+    // Term loans are repaid first in maturity order,
+    // so expired loans are always covered by the liquidation
+    let mut state: State = read_state(deps.storage)?;
+    compute_interest(
         deps.as_ref(),
-        env.contract.address.clone(),
-        deps.api.human_address(&config.stable_addr)?,
+        &config,
+        &mut state,
+        env.block.height,
+        Some(amount),
     )?;
+    compute_reward(&mut state, env.block.height);
 
-    let amount: Uint256 = cur_balance - prev_balance;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let term_repay_amount = repay_term_loans(
+        deps.storage,
+        &mut state,
+        &borrower_raw,
+        amount,
+        env.block.height,
+    )?;
 
-    repay_stable(deps, env, borrower, amount)
+    if term_repay_amount == amount {
+        // The repaid stable fills queued redemptions first
+        let exchange_rate = compute_exchange_rate(deps.as_ref(), &config, &state, None)?;
+        let mut messages =
+            fill_redeem_queue(&mut deps, &config, &mut state, exchange_rate, cur_balance)?;
+        messages.push(update_borrower_debt_msg(
+            deps.as_ref(),
+            &config,
+            borrower.clone(),
+            false,
+        )?);
+        store_state(deps.storage, &state)?;
+
+        return Ok(HandleResponse {
+            attributes: vec![
+                attr("action", "repay_stable"),
+                attr("borrower", borrower),
+                attr("repay_amount", Uint256::zero()),
+                attr("term_repay_amount", term_repay_amount),
+            ],
+            messages,
+            data: None,
+        });
+    }
+    store_state(deps.storage, &state)?;
+
+    let mut res = repay_stable(deps, env, borrower, amount - term_repay_amount)?;
+    res.attributes
+        .push(attr("term_repay_amount", term_repay_amount));
+    Ok(res)
 }
 
 pub fn repay_stable(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     borrower: HumanAddr,
     amount: Uint256,
@@ -128,7 +417,7 @@ pub fn repay_stable(
     let mut state: State = read_state(deps.storage)?;
 
     let borrower_raw = deps.api.canonical_address(&borrower)?;
-    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw)?;
 
     // Compute interest
     compute_interest(
@@ -144,27 +433,39 @@ pub fn repay_stable(
     compute_reward(&mut state, env.block.height);
     compute_borrower_reward(&state, &mut liability);
 
-    let repay_amount: Uint256;
-    let mut messages: Vec<CosmosMsg> = vec![];
-    if liability.loan_amount < amount {
-        repay_amount = liability.loan_amount;
-        liability.loan_amount = Uint256::zero();
+    let repay_amount = decrease_loan(&mut state, &mut liability, amount);
+    let refund_amount = amount - repay_amount;
 
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !refund_amount.is_zero() {
         // Payback left repay amount to sender
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: deps.api.human_address(&config.stable_addr)?,
-            msg: to_binary(&Cw20HandleMsg::Transfer {
-                recipient: borrower.clone(),
-                amount: deduct_tax(deps.as_ref(), (amount - repay_amount).into())?,
-            })?,
-            send: vec![],
-        }));
-    } else {
-        repay_amount = amount;
-        liability.loan_amount = liability.loan_amount - repay_amount;
+        messages.push(stable_transfer_msg(
+            deps.as_ref(),
+            &config,
+            borrower.clone(),
+            refund_amount,
+        )?);
     }
 
-    state.total_liabilities = state.total_liabilities - Decimal256::from_uint256(repay_amount);
+    // The repaid stable fills queued redemptions first,
+    // the refunded amount is still in the market balance
+    let exchange_rate = compute_exchange_rate(deps.as_ref(), &config, &state, Some(refund_amount))?;
+    let current_balance = query_stable_balance(deps.as_ref(), &config)? - refund_amount;
+    messages.extend(fill_redeem_queue(
+        &mut deps,
+        &config,
+        &mut state,
+        exchange_rate,
+        current_balance,
+    )?);
+
+    // the overseer releases the repaid debt from the collaterals
+    messages.push(update_borrower_debt_msg(
+        deps.as_ref(),
+        &config,
+        borrower.clone(),
+        false,
+    )?);
 
     store_borrower_info(deps.storage, &borrower_raw, &liability)?;
     store_state(deps.storage, &state)?;
@@ -175,12 +476,238 @@ pub fn repay_stable(
             attr("borrower", borrower),
             attr("repay_amount", repay_amount),
         ],
-        messages: messages,
+        messages,
         data: None,
     };
     Ok(res)
 }
 
+/// Ask the overseer to refresh the debt attributed to the collaterals
+/// of the borrower, debt ceilings are only enforced after a borrow
+pub(crate) fn update_borrower_debt_msg(
+    deps: Deps,
+    config: &Config,
+    borrower: HumanAddr,
+    enforce_ceilings: bool,
+) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: deps.api.human_address(&config.overseer_contract)?,
+        send: vec![],
+        msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+            borrower,
+            enforce_ceilings,
+        })?,
+    }))
+}
+
+pub fn write_off_bad_debt(
+    deps: DepsMut,
+    env: Env,
+    borrower: HumanAddr,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let overseer = deps.api.human_address(&config.overseer_contract)?;
+
+    // Only positions whose collaterals were fully seized can be written off
+    let collaterals = query_collaterals(deps.as_ref(), overseer.clone(), borrower.clone())?;
+    if collaterals
+        .collaterals
+        .iter()
+        .any(|(_, amount)| !amount.is_zero())
+    {
+        return Err(ContractError::BadDebtHasCollaterals {});
+    }
+
+    let mut state: State = read_state(deps.storage)?;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw)?;
+
+    // Compute interest
+    compute_interest(deps.as_ref(), &config, &mut state, env.block.height, None)?;
+    compute_borrower_interest(&state, &mut liability);
+
+    // Compute ANC reward
+    compute_reward(&mut state, env.block.height);
+    compute_borrower_reward(&state, &mut liability);
+
+    // Term loans are written off at their accrued value,
+    // the unearned interest was never owed to depositors
+    let term_bad_debt =
+        write_off_term_loans(deps.storage, &mut state, &borrower_raw, env.block.height)?;
+    let bad_debt = liability.loan_amount + term_bad_debt;
+    if bad_debt.is_zero() {
+        return Err(ContractError::ZeroBadDebt {});
+    }
+
+    // Absorb the shortfall from reserves first
+    let total_reserves = state.total_reserves * Uint256::one();
+    let covered_by_reserves = std::cmp::min(bad_debt, total_reserves);
+    state.total_reserves = state.total_reserves - Decimal256::from_uint256(covered_by_reserves);
+
+    // Then from the overseer interest buffer
+    let buffer = query_asset_balance(
+        deps.as_ref(),
+        overseer.clone(),
+        &config.stable_info.to_normal(deps.api)?,
+    )?;
+    let covered_by_buffer = std::cmp::min(bad_debt - covered_by_reserves, buffer);
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !covered_by_buffer.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: overseer,
+            send: vec![],
+            msg: to_binary(&OverseerExecuteMsg::CoverBadDebt {
+                amount: covered_by_buffer,
+            })?,
+        }));
+    }
+
+    // The rest is left to depositors, removing the debt from
+    // total_liabilities lowers the exchange rate accordingly
+    let socialized = bad_debt - covered_by_reserves - covered_by_buffer;
+    let loan_amount = liability.loan_amount;
+    decrease_loan(&mut state, &mut liability, loan_amount);
+    store_state(deps.storage, &state)?;
+    store_borrower_info(deps.storage, &borrower_raw, &liability)?;
+    store_bad_debt_write_off(
+        deps.storage,
+        &BadDebtWriteOff {
+            borrower: borrower_raw,
+            amount: bad_debt,
+            covered_by_reserves,
+            covered_by_buffer,
+            socialized,
+            block_height: env.block.height,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages,
+        attributes: vec![
+            attr("action", "write_off_bad_debt"),
+            attr("borrower", borrower),
+            attr("bad_debt", bad_debt),
+            attr("covered_by_reserves", covered_by_reserves),
+            attr("covered_by_buffer", covered_by_buffer),
+            attr("socialized", socialized),
+        ],
+        data: None,
+    })
+}
+
+pub fn swap_rate_mode(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+
+    let borrower = info.sender;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw)?;
+
+    // Compute interest
+    compute_interest(deps.as_ref(), &config, &mut state, env.block.height, None)?;
+    compute_borrower_interest(&state, &mut liability);
+
+    // Compute ANC reward
+    compute_reward(&mut state, env.block.height);
+    compute_borrower_reward(&state, &mut liability);
+
+    match liability.rate_mode {
+        RateMode::Variable => {
+            let current_balance = query_stable_balance(deps.as_ref(), &config)?;
+            let stable_rate = compute_stable_rate(deps.as_ref(), &config, &state, current_balance)?;
+            increase_stable_liabilities(&mut state, liability.loan_amount, stable_rate);
+            liability.rate_mode = RateMode::Stable;
+            liability.stable_rate = stable_rate;
+        }
+        RateMode::Stable => {
+            decrease_stable_liabilities(&mut state, liability.loan_amount, liability.stable_rate);
+            liability.rate_mode = RateMode::Variable;
+            liability.stable_rate = Decimal256::zero();
+        }
+    }
+
+    store_state(deps.storage, &state)?;
+    store_borrower_info(deps.storage, &borrower_raw, &liability)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        attributes: vec![
+            attr("action", "swap_rate_mode"),
+            attr("borrower", borrower),
+            attr(
+                "rate_mode",
+                match liability.rate_mode {
+                    RateMode::Variable => "variable",
+                    RateMode::Stable => "stable",
+                },
+            ),
+            attr("stable_rate", liability.stable_rate),
+        ],
+        data: None,
+    })
+}
+
+pub fn rebalance_stable_rate(
+    deps: DepsMut,
+    env: Env,
+    borrower: HumanAddr,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw)?;
+    if liability.rate_mode != RateMode::Stable {
+        return Err(ContractError::RebalanceNotAllowed {});
+    }
+
+    // Compute interest
+    compute_interest(deps.as_ref(), &config, &mut state, env.block.height, None)?;
+    compute_borrower_interest(&state, &mut liability);
+
+    // Compute ANC reward
+    compute_reward(&mut state, env.block.height);
+    compute_borrower_reward(&state, &mut liability);
+
+    // Only loans priced below the variable rate are repriced
+    // once utilization has been too high for rebalance_min_duration
+    let current_balance = query_stable_balance(deps.as_ref(), &config)?;
+    let stable_rate = compute_stable_rate(deps.as_ref(), &config, &state, current_balance)?;
+    let variable_rate = stable_rate - config.stable_rate_premium;
+    let high_utilization_elapsed = match state.high_utilization_since {
+        Some(since) => env.block.height >= since + config.rebalance_min_duration,
+        None => false,
+    };
+    if compute_utilization_ratio(&state, current_balance) < config.rebalance_utilization
+        || !high_utilization_elapsed
+        || liability.stable_rate >= variable_rate
+    {
+        return Err(ContractError::RebalanceNotAllowed {});
+    }
+
+    decrease_stable_liabilities(&mut state, liability.loan_amount, liability.stable_rate);
+    increase_stable_liabilities(&mut state, liability.loan_amount, stable_rate);
+    liability.stable_rate = stable_rate;
+
+    store_state(deps.storage, &state)?;
+    store_borrower_info(deps.storage, &borrower_raw, &liability)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        attributes: vec![
+            attr("action", "rebalance_stable_rate"),
+            attr("borrower", borrower),
+            attr("stable_rate", stable_rate),
+        ],
+        data: None,
+    })
+}
+
 pub fn claim_rewards(
     deps: DepsMut,
     env: Env,
@@ -192,7 +719,7 @@ pub fn claim_rewards(
 
     let borrower = info.sender;
     let borrower_raw = deps.api.canonical_address(&borrower)?;
-    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw)?;
 
     // Compute interest
     compute_interest(deps.as_ref(), &config, &mut state, env.block.height, None)?;
@@ -226,7 +753,7 @@ pub fn claim_rewards(
             attr("action", "claim_rewards"),
             attr("claim_amount", claim_amount),
         ],
-        messages: messages,
+        messages,
         data: None,
     };
     Ok(res)
@@ -246,11 +773,8 @@ pub fn compute_interest(
     }
 
     let aterra_supply = query_supply(deps, deps.api.human_address(&config.aterra_contract)?)?;
-    let balance: Uint256 = query_balance(
-        deps,
-        deps.api.human_address(&config.contract_addr)?,
-        deps.api.human_address(&config.stable_addr)?,
-    )? - deposit_amount.unwrap_or_else(Uint256::zero);
+    let balance: Uint256 =
+        query_stable_balance(deps, config)? - deposit_amount.unwrap_or_else(Uint256::zero);
 
     let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
         deps,
@@ -260,18 +784,24 @@ pub fn compute_interest(
         state.total_reserves,
     )?;
 
-    let target_deposit_rate: Decimal256 =
-        query_target_deposit_rate(deps, deps.api.human_address(&config.overseer_contract)?)?;
-
+    compute_term_interest(deps.storage, state, block_height, config.reserve_factor)?;
     compute_interest_raw(
         state,
         block_height,
         balance,
         aterra_supply,
         borrow_rate_res.rate,
-        target_deposit_rate,
+        config.reserve_factor,
     );
 
+    // Record when utilization crossed the rebalance threshold,
+    // stable rate rebalances need it to stay above for a while
+    if compute_utilization_ratio(state, balance) < config.rebalance_utilization {
+        state.high_utilization_since = None;
+    } else if state.high_utilization_since.is_none() {
+        state.high_utilization_since = Some(block_height);
+    }
+
     Ok(())
 }
 
@@ -286,7 +816,7 @@ pub fn compute_interest_raw(
     balance: Uint256,
     aterra_supply: Uint256,
     borrow_rate: Decimal256,
-    target_deposit_rate: Decimal256,
+    reserve_factor: Decimal256,
 ) {
     if state.last_interest_updated >= block_height {
         return;
@@ -294,40 +824,221 @@ pub fn compute_interest_raw(
 
     let passed_blocks = Decimal256::from_uint256(block_height - state.last_interest_updated);
 
+    // variable loans follow the market borrow rate,
+    // stable loans accrue at their average locked rate
     let interest_factor = passed_blocks * borrow_rate * Decimal256::from_ratio(1, BLOCKS_PER_YEAR);
-    let interest_accrued = state.total_liabilities * interest_factor;
+    let stable_interest_factor =
+        passed_blocks * state.avg_stable_rate * Decimal256::from_ratio(1, BLOCKS_PER_YEAR);
+    let variable_liabilities =
+        state.total_liabilities - state.total_stable_liabilities - state.total_term_liabilities;
+    let stable_interest_accrued = state.total_stable_liabilities * stable_interest_factor;
+    let interest_accrued = variable_liabilities * interest_factor + stable_interest_accrued;
 
     state.global_interest_index =
         state.global_interest_index * (Decimal256::one() + interest_factor);
+    state.total_stable_liabilities += stable_interest_accrued;
     state.total_liabilities += interest_accrued;
 
-    let mut exchange_rate = compute_exchange_rate_raw(state, aterra_supply, balance);
-    let effective_deposit_rate = exchange_rate / state.prev_exchange_rate;
-    let deposit_rate = (effective_deposit_rate - Decimal256::one()) / passed_blocks;
-
-    // if deposit_rate > target_deposit_rate {
-    //     // excess_deposit_rate(_per_block)
-    //     let excess_deposit_rate = deposit_rate - target_deposit_rate;
-    //     let prev_deposits =
-    //         Decimal256::from_uint256(state.prev_aterra_supply * state.prev_exchange_rate);
+    // reserve_factor portion of the accrued interest
+    // goes to the reserves instead of the depositors
+    state.total_reserves += interest_accrued * reserve_factor;
 
-    //     // excess_yield = prev_deposits * excess_deposit_rate(_per_block) * blocks
-    //     let excess_yield = prev_deposits * passed_blocks * excess_deposit_rate;
-
-    //     state.total_reserves += excess_yield;
-    //     exchange_rate = compute_exchange_rate_raw(state, aterra_supply, balance);
-    // }
+    let exchange_rate = compute_exchange_rate_raw(state, aterra_supply, balance);
 
     state.prev_aterra_supply = aterra_supply;
     state.prev_exchange_rate = exchange_rate;
     state.last_interest_updated = block_height;
 }
 
+/// Assert the borrow limit, the market liquidity, the borrow caps
+/// and the collateral debt ceilings for a loan increase
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn assert_borrow_limits(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    state: &State,
+    borrower: &HumanAddr,
+    loan_amount: Uint256,
+    loan_increase: Uint256,
+    current_balance: Uint256,
+) -> Result<(), ContractError> {
+    let overseer = deps.api.human_address(&config.overseer_contract)?;
+    let borrow_limit_res: BorrowLimitResponse = query_borrow_limit(
+        deps,
+        overseer.clone(),
+        borrower.clone(),
+        Some(env.block.time),
+    )?;
+    if borrow_limit_res.borrow_limit < loan_increase + loan_amount {
+        return Err(ContractError::BorrowExceedsLimit(
+            borrow_limit_res.borrow_limit.into(),
+        ));
+    }
+
+    assert_max_borrow_factor(config, state, current_balance, loan_increase)?;
+    assert_borrow_caps(
+        config,
+        state,
+        read_borrower_cap(deps.storage, &deps.api.canonical_address(borrower)?),
+        loan_amount,
+        loan_increase,
+    )?;
+
+    let debt_ceilings_res = query_debt_ceilings(
+        deps,
+        overseer,
+        borrower.clone(),
+        loan_amount + loan_increase,
+    )?;
+    if !debt_ceilings_res.within_ceilings {
+        return Err(ContractError::DebtCeilingExceeded {});
+    }
+
+    Ok(())
+}
+
+/// Add a loan increase to the borrower loan and the market liabilities,
+/// a stable rate loan averages its rate with stable_rate
+pub(crate) fn increase_loan(
+    state: &mut State,
+    liability: &mut BorrowerInfo,
+    loan_increase: Uint256,
+    stable_rate: Decimal256,
+) {
+    if liability.rate_mode == RateMode::Stable {
+        liability.stable_rate = (Decimal256::from_uint256(liability.loan_amount)
+            * liability.stable_rate
+            + Decimal256::from_uint256(loan_increase) * stable_rate)
+            / Decimal256::from_uint256(liability.loan_amount + loan_increase);
+        increase_stable_liabilities(state, loan_increase, stable_rate);
+    }
+
+    liability.loan_amount += loan_increase;
+    state.total_liabilities += Decimal256::from_uint256(loan_increase);
+}
+
+/// Apply a repayment to the borrower loan and the market liabilities,
+/// returns the repaid amount, the rest is to be refunded
+pub(crate) fn decrease_loan(
+    state: &mut State,
+    liability: &mut BorrowerInfo,
+    amount: Uint256,
+) -> Uint256 {
+    let repay_amount = std::cmp::min(amount, liability.loan_amount);
+    liability.loan_amount = liability.loan_amount - repay_amount;
+    state.total_liabilities = state.total_liabilities
+        - std::cmp::min(
+            Decimal256::from_uint256(repay_amount),
+            state.total_liabilities,
+        );
+    if liability.rate_mode == RateMode::Stable {
+        decrease_stable_liabilities(state, repay_amount, liability.stable_rate);
+    }
+
+    repay_amount
+}
+
+/// Origination fee charged on borrow_amount,
+/// returns (origination_fee, loan_increase, disbursed_amount)
+pub(crate) fn compute_origination_fee(
+    config: &Config,
+    borrow_amount: Uint256,
+) -> (Uint256, Uint256, Uint256) {
+    let origination_fee = borrow_amount * config.origination_fee_rate;
+    match config.origination_fee_mode {
+        OriginationFeeMode::AddToLoan => (
+            origination_fee,
+            borrow_amount + origination_fee,
+            borrow_amount,
+        ),
+        OriginationFeeMode::DeductFromBorrow => (
+            origination_fee,
+            borrow_amount,
+            borrow_amount - origination_fee,
+        ),
+    }
+}
+
 /// Compute new interest and apply to liability
 pub(crate) fn compute_borrower_interest(state: &State, liability: &mut BorrowerInfo) {
-    liability.loan_amount =
-        liability.loan_amount * (state.global_interest_index / liability.interest_index);
+    match liability.rate_mode {
+        RateMode::Variable => {
+            liability.loan_amount =
+                liability.loan_amount * (state.global_interest_index / liability.interest_index);
+        }
+        RateMode::Stable => {
+            if state.last_interest_updated > liability.stable_updated {
+                let passed_blocks = Decimal256::from_uint256(
+                    state.last_interest_updated - liability.stable_updated,
+                );
+                let interest_factor = passed_blocks
+                    * liability.stable_rate
+                    * Decimal256::from_ratio(1, BLOCKS_PER_YEAR);
+                liability.loan_amount =
+                    liability.loan_amount * (Decimal256::one() + interest_factor);
+            }
+        }
+    }
+
     liability.interest_index = state.global_interest_index;
+    liability.stable_updated = state.last_interest_updated;
+}
+
+/// Stable rate offered at the current block,
+/// the variable borrow rate plus the stable rate premium
+pub(crate) fn compute_stable_rate(
+    deps: Deps,
+    config: &Config,
+    state: &State,
+    current_balance: Uint256,
+) -> StdResult<Decimal256> {
+    let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
+        deps,
+        deps.api.human_address(&config.interest_model)?,
+        current_balance,
+        state.total_liabilities,
+        state.total_reserves,
+    )?;
+
+    Ok(borrow_rate_res.rate + config.stable_rate_premium)
+}
+
+/// Add amount borrowed at rate to the stable liabilities
+pub(crate) fn increase_stable_liabilities(state: &mut State, amount: Uint256, rate: Decimal256) {
+    let amount = Decimal256::from_uint256(amount);
+    let total_stable_liabilities = state.total_stable_liabilities + amount;
+    state.avg_stable_rate = (state.total_stable_liabilities * state.avg_stable_rate
+        + amount * rate)
+        / total_stable_liabilities;
+    state.total_stable_liabilities = total_stable_liabilities;
+}
+
+/// Remove amount borrowed at rate from the stable liabilities
+pub(crate) fn decrease_stable_liabilities(state: &mut State, amount: Uint256, rate: Decimal256) {
+    let amount = std::cmp::min(
+        Decimal256::from_uint256(amount),
+        state.total_stable_liabilities,
+    );
+    let total_stable_liabilities = state.total_stable_liabilities - amount;
+    let weighted_rate = state.total_stable_liabilities * state.avg_stable_rate;
+    state.avg_stable_rate = if total_stable_liabilities.is_zero() || weighted_rate < amount * rate {
+        Decimal256::zero()
+    } else {
+        (weighted_rate - amount * rate) / total_stable_liabilities
+    };
+    state.total_stable_liabilities = total_stable_liabilities;
+}
+
+/// utilization_ratio = total_liabilities / (balance + total_liabilities - total_reserves)
+pub(crate) fn compute_utilization_ratio(state: &State, balance: Uint256) -> Decimal256 {
+    let total_deposits =
+        Decimal256::from_uint256(balance) + state.total_liabilities - state.total_reserves;
+    if total_deposits.is_zero() {
+        return Decimal256::zero();
+    }
+
+    state.total_liabilities / total_deposits
 }
 
 /// Compute distributed reward and update global index
@@ -338,7 +1049,9 @@ pub fn compute_reward(state: &mut State, block_height: u64) {
 
     let passed_blocks = Decimal256::from_uint256(block_height - state.last_reward_updated);
     let reward_accrued = passed_blocks * state.orchai_epb_rate;
-    let borrow_amount = state.total_liabilities / state.global_interest_index;
+    // term loans do not earn rewards
+    let borrow_amount =
+        (state.total_liabilities - state.total_term_liabilities) / state.global_interest_index;
     if !reward_accrued.is_zero() && !borrow_amount.is_zero() {
         state.global_reward_index += reward_accrued / borrow_amount;
     }
@@ -361,7 +1074,7 @@ pub fn query_borrower_info(
     block_height: Option<u64>,
 ) -> StdResult<BorrowerInfoResponse> {
     let mut borrower_info: BorrowerInfo =
-        read_borrower_info(deps.storage, &deps.api.canonical_address(&borrower)?);
+        read_borrower_info(deps.storage, &deps.api.canonical_address(&borrower)?)?;
 
     let block_height = if let Some(block_height) = block_height {
         block_height
@@ -378,17 +1091,29 @@ pub fn query_borrower_info(
     compute_reward(&mut state, block_height);
     compute_borrower_reward(&state, &mut borrower_info);
 
+    let (term_loan_amount, expired_term_loan_amount) = read_term_loan_amounts(
+        deps.storage,
+        &deps.api.canonical_address(&borrower)?,
+        config.term_grace_period,
+        block_height,
+    )?;
+
     Ok(BorrowerInfoResponse {
         borrower: borrower.to_string(),
         interest_index: borrower_info.interest_index,
         reward_index: borrower_info.reward_index,
         loan_amount: borrower_info.loan_amount,
         pending_rewards: borrower_info.pending_rewards,
+        rate_mode: borrower_info.rate_mode,
+        stable_rate: borrower_info.stable_rate,
+        term_loan_amount,
+        expired_term_loan_amount,
     })
 }
 
 pub fn query_borrower_infos(
     deps: Deps,
+    env: Env,
     start_after: Option<HumanAddr>,
     limit: Option<u32>,
 ) -> StdResult<BorrowerInfosResponse> {
@@ -398,11 +1123,44 @@ pub fn query_borrower_infos(
         None
     };
 
-    let borrower_infos: Vec<BorrowerInfoResponse> = read_borrower_infos(deps, start_after, limit)?;
+    let borrower_infos: Vec<BorrowerInfoResponse> =
+        read_borrower_infos(deps, start_after, limit, env.block.height)?;
     Ok(BorrowerInfosResponse { borrower_infos })
 }
 
-fn assert_max_borrow_factor(
+pub fn query_delegation_allowance(
+    deps: Deps,
+    borrower: HumanAddr,
+    delegate: HumanAddr,
+) -> StdResult<DelegationAllowanceResponse> {
+    let delegation = read_delegation(
+        deps.storage,
+        &deps.api.canonical_address(&borrower)?,
+        &deps.api.canonical_address(&delegate)?,
+    )
+    .unwrap_or(Delegation {
+        allowance: Uint256::zero(),
+        expires: Expiration::default(),
+    });
+
+    Ok(DelegationAllowanceResponse {
+        borrower: borrower.to_string(),
+        delegate: delegate.to_string(),
+        allowance: delegation.allowance,
+        expires: delegation.expires,
+    })
+}
+
+pub fn query_bad_debt_write_offs(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<BadDebtWriteOffsResponse> {
+    let write_offs = read_bad_debt_write_offs(deps, start_after, limit)?;
+    Ok(BadDebtWriteOffsResponse { write_offs })
+}
+
+pub(crate) fn assert_max_borrow_factor(
     config: &Config,
     state: &State,
     current_balance: Uint256,
@@ -426,3 +1184,41 @@ fn assert_max_borrow_factor(
 
     Ok(HandleResponse::default())
 }
+
+pub(crate) fn assert_borrow_caps(
+    config: &Config,
+    state: &State,
+    borrower_cap: Option<Uint256>,
+    loan_amount: Uint256,
+    borrow_amount: Uint256,
+) -> Result<(), ContractError> {
+    // Assert market borrow cap
+    if let Some(borrow_cap) = config.borrow_cap {
+        let total_liabilities = state.total_liabilities * Uint256::one();
+        if total_liabilities + borrow_amount > borrow_cap {
+            return Err(ContractError::BorrowCapExceeded(
+                compute_headroom(borrow_cap, total_liabilities).into(),
+            ));
+        }
+    }
+
+    // Assert borrower cap
+    if let Some(borrower_cap) = borrower_cap {
+        if loan_amount + borrow_amount > borrower_cap {
+            return Err(ContractError::BorrowerCapExceeded(
+                compute_headroom(borrower_cap, loan_amount).into(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Remaining amount before `used` reaches `cap`
+pub(crate) fn compute_headroom(cap: Uint256, used: Uint256) -> Uint256 {
+    if cap > used {
+        cap - used
+    } else {
+        Uint256::zero()
+    }
+}
@@ -2,13 +2,29 @@
 use cosmwasm_std::entry_point;
 
 use crate::borrow::{
-    borrow_stable, claim_rewards, compute_interest, compute_interest_raw, compute_reward,
-    query_borrower_info, query_borrower_infos, repay_stable, repay_stable_from_liquidation,
+    approve_delegation, assert_borrow_limit, borrow_stable, borrow_stable_and_call, claim_rewards,
+    compute_borrower_interest, compute_headroom, compute_interest, compute_interest_raw,
+    compute_reward, query_bad_debt_write_offs, query_borrower_info, query_borrower_infos,
+    query_delegation_allowance, rebalance_stable_rate, repay_stable, repay_stable_from_liquidation,
+    revoke_delegation, swap_rate_mode, write_off_bad_debt, BLOCKS_PER_YEAR,
+};
+use crate::deposit::{
+    cancel_redeem_request, compute_exchange_rate_raw, compute_total_deposits, deposit_stable,
+    fill_redeem_queue, query_redeem_queue, query_redeem_request, redeem_stable,
+    stable_transfer_msg,
 };
-use crate::deposit::{compute_exchange_rate_raw, deposit_stable, redeem_stable};
 use crate::error::ContractError;
-use crate::querier::{query_borrow_rate, query_target_deposit_rate};
-use crate::state::{read_config, read_state, store_config, store_state, Config, State};
+use crate::migration::{migrate_config, migrate_state};
+use crate::querier::{query_borrow_rate, query_stable_balance};
+use crate::simulation::{query_simulation, SimulatedAction};
+use crate::state::{
+    read_borrower_cap, read_borrower_info, read_config, read_historical_states, read_pause_info,
+    read_reserve_history, read_state, read_term_loan_amounts, remove_borrower_cap,
+    store_borrower_cap, store_config, store_historical_state, store_pause_info,
+    store_reserve_history, store_state, BorrowerInfo, Config, HistoricalState, PauseInfo,
+    ReserveHistory, State,
+};
+use crate::term_loan::{compute_term_interest, query_term_loans, repay_term_loan};
 
 use anchor_token::hook::InitHook;
 use anchor_token::token::InitMsg;
@@ -19,15 +35,21 @@ use cosmwasm_std::{
     StdError, StdResult, Uint128, WasmMsg, WasmQuery,
 };
 use cw20::TokenInfoResponse;
-use cw20::{Cw20Coin, Cw20CoinHuman, Cw20HandleMsg, Cw20ReceiveMsg, MinterResponse};
+use cw20::{Cw20CoinHuman, Cw20ReceiveMsg, MinterResponse};
+use moneymarket::common::format_cap;
 use moneymarket::interest_model::BorrowRateResponse;
 use moneymarket::market::{
-    ConfigResponse, Cw20HookMsg, EpochStateResponse, ExecuteMsg, InstantiateMsg, MigrateMsg,
-    QueryMsg, StateResponse, TokenInstantiateMsg,
+    CapsResponse, ConfigResponse, Cw20HookMsg, EpochStateResponse, ExecuteMsg, FeeRecipient,
+    HistoricalStatesResponse, InstantiateMsg, MigrateMsg, OriginationFeeMode, QueryMsg,
+    ReserveHistoryResponse, StateResponse, UpdateConfigMsg,
 };
-use moneymarket::querier::{deduct_tax, query_balance, query_supply};
+use moneymarket::querier::query_supply;
+use oraiswap::asset::AssetInfoRaw;
 
 pub const INITIAL_DEPOSIT_AMOUNT: u128 = 1000000;
+/// About a week of blocks
+pub const DEFAULT_TERM_GRACE_PERIOD: u64 = BLOCKS_PER_YEAR / 52;
+pub const DEFAULT_REBALANCE_MIN_DURATION: u64 = BLOCKS_PER_YEAR / 365;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn init(
@@ -36,6 +58,10 @@ pub fn init(
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<InitResponse, ContractError> {
+    if msg.reserve_factor > Decimal256::one() {
+        return Err(ContractError::InvalidReserveFactor {});
+    }
+
     let mut messages: Vec<CosmosMsg> = vec![];
     // match msg.hook_msg {
     //     moneymarket::market::HookMsg {contract_addr, amount, recipient} => {
@@ -68,9 +94,21 @@ pub fn init(
             distribution_model: CanonicalAddr::from(vec![]),
             collector_contract: CanonicalAddr::from(vec![]),
             distributor_contract: CanonicalAddr::from(vec![]),
-            stable_addr: deps.api.canonical_address(&msg.stable_addr)?,
+            stable_info: msg.stable_info.to_raw(deps.api)?,
             orchai_token: deps.api.canonical_address(&msg.orchai_token)?,
             max_borrow_factor: msg.max_borrow_factor,
+            deposit_cap: msg.deposit_cap,
+            borrow_cap: msg.borrow_cap,
+            reserve_factor: msg.reserve_factor,
+            reserve_target: msg.reserve_target,
+            redeem_rate_lock: msg.redeem_rate_lock,
+            origination_fee_rate: Decimal256::zero(),
+            origination_fee_mode: OriginationFeeMode::AddToLoan,
+            origination_fee_recipient: FeeRecipient::Reserves,
+            stable_rate_premium: Decimal256::zero(),
+            rebalance_utilization: Decimal256::percent(95),
+            rebalance_min_duration: DEFAULT_REBALANCE_MIN_DURATION,
+            term_grace_period: DEFAULT_TERM_GRACE_PERIOD,
         },
     )?;
 
@@ -78,6 +116,10 @@ pub fn init(
         deps.storage,
         &State {
             total_liabilities: Decimal256::zero(),
+            total_stable_liabilities: Decimal256::zero(),
+            avg_stable_rate: Decimal256::zero(),
+            total_term_liabilities: Decimal256::zero(),
+            term_interest_rate: Decimal256::zero(),
             total_reserves: Decimal256::zero(),
             last_interest_updated: env.block.height,
             last_reward_updated: env.block.height,
@@ -86,6 +128,7 @@ pub fn init(
             orchai_epb_rate: msg.orchai_epb_rate,
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            high_utilization_since: None,
         },
     )?;
 
@@ -122,8 +165,8 @@ pub fn init(
         send: vec![],
         label: Some("".to_string()),
         msg: to_binary(&InitMsg {
-            name: "Orchai Usdt".to_string(),
-            symbol: "oUSDT".to_string(),
+            name: msg.atoken_name,
+            symbol: msg.atoken_symbol,
             decimals: 6u8,
             initial_balances: vec![Cw20CoinHuman {
                 address: HumanAddr(env.contract.address.clone().to_string()),
@@ -140,9 +183,17 @@ pub fn init(
         })?,
     }));
 
+    if let Some(init_hook) = msg.init_hook {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: init_hook.contract_addr,
+            msg: init_hook.msg,
+            send: vec![],
+        }));
+    }
+
     let res = InitResponse {
         attributes: vec![],
-        messages: messages,
+        messages,
     };
     Ok(res)
 }
@@ -181,20 +232,26 @@ pub fn handle(
             collector_contract,
             distributor_contract,
         ),
-        ExecuteMsg::UpdateConfig {
-            owner_addr,
-            interest_model,
-            distribution_model,
-            max_borrow_factor,
-        } => update_config(
-            deps,
-            env,
-            info,
-            owner_addr,
-            interest_model,
-            distribution_model,
-            max_borrow_factor,
-        ),
+        ExecuteMsg::UpdateConfig(msg) => update_config(deps, env, info, *msg),
+        ExecuteMsg::WithdrawReserves { amount, recipient } => {
+            withdraw_reserves(deps, env, info, amount, recipient)
+        }
+        ExecuteMsg::UpdateGuardian { guardian } => update_guardian(deps, info, guardian),
+        ExecuteMsg::UpdatePauseFlags {
+            deposit,
+            redeem,
+            borrow,
+            repay,
+            claim,
+        } => update_pause_flags(deps, info, deposit, redeem, borrow, repay, claim),
+        ExecuteMsg::UpdateCaps {
+            deposit_cap,
+            borrow_cap,
+        } => update_caps(deps, info, deposit_cap, borrow_cap),
+        ExecuteMsg::UpdateBorrowerCap {
+            borrower,
+            borrow_cap,
+        } => update_borrower_cap(deps, info, borrower, borrow_cap),
         ExecuteMsg::ExecuteEpochOperations {
             deposit_rate,
             target_deposit_rate,
@@ -209,23 +266,74 @@ pub fn handle(
             threshold_deposit_rate,
             distributed_interest,
         ),
-        ExecuteMsg::BorrowStable { borrow_amount, to } => {
-            borrow_stable(deps, env, info, borrow_amount, to)
+        ExecuteMsg::DepositStable {} => {
+            assert_not_paused(read_pause_info(deps.storage)?.flags.deposit, "deposit")?;
+            let amount = native_stable_amount(deps.as_ref(), &info)?;
+            deposit_stable(deps, env, info.sender, amount)
         }
-        ExecuteMsg::RepayStableFromLiquidation {
-            borrower,
-            prev_balance,
+        ExecuteMsg::RepayStable {} => {
+            assert_not_paused(read_pause_info(deps.storage)?.flags.repay, "repay")?;
+            let amount = native_stable_amount(deps.as_ref(), &info)?;
+            repay_stable(deps, env, info.sender, amount.into())
+        }
+        ExecuteMsg::RepayTermLoan { loan_id } => {
+            assert_not_paused(read_pause_info(deps.storage)?.flags.repay, "repay")?;
+            let amount = native_stable_amount(deps.as_ref(), &info)?;
+            repay_term_loan(deps, env, info.sender, loan_id, amount.into())
+        }
+        ExecuteMsg::BorrowStable {
+            borrow_amount,
+            to,
+            on_behalf_of,
+            rate_mode,
+            term,
         } => {
-            let api = deps.api;
-            repay_stable_from_liquidation(
+            assert_not_paused(read_pause_info(deps.storage)?.flags.borrow, "borrow")?;
+            borrow_stable(
                 deps,
                 env,
                 info,
-                api.human_address(&CanonicalAddr(to_binary(&borrower)?))?,
-                prev_balance,
+                borrow_amount,
+                to,
+                on_behalf_of,
+                rate_mode,
+                term,
+                None,
             )
         }
-        ExecuteMsg::ClaimRewards { to } => claim_rewards(deps, env, info, to),
+        ExecuteMsg::BorrowStableAndCall {
+            borrow_amount,
+            contract,
+            msg,
+        } => {
+            assert_not_paused(read_pause_info(deps.storage)?.flags.borrow, "borrow")?;
+            borrow_stable_and_call(deps, env, info, borrow_amount, contract, msg)
+        }
+        ExecuteMsg::AssertBorrowLimit { borrower } => {
+            assert_borrow_limit(deps.as_ref(), env, info, borrower)
+        }
+        ExecuteMsg::SwapRateMode {} => swap_rate_mode(deps, env, info),
+        ExecuteMsg::ApproveDelegation {
+            delegate,
+            amount,
+            expires,
+        } => approve_delegation(deps, env, info, delegate, amount, expires),
+        ExecuteMsg::RevokeDelegation { delegate } => revoke_delegation(deps, info, delegate),
+        ExecuteMsg::CancelRedeemRequest { request_id } => {
+            cancel_redeem_request(deps, info, request_id)
+        }
+        ExecuteMsg::WriteOffBadDebt { borrower } => write_off_bad_debt(deps, env, borrower),
+        ExecuteMsg::RebalanceStableRate { borrower } => rebalance_stable_rate(deps, env, borrower),
+        ExecuteMsg::RepayStableFromLiquidation {
+            borrower,
+            prev_balance,
+        } => {
+            repay_stable_from_liquidation(deps, env, info, HumanAddr::from(borrower), prev_balance)
+        }
+        ExecuteMsg::ClaimRewards { to } => {
+            assert_not_paused(read_pause_info(deps.storage)?.flags.claim, "claim")?;
+            claim_rewards(deps, env, info, to)
+        }
     }
 }
 
@@ -237,35 +345,71 @@ pub fn receive_cw20(
 ) -> Result<HandleResponse, ContractError> {
     let contract_addr = info.sender;
     match from_binary(&cw20_msg.msg.unwrap()) {
-        Ok(Cw20HookMsg::RedeemStable {}) => {
+        Ok(Cw20HookMsg::RedeemStable { queue }) => {
             // only asset contract can execute this message
             let config: Config = read_config(deps.storage)?;
             if deps.api.canonical_address(&contract_addr)? != config.aterra_contract {
                 return Err(ContractError::Unauthorized {});
             }
 
-            redeem_stable(deps, env, cw20_msg.sender, cw20_msg.amount)
+            assert_not_paused(read_pause_info(deps.storage)?.flags.redeem, "redeem")?;
+            redeem_stable(
+                deps,
+                env,
+                cw20_msg.sender,
+                cw20_msg.amount,
+                queue.unwrap_or(false),
+            )
         }
         Ok(Cw20HookMsg::DepositStabe {}) => {
-            let config: Config = read_config(deps.storage)?;
-            if deps.api.canonical_address(&contract_addr)? != config.stable_addr {
-                return Err(ContractError::Unauthorized {});
-            }
+            assert_cw20_stable(deps.as_ref(), &contract_addr)?;
+            assert_not_paused(read_pause_info(deps.storage)?.flags.deposit, "deposit")?;
 
             deposit_stable(deps, env, cw20_msg.sender, cw20_msg.amount)
         }
         Ok(Cw20HookMsg::RepayStable {}) => {
-            let config: Config = read_config(deps.storage)?;
-            if deps.api.canonical_address(&contract_addr)? != config.stable_addr {
-                return Err(ContractError::Unauthorized {});
-            }
+            assert_cw20_stable(deps.as_ref(), &contract_addr)?;
+            assert_not_paused(read_pause_info(deps.storage)?.flags.repay, "repay")?;
 
             repay_stable(deps, env, cw20_msg.sender, cw20_msg.amount.into())
         }
+        Ok(Cw20HookMsg::RepayTermLoan { loan_id }) => {
+            assert_cw20_stable(deps.as_ref(), &contract_addr)?;
+            assert_not_paused(read_pause_info(deps.storage)?.flags.repay, "repay")?;
+
+            repay_term_loan(deps, env, cw20_msg.sender, loan_id, cw20_msg.amount.into())
+        }
         _ => Err(ContractError::MissingRedeemStableHook {}),
     }
 }
 
+/// Only the cw20 stable token can deposit or repay through Receive
+fn assert_cw20_stable(deps: Deps, contract_addr: &HumanAddr) -> Result<(), ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    match config.stable_info {
+        AssetInfoRaw::Token {
+            contract_addr: stable_addr,
+        } if stable_addr == deps.api.canonical_address(contract_addr)? => Ok(()),
+        _ => Err(ContractError::Unauthorized {}),
+    }
+}
+
+/// Amount of the native stable sent along with the message
+fn native_stable_amount(deps: Deps, info: &MessageInfo) -> Result<Uint128, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    match config.stable_info {
+        AssetInfoRaw::NativeToken { denom } => Ok(info
+            .sent_funds
+            .iter()
+            .find(|c| c.denom == denom)
+            .map(|c| c.amount)
+            .unwrap_or_else(Uint128::zero)),
+        AssetInfoRaw::Token { contract_addr } => Err(ContractError::StableAssetMismatch(
+            deps.api.human_address(&contract_addr)?.to_string(),
+        )),
+    }
+}
+
 pub fn register_aterra(
     deps: DepsMut,
     _env: Env,
@@ -299,14 +443,14 @@ pub fn register_contracts(
     distributor_contract: HumanAddr,
 ) -> Result<HandleResponse, ContractError> {
     let mut config: Config = read_config(deps.storage)?;
-    // if config.overseer_contract != CanonicalAddr::from(vec![])
-    //     || config.interest_model != CanonicalAddr::from(vec![])
-    //     || config.distribution_model != CanonicalAddr::from(vec![])
-    //     || config.collector_contract != CanonicalAddr::from(vec![])
-    //     || config.distributor_contract != CanonicalAddr::from(vec![])
-    // {
-    //     return Err(ContractError::Unauthorized {});
-    // }
+    if config.overseer_contract != CanonicalAddr::from(vec![])
+        || config.interest_model != CanonicalAddr::from(vec![])
+        || config.distribution_model != CanonicalAddr::from(vec![])
+        || config.collector_contract != CanonicalAddr::from(vec![])
+        || config.distributor_contract != CanonicalAddr::from(vec![])
+    {
+        return Err(ContractError::Unauthorized {});
+    }
 
     config.overseer_contract = deps.api.canonical_address(&overseer_contract)?;
     config.interest_model = deps.api.canonical_address(&interest_model)?;
@@ -334,11 +478,24 @@ pub fn update_config(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    owner_addr: Option<HumanAddr>,
-    interest_model: Option<HumanAddr>,
-    distribution_model: Option<HumanAddr>,
-    max_borrow_factor: Option<Decimal256>,
+    msg: UpdateConfigMsg,
 ) -> Result<HandleResponse, ContractError> {
+    let UpdateConfigMsg {
+        owner_addr,
+        max_borrow_factor,
+        interest_model,
+        distribution_model,
+        reserve_factor,
+        reserve_target,
+        redeem_rate_lock,
+        origination_fee_rate,
+        origination_fee_mode,
+        origination_fee_recipient,
+        stable_rate_premium,
+        rebalance_utilization,
+        rebalance_min_duration,
+        term_grace_period,
+    } = msg;
     let mut config: Config = read_config(deps.storage)?;
 
     // permission check
@@ -354,7 +511,8 @@ pub fn update_config(
         config.owner_addr = deps.api.canonical_address(&owner_addr)?;
     }
 
-    if interest_model.is_some() {
+    // accrue interest with the previous interest model and reserve factor
+    if interest_model.is_some() || reserve_factor.is_some() {
         let mut state: State = read_state(deps.storage)?;
         compute_interest(deps.as_ref(), &config, &mut state, env.block.height, None)?;
         store_state(deps.storage, &state)?;
@@ -362,6 +520,18 @@ pub fn update_config(
         if let Some(interest_model) = interest_model {
             config.interest_model = deps.api.canonical_address(&interest_model)?;
         }
+
+        if let Some(reserve_factor) = reserve_factor {
+            if reserve_factor > Decimal256::one() {
+                return Err(ContractError::InvalidReserveFactor {});
+            }
+
+            config.reserve_factor = reserve_factor;
+        }
+    }
+
+    if let Some(reserve_target) = reserve_target {
+        config.reserve_target = reserve_target;
     }
 
     if let Some(distribution_model) = distribution_model {
@@ -372,6 +542,46 @@ pub fn update_config(
         config.max_borrow_factor = max_borrow_factor;
     }
 
+    if let Some(redeem_rate_lock) = redeem_rate_lock {
+        config.redeem_rate_lock = redeem_rate_lock;
+    }
+
+    if let Some(origination_fee_rate) = origination_fee_rate {
+        if origination_fee_rate > Decimal256::one() {
+            return Err(ContractError::InvalidOriginationFeeRate {});
+        }
+
+        config.origination_fee_rate = origination_fee_rate;
+    }
+
+    if let Some(origination_fee_mode) = origination_fee_mode {
+        config.origination_fee_mode = origination_fee_mode;
+    }
+
+    if let Some(origination_fee_recipient) = origination_fee_recipient {
+        config.origination_fee_recipient = origination_fee_recipient;
+    }
+
+    if let Some(stable_rate_premium) = stable_rate_premium {
+        config.stable_rate_premium = stable_rate_premium;
+    }
+
+    if let Some(rebalance_utilization) = rebalance_utilization {
+        if rebalance_utilization > Decimal256::one() {
+            return Err(ContractError::InvalidRebalanceUtilization {});
+        }
+
+        config.rebalance_utilization = rebalance_utilization;
+    }
+
+    if let Some(rebalance_min_duration) = rebalance_min_duration {
+        config.rebalance_min_duration = rebalance_min_duration;
+    }
+
+    if let Some(term_grace_period) = term_grace_period {
+        config.term_grace_period = term_grace_period;
+    }
+
     store_config(deps.storage, &config)?;
     Ok(HandleResponse {
         attributes: (vec![attr("action", "update_config")]),
@@ -380,12 +590,218 @@ pub fn update_config(
     })
 }
 
-pub fn execute_epoch_operations(
+pub fn update_caps(
+    deps: DepsMut,
+    info: MessageInfo,
+    deposit_cap: Option<Uint256>,
+    borrow_cap: Option<Uint256>,
+) -> Result<HandleResponse, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.deposit_cap = deposit_cap;
+    config.borrow_cap = borrow_cap;
+    store_config(deps.storage, &config)?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "update_caps"),
+            attr("deposit_cap", format_cap(deposit_cap)),
+            attr("borrow_cap", format_cap(borrow_cap)),
+        ],
+        messages: vec![],
+        data: None,
+    })
+}
+
+pub fn update_borrower_cap(
+    deps: DepsMut,
+    info: MessageInfo,
+    borrower: HumanAddr,
+    borrow_cap: Option<Uint256>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    if let Some(borrow_cap) = borrow_cap {
+        store_borrower_cap(deps.storage, &borrower_raw, &borrow_cap)?;
+    } else {
+        remove_borrower_cap(deps.storage, &borrower_raw);
+    }
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "update_borrower_cap"),
+            attr("borrower", borrower),
+            attr("borrow_cap", format_cap(borrow_cap)),
+        ],
+        messages: vec![],
+        data: None,
+    })
+}
+
+pub fn update_guardian(
+    deps: DepsMut,
+    info: MessageInfo,
+    guardian: Option<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut pause_info: PauseInfo = read_pause_info(deps.storage)?;
+    pause_info.guardian = if let Some(guardian) = guardian.as_ref() {
+        Some(deps.api.canonical_address(guardian)?)
+    } else {
+        None
+    };
+    store_pause_info(deps.storage, &pause_info)?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "update_guardian"),
+            attr(
+                "guardian",
+                guardian.map_or_else(|| "none".to_string(), |g| g.to_string()),
+            ),
+        ],
+        messages: vec![],
+        data: None,
+    })
+}
+
+/// The guardian can only pause operations,
+/// the owner can both pause and unpause
+pub fn update_pause_flags(
     deps: DepsMut,
+    info: MessageInfo,
+    deposit: Option<bool>,
+    redeem: Option<bool>,
+    borrow: Option<bool>,
+    repay: Option<bool>,
+    claim: Option<bool>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut pause_info: PauseInfo = read_pause_info(deps.storage)?;
+
+    let sender_raw = deps.api.canonical_address(&info.sender)?;
+    if sender_raw != config.owner_addr {
+        if pause_info.guardian != Some(sender_raw) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        if [deposit, redeem, borrow, repay, claim].contains(&Some(false)) {
+            return Err(ContractError::Unauthorized {});
+        }
+    }
+
+    if let Some(deposit) = deposit {
+        pause_info.flags.deposit = deposit;
+    }
+
+    if let Some(redeem) = redeem {
+        pause_info.flags.redeem = redeem;
+    }
+
+    if let Some(borrow) = borrow {
+        pause_info.flags.borrow = borrow;
+    }
+
+    if let Some(repay) = repay {
+        pause_info.flags.repay = repay;
+    }
+
+    if let Some(claim) = claim {
+        pause_info.flags.claim = claim;
+    }
+
+    store_pause_info(deps.storage, &pause_info)?;
+    Ok(HandleResponse {
+        attributes: vec![attr("action", "update_pause_flags")],
+        messages: vec![],
+        data: None,
+    })
+}
+
+fn assert_not_paused(paused: bool, operation: &str) -> Result<(), ContractError> {
+    if paused {
+        return Err(ContractError::OperationPaused(operation.to_string()));
+    }
+
+    Ok(())
+}
+
+pub fn withdraw_reserves(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Option<Uint256>,
+    recipient: HumanAddr,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut state: State = read_state(deps.storage)?;
+    compute_interest(deps.as_ref(), &config, &mut state, env.block.height, None)?;
+    compute_reward(&mut state, env.block.height);
+
+    let total_reserves = state.total_reserves * Uint256::one();
+    let amount = amount.unwrap_or(total_reserves);
+    if amount.is_zero() {
+        return Err(ContractError::ZeroWithdrawReserves {});
+    }
+
+    if amount > total_reserves {
+        return Err(ContractError::InsufficientReserves(total_reserves.into()));
+    }
+
+    if amount > query_stable_balance(deps.as_ref(), &config)? {
+        return Err(ContractError::NoStableAvailable {});
+    }
+
+    state.total_reserves = state.total_reserves - Decimal256::from_uint256(amount);
+    store_state(deps.storage, &state)?;
+    store_reserve_history(
+        deps.storage,
+        &ReserveHistory {
+            action: "withdraw".to_string(),
+            amount,
+            recipient: deps.api.canonical_address(&recipient)?,
+            block_height: env.block.height,
+            total_reserves: state.total_reserves * Uint256::one(),
+        },
+    )?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "withdraw_reserves"),
+            attr("recipient", &recipient),
+            attr("amount", amount),
+        ],
+        messages: vec![stable_transfer_msg(
+            deps.as_ref(),
+            &config,
+            recipient,
+            amount,
+        )?],
+        data: None,
+    })
+}
+
+pub fn execute_epoch_operations(
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    _deposit_rate: Decimal256,
-    target_deposit_rate: Decimal256,
+    deposit_rate: Decimal256,
+    _target_deposit_rate: Decimal256,
     _threshold_deposit_rate: Decimal256,
     distributed_interest: Uint256,
 ) -> Result<HandleResponse, ContractError> {
@@ -401,11 +817,7 @@ pub fn execute_epoch_operations(
         deps.as_ref(),
         deps.api.human_address(&config.aterra_contract)?,
     )?;
-    let balance: Uint256 = query_balance(
-        deps.as_ref(),
-        deps.api.human_address(&config.contract_addr)?,
-        deps.api.human_address(&config.stable_addr)?,
-    )? - distributed_interest;
+    let balance: Uint256 = query_stable_balance(deps.as_ref(), &config)? - distributed_interest;
 
     let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
         deps.as_ref(),
@@ -415,13 +827,19 @@ pub fn execute_epoch_operations(
         state.total_reserves,
     )?;
 
+    compute_term_interest(
+        deps.storage,
+        &mut state,
+        env.block.height,
+        config.reserve_factor,
+    )?;
     compute_interest_raw(
         &mut state,
         env.block.height,
         balance,
         aterra_supply,
         borrow_rate_res.rate,
-        target_deposit_rate,
+        config.reserve_factor,
     );
 
     // recompute prev_exchange_rate with distributed_interest
@@ -430,33 +848,74 @@ pub fn execute_epoch_operations(
 
     compute_reward(&mut state, env.block.height);
 
-    // Compute total_reserves to fund collector contract
-    // Update total_reserves and send it to collector contract
+    // Reserves are kept in the market up to reserve_target,
+    // send the excess to collector contract
     // only when there is enough balance
     let total_reserves = state.total_reserves * Uint256::one();
-    let messages: Vec<CosmosMsg> = if !total_reserves.is_zero() && balance > total_reserves {
-        state.total_reserves = state.total_reserves - Decimal256::from_uint256(total_reserves);
-
-        vec![CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: deps.api.human_address(&config.stable_addr)?,
-            msg: to_binary(&Cw20HandleMsg::Transfer {
-                recipient: deps.api.human_address(&config.collector_contract)?,
-                amount: deduct_tax(deps.as_ref(), total_reserves.into())?,
-            })?,
-            send: vec![],
-        })]
+    let excess_reserves = if total_reserves > config.reserve_target {
+        total_reserves - config.reserve_target
+    } else {
+        Uint256::zero()
+    };
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let distributed_reserves = if !excess_reserves.is_zero() && balance > excess_reserves {
+        state.total_reserves = state.total_reserves - Decimal256::from_uint256(excess_reserves);
+        store_reserve_history(
+            deps.storage,
+            &ReserveHistory {
+                action: "distribute".to_string(),
+                amount: excess_reserves,
+                recipient: config.collector_contract.clone(),
+                block_height: env.block.height,
+                total_reserves: state.total_reserves * Uint256::one(),
+            },
+        )?;
+
+        messages.push(stable_transfer_msg(
+            deps.as_ref(),
+            &config,
+            deps.api.human_address(&config.collector_contract)?,
+            excess_reserves,
+        )?);
+        excess_reserves
     } else {
-        vec![]
+        Uint256::zero()
     };
 
+    // The distributed interest fills queued redemptions first,
+    // distributing the excess reserves leaves the exchange rate unchanged
+    let exchange_rate = state.prev_exchange_rate;
+    messages.extend(fill_redeem_queue(
+        &mut deps,
+        &config,
+        &mut state,
+        exchange_rate,
+        balance + distributed_interest - distributed_reserves,
+    )?);
+
     store_state(deps.storage, &state)?;
+    store_historical_state(
+        deps.storage,
+        env.block.height,
+        &HistoricalState {
+            exchange_rate: state.prev_exchange_rate,
+            total_liabilities: state.total_liabilities,
+            total_reserves: state.total_reserves,
+            borrow_rate: borrow_rate_res.rate,
+            deposit_rate,
+            orchai_epb_rate: state.orchai_epb_rate,
+        },
+    )?;
+
     let res = HandleResponse {
         attributes: vec![
             attr("action", "execute_epoch_operations"),
-            attr("total_reserves", total_reserves),
+            attr("total_reserves", state.total_reserves * Uint256::one()),
+            attr("distributed_reserves", distributed_reserves),
             attr("orchai_epb_rate", state.orchai_epb_rate.to_string()),
         ],
-        messages: messages,
+        messages,
         data: None,
     };
     Ok(res)
@@ -480,13 +939,60 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             block_height,
         } => to_binary(&query_borrower_info(deps, env, borrower, block_height)?),
         QueryMsg::BorrowerInfos { start_after, limit } => {
-            to_binary(&query_borrower_infos(deps, start_after, limit)?)
+            to_binary(&query_borrower_infos(deps, env, start_after, limit)?)
+        }
+        QueryMsg::TermLoans {
+            borrower,
+            block_height,
+        } => to_binary(&query_term_loans(deps, env, borrower, block_height)?),
+        QueryMsg::DelegationAllowance { borrower, delegate } => {
+            to_binary(&query_delegation_allowance(deps, borrower, delegate)?)
+        }
+        QueryMsg::Caps {
+            borrower,
+            block_height,
+        } => to_binary(&query_caps(deps, env, borrower, block_height)?),
+        QueryMsg::ReserveHistory { start_after, limit } => {
+            to_binary(&query_reserve_history(deps, start_after, limit)?)
+        }
+        QueryMsg::BadDebtWriteOffs { start_after, limit } => {
+            to_binary(&query_bad_debt_write_offs(deps, start_after, limit)?)
+        }
+        QueryMsg::RedeemRequest { request_id } => {
+            to_binary(&query_redeem_request(deps, request_id)?)
+        }
+        QueryMsg::RedeemQueue { start_after, limit } => {
+            to_binary(&query_redeem_queue(deps, start_after, limit)?)
+        }
+        QueryMsg::SimulateDeposit { amount } => to_binary(&query_simulation(
+            deps,
+            env,
+            SimulatedAction::Deposit { amount },
+        )?),
+        QueryMsg::SimulateRedeem { aterra_amount } => to_binary(&query_simulation(
+            deps,
+            env,
+            SimulatedAction::Redeem { aterra_amount },
+        )?),
+        QueryMsg::SimulateBorrow { borrower, amount } => to_binary(&query_simulation(
+            deps,
+            env,
+            SimulatedAction::Borrow { borrower, amount },
+        )?),
+        QueryMsg::SimulateRepay { borrower, amount } => to_binary(&query_simulation(
+            deps,
+            env,
+            SimulatedAction::Repay { borrower, amount },
+        )?),
+        QueryMsg::HistoricalStates { start_after, limit } => {
+            to_binary(&query_historical_states(deps, start_after, limit)?)
         }
     }
 }
 
 pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let config: Config = read_config(deps.storage)?;
+    let pause_info: PauseInfo = read_pause_info(deps.storage)?;
     Ok(ConfigResponse {
         owner_addr: deps.api.human_address(&config.owner_addr)?.to_string(),
         aterra_contract: deps.api.human_address(&config.aterra_contract)?.to_string(),
@@ -507,9 +1013,27 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
             .api
             .human_address(&config.distributor_contract)?
             .to_string(),
-        stable_addr: deps.api.human_address(&config.stable_addr)?.to_string(),
+        stable_info: config.stable_info.to_normal(deps.api)?,
         orchai_token: deps.api.human_address(&config.orchai_token)?.to_string(),
         max_borrow_factor: config.max_borrow_factor,
+        deposit_cap: config.deposit_cap,
+        borrow_cap: config.borrow_cap,
+        reserve_factor: config.reserve_factor,
+        reserve_target: config.reserve_target,
+        redeem_rate_lock: config.redeem_rate_lock,
+        origination_fee_rate: config.origination_fee_rate,
+        origination_fee_mode: config.origination_fee_mode,
+        origination_fee_recipient: config.origination_fee_recipient,
+        stable_rate_premium: config.stable_rate_premium,
+        rebalance_utilization: config.rebalance_utilization,
+        rebalance_min_duration: config.rebalance_min_duration,
+        term_grace_period: config.term_grace_period,
+        guardian: if let Some(guardian) = pause_info.guardian {
+            Some(deps.api.human_address(&guardian)?.to_string())
+        } else {
+            None
+        },
+        pause_flags: pause_info.flags,
     })
 }
 
@@ -544,6 +1068,10 @@ pub fn query_state(deps: Deps, env: Env, block_height: Option<u64>) -> StdResult
 
     Ok(StateResponse {
         total_liabilities: state.total_liabilities,
+        total_stable_liabilities: state.total_stable_liabilities,
+        avg_stable_rate: state.avg_stable_rate,
+        total_term_liabilities: state.total_term_liabilities,
+        term_interest_rate: state.term_interest_rate,
         total_reserves: state.total_reserves,
         last_interest_updated: state.last_interest_updated,
         last_reward_updated: state.last_reward_updated,
@@ -552,6 +1080,7 @@ pub fn query_state(deps: Deps, env: Env, block_height: Option<u64>) -> StdResult
         orchai_epb_rate: state.orchai_epb_rate,
         prev_aterra_supply: state.prev_aterra_supply,
         prev_exchange_rate: state.prev_exchange_rate,
+        high_utilization_since: state.high_utilization_since,
     })
 }
 
@@ -565,11 +1094,7 @@ pub fn query_epoch_state(
 
     let distributed_interest = distributed_interest.unwrap_or_else(Uint256::zero);
     let aterra_supply = query_supply(deps, deps.api.human_address(&config.aterra_contract)?)?;
-    let balance = query_balance(
-        deps,
-        deps.api.human_address(&config.contract_addr)?,
-        deps.api.human_address(&config.stable_addr)?,
-    )? - distributed_interest;
+    let balance = query_stable_balance(deps, &config)? - distributed_interest;
 
     if let Some(block_height) = block_height {
         if block_height < state.last_interest_updated {
@@ -586,17 +1111,20 @@ pub fn query_epoch_state(
             state.total_reserves,
         )?;
 
-        let target_deposit_rate: Decimal256 =
-            query_target_deposit_rate(deps, deps.api.human_address(&config.overseer_contract)?)?;
-
         // Compute interest rate to return latest epoch state
+        compute_term_interest(
+            deps.storage,
+            &mut state,
+            block_height,
+            config.reserve_factor,
+        )?;
         compute_interest_raw(
             &mut state,
             block_height,
             balance,
             aterra_supply,
             borrow_rate_res.rate,
-            target_deposit_rate,
+            config.reserve_factor,
         );
     }
 
@@ -611,12 +1139,92 @@ pub fn query_epoch_state(
     })
 }
 
+pub fn query_caps(
+    deps: Deps,
+    env: Env,
+    borrower: Option<HumanAddr>,
+    block_height: Option<u64>,
+) -> StdResult<CapsResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+
+    let block_height = block_height.unwrap_or(env.block.height);
+    compute_interest(deps, &config, &mut state, block_height, None)?;
+
+    let balance = query_stable_balance(deps, &config)?;
+    let total_deposits = compute_total_deposits(&state, balance);
+    let total_liabilities = state.total_liabilities * Uint256::one();
+
+    let (borrower_loan_amount, borrower_cap) = if let Some(borrower) = borrower {
+        let borrower_raw = deps.api.canonical_address(&borrower)?;
+        let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw)?;
+        compute_borrower_interest(&state, &mut liability);
+        let (term_loan_amount, _) = read_term_loan_amounts(
+            deps.storage,
+            &borrower_raw,
+            config.term_grace_period,
+            block_height,
+        )?;
+
+        (
+            Some(liability.loan_amount + term_loan_amount),
+            read_borrower_cap(deps.storage, &borrower_raw),
+        )
+    } else {
+        (None, None)
+    };
+
+    Ok(CapsResponse {
+        total_deposits,
+        deposit_cap: config.deposit_cap,
+        deposit_headroom: config
+            .deposit_cap
+            .map(|cap| compute_headroom(cap, total_deposits)),
+        total_liabilities,
+        borrow_cap: config.borrow_cap,
+        borrow_headroom: config
+            .borrow_cap
+            .map(|cap| compute_headroom(cap, total_liabilities)),
+        borrower_loan_amount,
+        borrower_cap,
+        borrower_headroom: borrower_cap
+            .zip(borrower_loan_amount)
+            .map(|(cap, loan_amount)| compute_headroom(cap, loan_amount)),
+    })
+}
+
+pub fn query_reserve_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ReserveHistoryResponse> {
+    let history = read_reserve_history(deps, start_after, limit)?;
+    Ok(ReserveHistoryResponse { history })
+}
+
+pub fn query_historical_states(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<HistoricalStatesResponse> {
+    let states = read_historical_states(deps.storage, start_after, limit)?;
+    Ok(HistoricalStatesResponse { states })
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(
-    _deps: DepsMut,
+    deps: DepsMut,
     _env: Env,
     _info: MessageInfo,
-    _msg: MigrateMsg,
+    msg: MigrateMsg,
 ) -> StdResult<MigrateResponse> {
+    migrate_config(
+        deps.storage,
+        msg.reserve_factor,
+        msg.reserve_target,
+        msg.redeem_rate_lock,
+    )?;
+    migrate_state(deps.storage)?;
+
     Ok(MigrateResponse::default())
 }
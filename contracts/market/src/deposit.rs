@@ -1,18 +1,28 @@
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    attr, to_binary, BankMsg, Coin, CosmosMsg, Deps, DepsMut, Env, HandleResponse, HumanAddr,
-    MessageInfo, StdResult, Uint128, WasmMsg,
+    attr, to_binary, Binary, CosmosMsg, Deps, DepsMut, Env, HandleResponse, HumanAddr, MessageInfo,
+    StdError, StdResult, Storage, Uint128, WasmMsg,
 };
 
-use crate::borrow::{compute_interest, compute_reward};
+use crate::borrow::{compute_headroom, compute_interest, compute_reward};
 use crate::error::ContractError;
-use crate::state::{read_config, read_state, store_state, Config, State};
-use moneymarket::querier::{deduct_tax, query_balance, query_supply};
+use crate::querier::query_stable_balance;
+use crate::state::{
+    pop_redeem_request, push_redeem_request, read_config, read_redeem_queue,
+    read_redeem_queue_front, read_redeem_request, read_redeem_request_position, read_state,
+    remove_redeem_request, store_redeem_request, store_state, Config, RedeemRequest, State,
+};
+use moneymarket::market::{RedeemQueueResponse, RedeemRateLock, RedeemRequestResponse};
+use moneymarket::querier::{asset_transfer_msg, query_supply};
+use oraiswap::asset::AssetInfo;
 
 use cw20::Cw20HandleMsg;
 
+/// Maximum number of queued redemptions filled in one operation
+const MAX_REDEEM_FILLS: usize = 30;
+
 pub fn deposit_stable(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     sender: HumanAddr,
     amount: Uint128,
@@ -38,12 +48,36 @@ pub fn deposit_stable(
     )?;
     compute_reward(&mut state, env.block.height);
 
+    // Assert deposit cap
+    let balance = query_stable_balance(deps.as_ref(), &config)?;
+    if let Some(deposit_cap) = config.deposit_cap {
+        assert_deposit_cap(&state, balance, deposit_amount, deposit_cap)?;
+    }
+
     // Load anchor token exchange rate with updated state
     let exchange_rate =
         compute_exchange_rate(deps.as_ref(), &config, &state, Some(deposit_amount))?;
     let mint_amount = deposit_amount / exchange_rate;
 
     state.prev_aterra_supply += mint_amount;
+
+    // The new deposit fills queued redemptions first
+    let mut messages = vec![CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: deps.api.human_address(&config.aterra_contract)?,
+        send: vec![],
+        msg: to_binary(&Cw20HandleMsg::Mint {
+            recipient: sender.clone(),
+            amount: mint_amount.into(),
+        })?,
+    })];
+    messages.extend(fill_redeem_queue(
+        &mut deps,
+        &config,
+        &mut state,
+        exchange_rate,
+        balance,
+    )?);
+
     store_state(deps.storage, &state)?;
     let res = HandleResponse {
         attributes: vec![
@@ -52,14 +86,7 @@ pub fn deposit_stable(
             attr("mint_amount", mint_amount),
             attr("deposit_amount", deposit_amount),
         ],
-        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: deps.api.human_address(&config.aterra_contract)?,
-            send: vec![],
-            msg: to_binary(&Cw20HandleMsg::Mint {
-                recipient: sender,
-                amount: mint_amount.into(),
-            })?,
-        })],
+        messages,
         data: None,
     };
     Ok(res)
@@ -70,6 +97,7 @@ pub fn redeem_stable(
     env: Env,
     sender: HumanAddr,
     burn_amount: Uint128,
+    queue: bool,
 ) -> Result<HandleResponse, ContractError> {
     let config: Config = read_config(deps.storage)?;
 
@@ -82,15 +110,24 @@ pub fn redeem_stable(
     let exchange_rate = compute_exchange_rate(deps.as_ref(), &config, &state, None)?;
     let redeem_amount = Uint256::from(burn_amount) * exchange_rate;
 
-    let query_target = env.contract.address;
-    let current_balance = query_balance(
-        deps.as_ref(),
-        query_target,
-        deps.api.human_address(&config.stable_addr)?,
-    )?;
+    let current_balance = query_stable_balance(deps.as_ref(), &config)?;
 
-    // Assert redeem amount
-    assert_redeem_amount(&config, &state, current_balance, redeem_amount)?;
+    if assert_redeem_available(
+        deps.storage,
+        &config,
+        &state,
+        current_balance,
+        redeem_amount,
+    )
+    .is_err()
+    {
+        if !queue {
+            return Err(ContractError::NoStableAvailable {});
+        }
+
+        store_state(deps.storage, &state)?;
+        return queue_redeem_stable(deps, env, &config, sender, burn_amount, exchange_rate);
+    }
 
     state.prev_aterra_supply = state.prev_aterra_supply - Uint256::from(burn_amount);
     store_state(deps.storage, &state)?;
@@ -108,21 +145,170 @@ pub fn redeem_stable(
                     amount: burn_amount,
                 })?,
             }),
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: deps.api.human_address(&config.stable_addr)?,
-                msg: to_binary(&Cw20HandleMsg::Transfer {
-                    recipient: sender,
-                    amount: redeem_amount.into(),
-                })?,
-                send: vec![],
-            }),
+            stable_transfer_msg(deps.as_ref(), &config, sender, redeem_amount)?,
         ],
         data: None,
     };
     Ok(res)
 }
 
-fn assert_redeem_amount(
+/// Keep the sent aToken in the market and queue the redemption
+fn queue_redeem_stable(
+    deps: DepsMut,
+    env: Env,
+    config: &Config,
+    sender: HumanAddr,
+    burn_amount: Uint128,
+    exchange_rate: Decimal256,
+) -> Result<HandleResponse, ContractError> {
+    let locked_rate = match config.redeem_rate_lock {
+        RedeemRateLock::Request => Some(exchange_rate),
+        RedeemRateLock::Fill => None,
+    };
+
+    let request_id = push_redeem_request(
+        deps.storage,
+        &RedeemRequest {
+            redeemer: deps.api.canonical_address(&sender)?,
+            aterra_amount: Uint256::from(burn_amount),
+            locked_rate,
+            block_height: env.block.height,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        attributes: vec![
+            attr("action", "queue_redeem_stable"),
+            attr("redeemer", sender),
+            attr("request_id", request_id),
+            attr("aterra_amount", burn_amount),
+        ],
+        data: None,
+    })
+}
+
+pub fn cancel_redeem_request(
+    deps: DepsMut,
+    info: MessageInfo,
+    request_id: u64,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let request: RedeemRequest = read_redeem_request(deps.storage, request_id)?;
+    if deps.api.canonical_address(&info.sender)? != request.redeemer {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    remove_redeem_request(deps.storage, request_id)?;
+
+    Ok(HandleResponse {
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&config.aterra_contract)?,
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: info.sender.clone(),
+                amount: request.aterra_amount.into(),
+            })?,
+        })],
+        attributes: vec![
+            attr("action", "cancel_redeem_request"),
+            attr("redeemer", info.sender),
+            attr("request_id", request_id),
+            attr("aterra_amount", request.aterra_amount),
+        ],
+        data: None,
+    })
+}
+
+/// Fill queued redemptions first-in first-out with the balance
+/// available above reserves, the last filled request can be partial.
+/// exchange_rate must be computed with the same balance and aToken supply
+pub(crate) fn fill_redeem_queue(
+    deps: &mut DepsMut,
+    config: &Config,
+    state: &mut State,
+    exchange_rate: Decimal256,
+    current_balance: Uint256,
+) -> StdResult<Vec<CosmosMsg>> {
+    let current_balance = Decimal256::from_uint256(current_balance);
+    let mut available = if current_balance > state.total_reserves {
+        (current_balance - state.total_reserves) * Uint256::one()
+    } else {
+        Uint256::zero()
+    };
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut total_burn_amount = Uint256::zero();
+    for (request_id, mut request) in read_redeem_queue_front(deps.storage, MAX_REDEEM_FILLS)? {
+        if available.is_zero() {
+            break;
+        }
+
+        let rate = request.locked_rate.unwrap_or(exchange_rate);
+        let (burn_amount, redeem_amount) = if request.aterra_amount * rate <= available {
+            (request.aterra_amount, request.aterra_amount * rate)
+        } else {
+            let burn_amount = available / rate;
+            (burn_amount, burn_amount * rate)
+        };
+
+        if burn_amount.is_zero() {
+            break;
+        }
+
+        if burn_amount == request.aterra_amount {
+            pop_redeem_request(deps.storage, request_id)?;
+        } else {
+            request.aterra_amount = request.aterra_amount - burn_amount;
+            store_redeem_request(deps.storage, request_id, &request)?;
+        }
+
+        available = available - redeem_amount;
+        total_burn_amount += burn_amount;
+        messages.push(stable_transfer_msg(
+            deps.as_ref(),
+            config,
+            deps.api.human_address(&request.redeemer)?,
+            redeem_amount,
+        )?);
+    }
+
+    if !total_burn_amount.is_zero() {
+        state.prev_aterra_supply = state.prev_aterra_supply - total_burn_amount;
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&config.aterra_contract)?,
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Burn {
+                amount: total_burn_amount.into(),
+            })?,
+        }));
+    }
+
+    Ok(messages)
+}
+
+pub fn query_redeem_request(deps: Deps, request_id: u64) -> StdResult<RedeemRequestResponse> {
+    let request: RedeemRequest = read_redeem_request(deps.storage, request_id)?;
+    Ok(RedeemRequestResponse {
+        request_id,
+        redeemer: deps.api.human_address(&request.redeemer)?.to_string(),
+        aterra_amount: request.aterra_amount,
+        locked_rate: request.locked_rate,
+        block_height: request.block_height,
+        position: read_redeem_request_position(deps.storage, request_id),
+    })
+}
+
+pub fn query_redeem_queue(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<RedeemQueueResponse> {
+    let requests = read_redeem_queue(deps, start_after, limit)?;
+    Ok(RedeemQueueResponse { requests })
+}
+
+pub(crate) fn assert_redeem_amount(
     config: &Config,
     state: &State,
     current_balance: Uint256,
@@ -137,6 +323,47 @@ fn assert_redeem_amount(
     Ok(HandleResponse::default())
 }
 
+/// Redemptions wait behind queued requests to keep the queue
+/// first-in first-out, then need enough liquidity above the reserves
+pub(crate) fn assert_redeem_available(
+    storage: &dyn Storage,
+    config: &Config,
+    state: &State,
+    current_balance: Uint256,
+    redeem_amount: Uint256,
+) -> Result<(), ContractError> {
+    if !read_redeem_queue_front(storage, 1)?.is_empty() {
+        return Err(ContractError::NoStableAvailable {});
+    }
+
+    assert_redeem_amount(config, state, current_balance, redeem_amount)?;
+    Ok(())
+}
+
+pub(crate) fn assert_deposit_cap(
+    state: &State,
+    current_balance: Uint256,
+    deposit_amount: Uint256,
+    deposit_cap: Uint256,
+) -> Result<(), ContractError> {
+    // current_balance already contains the deposit amount
+    let total_deposits = compute_total_deposits(state, current_balance);
+    if total_deposits > deposit_cap {
+        return Err(ContractError::DepositCapExceeded(
+            compute_headroom(deposit_cap, total_deposits - deposit_amount).into(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Total stable owed to aterra holders
+/// total_deposits = balance + total_liabilities - total_reserves
+pub(crate) fn compute_total_deposits(state: &State, contract_balance: Uint256) -> Uint256 {
+    (Decimal256::from_uint256(contract_balance) + state.total_liabilities - state.total_reserves)
+        * Uint256::one()
+}
+
 pub(crate) fn compute_exchange_rate(
     deps: Deps,
     config: &Config,
@@ -144,13 +371,53 @@ pub(crate) fn compute_exchange_rate(
     deposit_amount: Option<Uint256>,
 ) -> StdResult<Decimal256> {
     let aterra_supply = query_supply(deps, deps.api.human_address(&config.aterra_contract)?)?;
-    let balance = query_balance(
+    let balance =
+        query_stable_balance(deps, config)? - deposit_amount.unwrap_or_else(Uint256::zero);
+
+    Ok(compute_exchange_rate_raw(state, aterra_supply, balance))
+}
+
+/// Build the message sending `amount` of the stable asset
+/// from the market to `recipient`, taxed if the stable is native
+pub(crate) fn stable_transfer_msg(
+    deps: Deps,
+    config: &Config,
+    recipient: HumanAddr,
+    amount: Uint256,
+) -> StdResult<CosmosMsg> {
+    asset_transfer_msg(
         deps,
+        &config.stable_info.to_normal(deps.api)?,
         deps.api.human_address(&config.contract_addr)?,
-        deps.api.human_address(&config.stable_addr)?,
-    )? - deposit_amount.unwrap_or_else(Uint256::zero);
+        recipient,
+        amount,
+    )
+}
 
-    Ok(compute_exchange_rate_raw(state, aterra_supply, balance))
+/// Build the cw20 Send of `amount` of the stable token to `contract`
+/// along with `msg`. Native stables cannot be sent this way, the market
+/// would execute `msg` itself
+pub(crate) fn stable_send_msg(
+    deps: Deps,
+    config: &Config,
+    contract: HumanAddr,
+    amount: Uint256,
+    msg: Binary,
+) -> StdResult<CosmosMsg> {
+    match config.stable_info.to_normal(deps.api)? {
+        AssetInfo::Token { contract_addr } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Send {
+                contract,
+                amount: amount.into(),
+                msg: Some(msg),
+            })?,
+        })),
+        AssetInfo::NativeToken { .. } => Err(StdError::generic_err(
+            "native stables cannot be sent with a hook message",
+        )),
+    }
 }
 
 pub fn compute_exchange_rate_raw(
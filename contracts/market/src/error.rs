@@ -12,12 +12,51 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Borrower still has collaterals; only positions without collaterals can be written off")]
+    BadDebtHasCollaterals {},
+
     #[error("Borrow amount too high; Loan liability becomes greater than borrow limit: {0}")]
     BorrowExceedsLimit(u128),
 
+    #[error("Borrow amount too high; Market total liabilities exceed borrow cap, remaining: {0}")]
+    BorrowCapExceeded(u128),
+
+    #[error("Borrow amount too high; Loan liability exceeds a collateral debt ceiling")]
+    DebtCeilingExceeded {},
+
+    #[error("Borrow amount too high; Loan liability exceeds borrower cap, remaining: {0}")]
+    BorrowerCapExceeded(u128),
+
+    #[error("Deposit amount too high; Market total deposits exceed deposit cap, remaining: {0}")]
+    DepositCapExceeded(u128),
+
+    #[error("Borrow amount exceeds delegated allowance: {0}")]
+    DelegationExceeded(u128),
+
+    #[error("No delegation from the borrower to the sender")]
+    DelegationNotFound {},
+
+    #[error("Delegation expired")]
+    DelegationExpired {},
+
     #[error("Must deposit initial funds {0}{0}")]
     InitialFundsNotDeposited(u128, String),
 
+    #[error("Withdraw amount exceeds total reserves: {0}")]
+    InsufficientReserves(u128),
+
+    #[error("Reserve factor must be less than or equal to 1")]
+    InvalidReserveFactor {},
+
+    #[error("Origination fee rate must be less than or equal to 1")]
+    InvalidOriginationFeeRate {},
+
+    #[error("Rebalance utilization must be less than or equal to 1")]
+    InvalidRebalanceUtilization {},
+
+    #[error("Loan term must be greater than 0")]
+    InvalidTerm {},
+
     #[error("Invalid reply ID")]
     InvalidReplyId {},
 
@@ -27,12 +66,42 @@ pub enum ContractError {
     #[error("Invalid request: \"redeem stable\" message not included in request")]
     MissingRedeemStableHook {},
 
+    #[error("Loan has a different rate mode; swap the rate mode first")]
+    RateModeMismatch {},
+
+    #[error("Stable rate rebalance conditions are not met")]
+    RebalanceNotAllowed {},
+
+    #[error("Operation paused: {0}")]
+    OperationPaused(String),
+
+    #[error("Borrow and call is only supported for cw20 stables")]
+    NativeStableCallNotSupported {},
+
     #[error("Not enough stable available; borrow demand too high")]
     NoStableAvailable {},
 
+    #[error("Stable asset mismatch; the market stable is {0}")]
+    StableAssetMismatch(String),
+
+    #[error("Borrower already has the maximum number of term loans: {0}")]
+    TermLoanLimitReached(u64),
+
+    #[error("Term loan not found: {0}")]
+    TermLoanNotFound(u64),
+
+    #[error("Borrower has no debt to write off")]
+    ZeroBadDebt {},
+
+    #[error("Borrow amount must be greater than 0")]
+    ZeroBorrow {},
+
     #[error("Deposit amount must be greater than 0")]
     ZeroDeposit{},
 
     #[error("Repay amount must be greater than 0")]
     ZeroRepay {},
+
+    #[error("Withdraw reserves amount must be greater than 0")]
+    ZeroWithdrawReserves {},
 }
@@ -2,9 +2,12 @@ pub mod borrow;
 pub mod contract;
 pub mod deposit;
 pub mod error;
+pub mod migration;
 pub mod querier;
 pub mod response;
+pub mod simulation;
 pub mod state;
+pub mod term_loan;
 
 #[cfg(test)]
 mod testing;
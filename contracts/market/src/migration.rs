@@ -0,0 +1,128 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::contract::{DEFAULT_REBALANCE_MIN_DURATION, DEFAULT_TERM_GRACE_PERIOD};
+use crate::state::{store_config, store_state, Config, State, KEY_CONFIG, KEY_STATE};
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{CanonicalAddr, StdResult, Storage};
+use cosmwasm_storage::ReadonlySingleton;
+use moneymarket::market::{FeeRecipient, OriginationFeeMode, RedeemRateLock};
+use oraiswap::asset::AssetInfoRaw;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyConfig {
+    pub contract_addr: CanonicalAddr,
+    pub owner_addr: CanonicalAddr,
+    pub aterra_contract: CanonicalAddr,
+    pub interest_model: CanonicalAddr,
+    pub distribution_model: CanonicalAddr,
+    pub overseer_contract: CanonicalAddr,
+    pub collector_contract: CanonicalAddr,
+    pub distributor_contract: CanonicalAddr,
+    pub stable_addr: CanonicalAddr,
+    pub orchai_token: CanonicalAddr,
+    pub max_borrow_factor: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyState {
+    pub total_liabilities: Decimal256,
+    pub total_reserves: Decimal256,
+    pub last_interest_updated: u64,
+    pub last_reward_updated: u64,
+    pub global_interest_index: Decimal256,
+    pub global_reward_index: Decimal256,
+    pub orchai_epb_rate: Decimal256,
+    pub prev_aterra_supply: Uint256,
+    pub prev_exchange_rate: Decimal256,
+}
+
+fn read_legacy_config(storage: &dyn Storage) -> StdResult<LegacyConfig> {
+    ReadonlySingleton::new(storage, KEY_CONFIG).load()
+}
+
+fn read_legacy_state(storage: &dyn Storage) -> StdResult<LegacyState> {
+    ReadonlySingleton::new(storage, KEY_STATE).load()
+}
+
+/// Rewrites a config stored before cw20 stables became an AssetInfo,
+/// a config that already decodes is left untouched
+pub fn migrate_config(
+    storage: &mut dyn Storage,
+    reserve_factor: Decimal256,
+    reserve_target: Uint256,
+    redeem_rate_lock: RedeemRateLock,
+) -> StdResult<()> {
+    if ReadonlySingleton::<Config>::new(storage, KEY_CONFIG)
+        .load()
+        .is_ok()
+    {
+        return Ok(());
+    }
+
+    let legacy_config: LegacyConfig = read_legacy_config(storage)?;
+
+    store_config(
+        storage,
+        &Config {
+            contract_addr: legacy_config.contract_addr,
+            owner_addr: legacy_config.owner_addr,
+            aterra_contract: legacy_config.aterra_contract,
+            interest_model: legacy_config.interest_model,
+            distribution_model: legacy_config.distribution_model,
+            overseer_contract: legacy_config.overseer_contract,
+            collector_contract: legacy_config.collector_contract,
+            distributor_contract: legacy_config.distributor_contract,
+            stable_info: AssetInfoRaw::Token {
+                contract_addr: legacy_config.stable_addr,
+            },
+            orchai_token: legacy_config.orchai_token,
+            max_borrow_factor: legacy_config.max_borrow_factor,
+            deposit_cap: None,
+            borrow_cap: None,
+            reserve_factor,
+            reserve_target,
+            redeem_rate_lock,
+            origination_fee_rate: Decimal256::zero(),
+            origination_fee_mode: OriginationFeeMode::AddToLoan,
+            origination_fee_recipient: FeeRecipient::Reserves,
+            stable_rate_premium: Decimal256::zero(),
+            rebalance_utilization: Decimal256::percent(95),
+            rebalance_min_duration: DEFAULT_REBALANCE_MIN_DURATION,
+            term_grace_period: DEFAULT_TERM_GRACE_PERIOD,
+        },
+    )
+}
+
+/// Rewrites a state stored before stable rate and term borrowing,
+/// a state that already decodes is left untouched
+pub fn migrate_state(storage: &mut dyn Storage) -> StdResult<()> {
+    if ReadonlySingleton::<State>::new(storage, KEY_STATE)
+        .load()
+        .is_ok()
+    {
+        return Ok(());
+    }
+
+    let legacy_state: LegacyState = read_legacy_state(storage)?;
+
+    store_state(
+        storage,
+        &State {
+            total_liabilities: legacy_state.total_liabilities,
+            total_stable_liabilities: Decimal256::zero(),
+            avg_stable_rate: Decimal256::zero(),
+            total_term_liabilities: Decimal256::zero(),
+            term_interest_rate: Decimal256::zero(),
+            total_reserves: legacy_state.total_reserves,
+            last_interest_updated: legacy_state.last_interest_updated,
+            last_reward_updated: legacy_state.last_reward_updated,
+            global_interest_index: legacy_state.global_interest_index,
+            global_reward_index: legacy_state.global_reward_index,
+            orchai_epb_rate: legacy_state.orchai_epb_rate,
+            prev_aterra_supply: legacy_state.prev_aterra_supply,
+            prev_exchange_rate: legacy_state.prev_exchange_rate,
+            high_utilization_since: None,
+        },
+    )
+}
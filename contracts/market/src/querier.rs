@@ -3,7 +3,21 @@ use cosmwasm_std::{to_binary, HumanAddr, Deps, QueryRequest, StdResult, WasmQuer
 
 use moneymarket::distribution_model::{AncEmissionRateResponse, QueryMsg as DistributionQueryMsg};
 use moneymarket::interest_model::{BorrowRateResponse, QueryMsg as InterestQueryMsg};
-use moneymarket::overseer::{BorrowLimitResponse, ConfigResponse, QueryMsg as OverseerQueryMsg};
+use moneymarket::overseer::{
+    BorrowLimitResponse, CollateralsResponse, DebtCeilingsResponse, QueryMsg as OverseerQueryMsg,
+};
+use moneymarket::querier::query_asset_balance;
+
+use crate::state::Config;
+
+/// Query the market's balance of its stable asset
+pub fn query_stable_balance(deps: Deps, config: &Config) -> StdResult<Uint256> {
+    query_asset_balance(
+        deps,
+        deps.api.human_address(&config.contract_addr)?,
+        &config.stable_info.to_normal(deps.api)?,
+    )
+}
 
 pub fn query_borrow_rate(
     deps: Deps,
@@ -43,6 +57,38 @@ pub fn query_borrow_limit(
     Ok(borrow_limit)
 }
 
+pub fn query_debt_ceilings(
+    deps: Deps,
+    overseer_addr: HumanAddr,
+    borrower: HumanAddr,
+    loan_amount: Uint256,
+) -> StdResult<DebtCeilingsResponse> {
+    let debt_ceilings: DebtCeilingsResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: overseer_addr,
+            msg: to_binary(&OverseerQueryMsg::DebtCeilings {
+                borrower,
+                loan_amount,
+            })?,
+        }))?;
+
+    Ok(debt_ceilings)
+}
+
+pub fn query_collaterals(
+    deps: Deps,
+    overseer_addr: HumanAddr,
+    borrower: HumanAddr,
+) -> StdResult<CollateralsResponse> {
+    let collaterals: CollateralsResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: overseer_addr,
+            msg: to_binary(&OverseerQueryMsg::Collaterals { borrower })?,
+        }))?;
+
+    Ok(collaterals)
+}
+
 pub fn query_orchai_epb_rate(
     deps: Deps,
     distribution_model: HumanAddr,
@@ -64,13 +110,3 @@ pub fn query_orchai_epb_rate(
 
     Ok(orchai_epb_rate)
 }
-
-pub fn query_target_deposit_rate(deps: Deps, overseer_contract: HumanAddr) -> StdResult<Decimal256> {
-    let overseer_config: ConfigResponse =
-        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-            contract_addr: overseer_contract,
-            msg: to_binary(&OverseerQueryMsg::Config {})?,
-        }))?;
-
-    Ok(overseer_config.target_deposit_rate)
-}
@@ -0,0 +1,194 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{Deps, Env, HumanAddr, StdResult};
+use moneymarket::market::{FeeRecipient, SimulationResponse};
+use moneymarket::querier::query_supply;
+use std::cmp::min;
+
+use crate::borrow::{
+    assert_borrow_limits, compute_borrower_interest, compute_interest_raw, compute_origination_fee,
+    compute_stable_rate, compute_utilization_ratio, decrease_loan, increase_loan,
+};
+use crate::deposit::{assert_deposit_cap, assert_redeem_available, compute_exchange_rate_raw};
+use crate::querier::{query_borrow_rate, query_stable_balance};
+use crate::state::{
+    read_borrower_info, read_config, read_state, read_term_loan_amounts, BorrowerInfo, Config,
+    State,
+};
+use crate::term_loan::compute_term_interest;
+use moneymarket::market::RateMode;
+
+pub enum SimulatedAction {
+    Deposit {
+        amount: Uint256,
+    },
+    Redeem {
+        aterra_amount: Uint256,
+    },
+    Borrow {
+        borrower: HumanAddr,
+        amount: Uint256,
+    },
+    Repay {
+        borrower: HumanAddr,
+        amount: Uint256,
+    },
+}
+
+/// Apply the action on an in-memory copy of the market state,
+/// nothing is stored
+pub fn query_simulation(
+    deps: Deps,
+    env: Env,
+    action: SimulatedAction,
+) -> StdResult<SimulationResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+
+    let interest_model = deps.api.human_address(&config.interest_model)?;
+    let mut aterra_supply = query_supply(deps, deps.api.human_address(&config.aterra_contract)?)?;
+    let mut balance = query_stable_balance(deps, &config)?;
+
+    // Accrue interest up to the current block
+    let borrow_rate_res = query_borrow_rate(
+        deps,
+        interest_model.clone(),
+        balance,
+        state.total_liabilities,
+        state.total_reserves,
+    )?;
+    compute_term_interest(
+        deps.storage,
+        &mut state,
+        env.block.height,
+        config.reserve_factor,
+    )?;
+    compute_interest_raw(
+        &mut state,
+        env.block.height,
+        balance,
+        aterra_supply,
+        borrow_rate_res.rate,
+        config.reserve_factor,
+    );
+    let exchange_rate = compute_exchange_rate_raw(&state, aterra_supply, balance);
+
+    let mut aterra_amount = Uint256::zero();
+    let mut loan_amount: Option<Uint256> = None;
+    let mut origination_fee = Uint256::zero();
+    let stable_amount: Uint256;
+    let within_limits: bool;
+    match action {
+        SimulatedAction::Deposit { amount } => {
+            aterra_amount = amount / exchange_rate;
+            stable_amount = amount;
+
+            balance += amount;
+            aterra_supply += aterra_amount;
+            within_limits = match config.deposit_cap {
+                Some(deposit_cap) => {
+                    assert_deposit_cap(&state, balance, amount, deposit_cap).is_ok()
+                }
+                None => true,
+            };
+        }
+        SimulatedAction::Redeem {
+            aterra_amount: redeem_aterra_amount,
+        } => {
+            aterra_amount = redeem_aterra_amount;
+            stable_amount = aterra_amount * exchange_rate;
+            within_limits = aterra_amount <= aterra_supply
+                && assert_redeem_available(deps.storage, &config, &state, balance, stable_amount)
+                    .is_ok();
+
+            // a redemption that cannot be paid now waits in the redeem queue
+            if within_limits {
+                balance = balance - stable_amount;
+                aterra_supply = aterra_supply - aterra_amount;
+            }
+        }
+        SimulatedAction::Borrow { borrower, amount } => {
+            let borrower_raw = deps.api.canonical_address(&borrower)?;
+            let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw)?;
+            compute_borrower_interest(&state, &mut liability);
+
+            let (fee, loan_increase, disbursed_amount) = compute_origination_fee(&config, amount);
+            let (term_loan_amount, _) = read_term_loan_amounts(
+                deps.storage,
+                &borrower_raw,
+                config.term_grace_period,
+                env.block.height,
+            )?;
+            within_limits = assert_borrow_limits(
+                deps,
+                &env,
+                &config,
+                &state,
+                &borrower,
+                liability.loan_amount + term_loan_amount,
+                loan_increase,
+                balance,
+            )
+            .is_ok();
+
+            let stable_rate = if liability.rate_mode == RateMode::Stable {
+                compute_stable_rate(deps, &config, &state, balance)?
+            } else {
+                Decimal256::zero()
+            };
+            increase_loan(&mut state, &mut liability, loan_increase, stable_rate);
+
+            stable_amount = amount;
+            origination_fee = fee;
+            loan_amount = Some(liability.loan_amount + term_loan_amount);
+
+            // the fee either stays in the market as reserves
+            // or leaves it with the disbursed amount
+            let outflow = match config.origination_fee_recipient {
+                FeeRecipient::Reserves => {
+                    state.total_reserves += Decimal256::from_uint256(fee);
+                    disbursed_amount
+                }
+                FeeRecipient::Collector => disbursed_amount + fee,
+            };
+            balance = balance - min(outflow, balance);
+        }
+        SimulatedAction::Repay { borrower, amount } => {
+            let borrower_raw = deps.api.canonical_address(&borrower)?;
+            let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw)?;
+            compute_borrower_interest(&state, &mut liability);
+
+            // the amount above the loan is refunded,
+            // term loans are repaid through RepayTermLoan
+            stable_amount = decrease_loan(&mut state, &mut liability, amount);
+            within_limits = !stable_amount.is_zero();
+
+            let (term_loan_amount, _) = read_term_loan_amounts(
+                deps.storage,
+                &borrower_raw,
+                config.term_grace_period,
+                env.block.height,
+            )?;
+            loan_amount = Some(liability.loan_amount + term_loan_amount);
+            balance += stable_amount;
+        }
+    }
+
+    let borrow_rate_res = query_borrow_rate(
+        deps,
+        interest_model,
+        balance,
+        state.total_liabilities,
+        state.total_reserves,
+    )?;
+
+    Ok(SimulationResponse {
+        exchange_rate: compute_exchange_rate_raw(&state, aterra_supply, balance),
+        aterra_amount,
+        stable_amount,
+        utilization_ratio: compute_utilization_ratio(&state, balance),
+        borrow_rate: borrow_rate_res.rate,
+        loan_amount,
+        origination_fee,
+        within_limits,
+    })
+}
@@ -3,14 +3,34 @@ use serde::{Deserialize, Serialize};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{CanonicalAddr, Deps, Order, StdResult, Storage};
-use cosmwasm_storage::{bucket, bucket_read, ReadonlyBucket, ReadonlySingleton, Singleton};
+use cosmwasm_storage::{bucket, bucket_read, Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
+use cw20::Expiration;
 
-use moneymarket::market::BorrowerInfoResponse;
+use moneymarket::market::{
+    BadDebtWriteOffElem, BorrowerInfoResponse, FeeRecipient, HistoricalStateElem,
+    OriginationFeeMode, PauseFlags, RateMode, RedeemRateLock, RedeemRequestElem,
+    ReserveHistoryElem,
+};
+use oraiswap::asset::AssetInfoRaw;
 
 pub const KEY_CONFIG: &[u8] = b"config";
 pub const KEY_STATE: &[u8] = b"state";
+const KEY_RESERVE_HISTORY_IDX: &[u8] = b"reserve_history_idx";
+const KEY_PAUSE_INFO: &[u8] = b"pause_info";
+const KEY_BAD_DEBT_IDX: &[u8] = b"bad_debt_idx";
+const KEY_REDEEM_REQUEST_IDX: &[u8] = b"redeem_request_idx";
+const KEY_REDEEM_QUEUE_HEAD: &[u8] = b"redeem_queue_head";
+const KEY_TERM_LOAN_IDX: &[u8] = b"term_loan_idx";
 
 const PREFIX_LIABILITY: &[u8] = b"liability";
+const PREFIX_BORROWER_CAP: &[u8] = b"borrower_cap";
+const PREFIX_RESERVE_HISTORY: &[u8] = b"reserve_history";
+const PREFIX_HISTORICAL_STATE: &[u8] = b"historical_state";
+const PREFIX_DELEGATION: &[u8] = b"delegation";
+const PREFIX_BAD_DEBT: &[u8] = b"bad_debt";
+const PREFIX_REDEEM_QUEUE: &[u8] = b"redeem_queue";
+const PREFIX_TERM_LOAN: &[u8] = b"term_loan";
+const PREFIX_TERM_MATURITY: &[u8] = b"term_maturity";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -22,14 +42,30 @@ pub struct Config {
     pub overseer_contract: CanonicalAddr,
     pub collector_contract: CanonicalAddr,
     pub distributor_contract: CanonicalAddr,
-    pub stable_addr: CanonicalAddr,
+    pub stable_info: AssetInfoRaw,
     pub orchai_token: CanonicalAddr,
     pub max_borrow_factor: Decimal256,
+    pub deposit_cap: Option<Uint256>,
+    pub borrow_cap: Option<Uint256>,
+    pub reserve_factor: Decimal256,
+    pub reserve_target: Uint256,
+    pub redeem_rate_lock: RedeemRateLock,
+    pub origination_fee_rate: Decimal256,
+    pub origination_fee_mode: OriginationFeeMode,
+    pub origination_fee_recipient: FeeRecipient,
+    pub stable_rate_premium: Decimal256,
+    pub rebalance_utilization: Decimal256,
+    pub rebalance_min_duration: u64,
+    pub term_grace_period: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
     pub total_liabilities: Decimal256,
+    pub total_stable_liabilities: Decimal256,
+    pub avg_stable_rate: Decimal256,
+    pub total_term_liabilities: Decimal256,
+    pub term_interest_rate: Decimal256,
     pub total_reserves: Decimal256,
     pub last_interest_updated: u64,
     pub last_reward_updated: u64,
@@ -38,6 +74,7 @@ pub struct State {
     pub orchai_epb_rate: Decimal256,
     pub prev_aterra_supply: Uint256,
     pub prev_exchange_rate: Decimal256,
+    pub high_utilization_since: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -46,6 +83,89 @@ pub struct BorrowerInfo {
     pub reward_index: Decimal256,
     pub loan_amount: Uint256,
     pub pending_rewards: Decimal256,
+    // records stored before stable rate borrowing are variable rate loans
+    #[serde(default)]
+    pub rate_mode: RateMode,
+    #[serde(default)]
+    pub stable_rate: Decimal256,
+    #[serde(default)]
+    pub stable_updated: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TermLoan {
+    pub loan_id: u64,
+    pub principal: Uint256,
+    pub interest: Uint256,
+    pub rate: Decimal256,
+    pub start_height: u64,
+    pub maturity: u64,
+}
+
+impl TermLoan {
+    pub fn loan_amount(&self) -> Uint256 {
+        self.principal + self.interest
+    }
+
+    pub fn is_expired(&self, grace_period: u64, block_height: u64) -> bool {
+        block_height > self.maturity + grace_period
+    }
+
+    // term loans of a borrower are ordered by maturity
+    fn key(&self) -> Vec<u8> {
+        let mut key = self.maturity.to_be_bytes().to_vec();
+        key.extend_from_slice(&self.loan_id.to_be_bytes());
+        key
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReserveHistory {
+    pub action: String,
+    pub amount: Uint256,
+    pub recipient: CanonicalAddr,
+    pub block_height: u64,
+    pub total_reserves: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BadDebtWriteOff {
+    pub borrower: CanonicalAddr,
+    pub amount: Uint256,
+    pub covered_by_reserves: Uint256,
+    pub covered_by_buffer: Uint256,
+    pub socialized: Uint256,
+    pub block_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RedeemRequest {
+    pub redeemer: CanonicalAddr,
+    pub aterra_amount: Uint256,
+    pub locked_rate: Option<Decimal256>,
+    pub block_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Delegation {
+    pub allowance: Uint256,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HistoricalState {
+    pub exchange_rate: Decimal256,
+    pub total_liabilities: Decimal256,
+    pub total_reserves: Decimal256,
+    pub borrow_rate: Decimal256,
+    pub deposit_rate: Decimal256,
+    pub orchai_epb_rate: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct PauseInfo {
+    pub guardian: Option<CanonicalAddr>,
+    pub flags: PauseFlags,
 }
 
 pub fn store_config(storage: &mut dyn Storage, data: &Config) -> StdResult<()> {
@@ -64,6 +184,16 @@ pub fn read_state(storage: &dyn Storage) -> StdResult<State> {
     ReadonlySingleton::new(storage, KEY_STATE).load()
 }
 
+pub fn store_pause_info(storage: &mut dyn Storage, data: &PauseInfo) -> StdResult<()> {
+    Singleton::new(storage, KEY_PAUSE_INFO).save(data)
+}
+
+pub fn read_pause_info(storage: &dyn Storage) -> StdResult<PauseInfo> {
+    Ok(ReadonlySingleton::new(storage, KEY_PAUSE_INFO)
+        .may_load()?
+        .unwrap_or_default())
+}
+
 pub fn store_borrower_info(
     storage: &mut dyn Storage,
     borrower: &CanonicalAddr,
@@ -72,18 +202,281 @@ pub fn store_borrower_info(
     bucket(storage, PREFIX_LIABILITY).save(borrower.as_slice(), liability)
 }
 
-pub fn read_borrower_info(storage: &dyn Storage, borrower: &CanonicalAddr) -> BorrowerInfo {
-    match bucket_read(storage, PREFIX_LIABILITY).load(borrower.as_slice()) {
-        Ok(v) => v,
-        _ => BorrowerInfo {
+pub fn read_borrower_info(
+    storage: &dyn Storage,
+    borrower: &CanonicalAddr,
+) -> StdResult<BorrowerInfo> {
+    match bucket_read(storage, PREFIX_LIABILITY).may_load(borrower.as_slice())? {
+        Some(v) => Ok(v),
+        None => Ok(BorrowerInfo {
             interest_index: Decimal256::one(),
             reward_index: Decimal256::zero(),
             loan_amount: Uint256::zero(),
             pending_rewards: Decimal256::zero(),
-        },
+            rate_mode: RateMode::Variable,
+            stable_rate: Decimal256::zero(),
+            stable_updated: 0u64,
+        }),
     }
 }
 
+pub fn store_borrower_cap(
+    storage: &mut dyn Storage,
+    borrower: &CanonicalAddr,
+    borrow_cap: &Uint256,
+) -> StdResult<()> {
+    bucket(storage, PREFIX_BORROWER_CAP).save(borrower.as_slice(), borrow_cap)
+}
+
+pub fn remove_borrower_cap(storage: &mut dyn Storage, borrower: &CanonicalAddr) {
+    bucket::<Uint256>(storage, PREFIX_BORROWER_CAP).remove(borrower.as_slice())
+}
+
+pub fn read_borrower_cap(storage: &dyn Storage, borrower: &CanonicalAddr) -> Option<Uint256> {
+    bucket_read(storage, PREFIX_BORROWER_CAP)
+        .may_load(borrower.as_slice())
+        .unwrap_or(None)
+}
+
+pub fn store_delegation(
+    storage: &mut dyn Storage,
+    borrower: &CanonicalAddr,
+    delegate: &CanonicalAddr,
+    delegation: &Delegation,
+) -> StdResult<()> {
+    let mut delegation_bucket: Bucket<Delegation> =
+        Bucket::multilevel(storage, &[PREFIX_DELEGATION, borrower.as_slice()]);
+    delegation_bucket.save(delegate.as_slice(), delegation)
+}
+
+pub fn remove_delegation(
+    storage: &mut dyn Storage,
+    borrower: &CanonicalAddr,
+    delegate: &CanonicalAddr,
+) {
+    let mut delegation_bucket: Bucket<Delegation> =
+        Bucket::multilevel(storage, &[PREFIX_DELEGATION, borrower.as_slice()]);
+    delegation_bucket.remove(delegate.as_slice())
+}
+
+pub fn read_delegation(
+    storage: &dyn Storage,
+    borrower: &CanonicalAddr,
+    delegate: &CanonicalAddr,
+) -> Option<Delegation> {
+    let delegation_bucket: ReadonlyBucket<Delegation> =
+        ReadonlyBucket::multilevel(storage, &[PREFIX_DELEGATION, borrower.as_slice()]);
+    delegation_bucket
+        .may_load(delegate.as_slice())
+        .unwrap_or(None)
+}
+
+pub fn next_term_loan_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let mut idx_store = Singleton::new(storage, KEY_TERM_LOAN_IDX);
+    let idx: u64 = idx_store.load().unwrap_or(0u64) + 1;
+    idx_store.save(&idx)?;
+
+    Ok(idx)
+}
+
+pub fn store_term_loan(
+    storage: &mut dyn Storage,
+    borrower: &CanonicalAddr,
+    term_loan: &TermLoan,
+) -> StdResult<()> {
+    let mut term_loan_bucket: Bucket<TermLoan> =
+        Bucket::multilevel(storage, &[PREFIX_TERM_LOAN, borrower.as_slice()]);
+    term_loan_bucket.save(&term_loan.key(), term_loan)
+}
+
+pub fn remove_term_loan(storage: &mut dyn Storage, borrower: &CanonicalAddr, term_loan: &TermLoan) {
+    let mut term_loan_bucket: Bucket<TermLoan> =
+        Bucket::multilevel(storage, &[PREFIX_TERM_LOAN, borrower.as_slice()]);
+    term_loan_bucket.remove(&term_loan.key())
+}
+
+/// Term loans of a borrower ordered by maturity,
+/// bounded by MAX_TERM_LOANS so they are all loaded
+pub fn read_term_loans(
+    storage: &dyn Storage,
+    borrower: &CanonicalAddr,
+) -> StdResult<Vec<TermLoan>> {
+    let term_loan_bucket: ReadonlyBucket<TermLoan> =
+        ReadonlyBucket::multilevel(storage, &[PREFIX_TERM_LOAN, borrower.as_slice()]);
+    term_loan_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (_, v) = elem?;
+            Ok(v)
+        })
+        .collect()
+}
+
+/// Returns (term_loan_amount, expired_term_loan_amount) of a borrower
+pub fn read_term_loan_amounts(
+    storage: &dyn Storage,
+    borrower: &CanonicalAddr,
+    grace_period: u64,
+    block_height: u64,
+) -> StdResult<(Uint256, Uint256)> {
+    let mut term_loan_amount = Uint256::zero();
+    let mut expired_term_loan_amount = Uint256::zero();
+    for term_loan in read_term_loans(storage, borrower)? {
+        term_loan_amount += term_loan.loan_amount();
+        if term_loan.is_expired(grace_period, block_height) {
+            expired_term_loan_amount += term_loan.loan_amount();
+        }
+    }
+
+    Ok((term_loan_amount, expired_term_loan_amount))
+}
+
+/// Annual interest of the term loans maturing at maturity
+pub fn read_term_maturity(storage: &dyn Storage, maturity: u64) -> Decimal256 {
+    let maturity_bucket: ReadonlyBucket<Decimal256> = bucket_read(storage, PREFIX_TERM_MATURITY);
+    maturity_bucket
+        .load(&maturity.to_be_bytes())
+        .unwrap_or_else(|_| Decimal256::zero())
+}
+
+pub fn store_term_maturity(
+    storage: &mut dyn Storage,
+    maturity: u64,
+    annual_interest: Decimal256,
+) -> StdResult<()> {
+    let mut maturity_bucket: Bucket<Decimal256> = bucket(storage, PREFIX_TERM_MATURITY);
+    if annual_interest.is_zero() {
+        maturity_bucket.remove(&maturity.to_be_bytes());
+        return Ok(());
+    }
+
+    maturity_bucket.save(&maturity.to_be_bytes(), &annual_interest)
+}
+
+/// Term loan maturities in (start_after, end] with the
+/// annual interest that stops accruing at each of them
+pub fn read_term_maturities(
+    storage: &dyn Storage,
+    start_after: u64,
+    end: u64,
+) -> StdResult<Vec<(u64, Decimal256)>> {
+    let maturity_bucket: ReadonlyBucket<Decimal256> = bucket_read(storage, PREFIX_TERM_MATURITY);
+    let start = (start_after + 1).to_be_bytes();
+    let end = (end + 1).to_be_bytes();
+
+    maturity_bucket
+        .range(Some(&start), Some(&end), Order::Ascending)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut maturity = [0u8; 8];
+            maturity.copy_from_slice(&k);
+            Ok((u64::from_be_bytes(maturity), v))
+        })
+        .collect()
+}
+
+pub fn store_reserve_history(storage: &mut dyn Storage, history: &ReserveHistory) -> StdResult<()> {
+    let mut idx_store = Singleton::new(storage, KEY_RESERVE_HISTORY_IDX);
+    let idx: u64 = idx_store.load().unwrap_or(0u64) + 1;
+    idx_store.save(&idx)?;
+
+    bucket(storage, PREFIX_RESERVE_HISTORY).save(&idx.to_be_bytes(), history)
+}
+
+pub fn store_bad_debt_write_off(
+    storage: &mut dyn Storage,
+    write_off: &BadDebtWriteOff,
+) -> StdResult<()> {
+    let mut idx_store = Singleton::new(storage, KEY_BAD_DEBT_IDX);
+    let idx: u64 = idx_store.load().unwrap_or(0u64) + 1;
+    idx_store.save(&idx)?;
+
+    bucket(storage, PREFIX_BAD_DEBT).save(&idx.to_be_bytes(), write_off)
+}
+
+/// Append a request at the end of the redeem queue,
+/// returns the request id
+pub fn push_redeem_request(storage: &mut dyn Storage, request: &RedeemRequest) -> StdResult<u64> {
+    let mut idx_store = Singleton::new(storage, KEY_REDEEM_REQUEST_IDX);
+    let idx: u64 = idx_store.load().unwrap_or(0u64) + 1;
+    idx_store.save(&idx)?;
+
+    store_redeem_request(storage, idx, request)?;
+    Ok(idx)
+}
+
+pub fn store_redeem_request(
+    storage: &mut dyn Storage,
+    request_id: u64,
+    request: &RedeemRequest,
+) -> StdResult<()> {
+    bucket(storage, PREFIX_REDEEM_QUEUE).save(&request_id.to_be_bytes(), request)
+}
+
+/// Remove the request at the front of the redeem queue. Requests only
+/// leave from the front, so the queue head is the id of the last
+/// request removed from it
+pub fn pop_redeem_request(storage: &mut dyn Storage, request_id: u64) -> StdResult<()> {
+    bucket::<RedeemRequest>(storage, PREFIX_REDEEM_QUEUE).remove(&request_id.to_be_bytes());
+    Singleton::new(storage, KEY_REDEEM_QUEUE_HEAD).save(&request_id)
+}
+
+/// Remove a cancelled request wherever it is in the redeem queue,
+/// the queue head only moves if it was at the front
+pub fn remove_redeem_request(storage: &mut dyn Storage, request_id: u64) -> StdResult<()> {
+    let front_id = read_redeem_queue_front(storage, 1)?
+        .first()
+        .map(|(front_id, _)| *front_id);
+    if front_id == Some(request_id) {
+        return pop_redeem_request(storage, request_id);
+    }
+
+    bucket::<RedeemRequest>(storage, PREFIX_REDEEM_QUEUE).remove(&request_id.to_be_bytes());
+    Ok(())
+}
+
+pub fn read_redeem_queue_head(storage: &dyn Storage) -> u64 {
+    ReadonlySingleton::new(storage, KEY_REDEEM_QUEUE_HEAD)
+        .load()
+        .unwrap_or(0u64)
+}
+
+pub fn read_redeem_request(storage: &dyn Storage, request_id: u64) -> StdResult<RedeemRequest> {
+    bucket_read(storage, PREFIX_REDEEM_QUEUE).load(&request_id.to_be_bytes())
+}
+
+/// Oldest requests of the redeem queue
+pub fn read_redeem_queue_front(
+    storage: &dyn Storage,
+    limit: usize,
+) -> StdResult<Vec<(u64, RedeemRequest)>> {
+    let queue_bucket: ReadonlyBucket<RedeemRequest> = bucket_read(storage, PREFIX_REDEEM_QUEUE);
+    queue_bucket
+        .range(None, None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut id = [0u8; 8];
+            id.copy_from_slice(&k);
+            Ok((u64::from_be_bytes(id), v))
+        })
+        .collect()
+}
+
+/// Number of queue slots ahead of request_id,
+/// including the slots of cancelled requests behind the front
+pub fn read_redeem_request_position(storage: &dyn Storage, request_id: u64) -> u64 {
+    request_id - read_redeem_queue_head(storage) - 1
+}
+
+pub fn store_historical_state(
+    storage: &mut dyn Storage,
+    block_height: u64,
+    historical_state: &HistoricalState,
+) -> StdResult<()> {
+    bucket(storage, PREFIX_HISTORICAL_STATE).save(&block_height.to_be_bytes(), historical_state)
+}
+
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
@@ -91,7 +484,9 @@ pub fn read_borrower_infos(
     deps: Deps,
     start_after: Option<CanonicalAddr>,
     limit: Option<u32>,
+    block_height: u64,
 ) -> StdResult<Vec<BorrowerInfoResponse>> {
+    let config: Config = read_config(deps.storage)?;
     let liability_bucket: ReadonlyBucket<BorrowerInfo> =
         bucket_read(deps.storage, PREFIX_LIABILITY);
 
@@ -103,13 +498,24 @@ pub fn read_borrower_infos(
         .take(limit)
         .map(|elem| {
             let (k, v) = elem?;
-            let borrower = deps.api.human_address(&CanonicalAddr::from(k))?.to_string();
+            let borrower_raw = CanonicalAddr::from(k);
+            let (term_loan_amount, expired_term_loan_amount) = read_term_loan_amounts(
+                deps.storage,
+                &borrower_raw,
+                config.term_grace_period,
+                block_height,
+            )?;
+            let borrower = deps.api.human_address(&borrower_raw)?.to_string();
             Ok(BorrowerInfoResponse {
                 borrower,
                 interest_index: v.interest_index,
                 reward_index: v.reward_index,
                 loan_amount: v.loan_amount,
                 pending_rewards: v.pending_rewards,
+                rate_mode: v.rate_mode,
+                stable_rate: v.stable_rate,
+                term_loan_amount,
+                expired_term_loan_amount,
             })
         })
         .collect()
@@ -123,3 +529,124 @@ fn calc_range_start(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
         v
     })
 }
+
+pub fn read_reserve_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<ReserveHistoryElem>> {
+    let history_bucket: ReadonlyBucket<ReserveHistory> =
+        bucket_read(deps.storage, PREFIX_RESERVE_HISTORY);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|idx| (idx + 1).to_be_bytes().to_vec());
+
+    history_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut id = [0u8; 8];
+            id.copy_from_slice(&k);
+            Ok(ReserveHistoryElem {
+                id: u64::from_be_bytes(id),
+                action: v.action,
+                amount: v.amount,
+                recipient: deps.api.human_address(&v.recipient)?.to_string(),
+                block_height: v.block_height,
+                total_reserves: v.total_reserves,
+            })
+        })
+        .collect()
+}
+
+pub fn read_historical_states(
+    storage: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<HistoricalStateElem>> {
+    let state_bucket: ReadonlyBucket<HistoricalState> =
+        bucket_read(storage, PREFIX_HISTORICAL_STATE);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|height| (height + 1).to_be_bytes().to_vec());
+
+    state_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut block_height = [0u8; 8];
+            block_height.copy_from_slice(&k);
+            Ok(HistoricalStateElem {
+                block_height: u64::from_be_bytes(block_height),
+                exchange_rate: v.exchange_rate,
+                total_liabilities: v.total_liabilities,
+                total_reserves: v.total_reserves,
+                borrow_rate: v.borrow_rate,
+                deposit_rate: v.deposit_rate,
+                orchai_epb_rate: v.orchai_epb_rate,
+            })
+        })
+        .collect()
+}
+
+pub fn read_bad_debt_write_offs(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<BadDebtWriteOffElem>> {
+    let write_off_bucket: ReadonlyBucket<BadDebtWriteOff> =
+        bucket_read(deps.storage, PREFIX_BAD_DEBT);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|idx| (idx + 1).to_be_bytes().to_vec());
+
+    write_off_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut id = [0u8; 8];
+            id.copy_from_slice(&k);
+            Ok(BadDebtWriteOffElem {
+                id: u64::from_be_bytes(id),
+                borrower: deps.api.human_address(&v.borrower)?.to_string(),
+                amount: v.amount,
+                covered_by_reserves: v.covered_by_reserves,
+                covered_by_buffer: v.covered_by_buffer,
+                socialized: v.socialized,
+                block_height: v.block_height,
+            })
+        })
+        .collect()
+}
+
+pub fn read_redeem_queue(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<RedeemRequestElem>> {
+    let queue_bucket: ReadonlyBucket<RedeemRequest> =
+        bucket_read(deps.storage, PREFIX_REDEEM_QUEUE);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|idx| (idx + 1).to_be_bytes().to_vec());
+
+    queue_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut id = [0u8; 8];
+            id.copy_from_slice(&k);
+            Ok(RedeemRequestElem {
+                request_id: u64::from_be_bytes(id),
+                redeemer: deps.api.human_address(&v.redeemer)?.to_string(),
+                aterra_amount: v.aterra_amount,
+                locked_rate: v.locked_rate,
+                block_height: v.block_height,
+            })
+        })
+        .collect()
+}
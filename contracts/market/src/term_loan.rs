@@ -0,0 +1,332 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, CanonicalAddr, CosmosMsg, Deps, DepsMut, Env, HandleResponse, HumanAddr, StdResult,
+    Storage,
+};
+use moneymarket::market::{TermLoanElem, TermLoansResponse};
+
+use crate::borrow::{compute_interest, compute_reward, update_borrower_debt_msg, BLOCKS_PER_YEAR};
+use crate::deposit::{compute_exchange_rate, fill_redeem_queue, stable_transfer_msg};
+use crate::error::ContractError;
+use crate::querier::query_stable_balance;
+use crate::state::{
+    next_term_loan_id, read_config, read_state, read_term_loans, read_term_maturities,
+    read_term_maturity, remove_term_loan, store_state, store_term_loan, store_term_maturity,
+    Config, State, TermLoan,
+};
+
+/// Maximum number of open term loans per borrower
+pub const MAX_TERM_LOANS: usize = 10;
+
+/// Open a term loan of principal at rate, the total interest
+/// is fixed for the term and accrues to depositors as simple
+/// interest in the term liabilities until maturity
+pub(crate) fn open_term_loan(
+    storage: &mut dyn Storage,
+    state: &mut State,
+    borrower: &CanonicalAddr,
+    principal: Uint256,
+    rate: Decimal256,
+    block_height: u64,
+    term: u64,
+) -> Result<TermLoan, ContractError> {
+    if term == 0 {
+        return Err(ContractError::InvalidTerm {});
+    }
+
+    if read_term_loans(storage, borrower)?.len() >= MAX_TERM_LOANS {
+        return Err(ContractError::TermLoanLimitReached(MAX_TERM_LOANS as u64));
+    }
+
+    let term_loan = TermLoan {
+        loan_id: next_term_loan_id(storage)?,
+        principal,
+        interest: principal * (rate * Decimal256::from_ratio(term, BLOCKS_PER_YEAR)),
+        rate,
+        start_height: block_height,
+        maturity: block_height + term,
+    };
+    store_term_loan(storage, borrower, &term_loan)?;
+
+    let annual_interest = Decimal256::from_uint256(principal) * rate;
+    let maturity_interest = read_term_maturity(storage, term_loan.maturity);
+    store_term_maturity(
+        storage,
+        term_loan.maturity,
+        maturity_interest + annual_interest,
+    )?;
+
+    state.term_interest_rate += annual_interest;
+    state.total_term_liabilities += Decimal256::from_uint256(principal);
+    state.total_liabilities += Decimal256::from_uint256(principal);
+
+    Ok(term_loan)
+}
+
+/// Accrue the simple interest of the term loans from the last interest
+/// update to block_height, a loan stops accruing at its maturity.
+/// Must run before compute_interest_raw moves last_interest_updated.
+pub(crate) fn compute_term_interest(
+    storage: &dyn Storage,
+    state: &mut State,
+    block_height: u64,
+    reserve_factor: Decimal256,
+) -> StdResult<()> {
+    if state.last_interest_updated >= block_height {
+        return Ok(());
+    }
+
+    let mut interest_accrued = state.term_interest_rate
+        * Decimal256::from_ratio(block_height - state.last_interest_updated, BLOCKS_PER_YEAR);
+    for (maturity, annual_interest) in
+        read_term_maturities(storage, state.last_interest_updated, block_height)?
+    {
+        let unaccrued_interest =
+            annual_interest * Decimal256::from_ratio(block_height - maturity, BLOCKS_PER_YEAR);
+        interest_accrued = interest_accrued - std::cmp::min(unaccrued_interest, interest_accrued);
+        state.term_interest_rate =
+            state.term_interest_rate - std::cmp::min(annual_interest, state.term_interest_rate);
+    }
+
+    state.total_term_liabilities += interest_accrued;
+    state.total_liabilities += interest_accrued;
+    state.total_reserves += interest_accrued * reserve_factor;
+
+    Ok(())
+}
+
+/// Remove principal of a term loan from the term liabilities along
+/// with the simple interest it accrued up to block_height or maturity,
+/// exactly what the principal added to them. Returns the removed value.
+fn remove_term_liabilities(
+    storage: &mut dyn Storage,
+    state: &mut State,
+    term_loan: &TermLoan,
+    principal: Uint256,
+    block_height: u64,
+) -> StdResult<Decimal256> {
+    let annual_interest = Decimal256::from_uint256(principal) * term_loan.rate;
+    let accrued_blocks = std::cmp::min(block_height, term_loan.maturity) - term_loan.start_height;
+    let accrued_value = Decimal256::from_uint256(principal)
+        + annual_interest * Decimal256::from_ratio(accrued_blocks, BLOCKS_PER_YEAR);
+
+    state.total_term_liabilities =
+        state.total_term_liabilities - std::cmp::min(accrued_value, state.total_term_liabilities);
+    state.total_liabilities =
+        state.total_liabilities - std::cmp::min(accrued_value, state.total_liabilities);
+
+    // a matured loan already stopped accruing
+    if term_loan.maturity > block_height {
+        let maturity_interest = read_term_maturity(storage, term_loan.maturity);
+        store_term_maturity(
+            storage,
+            term_loan.maturity,
+            maturity_interest - std::cmp::min(annual_interest, maturity_interest),
+        )?;
+        state.term_interest_rate =
+            state.term_interest_rate - std::cmp::min(annual_interest, state.term_interest_rate);
+    }
+
+    Ok(accrued_value)
+}
+
+/// Apply amount to a term loan, principal and interest are repaid
+/// pro rata. Returns the repaid amount.
+///
+/// The term liabilities are decreased by what the repaid principal
+/// accrued in them, so an early repayment credits the unearned
+/// interest to depositors.
+pub(crate) fn settle_term_loan(
+    storage: &mut dyn Storage,
+    state: &mut State,
+    term_loan: &mut TermLoan,
+    amount: Uint256,
+    block_height: u64,
+) -> StdResult<Uint256> {
+    let loan_amount = term_loan.loan_amount();
+    let (repay_amount, principal_repaid, interest_repaid) = if amount >= loan_amount {
+        (loan_amount, term_loan.principal, term_loan.interest)
+    } else {
+        let principal_repaid = term_loan.principal * Decimal256::from_uint256(amount)
+            / Decimal256::from_uint256(loan_amount);
+        let interest_repaid = std::cmp::min(amount - principal_repaid, term_loan.interest);
+        (amount, principal_repaid, interest_repaid)
+    };
+
+    remove_term_liabilities(storage, state, term_loan, principal_repaid, block_height)?;
+    term_loan.principal = term_loan.principal - principal_repaid;
+    term_loan.interest = term_loan.interest - interest_repaid;
+
+    Ok(repay_amount)
+}
+
+/// Repay the term loans of a borrower in maturity order,
+/// expired loans come first. Returns the repaid amount.
+pub(crate) fn repay_term_loans(
+    storage: &mut dyn Storage,
+    state: &mut State,
+    borrower: &CanonicalAddr,
+    amount: Uint256,
+    block_height: u64,
+) -> StdResult<Uint256> {
+    let mut repaid_amount = Uint256::zero();
+    for mut term_loan in read_term_loans(storage, borrower)? {
+        if repaid_amount == amount {
+            break;
+        }
+
+        repaid_amount += settle_term_loan(
+            storage,
+            state,
+            &mut term_loan,
+            amount - repaid_amount,
+            block_height,
+        )?;
+        if term_loan.loan_amount().is_zero() {
+            remove_term_loan(storage, borrower, &term_loan);
+        } else {
+            store_term_loan(storage, borrower, &term_loan)?;
+        }
+    }
+
+    Ok(repaid_amount)
+}
+
+/// Write off all the term loans of a borrower,
+/// returns the value removed from the term liabilities
+pub(crate) fn write_off_term_loans(
+    storage: &mut dyn Storage,
+    state: &mut State,
+    borrower: &CanonicalAddr,
+    block_height: u64,
+) -> StdResult<Uint256> {
+    let mut written_off = Decimal256::zero();
+    for term_loan in read_term_loans(storage, borrower)? {
+        written_off += remove_term_liabilities(
+            storage,
+            state,
+            &term_loan,
+            term_loan.principal,
+            block_height,
+        )?;
+        remove_term_loan(storage, borrower, &term_loan);
+    }
+
+    Ok(written_off * Uint256::one())
+}
+
+pub fn repay_term_loan(
+    mut deps: DepsMut,
+    env: Env,
+    borrower: HumanAddr,
+    loan_id: u64,
+    amount: Uint256,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+
+    // Cannot repay zero amount
+    if amount.is_zero() {
+        return Err(ContractError::ZeroRepay {});
+    }
+
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let mut term_loan = read_term_loans(deps.storage, &borrower_raw)?
+        .into_iter()
+        .find(|term_loan| term_loan.loan_id == loan_id)
+        .ok_or(ContractError::TermLoanNotFound(loan_id))?;
+
+    let mut state: State = read_state(deps.storage)?;
+    compute_interest(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        env.block.height,
+        Some(amount),
+    )?;
+    compute_reward(&mut state, env.block.height);
+
+    let repay_amount = settle_term_loan(
+        deps.storage,
+        &mut state,
+        &mut term_loan,
+        amount,
+        env.block.height,
+    )?;
+    if term_loan.loan_amount().is_zero() {
+        remove_term_loan(deps.storage, &borrower_raw, &term_loan);
+    } else {
+        store_term_loan(deps.storage, &borrower_raw, &term_loan)?;
+    }
+
+    // Payback left repay amount to sender
+    let refund_amount = amount - repay_amount;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !refund_amount.is_zero() {
+        messages.push(stable_transfer_msg(
+            deps.as_ref(),
+            &config,
+            borrower.clone(),
+            refund_amount,
+        )?);
+    }
+
+    // The repaid stable fills queued redemptions first,
+    // the refunded amount is still in the market balance
+    let exchange_rate = compute_exchange_rate(deps.as_ref(), &config, &state, Some(refund_amount))?;
+    let current_balance = query_stable_balance(deps.as_ref(), &config)? - refund_amount;
+    messages.extend(fill_redeem_queue(
+        &mut deps,
+        &config,
+        &mut state,
+        exchange_rate,
+        current_balance,
+    )?);
+    messages.push(update_borrower_debt_msg(
+        deps.as_ref(),
+        &config,
+        borrower.clone(),
+        false,
+    )?);
+
+    store_state(deps.storage, &state)?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "repay_term_loan"),
+            attr("borrower", borrower),
+            attr("loan_id", loan_id),
+            attr("repay_amount", repay_amount),
+        ],
+        messages,
+        data: None,
+    })
+}
+
+pub fn query_term_loans(
+    deps: Deps,
+    env: Env,
+    borrower: HumanAddr,
+    block_height: Option<u64>,
+) -> StdResult<TermLoansResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let block_height = block_height.unwrap_or(env.block.height);
+
+    let loans = read_term_loans(deps.storage, &deps.api.canonical_address(&borrower)?)?
+        .into_iter()
+        .map(|term_loan| TermLoanElem {
+            loan_id: term_loan.loan_id,
+            principal: term_loan.principal,
+            interest: term_loan.interest,
+            loan_amount: term_loan.loan_amount(),
+            rate: term_loan.rate,
+            start_height: term_loan.start_height,
+            maturity: term_loan.maturity,
+            expired: term_loan.is_expired(config.term_grace_period, block_height),
+        })
+        .collect();
+
+    Ok(TermLoansResponse {
+        borrower: borrower.to_string(),
+        loans,
+    })
+}
@@ -1,64 +1,150 @@
-use crate::borrow::{compute_borrower_interest, compute_interest};
+use crate::borrow::{
+    compute_borrower_interest, compute_interest, compute_origination_fee, BLOCKS_PER_YEAR,
+};
 use crate::state::{store_state, BorrowerInfo, Config, State};
 use crate::testing::mock_querier::mock_dependencies;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, MOCK_CONTRACT_ADDR};
-use cosmwasm_std::{Api, Coin, Uint128, HumanAddr};
+use cosmwasm_std::{Api, Coin, HumanAddr, Uint128};
+use moneymarket::market::{FeeRecipient, OriginationFeeMode, RateMode, RedeemRateLock};
+use oraiswap::asset::AssetInfoRaw;
+use std::str::FromStr;
 
-#[test]
-fn proper_compute_borrower_interest() {
-    let env = mock_env();
-    let mock_state = State {
-        total_liabilities: Decimal256::from_uint256(1000000u128),
-        total_reserves: Decimal256::from_uint256(0u128),
-        last_interest_updated: env.block.height,
-        last_reward_updated: env.block.height,
-        global_interest_index: Decimal256::one(),
+fn mock_config(api: &dyn Api) -> Config {
+    Config {
+        contract_addr: api
+            .canonical_address(&HumanAddr::from(MOCK_CONTRACT_ADDR))
+            .unwrap(),
+        owner_addr: api.canonical_address(&HumanAddr::from("owner")).unwrap(),
+        aterra_contract: api.canonical_address(&HumanAddr::from("at-uusd")).unwrap(),
+        interest_model: api.canonical_address(&HumanAddr::from("interest")).unwrap(),
+        distribution_model: api
+            .canonical_address(&HumanAddr::from("distribution"))
+            .unwrap(),
+        overseer_contract: api.canonical_address(&HumanAddr::from("overseer")).unwrap(),
+        collector_contract: api
+            .canonical_address(&HumanAddr::from("collector"))
+            .unwrap(),
+        distributor_contract: api
+            .canonical_address(&HumanAddr::from("distributor"))
+            .unwrap(),
+        stable_info: AssetInfoRaw::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        orchai_token: api.canonical_address(&HumanAddr::from("orchai")).unwrap(),
+        max_borrow_factor: Decimal256::one(),
+        deposit_cap: None,
+        borrow_cap: None,
+        reserve_factor: Decimal256::zero(),
+        reserve_target: Uint256::zero(),
+        redeem_rate_lock: RedeemRateLock::Request,
+        origination_fee_rate: Decimal256::zero(),
+        origination_fee_mode: OriginationFeeMode::AddToLoan,
+        origination_fee_recipient: FeeRecipient::Reserves,
+        stable_rate_premium: Decimal256::zero(),
+        rebalance_utilization: Decimal256::percent(95),
+        rebalance_min_duration: 0,
+        term_grace_period: 0,
+    }
+}
+
+fn default_state(
+    total_liabilities: Decimal256,
+    total_reserves: Decimal256,
+    global_interest_index: Decimal256,
+    block_height: u64,
+) -> State {
+    State {
+        total_liabilities,
+        total_stable_liabilities: Decimal256::zero(),
+        avg_stable_rate: Decimal256::zero(),
+        total_term_liabilities: Decimal256::zero(),
+        term_interest_rate: Decimal256::zero(),
+        total_reserves,
+        last_interest_updated: block_height,
+        last_reward_updated: block_height,
+        global_interest_index,
         global_reward_index: Decimal256::zero(),
         orchai_epb_rate: Decimal256::one(),
         prev_aterra_supply: Uint256::zero(),
         prev_exchange_rate: Decimal256::one(),
-    };
-    let mut liability1 = BorrowerInfo {
-        interest_index: Decimal256::one(),
+        high_utilization_since: None,
+    }
+}
+
+fn mock_borrower_info(
+    interest_index: Decimal256,
+    loan_amount: Uint256,
+    stable_updated: u64,
+) -> BorrowerInfo {
+    BorrowerInfo {
+        interest_index,
         reward_index: Decimal256::zero(),
-        loan_amount: Uint256::zero(),
+        loan_amount,
         pending_rewards: Decimal256::zero(),
-    };
+        rate_mode: RateMode::Variable,
+        stable_rate: Decimal256::zero(),
+        stable_updated,
+    }
+}
+
+#[test]
+fn proper_compute_borrower_interest() {
+    let env = mock_env();
+    let mock_state = default_state(
+        Decimal256::from_uint256(1000000u128),
+        Decimal256::zero(),
+        Decimal256::one(),
+        env.block.height,
+    );
+    let mut liability1 = mock_borrower_info(Decimal256::one(), Uint256::zero(), env.block.height);
     compute_borrower_interest(&mock_state, &mut liability1);
-    let liability2 = BorrowerInfo {
-        interest_index: Decimal256::one(),
-        reward_index: Decimal256::zero(),
-        loan_amount: Uint256::zero(),
-        pending_rewards: Decimal256::zero(),
-    };
+    let liability2 = mock_borrower_info(Decimal256::one(), Uint256::zero(), env.block.height);
     assert_eq!(liability1, liability2);
 
-    let mock_state2 = State {
-        total_liabilities: Decimal256::from_uint256(300000u128),
-        total_reserves: Decimal256::from_uint256(1000u128),
-        last_interest_updated: env.block.height,
-        last_reward_updated: env.block.height,
-        global_interest_index: Decimal256::from_uint256(2u128),
-        global_reward_index: Decimal256::zero(),
-        orchai_epb_rate: Decimal256::zero(),
-        prev_aterra_supply: Uint256::zero(),
-        prev_exchange_rate: Decimal256::one(),
-    };
-    let mut liability3 = BorrowerInfo {
-        interest_index: Decimal256::from_uint256(4u128),
-        reward_index: Decimal256::zero(),
-        loan_amount: Uint256::from(80u128),
-        pending_rewards: Decimal256::zero(),
-    };
+    let mock_state2 = default_state(
+        Decimal256::from_uint256(300000u128),
+        Decimal256::from_uint256(1000u128),
+        Decimal256::from_uint256(2u128),
+        env.block.height,
+    );
+    let mut liability3 =
+        mock_borrower_info(
+        Decimal256::from_uint256(4u128),
+        Uint256::from(80u128),
+        env.block.height,
+    );
     compute_borrower_interest(&mock_state2, &mut liability3);
-    let liability4 = BorrowerInfo {
-        interest_index: Decimal256::from_uint256(2u128),
-        reward_index: Decimal256::zero(),
-        loan_amount: Uint256::from(40u128),
-        pending_rewards: Decimal256::zero(),
-    };
+    let liability4 = mock_borrower_info(
+        Decimal256::from_uint256(2u128),
+        Uint256::from(40u128),
+        env.block.height,
+    );
     assert_eq!(liability3, liability4);
+
+    // stable loans accrue their own rate since the last update
+    let mut liability5 = BorrowerInfo {
+        rate_mode: RateMode::Stable,
+        stable_rate: Decimal256::percent(BLOCKS_PER_YEAR),
+        ..mock_borrower_info(
+            Decimal256::one(),
+            Uint256::from(1000u128),
+            env.block.height - 10,
+        )
+    };
+    compute_borrower_interest(&mock_state2, &mut liability5);
+    assert_eq!(
+        liability5,
+        BorrowerInfo {
+            rate_mode: RateMode::Stable,
+            stable_rate: Decimal256::percent(BLOCKS_PER_YEAR),
+            ..mock_borrower_info(
+                Decimal256::from_uint256(2u128),
+                Uint256::from(1099u128),
+                env.block.height,
+            )
+        }
+    );
 }
 
 #[test]
@@ -74,34 +160,19 @@ fn proper_compute_interest() {
     )]);
 
     let mut env = mock_env();
+    let mut mock_config = mock_config(&deps.api);
 
-    let mock_config = Config {
-        contract_addr: deps.api.canonical_address(&HumanAddr(MOCK_CONTRACT_ADDR.to_string())).unwrap(),
-        owner_addr: deps.api.canonical_address(&HumanAddr("owner".to_string())).unwrap(),
-        aterra_contract: deps.api.canonical_address(&HumanAddr("at-uusd".to_string())).unwrap(),
-        interest_model: deps.api.canonical_address(&HumanAddr("interest".to_string())).unwrap(),
-        distribution_model: deps.api.canonical_address(&HumanAddr("distribution".to_string())).unwrap(),
-        distributor_contract: deps.api.canonical_address(&HumanAddr("distributor".to_string())).unwrap(),
-        collector_contract: deps.api.canonical_address(&HumanAddr("collector".to_string())).unwrap(),
-        overseer_contract: deps.api.canonical_address(&HumanAddr("overseer".to_string())).unwrap(),
-        stable_denom: "uusd".to_string(),
-        max_borrow_factor: Decimal256::one(),
-    };
-
-    deps.querier
-        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier.with_borrow_rate(&[(
+        &"interest".to_string(),
+        &Decimal256::percent(BLOCKS_PER_YEAR),
+    )]);
 
-    let mut mock_state = State {
-        total_liabilities: Decimal256::from_uint256(1000000u128),
-        total_reserves: Decimal256::zero(),
-        last_interest_updated: env.block.height,
-        last_reward_updated: env.block.height,
-        global_interest_index: Decimal256::one(),
-        global_reward_index: Decimal256::zero(),
-        orchai_epb_rate: Decimal256::one(),
-        prev_aterra_supply: Uint256::zero(),
-        prev_exchange_rate: Decimal256::one(),
-    };
+    let mut mock_state = default_state(
+        Decimal256::from_uint256(1000000u128),
+        Decimal256::zero(),
+        Decimal256::one(),
+        env.block.height,
+    );
     store_state(&mut deps.storage, &mock_state).unwrap();
 
     let mock_deposit_amount = Some(Uint256::from(1000u128));
@@ -126,6 +197,7 @@ fn proper_compute_interest() {
             orchai_epb_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            ..mock_state.clone()
         }
     );
 
@@ -142,29 +214,30 @@ fn proper_compute_interest() {
     assert_eq!(
         mock_state,
         State {
-            total_liabilities: Decimal256::from_uint256(2000000u128),
+            total_liabilities: Decimal256::from_str("1999999.999999").unwrap(),
             total_reserves: Decimal256::zero(),
             last_interest_updated: env.block.height,
             last_reward_updated: env.block.height - 100,
-            global_interest_index: Decimal256::from_uint256(2u128),
+            global_interest_index: Decimal256::from_str("1.999999999999").unwrap(),
             global_reward_index: Decimal256::zero(),
             orchai_epb_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(2000000u64),
-            prev_exchange_rate: Decimal256::from_ratio(19995, 10000),
+            prev_exchange_rate: Decimal256::from_str("1.9994999999995").unwrap(),
+            high_utilization_since: None,
+            ..mock_state.clone()
         }
     );
 
-    // exceed target_deposit_rate = 0.01
+    // reserve_factor portion of the interest goes to the reserves
+    mock_config.reserve_factor = Decimal256::percent(50);
     let mut mock_state = State {
-        total_liabilities: Decimal256::zero(),
-        total_reserves: Decimal256::zero(),
-        last_interest_updated: env.block.height,
-        last_reward_updated: env.block.height,
-        global_interest_index: Decimal256::one(),
-        global_reward_index: Decimal256::zero(),
-        orchai_epb_rate: Decimal256::one(),
         prev_aterra_supply: Uint256::from(2000000u128),
-        prev_exchange_rate: Decimal256::one(),
+        ..default_state(
+            Decimal256::from_uint256(1000000u128),
+            Decimal256::zero(),
+            Decimal256::one(),
+            env.block.height,
+        )
     };
     store_state(&mut deps.storage, &mock_state).unwrap();
 
@@ -178,8 +251,6 @@ fn proper_compute_interest() {
 
     env.block.height += 100;
 
-    // deposit_rate: 0.02
-    // target_deposit_rate: 0.01
     compute_interest(
         deps.as_ref(),
         &mock_config,
@@ -191,15 +262,60 @@ fn proper_compute_interest() {
     assert_eq!(
         mock_state,
         State {
-            total_liabilities: Decimal256::zero(),
-            total_reserves: Decimal256::from_uint256(2000000u64),
+            total_liabilities: Decimal256::from_str("1999999.999999").unwrap(),
+            total_reserves: Decimal256::from_str("499999.9999995").unwrap(),
             last_interest_updated: env.block.height,
             last_reward_updated: env.block.height - 100,
-            global_interest_index: Decimal256::from_uint256(2u128),
+            global_interest_index: Decimal256::from_str("1.999999999999").unwrap(),
             global_reward_index: Decimal256::zero(),
             orchai_epb_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(2000000u64),
-            prev_exchange_rate: Decimal256::from_uint256(2u64),
+            prev_exchange_rate: Decimal256::from_str("3.74999999999975").unwrap(),
+            ..mock_state.clone()
         }
     );
 }
+
+#[test]
+fn proper_compute_origination_fee() {
+    let deps = mock_dependencies(&[]);
+    let mut mock_config = mock_config(&deps.api);
+
+    // no fee by default
+    assert_eq!(
+        compute_origination_fee(&mock_config, Uint256::from(1000000u64)),
+        (
+            Uint256::zero(),
+            Uint256::from(1000000u64),
+            Uint256::from(1000000u64)
+        )
+    );
+
+    // the fee is added on top of the loan, the borrow amount is disbursed
+    mock_config.origination_fee_rate = Decimal256::percent(1);
+    assert_eq!(
+        compute_origination_fee(&mock_config, Uint256::from(1000000u64)),
+        (
+            Uint256::from(10000u64),
+            Uint256::from(1010000u64),
+            Uint256::from(1000000u64)
+        )
+    );
+
+    // the fee is withheld from the disbursed amount
+    mock_config.origination_fee_mode = OriginationFeeMode::DeductFromBorrow;
+    assert_eq!(
+        compute_origination_fee(&mock_config, Uint256::from(1000000u64)),
+        (
+            Uint256::from(10000u64),
+            Uint256::from(1000000u64),
+            Uint256::from(990000u64)
+        )
+    );
+
+    // the fee rounds down
+    assert_eq!(
+        compute_origination_fee(&mock_config, Uint256::from(99u64)),
+        (Uint256::zero(), Uint256::from(99u64), Uint256::from(99u64))
+    );
+}
@@ -1,9 +1,98 @@
-use crate::deposit::compute_exchange_rate;
-use crate::state::{Config, State};
+use crate::deposit::{compute_exchange_rate, fill_redeem_queue};
+use crate::state::{
+    push_redeem_request, read_redeem_queue_front, read_redeem_queue_head, Config, RedeemRequest,
+    State,
+};
 use crate::testing::mock_querier::mock_dependencies;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, MOCK_CONTRACT_ADDR};
-use cosmwasm_std::{Api, Coin, Uint128};
+use cosmwasm_std::{to_binary, Api, BankMsg, Coin, CosmosMsg, HumanAddr, Uint128, WasmMsg};
+use cw20::Cw20HandleMsg;
+use moneymarket::market::{FeeRecipient, OriginationFeeMode, RedeemRateLock};
+use oraiswap::asset::AssetInfoRaw;
+
+fn mock_config(api: &dyn Api) -> Config {
+    Config {
+        contract_addr: api
+            .canonical_address(&HumanAddr::from(MOCK_CONTRACT_ADDR))
+            .unwrap(),
+        owner_addr: api.canonical_address(&HumanAddr::from("owner")).unwrap(),
+        aterra_contract: api.canonical_address(&HumanAddr::from("at-uusd")).unwrap(),
+        interest_model: api.canonical_address(&HumanAddr::from("interest")).unwrap(),
+        distribution_model: api
+            .canonical_address(&HumanAddr::from("distribution"))
+            .unwrap(),
+        overseer_contract: api.canonical_address(&HumanAddr::from("overseer")).unwrap(),
+        collector_contract: api
+            .canonical_address(&HumanAddr::from("collector"))
+            .unwrap(),
+        distributor_contract: api
+            .canonical_address(&HumanAddr::from("distributor"))
+            .unwrap(),
+        stable_info: AssetInfoRaw::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        orchai_token: api.canonical_address(&HumanAddr::from("orchai")).unwrap(),
+        max_borrow_factor: Decimal256::one(),
+        deposit_cap: None,
+        borrow_cap: None,
+        reserve_factor: Decimal256::zero(),
+        reserve_target: Uint256::zero(),
+        redeem_rate_lock: RedeemRateLock::Request,
+        origination_fee_rate: Decimal256::zero(),
+        origination_fee_mode: OriginationFeeMode::AddToLoan,
+        origination_fee_recipient: FeeRecipient::Reserves,
+        stable_rate_premium: Decimal256::zero(),
+        rebalance_utilization: Decimal256::percent(95),
+        rebalance_min_duration: 0,
+        term_grace_period: 0,
+    }
+}
+
+fn default_state(
+    total_liabilities: Decimal256,
+    total_reserves: Decimal256,
+    block_height: u64,
+) -> State {
+    State {
+        total_liabilities,
+        total_stable_liabilities: Decimal256::zero(),
+        avg_stable_rate: Decimal256::zero(),
+        total_term_liabilities: Decimal256::zero(),
+        term_interest_rate: Decimal256::zero(),
+        total_reserves,
+        last_interest_updated: block_height,
+        last_reward_updated: block_height,
+        global_interest_index: Decimal256::one(),
+        global_reward_index: Decimal256::zero(),
+        orchai_epb_rate: Decimal256::one(),
+        prev_aterra_supply: Uint256::zero(),
+        prev_exchange_rate: Decimal256::one(),
+        high_utilization_since: None,
+    }
+}
+
+fn stable_send(recipient: &str, amount: u128) -> CosmosMsg {
+    CosmosMsg::Bank(BankMsg::Send {
+        from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+        to_address: HumanAddr::from(recipient),
+        amount: vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(amount),
+        }],
+    })
+}
+
+fn aterra_burn(amount: u128) -> CosmosMsg {
+    CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: HumanAddr::from("at-uusd"),
+        send: vec![],
+        msg: to_binary(&Cw20HandleMsg::Burn {
+            amount: Uint128::from(amount),
+        })
+        .unwrap(),
+    })
+}
 
 #[test]
 fn proper_compute_exchange_rate() {
@@ -13,34 +102,17 @@ fn proper_compute_exchange_rate() {
     }]);
     let env = mock_env();
     //setting up the required environment for the function call (inputs)
-    let mock_config = Config {
-        contract_addr: deps.api.addr_canonicalize(MOCK_CONTRACT_ADDR).unwrap(),
-        owner_addr: deps.api.addr_canonicalize("owner").unwrap(),
-        aterra_contract: deps.api.addr_canonicalize("at-uusd").unwrap(),
-        interest_model: deps.api.addr_canonicalize("interest").unwrap(),
-        distribution_model: deps.api.addr_canonicalize("distribution").unwrap(),
-        distributor_contract: deps.api.addr_canonicalize("distributor").unwrap(),
-        collector_contract: deps.api.addr_canonicalize("collector").unwrap(),
-        overseer_contract: deps.api.addr_canonicalize("overseer").unwrap(),
-        stable_denom: "uusd".to_string(),
-        max_borrow_factor: Decimal256::one(),
-    };
+    let mock_config = mock_config(&deps.api);
     deps.querier.with_token_balances(&[(
         &"at-uusd".to_string(),
         &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1000000u128))],
     )]);
 
-    let mock_state = State {
-        total_liabilities: Decimal256::from_uint256(50000u128),
-        total_reserves: Decimal256::from_uint256(550000u128),
-        last_interest_updated: env.block.height,
-        last_reward_updated: env.block.height,
-        global_interest_index: Decimal256::one(),
-        global_reward_index: Decimal256::zero(),
-        orchai_epb_rate: Decimal256::one(),
-        prev_aterra_supply: Uint256::zero(),
-        prev_exchange_rate: Decimal256::one(),
-    };
+    let mock_state = default_state(
+        Decimal256::from_uint256(50000u128),
+        Decimal256::from_uint256(550000u128),
+        env.block.height,
+    );
     let mock_deposit_amount = Some(Uint256::from(1000000u128));
 
     let exchange_rate = compute_exchange_rate(
@@ -52,3 +124,105 @@ fn proper_compute_exchange_rate() {
     .unwrap();
     assert_eq!(exchange_rate, Decimal256::percent(50));
 }
+
+#[test]
+fn proper_fill_redeem_queue() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+    let mock_config = mock_config(&deps.api);
+
+    let redeemers = ["addr0000", "addr0001", "addr0002"];
+    for (redeemer, locked_rate) in redeemers
+        .iter()
+        .zip([None, Some(Decimal256::percent(200)), None].iter())
+    {
+        push_redeem_request(
+            &mut deps.storage,
+            &RedeemRequest {
+                redeemer: deps
+                    .api
+                    .canonical_address(&HumanAddr::from(*redeemer))
+                    .unwrap(),
+                aterra_amount: Uint256::from(100000u64),
+                locked_rate: *locked_rate,
+                block_height: env.block.height,
+            },
+        )
+        .unwrap();
+    }
+
+    let mut mock_state = State {
+        prev_aterra_supply: Uint256::from(1000000u64),
+        ..default_state(
+            Decimal256::zero(),
+            Decimal256::from_uint256(50000u128),
+            env.block.height,
+        )
+    };
+
+    // nothing is available above the reserves
+    let messages = fill_redeem_queue(
+        &mut deps.as_mut(),
+        &mock_config,
+        &mut mock_state,
+        Decimal256::percent(150),
+        Uint256::from(50000u64),
+    )
+    .unwrap();
+    assert_eq!(messages, vec![]);
+    assert_eq!(read_redeem_queue_front(&deps.storage, 10).unwrap().len(), 3);
+
+    // 400000 available: the first request is filled at the current rate,
+    // the second at its locked rate and the third partially
+    let messages = fill_redeem_queue(
+        &mut deps.as_mut(),
+        &mock_config,
+        &mut mock_state,
+        Decimal256::percent(150),
+        Uint256::from(450000u64),
+    )
+    .unwrap();
+    assert_eq!(
+        messages,
+        vec![
+            stable_send("addr0000", 150000),
+            stable_send("addr0001", 200000),
+            stable_send("addr0002", 49999),
+            aterra_burn(233333),
+        ]
+    );
+    assert_eq!(mock_state.prev_aterra_supply, Uint256::from(766667u64));
+    assert_eq!(read_redeem_queue_head(&deps.storage), 2);
+    assert_eq!(
+        read_redeem_queue_front(&deps.storage, 10).unwrap(),
+        vec![(
+            3,
+            RedeemRequest {
+                redeemer: deps
+                    .api
+                    .canonical_address(&HumanAddr::from("addr0002"))
+                    .unwrap(),
+                aterra_amount: Uint256::from(66667u64),
+                locked_rate: None,
+                block_height: env.block.height,
+            }
+        )]
+    );
+
+    // the rest of the queue is filled, the leftover balance stays
+    let messages = fill_redeem_queue(
+        &mut deps.as_mut(),
+        &mock_config,
+        &mut mock_state,
+        Decimal256::percent(150),
+        Uint256::from(1000000u64),
+    )
+    .unwrap();
+    assert_eq!(
+        messages,
+        vec![stable_send("addr0002", 100000), aterra_burn(66667)]
+    );
+    assert_eq!(mock_state.prev_aterra_supply, Uint256::from(700000u64));
+    assert_eq!(read_redeem_queue_head(&deps.storage), 3);
+    assert_eq!(read_redeem_queue_front(&deps.storage, 10).unwrap(), vec![]);
+}
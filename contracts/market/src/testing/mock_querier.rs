@@ -4,32 +4,38 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, from_slice, to_binary, Addr, Api, CanonicalAddr, Coin, ContractResult, Decimal,
-    OwnedDeps, Querier, QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
+    from_binary, from_slice, to_binary, Coin, ContractResult, Empty, HumanAddr, OwnedDeps, Querier,
+    QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
 };
-use cosmwasm_storage::to_length_prefixed;
 use std::collections::HashMap;
 
-use cw20::TokenInfoResponse;
+use cw20::{BalanceResponse, TokenInfoResponse};
 use moneymarket::distribution_model::AncEmissionRateResponse;
 use moneymarket::interest_model::BorrowRateResponse;
-use moneymarket::overseer::{BorrowLimitResponse, ConfigResponse};
-use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
+use moneymarket::overseer::{BorrowLimitResponse, CollateralsResponse, DebtCeilingsResponse};
+use moneymarket::tokens::TokensHuman;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     /// Query borrow rate to interest model contract
     BorrowRate {
-        market_balance: Uint128,
+        market_balance: Uint256,
         total_liabilities: Decimal256,
         total_reserves: Decimal256,
     },
     /// Query borrow limit to overseer contract
     BorrowLimit {
-        borrower: String,
+        borrower: HumanAddr,
         block_time: Option<u64>,
     },
+    /// Query locked collaterals to overseer contract
+    Collaterals { borrower: HumanAddr },
+    /// Query collateral debt ceilings to overseer contract
+    DebtCeilings {
+        borrower: HumanAddr,
+        loan_amount: Uint256,
+    },
     /// Query ANC emission rate to distribution model contract
     AncEmissionRate {
         deposit_rate: Decimal256,
@@ -37,10 +43,10 @@ pub enum QueryMsg {
         threshold_deposit_rate: Decimal256,
         current_emission_rate: Decimal256,
     },
-    /// Query overseer config to get target deposit rate
-    Config {},
     /// Query cw20 Token Info
     TokenInfo {},
+    /// Query cw20 Token Balance
+    Balance { address: HumanAddr },
 }
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
@@ -48,8 +54,10 @@ pub enum QueryMsg {
 pub fn mock_dependencies(
     contract_balance: &[Coin],
 ) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
-    let custom_querier: WasmMockQuerier =
-        WasmMockQuerier::new(MockQuerier::new(&[(MOCK_CONTRACT_ADDR, contract_balance)]));
+    let custom_querier: WasmMockQuerier = WasmMockQuerier::new(MockQuerier::new(&[(
+        &HumanAddr::from(MOCK_CONTRACT_ADDR),
+        contract_balance,
+    )]));
 
     OwnedDeps {
         storage: MockStorage::default(),
@@ -59,11 +67,11 @@ pub fn mock_dependencies(
 }
 
 pub struct WasmMockQuerier {
-    base: MockQuerier<TerraQueryWrapper>,
+    base: MockQuerier<Empty>,
     token_querier: TokenQuerier,
-    tax_querier: TaxQuerier,
     borrow_rate_querier: BorrowRateQuerier,
     borrow_limit_querier: BorrowLimitQuerier,
+    collaterals_querier: CollateralsQuerier,
 }
 
 #[derive(Clone, Default)]
@@ -95,30 +103,6 @@ pub(crate) fn balances_to_map(
     balances_map
 }
 
-#[derive(Clone, Default)]
-pub struct TaxQuerier {
-    rate: Decimal,
-    // this lets us iterate over all pairs that match the first string
-    caps: HashMap<String, Uint128>,
-}
-
-impl TaxQuerier {
-    pub fn new(rate: Decimal, caps: &[(&String, &Uint128)]) -> Self {
-        TaxQuerier {
-            rate,
-            caps: caps_to_map(caps),
-        }
-    }
-}
-
-pub(crate) fn caps_to_map(caps: &[(&String, &Uint128)]) -> HashMap<String, Uint128> {
-    let mut owner_map: HashMap<String, Uint128> = HashMap::new();
-    for (denom, cap) in caps.iter() {
-        owner_map.insert(denom.to_string(), **cap);
-    }
-    owner_map
-}
-
 #[derive(Clone, Default)]
 pub struct BorrowRateQuerier {
     // this lets us iterate over all pairs that match the first string
@@ -167,10 +151,34 @@ pub(crate) fn borrow_limit_to_map(
     borrow_limit_map
 }
 
+#[derive(Clone, Default)]
+pub struct CollateralsQuerier {
+    // this lets us iterate over all pairs that match the first string
+    collaterals: HashMap<String, TokensHuman>,
+}
+
+impl CollateralsQuerier {
+    pub fn new(collaterals: &[(&String, &TokensHuman)]) -> Self {
+        CollateralsQuerier {
+            collaterals: collaterals_to_map(collaterals),
+        }
+    }
+}
+
+pub(crate) fn collaterals_to_map(
+    collaterals: &[(&String, &TokensHuman)],
+) -> HashMap<String, TokensHuman> {
+    let mut collaterals_map: HashMap<String, TokensHuman> = HashMap::new();
+    for (borrower, collaterals) in collaterals.iter() {
+        collaterals_map.insert((*borrower).clone(), (*collaterals).clone());
+    }
+    collaterals_map
+}
+
 impl Querier for WasmMockQuerier {
     fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
         // MockQuerier doesn't support Custom, so we ignore it completely here
-        let request: QueryRequest<TerraQueryWrapper> = match from_slice(bin_request) {
+        let request: QueryRequest<Empty> = match from_slice(bin_request) {
             Ok(v) => v,
             Err(e) => {
                 return SystemResult::Err(SystemError::InvalidRequest {
@@ -184,33 +192,8 @@ impl Querier for WasmMockQuerier {
 }
 
 impl WasmMockQuerier {
-    pub fn handle_query(&self, request: &QueryRequest<TerraQueryWrapper>) -> QuerierResult {
+    pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
         match &request {
-            QueryRequest::Custom(TerraQueryWrapper { route, query_data }) => {
-                if &TerraRoute::Treasury == route {
-                    match query_data {
-                        TerraQuery::TaxRate {} => {
-                            let res = TaxRateResponse {
-                                rate: self.tax_querier.rate,
-                            };
-                            SystemResult::Ok(ContractResult::from(to_binary(&res)))
-                        }
-                        TerraQuery::TaxCap { denom } => {
-                            let cap = self
-                                .tax_querier
-                                .caps
-                                .get(denom)
-                                .copied()
-                                .unwrap_or_default();
-                            let res = TaxCapResponse { cap };
-                            SystemResult::Ok(ContractResult::from(to_binary(&res)))
-                        }
-                        _ => panic!("DO NOT ENTER HERE"),
-                    }
-                } else {
-                    panic!("DO NOT ENTER HERE")
-                }
-            }
             QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
                 match from_binary(msg).unwrap() {
                     QueryMsg::BorrowRate {
@@ -218,7 +201,11 @@ impl WasmMockQuerier {
                         total_liabilities: _,
                         total_reserves: _,
                     } => {
-                        match self.borrow_rate_querier.borrower_rate.get(contract_addr) {
+                        match self
+                            .borrow_rate_querier
+                            .borrower_rate
+                            .get(contract_addr.as_str())
+                        {
                             Some(v) => SystemResult::Ok(ContractResult::from(to_binary(
                                 &BorrowRateResponse { rate: *v },
                             ))),
@@ -231,10 +218,14 @@ impl WasmMockQuerier {
                     QueryMsg::BorrowLimit {
                         borrower,
                         block_time: _,
-                    } => match self.borrow_limit_querier.borrow_limit.get(&borrower) {
+                    } => match self
+                        .borrow_limit_querier
+                        .borrow_limit
+                        .get(borrower.as_str())
+                    {
                         Some(v) => SystemResult::Ok(ContractResult::from(to_binary(
                             &BorrowLimitResponse {
-                                borrower,
+                                borrower: borrower.to_string(),
                                 borrow_limit: *v,
                             },
                         ))),
@@ -243,6 +234,26 @@ impl WasmMockQuerier {
                             request: msg.as_slice().into(),
                         }),
                     },
+                    // the overseer returns no collaterals to unknown borrowers
+                    QueryMsg::Collaterals { borrower } => {
+                        SystemResult::Ok(ContractResult::from(to_binary(&CollateralsResponse {
+                            borrower: borrower.to_string(),
+                            collaterals: self
+                                .collaterals_querier
+                                .collaterals
+                                .get(borrower.as_str())
+                                .cloned()
+                                .unwrap_or_default(),
+                        })))
+                    }
+                    QueryMsg::DebtCeilings {
+                        borrower,
+                        loan_amount,
+                    } => SystemResult::Ok(ContractResult::from(to_binary(&DebtCeilingsResponse {
+                        borrower: borrower.to_string(),
+                        loan_amount,
+                        within_ceilings: true,
+                    }))),
                     QueryMsg::AncEmissionRate {
                         deposit_rate: _,
                         target_deposit_rate: _,
@@ -253,36 +264,9 @@ impl WasmMockQuerier {
                             emission_rate: Decimal256::from_uint256(5u64),
                         },
                     ))),
-                    QueryMsg::Config {} => {
-                        SystemResult::Ok(ContractResult::from(to_binary(&ConfigResponse {
-                            owner_addr: "".to_string(),
-                            oracle_contract: "".to_string(),
-                            market_contract: "".to_string(),
-                            liquidation_contract: "".to_string(),
-                            collector_contract: "".to_string(),
-                            threshold_deposit_rate: Decimal256::one(),
-                            target_deposit_rate: Decimal256::from_ratio(1, 100),
-                            buffer_distribution_factor: Decimal256::one(),
-                            anc_purchase_factor: Decimal256::one(),
-                            stable_denom: "uusd".to_string(),
-                            epoch_period: 100u64,
-                            price_timeframe: 100u64,
-                            dyn_rate_epoch: 8600u64,
-                            dyn_rate_maxchange: Decimal256::permille(5),
-                            dyn_rate_yr_increase_expectation: Decimal256::permille(1),
-                            dyn_rate_min: Decimal256::from_ratio(
-                                1000000000000u64,
-                                1000000000000000000u64,
-                            ),
-                            dyn_rate_max: Decimal256::from_ratio(
-                                1200000000000u64,
-                                1000000000000000000u64,
-                            ),
-                        })))
-                    }
                     QueryMsg::TokenInfo {} => {
                         let balances: HashMap<String, Uint128> =
-                            match self.token_querier.balances.get(contract_addr) {
+                            match self.token_querier.balances.get(contract_addr.as_str()) {
                                 Some(balances) => balances.clone(),
                                 None => HashMap::new(),
                             };
@@ -300,44 +284,19 @@ impl WasmMockQuerier {
                             total_supply,
                         })))
                     }
-                }
-            }
-            QueryRequest::Wasm(WasmQuery::Raw { contract_addr, key }) => {
-                let key: &[u8] = key.as_slice();
-
-                let prefix_balance = to_length_prefixed(b"balance").to_vec();
-
-                let balances: HashMap<String, Uint128> =
-                    match self.token_querier.balances.get(contract_addr) {
-                        Some(balances) => balances.clone(),
-                        None => HashMap::new(),
-                    };
-
-                if key[..prefix_balance.len()].to_vec() == prefix_balance {
-                    let key_address: &[u8] = &key[prefix_balance.len()..];
-                    let address_raw: CanonicalAddr = CanonicalAddr::from(key_address);
-                    let api: MockApi = MockApi::default();
-                    let address: Addr = match api.addr_humanize(&address_raw) {
-                        Ok(v) => v,
-                        Err(e) => {
-                            return SystemResult::Err(SystemError::InvalidRequest {
-                                error: format!("Parsing query request: {}", e),
-                                request: key.into(),
-                            })
-                        }
-                    };
-                    let balance = match balances.get(address.as_str()) {
-                        Some(v) => v,
-                        None => {
-                            return SystemResult::Err(SystemError::InvalidRequest {
-                                error: "Balance not found".to_string(),
-                                request: key.into(),
-                            })
-                        }
-                    };
-                    SystemResult::Ok(ContractResult::from(to_binary(&balance)))
-                } else {
-                    panic!("DO NOT ENTER HERE")
+                    QueryMsg::Balance { address } => {
+                        let balance = self
+                            .token_querier
+                            .balances
+                            .get(contract_addr.as_str())
+                            .and_then(|balances| balances.get(address.as_str()))
+                            .copied()
+                            .unwrap_or_else(Uint128::zero);
+
+                        SystemResult::Ok(ContractResult::from(to_binary(&BalanceResponse {
+                            balance,
+                        })))
+                    }
                 }
             }
             _ => self.base.handle_query(request),
@@ -346,18 +305,18 @@ impl WasmMockQuerier {
 }
 
 impl WasmMockQuerier {
-    pub fn new(base: MockQuerier<TerraQueryWrapper>) -> Self {
+    pub fn new(base: MockQuerier<Empty>) -> Self {
         WasmMockQuerier {
             base,
             token_querier: TokenQuerier::default(),
-            tax_querier: TaxQuerier::default(),
             borrow_rate_querier: BorrowRateQuerier::default(),
             borrow_limit_querier: BorrowLimitQuerier::default(),
+            collaterals_querier: CollateralsQuerier::default(),
         }
     }
 
     // set a new balance for the given address and return the old balance
-    pub fn update_balance<U: Into<String>>(
+    pub fn update_balance<U: Into<HumanAddr>>(
         &mut self,
         addr: U,
         balance: Vec<Coin>,
@@ -370,11 +329,6 @@ impl WasmMockQuerier {
         self.token_querier = TokenQuerier::new(balances);
     }
 
-    // configure the tax mock querier
-    pub fn with_tax(&mut self, rate: Decimal, caps: &[(&String, &Uint128)]) {
-        self.tax_querier = TaxQuerier::new(rate, caps);
-    }
-
     pub fn with_borrow_rate(&mut self, borrow_rate: &[(&String, &Decimal256)]) {
         self.borrow_rate_querier = BorrowRateQuerier::new(borrow_rate);
     }
@@ -382,4 +336,8 @@ impl WasmMockQuerier {
     pub fn with_borrow_limit(&mut self, borrow_limit: &[(&String, &Uint256)]) {
         self.borrow_limit_querier = BorrowLimitQuerier::new(borrow_limit);
     }
+
+    pub fn with_collaterals(&mut self, collaterals: &[(&String, &TokensHuman)]) {
+        self.collaterals_querier = CollateralsQuerier::new(collaterals);
+    }
 }
@@ -1,4 +1,5 @@
 mod borrow_ut;
 mod deposit_ut;
 mod mock_querier;
+mod term_loan_ut;
 mod tests;
@@ -0,0 +1,130 @@
+use crate::borrow::BLOCKS_PER_YEAR;
+use crate::state::{read_term_maturity, State, TermLoan};
+use crate::term_loan::{compute_term_interest, open_term_loan, settle_term_loan};
+use crate::testing::mock_querier::mock_dependencies;
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::testing::mock_env;
+use cosmwasm_std::{Api, HumanAddr};
+
+fn default_state(block_height: u64) -> State {
+    State {
+        total_liabilities: Decimal256::zero(),
+        total_stable_liabilities: Decimal256::zero(),
+        avg_stable_rate: Decimal256::zero(),
+        total_term_liabilities: Decimal256::zero(),
+        term_interest_rate: Decimal256::zero(),
+        total_reserves: Decimal256::zero(),
+        last_interest_updated: block_height,
+        last_reward_updated: block_height,
+        global_interest_index: Decimal256::one(),
+        global_reward_index: Decimal256::zero(),
+        orchai_epb_rate: Decimal256::one(),
+        prev_aterra_supply: Uint256::zero(),
+        prev_exchange_rate: Decimal256::one(),
+        high_utilization_since: None,
+    }
+}
+
+#[test]
+fn proper_settle_term_loan() {
+    let mut deps = mock_dependencies(&[]);
+    let mut env = mock_env();
+    let borrower = deps
+        .api
+        .canonical_address(&HumanAddr::from("addr0000"))
+        .unwrap();
+    let start_height = env.block.height;
+    let term = BLOCKS_PER_YEAR / 10;
+    let mut state = default_state(start_height);
+
+    // 10% a year over a tenth of a year, 1% of interest
+    let mut term_loan = open_term_loan(
+        &mut deps.storage,
+        &mut state,
+        &borrower,
+        Uint256::from(1000000u64),
+        Decimal256::percent(10),
+        start_height,
+        term,
+    )
+    .unwrap();
+    assert_eq!(
+        term_loan,
+        TermLoan {
+            loan_id: 1,
+            principal: Uint256::from(1000000u64),
+            interest: Uint256::from(10000u64),
+            rate: Decimal256::percent(10),
+            start_height,
+            maturity: start_height + term,
+        }
+    );
+    assert_eq!(
+        state.term_interest_rate,
+        Decimal256::from_uint256(100000u64)
+    );
+
+    // repaying half of the loan halfway through the term removes the half
+    // of the principal and what it accrued, principal and interest pro rata
+    env.block.height += term / 2;
+    compute_term_interest(
+        &deps.storage,
+        &mut state,
+        env.block.height,
+        Decimal256::zero(),
+    )
+    .unwrap();
+    state.last_interest_updated = env.block.height;
+    assert_eq!(
+        state.total_term_liabilities,
+        Decimal256::from_uint256(1005000u64)
+    );
+
+    let repaid = settle_term_loan(
+        &mut deps.storage,
+        &mut state,
+        &mut term_loan,
+        Uint256::from(505000u64),
+        env.block.height,
+    )
+    .unwrap();
+    assert_eq!(repaid, Uint256::from(505000u64));
+    assert_eq!(term_loan.principal, Uint256::from(500000u64));
+    assert_eq!(term_loan.interest, Uint256::from(5000u64));
+    assert_eq!(
+        state.total_term_liabilities,
+        Decimal256::from_uint256(502500u64)
+    );
+    assert_eq!(state.total_liabilities, state.total_term_liabilities);
+    assert_eq!(state.term_interest_rate, Decimal256::from_uint256(50000u64));
+    assert_eq!(
+        read_term_maturity(&deps.storage, term_loan.maturity),
+        Decimal256::from_uint256(50000u64)
+    );
+
+    // after maturity the loan stopped accruing, an overpayment
+    // only repays what is left of it
+    env.block.height = term_loan.maturity + 10;
+    compute_term_interest(
+        &deps.storage,
+        &mut state,
+        env.block.height,
+        Decimal256::zero(),
+    )
+    .unwrap();
+    state.last_interest_updated = env.block.height;
+    assert_eq!(state.term_interest_rate, Decimal256::zero());
+
+    let repaid = settle_term_loan(
+        &mut deps.storage,
+        &mut state,
+        &mut term_loan,
+        Uint256::from(1000000u64),
+        env.block.height,
+    )
+    .unwrap();
+    assert_eq!(repaid, Uint256::from(505000u64));
+    assert_eq!(term_loan.loan_amount(), Uint256::zero());
+    assert_eq!(state.total_term_liabilities, Decimal256::zero());
+    assert_eq!(state.total_liabilities, Decimal256::zero());
+}
@@ -1,25 +1,121 @@
-use crate::contract::{handle, init, query, reply, INITIAL_DEPOSIT_AMOUNT};
+use crate::borrow::BLOCKS_PER_YEAR;
+use crate::contract::{handle, init, migrate, query, INITIAL_DEPOSIT_AMOUNT};
+use crate::migration::{LegacyConfig, LegacyState};
 use crate::error::ContractError;
-use crate::response::MsgInstantiateContractResponse;
-use crate::state::{read_borrower_infos, read_state, store_state, State};
+use crate::state::{read_borrower_infos, read_state, store_state, State, KEY_CONFIG, KEY_STATE};
 use crate::testing::mock_querier::mock_dependencies;
 
-use anchor_token::distributor::ExecuteMsg as FaucetExecuteMsg;
+use anchor_token::hook::InitHook;
+use anchor_token::token::InitMsg;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, from_binary, to_binary, HumanAddr, CanonicalAddr, BankMsg, Coin, ContractResult, CosmosMsg, Decimal, Reply,
-    SubMsgExecutionResponse, Uint128, WasmMsg, 
+    attr, from_binary, to_binary, Api, BankMsg, Coin, CosmosMsg, HumanAddr, Storage, Uint128,
+    WasmMsg,
 };
-use cw20::{Cw20Coin, Cw20HandleMsg, Cw20ReceiveMsg, MinterResponse};
+use cw20::{Cw20CoinHuman, Cw20HandleMsg, Cw20ReceiveMsg, Expiration, MinterResponse};
 use moneymarket::market::{
-    BorrowerInfoResponse, ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg,
-    StateResponse, TokenInstantiateMsg,
+    BadDebtWriteOffElem, BadDebtWriteOffsResponse, BorrowerInfoResponse, CapsResponse,
+    ConfigResponse, Cw20HookMsg, DelegationAllowanceResponse, ExecuteMsg, HistoricalStateElem,
+    HistoricalStatesResponse, InstantiateMsg, MigrateMsg, PauseFlags, QueryMsg, RateMode,
+    RedeemQueueResponse, RedeemRateLock, RedeemRequestResponse, ReserveHistoryElem,
+    ReserveHistoryResponse, SimulationResponse, StateResponse, UpdateConfigMsg,
 };
-use moneymarket::querier::deduct_tax;
-use protobuf::Message;
+use moneymarket::overseer::ExecuteMsg as OverseerExecuteMsg;
+use cosmwasm_storage::{to_length_prefixed, Singleton};
+use oraiswap::asset::AssetInfo;
 use std::str::FromStr;
 
+fn instantiate_msg(max_borrow_factor: Decimal256) -> InstantiateMsg {
+    InstantiateMsg {
+        owner_addr: HumanAddr::from("owner"),
+        stable_info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        orchai_code_id: 123u64,
+        orchai_epb_rate: Decimal256::one(),
+        max_borrow_factor,
+        orchai_token: HumanAddr::from("orchai"),
+        deposit_cap: None,
+        borrow_cap: None,
+        reserve_factor: Decimal256::zero(),
+        reserve_target: Uint256::zero(),
+        redeem_rate_lock: RedeemRateLock::Request,
+        atoken_name: "Anchor Terra USD".to_string(),
+        atoken_symbol: "aUST".to_string(),
+        init_hook: None,
+    }
+}
+
+fn register_contracts_msg() -> ExecuteMsg {
+    ExecuteMsg::RegisterContracts {
+        overseer_contract: HumanAddr::from("overseer"),
+        interest_model: HumanAddr::from("interest"),
+        distribution_model: HumanAddr::from("distribution"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+    }
+}
+
+fn update_config_msg() -> UpdateConfigMsg {
+    UpdateConfigMsg {
+        owner_addr: None,
+        max_borrow_factor: None,
+        interest_model: None,
+        distribution_model: None,
+        reserve_factor: None,
+        reserve_target: None,
+        redeem_rate_lock: None,
+        origination_fee_rate: None,
+        origination_fee_mode: None,
+        origination_fee_recipient: None,
+        stable_rate_premium: None,
+        rebalance_utilization: None,
+        rebalance_min_duration: None,
+        term_grace_period: None,
+    }
+}
+
+fn mock_state(
+    total_liabilities: Decimal256,
+    total_reserves: Decimal256,
+    block_height: u64,
+) -> State {
+    State {
+        total_liabilities,
+        total_stable_liabilities: Decimal256::zero(),
+        avg_stable_rate: Decimal256::zero(),
+        total_term_liabilities: Decimal256::zero(),
+        term_interest_rate: Decimal256::zero(),
+        total_reserves,
+        last_interest_updated: block_height,
+        last_reward_updated: block_height,
+        global_interest_index: Decimal256::one(),
+        global_reward_index: Decimal256::zero(),
+        orchai_epb_rate: Decimal256::one(),
+        prev_aterra_supply: Uint256::zero(),
+        prev_exchange_rate: Decimal256::one(),
+        high_utilization_since: None,
+    }
+}
+
+/// Annual borrow rate accruing 1% per block
+fn one_percent_per_block() -> Decimal256 {
+    Decimal256::percent(BLOCKS_PER_YEAR)
+}
+
+fn update_borrower_debt_msg(borrower: &str, enforce_ceilings: bool) -> CosmosMsg {
+    CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: HumanAddr::from("overseer"),
+        send: vec![],
+        msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+            borrower: HumanAddr::from(borrower),
+            enforce_ceilings,
+        })
+        .unwrap(),
+    })
+}
+
 #[test]
 fn proper_initialization() {
     let mut deps = mock_dependencies(&[Coin {
@@ -27,84 +123,53 @@ fn proper_initialization() {
         amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
     }]);
 
-    let msg = InstantiateMsg {
-        owner_addr: HumanAddr("owner".to_string()),
-        stable_denom: "uusd".to_string(),
-        aterra_code_id: 123u64,
-        orchai_epb_rate: Decimal256::one(),
-        max_borrow_factor: Decimal256::one(),
-    };
-
-    let info = mock_info(
-        "addr0000",
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-        }],
-    );
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
 
     // we can just call .unwrap() to assert this was a success
     let res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(
         res.messages,
         vec![CosmosMsg::Wasm(WasmMsg::Instantiate {
-                code_id: 123u64,
-                send: vec![],
-                label: Some("".to_string()),
-                msg: to_binary(&TokenInstantiateMsg {
-                    name: "Anchor Terra USD".to_string(),
-                    symbol: "aUST".to_string(),
-                    decimals: 6u8,
-                    initial_balances: vec![Cw20Coin {
-                        address: CanonicalAddr(&HumanAddr(MOCK_CONTRACT_ADDR.to_string())),
-                        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-                    }],
-                    mint: Some(MinterResponse {
-                        minter: HumanAddr(MOCK_CONTRACT_ADDR.to_string()),
-                        cap: None,
-                    }),
-                })
-                .unwrap(),
-            }),
-        ]
+            code_id: 123u64,
+            send: vec![],
+            label: Some("".to_string()),
+            msg: to_binary(&InitMsg {
+                name: "Anchor Terra USD".to_string(),
+                symbol: "aUST".to_string(),
+                decimals: 6u8,
+                initial_balances: vec![Cw20CoinHuman {
+                    address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                    amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+                }],
+                mint: Some(MinterResponse {
+                    minter: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                    cap: None,
+                }),
+                init_hook: Some(InitHook {
+                    contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                    msg: to_binary(&ExecuteMsg::RegisterATerra {}).unwrap(),
+                }),
+            })
+            .unwrap(),
+        })]
     );
 
     // Register anchor token contract
-    let mut token_inst_res = MsgInstantiateContractResponse::new();
-    token_inst_res.set_contract_address("at-uusd".to_string());
-    let reply_msg = Reply {
-        id: 1,
-        result: ContractResult::Ok(SubMsgExecutionResponse {
-            events: vec![],
-            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
-        }),
-    };
-    let _res = reply(deps.as_mut(), mock_env(), reply_msg.clone()).unwrap();
+    let msg = ExecuteMsg::RegisterATerra {};
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
 
     // Cannot register again
-    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap_err();
+    let _res = handle(deps.as_mut(), mock_env(), info, msg).unwrap_err();
 
     // Register overseer contract
-    let msg = ExecuteMsg::RegisterContracts {
-        overseer_contract: "overseer".to_string(),
-        interest_model: "interest".to_string(),
-        distribution_model: "distribution".to_string(),
-        collector_contract: "collector".to_string(),
-        distributor_contract: "distributor".to_string(),
-    };
     let info = mock_info("addr0000", &[]);
-    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let _res = handle(deps.as_mut(), mock_env(), info, register_contracts_msg()).unwrap();
 
     // Cannot register again
-    let msg = ExecuteMsg::RegisterContracts {
-        overseer_contract: "overseer".to_string(),
-        interest_model: "interest".to_string(),
-        distribution_model: "distribution".to_string(),
-        collector_contract: "collector".to_string(),
-        distributor_contract: "distributor".to_string(),
-    };
     let info = mock_info("addr0000", &[]);
-    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    let _res = handle(deps.as_mut(), mock_env(), info, register_contracts_msg()).unwrap_err();
 
     let query_res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
     let config_res: ConfigResponse = from_binary(&query_res).unwrap();
@@ -115,7 +180,12 @@ fn proper_initialization() {
     assert_eq!("distributor".to_string(), config_res.distributor_contract);
     assert_eq!("collector".to_string(), config_res.collector_contract);
     assert_eq!("overseer".to_string(), config_res.overseer_contract);
-    assert_eq!("uusd".to_string(), config_res.stable_denom);
+    assert_eq!(
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string()
+        },
+        config_res.stable_info
+    );
     assert_eq!(Decimal256::one(), config_res.max_borrow_factor);
 
     let query_res = query(
@@ -143,58 +213,28 @@ fn update_config() {
     deps.querier
         .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
 
-    let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        stable_denom: "uusd".to_string(),
-        aterra_code_id: 123u64,
-        orchai_epb_rate: Decimal256::one(),
-        max_borrow_factor: Decimal256::one(),
-    };
-
-    let info = mock_info(
-        "addr0000",
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-        }],
-    );
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
 
     // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     // Register anchor token contract
-    let mut token_inst_res = MsgInstantiateContractResponse::new();
-    token_inst_res.set_contract_address("at-uusd".to_string());
-    let reply_msg = Reply {
-        id: 1,
-        result: ContractResult::Ok(SubMsgExecutionResponse {
-            events: vec![],
-            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
-        }),
-    };
-    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
 
     // Register overseer contract
-    let msg = ExecuteMsg::RegisterContracts {
-        overseer_contract: "overseer".to_string(),
-        interest_model: "interest".to_string(),
-        distribution_model: "distribution".to_string(),
-        collector_contract: "collector".to_string(),
-        distributor_contract: "distributor".to_string(),
-    };
     let info = mock_info("addr0000", &[]);
-    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let _res = handle(deps.as_mut(), mock_env(), info, register_contracts_msg()).unwrap();
 
     // update owner
     let info = mock_info("owner", &[]);
-    let msg = ExecuteMsg::UpdateConfig {
-        owner_addr: Some("owner1".to_string()),
-        interest_model: None,
-        distribution_model: None,
-        max_borrow_factor: None,
-    };
+    let msg = ExecuteMsg::UpdateConfig(Box::new(UpdateConfigMsg {
+        owner_addr: Some(HumanAddr::from("owner1")),
+        ..update_config_msg()
+    }));
 
-    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let res = handle(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(0, res.messages.len());
 
     // it worked, let's query the state
@@ -204,14 +244,14 @@ fn update_config() {
 
     // update left items
     let info = mock_info("owner1", &[]);
-    let msg = ExecuteMsg::UpdateConfig {
-        owner_addr: None,
-        interest_model: Some("interest2".to_string()),
-        distribution_model: Some("distribution2".to_string()),
+    let msg = ExecuteMsg::UpdateConfig(Box::new(UpdateConfigMsg {
+        interest_model: Some(HumanAddr::from("interest2")),
+        distribution_model: Some(HumanAddr::from("distribution2")),
         max_borrow_factor: Some(Decimal256::percent(100)),
-    };
+        ..update_config_msg()
+    }));
 
-    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let res = handle(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(0, res.messages.len());
 
     // it worked, let's query the state
@@ -224,20 +264,130 @@ fn update_config() {
 
     // Unauthorized err
     let info = mock_info("owner", &[]);
-    let msg = ExecuteMsg::UpdateConfig {
-        owner_addr: None,
-        interest_model: None,
-        distribution_model: None,
-        max_borrow_factor: None,
-    };
+    let msg = ExecuteMsg::UpdateConfig(Box::new(update_config_msg()));
 
-    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    let res = handle(deps.as_mut(), mock_env(), info, msg);
     match res {
         Err(ContractError::Unauthorized {}) => (),
         _ => panic!("Must return unauthorized error"),
     }
 }
 
+#[test]
+fn migrate_legacy_records() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+
+    let legacy_config = LegacyConfig {
+        contract_addr: deps.api.canonical_address(&HumanAddr::from(MOCK_CONTRACT_ADDR)).unwrap(),
+        owner_addr: deps.api.canonical_address(&HumanAddr::from("owner")).unwrap(),
+        aterra_contract: deps.api.canonical_address(&HumanAddr::from("at-uusd")).unwrap(),
+        interest_model: deps.api.canonical_address(&HumanAddr::from("interest")).unwrap(),
+        distribution_model: deps.api.canonical_address(&HumanAddr::from("distribution")).unwrap(),
+        overseer_contract: deps.api.canonical_address(&HumanAddr::from("overseer")).unwrap(),
+        collector_contract: deps.api.canonical_address(&HumanAddr::from("collector")).unwrap(),
+        distributor_contract: deps.api.canonical_address(&HumanAddr::from("distributor")).unwrap(),
+        stable_addr: deps.api.canonical_address(&HumanAddr::from("stable")).unwrap(),
+        orchai_token: deps.api.canonical_address(&HumanAddr::from("orchai")).unwrap(),
+        max_borrow_factor: Decimal256::one(),
+    };
+    Singleton::new(deps.as_mut().storage, KEY_CONFIG)
+        .save(&legacy_config)
+        .unwrap();
+    Singleton::new(deps.as_mut().storage, KEY_STATE)
+        .save(&LegacyState {
+            total_liabilities: Decimal256::from_uint256(1000000u64),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            orchai_epb_rate: Decimal256::zero(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+        })
+        .unwrap();
+
+    // a borrower record stored before stable rate borrowing
+    let borrower_raw = deps
+        .api
+        .canonical_address(&HumanAddr::from("addr0000"))
+        .unwrap();
+    deps.storage.set(
+        &[to_length_prefixed(b"liability"), borrower_raw.to_vec()].concat(),
+        br#"{"interest_index":"1","reward_index":"0","loan_amount":"1000000","pending_rewards":"0"}"#,
+    );
+
+    let msg = MigrateMsg {
+        reserve_factor: Decimal256::percent(10),
+        reserve_target: Uint256::from(500u64),
+        redeem_rate_lock: RedeemRateLock::Fill,
+    };
+    migrate(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg.clone()).unwrap();
+
+    let config_res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config_res.stable_info,
+        AssetInfo::Token {
+            contract_addr: HumanAddr::from("stable"),
+        }
+    );
+    assert_eq!(config_res.reserve_factor, Decimal256::percent(10));
+    assert_eq!(config_res.reserve_target, Uint256::from(500u64));
+    assert_eq!(config_res.borrow_cap, None);
+
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(state.total_liabilities, Decimal256::from_uint256(1000000u64));
+    assert_eq!(state.total_stable_liabilities, Decimal256::zero());
+
+    // the existing loan keeps its debt as a variable rate loan
+    let liability: BorrowerInfoResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BorrowerInfo {
+                borrower: HumanAddr::from("addr0000"),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(liability.loan_amount, Uint256::from(1000000u64));
+    assert_eq!(liability.rate_mode, RateMode::Variable);
+
+    // migrating again leaves the migrated config untouched
+    migrate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        MigrateMsg {
+            reserve_factor: Decimal256::zero(),
+            ..msg
+        },
+    )
+    .unwrap();
+    let config_res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config_res.reserve_factor, Decimal256::percent(10));
+
+    // an undecodable record is an error, not an empty loan
+    deps.storage.set(
+        &[to_length_prefixed(b"liability"), borrower_raw.to_vec()].concat(),
+        br#"{"loan_amount":"1000000"}"#,
+    );
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::BorrowerInfo {
+            borrower: HumanAddr::from("addr0000"),
+            block_height: None,
+        },
+    );
+    assert!(res.is_err());
+}
+
 #[test]
 fn deposit_stable_huge_amount() {
     let mut deps = mock_dependencies(&[Coin {
@@ -245,47 +395,19 @@ fn deposit_stable_huge_amount() {
         amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
     }]);
 
-    let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        stable_denom: "uusd".to_string(),
-        aterra_code_id: 123u64,
-        orchai_epb_rate: Decimal256::one(),
-        max_borrow_factor: Decimal256::one(),
-    };
-
-    let info = mock_info(
-        "addr0000",
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-        }],
-    );
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
 
     // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     // Register anchor token contract
-    let mut token_inst_res = MsgInstantiateContractResponse::new();
-    token_inst_res.set_contract_address("at-uusd".to_string());
-    let reply_msg = Reply {
-        id: 1,
-        result: ContractResult::Ok(SubMsgExecutionResponse {
-            events: vec![],
-            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
-        }),
-    };
-    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
 
     // Register overseer contract
-    let msg = ExecuteMsg::RegisterContracts {
-        overseer_contract: "overseer".to_string(),
-        interest_model: "interest".to_string(),
-        distribution_model: "distribution".to_string(),
-        collector_contract: "collector".to_string(),
-        distributor_contract: "distributor".to_string(),
-    };
     let info = mock_info("addr0000", &[]);
-    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let _res = handle(deps.as_mut(), mock_env(), info, register_contracts_msg()).unwrap();
 
     // Must deposit stable_denom
     let msg = ExecuteMsg::DepositStable {};
@@ -297,10 +419,9 @@ fn deposit_stable_huge_amount() {
         }],
     );
 
-    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
-    let _uusd_string = "uusd";
+    let res = handle(deps.as_mut(), mock_env(), info, msg.clone());
     match res {
-        Err(ContractError::ZeroDeposit(_uusd_string)) => (),
+        Err(ContractError::ZeroDeposit {}) => (),
         _ => panic!("DO NOT ENTER HERE"),
     }
 
@@ -322,14 +443,14 @@ fn deposit_stable_huge_amount() {
         )],
     )]);
     deps.querier.update_balance(
-        MOCK_CONTRACT_ADDR.to_string(),
+        MOCK_CONTRACT_ADDR,
         vec![Coin {
             denom: "uusd".to_string(),
             amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 55_555_555_000_000u128),
         }],
     );
 
-    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    let res = handle(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
     assert_eq!(
         res.attributes,
         vec![
@@ -342,19 +463,19 @@ fn deposit_stable_huge_amount() {
 
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: "at-uusd".to_string(),
-            funds: vec![],
-            msg: to_binary(&Cw20ExecuteMsg::Mint {
-                recipient: "addr0000".to_string(),
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("at-uusd"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Mint {
+                recipient: HumanAddr::from("addr0000"),
                 amount: Uint128::from(55_555_555_000_000u128),
             })
             .unwrap(),
-        }))]
+        })]
     );
 
     deps.querier.update_balance(
-        MOCK_CONTRACT_ADDR.to_string(),
+        MOCK_CONTRACT_ADDR,
         vec![Coin {
             denom: "uusd".to_string(),
             amount: Uint128::from(111_111_110_000_000u128),
@@ -369,7 +490,7 @@ fn deposit_stable_huge_amount() {
         )],
     )]);
 
-    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let res = handle(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(
         res.attributes,
         vec![
@@ -382,15 +503,15 @@ fn deposit_stable_huge_amount() {
 
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: "at-uusd".to_string(),
-            funds: vec![],
-            msg: to_binary(&Cw20ExecuteMsg::Mint {
-                recipient: "addr0000".to_string(),
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("at-uusd"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Mint {
+                recipient: HumanAddr::from("addr0000"),
                 amount: Uint128::from(55_555_555_000_000u128),
             })
             .unwrap(),
-        }))]
+        })]
     );
 }
 
@@ -401,47 +522,19 @@ fn deposit_stable() {
         amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
     }]);
 
-    let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        stable_denom: "uusd".to_string(),
-        aterra_code_id: 123u64,
-        orchai_epb_rate: Decimal256::one(),
-        max_borrow_factor: Decimal256::one(),
-    };
-
-    let info = mock_info(
-        "addr0000",
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-        }],
-    );
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
 
     // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     // Register anchor token contract
-    let mut token_inst_res = MsgInstantiateContractResponse::new();
-    token_inst_res.set_contract_address("at-uusd".to_string());
-    let reply_msg = Reply {
-        id: 1,
-        result: ContractResult::Ok(SubMsgExecutionResponse {
-            events: vec![],
-            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
-        }),
-    };
-    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
 
     // Register overseer contract
-    let msg = ExecuteMsg::RegisterContracts {
-        overseer_contract: "overseer".to_string(),
-        interest_model: "interest".to_string(),
-        distribution_model: "distribution".to_string(),
-        collector_contract: "collector".to_string(),
-        distributor_contract: "distributor".to_string(),
-    };
     let info = mock_info("addr0000", &[]);
-    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let _res = handle(deps.as_mut(), mock_env(), info, register_contracts_msg()).unwrap();
 
     // Must deposit stable_denom
     let msg = ExecuteMsg::DepositStable {};
@@ -453,10 +546,9 @@ fn deposit_stable() {
         }],
     );
 
-    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
-    let _uusd_string = "uusd";
+    let res = handle(deps.as_mut(), mock_env(), info, msg.clone());
     match res {
-        Err(ContractError::ZeroDeposit(_uusd_string)) => (),
+        Err(ContractError::ZeroDeposit {}) => (),
         _ => panic!("DO NOT ENTER HERE"),
     }
 
@@ -469,9 +561,9 @@ fn deposit_stable() {
         }],
     );
 
-    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    let res = handle(deps.as_mut(), mock_env(), info, msg.clone());
     match res {
-        Err(ContractError::ZeroDeposit(_uusd_string)) => (),
+        Err(ContractError::ZeroDeposit {}) => (),
         _ => panic!("DO NOT ENTER HERE"),
     }
 
@@ -484,7 +576,7 @@ fn deposit_stable() {
     );
 
     deps.querier
-        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+        .with_borrow_rate(&[(&"interest".to_string(), &one_percent_per_block())]);
     deps.querier.with_token_balances(&[(
         &"at-uusd".to_string(),
         &[(
@@ -493,14 +585,14 @@ fn deposit_stable() {
         )],
     )]);
     deps.querier.update_balance(
-        MOCK_CONTRACT_ADDR.to_string(),
+        MOCK_CONTRACT_ADDR,
         vec![Coin {
             denom: "uusd".to_string(),
             amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
         }],
     );
 
-    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    let res = handle(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
     // 1- As the last place to modify the state is compute_interest, a check on the state ensures the invocation of compute_interest.
     // However, because passed_blocks = 0, interest factor & interest accrued are also 0, and thus the values do not change
     // (looking as if the function might not have been invoked at all.)
@@ -508,15 +600,8 @@ fn deposit_stable() {
     assert_eq!(
         read_state(deps.as_ref().storage).unwrap(),
         State {
-            global_interest_index: Decimal256::one(),
-            global_reward_index: Decimal256::zero(),
-            total_liabilities: Decimal256::zero(),
-            total_reserves: Decimal256::zero(),
-            last_interest_updated: mock_env().block.height,
-            last_reward_updated: mock_env().block.height,
-            orchai_epb_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(1000000u64),
-            prev_exchange_rate: Decimal256::one(),
+            ..mock_state(Decimal256::zero(), Decimal256::zero(), mock_env().block.height)
         }
     );
 
@@ -532,35 +617,32 @@ fn deposit_stable() {
 
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: "at-uusd".to_string(),
-            funds: vec![],
-            msg: to_binary(&Cw20ExecuteMsg::Mint {
-                recipient: "addr0000".to_string(),
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("at-uusd"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Mint {
+                recipient: HumanAddr::from("addr0000"),
                 amount: Uint128::from(1000000u128),
             })
             .unwrap(),
-        }))]
+        })]
     );
 
     // make exchange rate to 50%
     store_state(
         deps.as_mut().storage,
         &State {
-            total_liabilities: Decimal256::from_uint256(50000u128),
-            total_reserves: Decimal256::from_uint256(550000u128),
-            last_interest_updated: mock_env().block.height,
-            last_reward_updated: mock_env().block.height,
-            global_interest_index: Decimal256::one(),
-            global_reward_index: Decimal256::zero(),
-            orchai_epb_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::from_ratio(1u64, 2u64),
+            ..mock_state(
+                Decimal256::from_uint256(50000u128),
+                Decimal256::from_uint256(550000u128),
+                mock_env().block.height,
+            )
         },
     )
     .unwrap();
 
-    let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+    let res = handle(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
     assert_eq!(
         res.attributes,
         vec![
@@ -573,15 +655,15 @@ fn deposit_stable() {
 
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: "at-uusd".to_string(),
-            funds: vec![],
-            msg: to_binary(&Cw20ExecuteMsg::Mint {
-                recipient: "addr0000".to_string(),
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("at-uusd"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Mint {
+                recipient: HumanAddr::from("addr0000"),
                 amount: Uint128::from(2000000u128),
             })
             .unwrap(),
-        }))]
+        })]
     );
 
     // Case: compute_interest & compute_reward with block increment
@@ -597,42 +679,38 @@ fn deposit_stable() {
     store_state(
         deps.as_mut().storage,
         &State {
-            total_liabilities: Decimal256::from_uint256(50000u128),
-            total_reserves: Decimal256::from_uint256(550000u128),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::one(),
-            global_reward_index: Decimal256::zero(),
-            orchai_epb_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::from_ratio(1u64, 2u64),
+            ..mock_state(
+                Decimal256::from_uint256(50000u128),
+                Decimal256::from_uint256(550000u128),
+                env.block.height,
+            )
         },
     )
     .unwrap();
 
     env.block.height += 100;
-    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let _res = handle(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-    // State: global_interest_index: 1
+    // State: global_interest_index: ~2
     // balance: 1000000
     // aterra_supply: 1000000
-    // total_liabilities: 100000
+    // total_liabilities: ~100000
     // total_reserves: 550000
-    // exchange_rate: 0.55
-    // mint_amount: 0.55 * 1000000 = 1,818,181
-
+    // exchange_rate: ~0.55
+    // mint_amount: 1000000 / 0.55 = 1,818,181
     assert_eq!(
         read_state(deps.as_ref().storage).unwrap(),
         State {
-            global_interest_index: Decimal256::from_uint256(2u128),
+            global_interest_index: Decimal256::from_str("1.999999999999").unwrap(),
             global_reward_index: Decimal256::from_str("0.002").unwrap(),
-            total_liabilities: Decimal256::from_uint256(100000u128),
+            total_liabilities: Decimal256::from_str("99999.99999995").unwrap(),
             total_reserves: Decimal256::from_uint256(550000u128),
             last_interest_updated: env.block.height,
             last_reward_updated: env.block.height,
-            orchai_epb_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT + 1818181),
-            prev_exchange_rate: Decimal256::from_ratio(55u64, 100u64),
+            prev_exchange_rate: Decimal256::from_str("0.54999999999995").unwrap(),
+            ..mock_state(Decimal256::zero(), Decimal256::zero(), env.block.height)
         }
     );
 }
@@ -644,47 +722,19 @@ fn redeem_stable() {
         amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
     }]);
 
-    let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        stable_denom: "uusd".to_string(),
-        aterra_code_id: 123u64,
-        orchai_epb_rate: Decimal256::one(),
-        max_borrow_factor: Decimal256::one(),
-    };
-
-    let info = mock_info(
-        "addr0000",
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-        }],
-    );
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
 
     // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     // Register anchor token contract
-    let mut token_inst_res = MsgInstantiateContractResponse::new();
-    token_inst_res.set_contract_address("at-uusd".to_string());
-    let reply_msg = Reply {
-        id: 1,
-        result: ContractResult::Ok(SubMsgExecutionResponse {
-            events: vec![],
-            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
-        }),
-    };
-    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
 
     // Register overseer contract
-    let msg = ExecuteMsg::RegisterContracts {
-        overseer_contract: "overseer".to_string(),
-        interest_model: "interest".to_string(),
-        distribution_model: "distribution".to_string(),
-        collector_contract: "collector".to_string(),
-        distributor_contract: "distributor".to_string(),
-    };
     let info = mock_info("addr0000", &[]);
-    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let _res = handle(deps.as_mut(), mock_env(), info, register_contracts_msg()).unwrap();
 
     // Deposit 1000000
     let msg = ExecuteMsg::DepositStable {};
@@ -703,14 +753,14 @@ fn redeem_stable() {
         &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1000000u128))],
     )]);
     deps.querier.update_balance(
-        MOCK_CONTRACT_ADDR.to_string(),
+        MOCK_CONTRACT_ADDR,
         vec![Coin {
             denom: "uusd".to_string(),
             amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
         }],
     );
 
-    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let _res = handle(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     deps.querier.with_token_balances(&[(
         &"at-uusd".to_string(),
@@ -719,41 +769,38 @@ fn redeem_stable() {
 
     // Redeem 1000000
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-        sender: "addr0000".to_string(),
+        sender: HumanAddr::from("addr0000"),
         amount: Uint128::from(1000000u128),
-        msg: to_binary(&Cw20HookMsg::RedeemStable {}).unwrap(),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable { queue: None }).unwrap()),
     });
     let info = mock_info("addr0000", &[]);
-    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    let res = handle(deps.as_mut(), mock_env(), info, msg.clone());
     match res {
         Err(ContractError::Unauthorized {}) => (),
         _ => panic!("DO NOT ENTER HERE"),
     }
 
     let info = mock_info("at-uusd", &[]);
-    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    let res = handle(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
     assert_eq!(
         res.messages,
         vec![
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "at-uusd".to_string(),
-                funds: vec![],
-                msg: to_binary(&Cw20ExecuteMsg::Burn {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("at-uusd"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Burn {
                     amount: Uint128::from(1000000u128),
                 })
                 .unwrap()
-            })),
-            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-                to_address: "addr0000".to_string(),
-                amount: vec![deduct_tax(
-                    deps.as_ref(),
-                    Coin {
-                        denom: "uusd".to_string(),
-                        amount: Uint128::from(1000000u128),
-                    }
-                )
-                .unwrap(),]
-            }))
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(1000000u128),
+                }],
+            })
         ]
     );
 
@@ -761,240 +808,388 @@ fn redeem_stable() {
     store_state(
         deps.as_mut().storage,
         &State {
-            total_liabilities: Decimal256::from_uint256(500000u128),
-            total_reserves: Decimal256::from_uint256(100000u128),
-            last_interest_updated: mock_env().block.height,
-            last_reward_updated: mock_env().block.height,
-            global_interest_index: Decimal256::one(),
-            global_reward_index: Decimal256::zero(),
-            orchai_epb_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(2000000u64),
-            prev_exchange_rate: Decimal256::one(),
+            ..mock_state(
+                Decimal256::from_uint256(500000u128),
+                Decimal256::from_uint256(100000u128),
+                mock_env().block.height,
+            )
         },
     )
     .unwrap();
 
     deps.querier.update_balance(
-        MOCK_CONTRACT_ADDR.to_string(),
+        MOCK_CONTRACT_ADDR,
         vec![Coin {
             denom: "uusd".to_string(),
             amount: Uint128::from(500000u128),
         }],
     );
 
-    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone());
-    let _uusd_string = "uusd";
-    println!("{:?}", res);
+    let res = handle(deps.as_mut(), mock_env(), info.clone(), msg.clone());
     match res {
-        Err(ContractError::NoStableAvailable(_uusd_string)) => (),
+        Err(ContractError::NoStableAvailable {}) => (),
         _ => panic!("DO NOT ENTER HERE"),
     }
 
     deps.querier.update_balance(
-        MOCK_CONTRACT_ADDR.to_string(),
+        MOCK_CONTRACT_ADDR,
         vec![Coin {
             denom: "uusd".to_string(),
             amount: Uint128::from(600000u128),
         }],
     );
 
-    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let res = handle(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(
         res.messages,
         vec![
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "at-uusd".to_string(),
-                funds: vec![],
-                msg: to_binary(&Cw20ExecuteMsg::Burn {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("at-uusd"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Burn {
                     amount: Uint128::from(1000000u128),
                 })
                 .unwrap()
-            })),
-            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-                to_address: "addr0000".to_string(),
-                amount: vec![deduct_tax(
-                    deps.as_ref(),
-                    Coin {
-                        denom: "uusd".to_string(),
-                        amount: Uint128::from(500000u128),
-                    }
-                )
-                .unwrap(),]
-            }))
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(500000u128),
+                }],
+            })
         ]
     );
 }
 
 #[test]
-fn borrow_stable() {
-    let mut deps = mock_dependencies(&[Coin {
-        denom: "uusd".to_string(),
-        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-    }]);
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
-    );
-
-    let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        stable_denom: "uusd".to_string(),
-        aterra_code_id: 123u64,
-        orchai_epb_rate: Decimal256::one(),
-        max_borrow_factor: Decimal256::one(),
-    };
+fn cancel_redeem_requests() {
+    let mut deps = mock_dependencies(&[]);
 
-    let info = mock_info(
-        "addr0000",
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-        }],
-    );
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
 
     // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     // Register anchor token contract
-    let mut token_inst_res = MsgInstantiateContractResponse::new();
-    token_inst_res.set_contract_address("at-uusd".to_string());
-    let reply_msg = Reply {
-        id: 1,
-        result: ContractResult::Ok(SubMsgExecutionResponse {
-            events: vec![],
-            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
-        }),
-    };
-    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
 
     // Register overseer contract
-    let msg = ExecuteMsg::RegisterContracts {
-        overseer_contract: "overseer".to_string(),
-        interest_model: "interest".to_string(),
-        distribution_model: "distribution".to_string(),
-        collector_contract: "collector".to_string(),
-        distributor_contract: "distributor".to_string(),
-    };
-    let mut env = mock_env();
     let info = mock_info("addr0000", &[]);
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let _res = handle(deps.as_mut(), mock_env(), info, register_contracts_msg()).unwrap();
 
     deps.querier
-        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
-    deps.querier
-        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::zero())]);
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1000000u128))],
+    )]);
 
+    // all the deposits are lent out, the exchange rate is 1
     store_state(
         deps.as_mut().storage,
         &State {
-            total_liabilities: Decimal256::from_uint256(1000000u128),
-            total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::one(),
-            global_reward_index: Decimal256::zero(),
-            orchai_epb_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::from(1000000u64),
+            ..mock_state(
+                Decimal256::from_uint256(1000000u128),
+                Decimal256::zero(),
+                mock_env().block.height,
+            )
         },
     )
     .unwrap();
 
-    let msg = ExecuteMsg::BorrowStable {
-        borrow_amount: Uint256::from(500000u64),
-        to: None,
-    };
-
-    env.block.height += 100;
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    // more requests than a fill goes through are queued
+    let queue_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(100u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable { queue: Some(true) }).unwrap()),
+    });
+    let info = mock_info("at-uusd", &[]);
+    for _ in 0..35 {
+        let _res = handle(deps.as_mut(), mock_env(), info.clone(), queue_msg.clone()).unwrap();
+    }
 
-    // interest_factor = 1% * 100blocks = 1
-    // interest_accrued = 1000000
-    // global_interest_index = 2
-    // total_liabilities = 2500000
-    // total_reserves = 3000
-    // last_interest_updated = 100
-    // reward_accrued = 100
-    // global_reward_index = 0.00002
-    // last_rewards_updated = 100
+    // a cancelled request leaves the queue right away
+    let owner_info = mock_info("addr0000", &[]);
+    let _res = handle(
+        deps.as_mut(),
+        mock_env(),
+        owner_info.clone(),
+        ExecuteMsg::CancelRedeemRequest { request_id: 3 },
+    )
+    .unwrap();
+    let res: RedeemQueueResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::RedeemQueue {
+                start_after: Some(2),
+                limit: Some(2),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.requests
+            .iter()
+            .map(|request| request.request_id)
+            .collect::<Vec<u64>>(),
+        vec![4, 5]
+    );
+
+    // the cancelled slot still counts until the front passes it
+    let res: RedeemRequestResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::RedeemRequest { request_id: 4 },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.position, 3);
+
+    for request_id in (1..=35u64).filter(|request_id| *request_id != 3) {
+        let _res = handle(
+            deps.as_mut(),
+            mock_env(),
+            owner_info.clone(),
+            ExecuteMsg::CancelRedeemRequest { request_id },
+        )
+        .unwrap();
+    }
+
+    let res: RedeemQueueResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::RedeemQueue {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.requests, vec![]);
+
+    // cancelling the front moves the queue head
+    let _res = handle(deps.as_mut(), mock_env(), info.clone(), queue_msg.clone()).unwrap();
+    let _res = handle(deps.as_mut(), mock_env(), info.clone(), queue_msg).unwrap();
+    let _res = handle(
+        deps.as_mut(),
+        mock_env(),
+        owner_info,
+        ExecuteMsg::CancelRedeemRequest { request_id: 36 },
+    )
+    .unwrap();
+    let res: RedeemRequestResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::RedeemRequest { request_id: 37 },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.position, 0);
+
+    // the queue is filled by the next inflow, then redemptions are direct again
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000u128),
+        }],
+    );
+    let res = handle(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0001",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(500u128),
+            }],
+        ),
+        ExecuteMsg::DepositStable {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[1],
+        CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(100u128),
+            }],
+        })
+    );
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(100u128),
+        msg: Some(to_binary(&Cw20HookMsg::RedeemStable { queue: None }).unwrap()),
+    });
+    let res = handle(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "redeem_stable"),
+            attr("burn_amount", "100"),
+            attr("redeem_amount", "100"),
+        ]
+    );
+}
+
+#[test]
+fn borrow_stable() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
+
+    // Register overseer contract
+    let mut env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), register_contracts_msg()).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &one_percent_per_block())]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    store_state(
+        deps.as_mut().storage,
+        &mock_state(
+            Decimal256::from_uint256(1000000u128),
+            Decimal256::zero(),
+            env.block.height,
+        ),
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+        on_behalf_of: None,
+        rate_mode: None,
+        term: None,
+    };
+
+    env.block.height += 100;
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // interest_factor = ~1% * 100blocks = ~1
+    // interest_accrued = ~1000000
+    // global_interest_index = ~2
+    // total_liabilities = ~2500000
+    // total_reserves = 0
+    // last_interest_updated = 100
+    // reward_accrued = 100
+    // global_reward_index = ~0.0001
+    // last_rewards_updated = 100
     assert_eq!(
         res.attributes,
         vec![
             attr("action", "borrow_stable"),
             attr("borrower", "addr0000"),
-            attr("borrow_amount", "500000")
+            attr("delegate", "addr0000"),
+            attr("borrow_amount", "500000"),
+            attr("origination_fee", "0"),
         ]
     );
 
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "addr0000".to_string(),
-            amount: vec![deduct_tax(
-                deps.as_ref(),
-                Coin {
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("addr0000"),
+                amount: vec![Coin {
                     denom: "uusd".to_string(),
                     amount: Uint128::from(500000u128),
-                }
-            )
-            .unwrap()],
-        }))]
+                }],
+            }),
+            update_borrower_debt_msg("addr0000", true),
+        ]
     );
 
-    assert_eq!(
-        from_binary::<State>(
-            &query(
-                deps.as_ref(),
-                env.clone(),
-                QueryMsg::State { block_height: None }
-            )
-            .unwrap()
+    let state_res: StateResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::State { block_height: None },
         )
         .unwrap(),
-        State {
-            total_liabilities: Decimal256::from_uint256(2500000u128),
-            total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::from_uint256(2u128),
-            global_reward_index: Decimal256::from_str("0.0001").unwrap(),
-            orchai_epb_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
-        }
+    )
+    .unwrap();
+    assert_eq!(
+        state_res.total_liabilities,
+        Decimal256::from_str("2499999.999999").unwrap()
+    );
+    assert_eq!(state_res.total_reserves, Decimal256::zero());
+    assert_eq!(state_res.last_interest_updated, env.block.height);
+    assert_eq!(state_res.last_reward_updated, env.block.height);
+    assert_eq!(
+        state_res.global_interest_index,
+        Decimal256::from_str("1.999999999999").unwrap()
+    );
+    assert_eq!(
+        state_res.global_reward_index,
+        Decimal256::from_str("0.0001").unwrap()
     );
 
     // after 1 block state
-    assert_eq!(
-        from_binary::<State>(
-            &query(
-                deps.as_ref(),
-                mock_env(),
-                QueryMsg::State {
-                    block_height: Some(env.block.height + 1u64)
-                }
-            )
-            .unwrap()
+    let state_res: StateResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::State {
+                block_height: Some(env.block.height + 1u64),
+            },
         )
         .unwrap(),
-        State {
-            total_liabilities: Decimal256::from_uint256(2525000u128),
-            total_reserves: Decimal256::from_uint256(0u128),
-            last_interest_updated: env.block.height + 1u64,
-            last_reward_updated: env.block.height + 1u64,
-            global_interest_index: Decimal256::from_str("2.02").unwrap(),
-            global_reward_index: Decimal256::from_str("0.0001008").unwrap(),
-            orchai_epb_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
-        }
+    )
+    .unwrap();
+    assert_eq!(
+        state_res.total_liabilities,
+        Decimal256::from_str("2524999.999998965").unwrap()
+    );
+    assert_eq!(state_res.total_reserves, Decimal256::zero());
+    assert_eq!(state_res.last_interest_updated, env.block.height + 1u64);
+    assert_eq!(state_res.last_reward_updated, env.block.height + 1u64);
+    assert_eq!(
+        state_res.global_interest_index,
+        Decimal256::from_str("2.01999999999897").unwrap()
+    );
+    assert_eq!(
+        state_res.global_reward_index,
+        Decimal256::from_str("0.000100799999999999").unwrap()
     );
 
     let res = query(
         deps.as_ref(),
         mock_env(),
         QueryMsg::BorrowerInfo {
-            borrower: "addr0000".to_string(),
+            borrower: HumanAddr::from("addr0000"),
             block_height: None,
         },
     )
@@ -1005,10 +1200,14 @@ fn borrow_stable() {
         liability,
         BorrowerInfoResponse {
             borrower: "addr0000".to_string(),
-            interest_index: Decimal256::from_uint256(2u128),
+            interest_index: Decimal256::from_str("1.999999999999").unwrap(),
             reward_index: Decimal256::from_str("0.0001").unwrap(),
             loan_amount: Uint256::from(500000u64),
             pending_rewards: Decimal256::zero(),
+            rate_mode: RateMode::Variable,
+            stable_rate: Decimal256::zero(),
+            term_loan_amount: Uint256::zero(),
+            expired_term_loan_amount: Uint256::zero(),
         }
     );
 
@@ -1016,7 +1215,7 @@ fn borrow_stable() {
         deps.as_ref(),
         mock_env(),
         QueryMsg::BorrowerInfo {
-            borrower: "addr0000".to_string(),
+            borrower: HumanAddr::from("addr0000"),
             block_height: Some(env.block.height),
         },
     )
@@ -1027,20 +1226,24 @@ fn borrow_stable() {
         borrower_info,
         BorrowerInfoResponse {
             borrower: "addr0000".to_string(),
-            interest_index: Decimal256::from_uint256(2u128),
+            interest_index: Decimal256::from_str("1.999999999999").unwrap(),
             reward_index: Decimal256::from_str("0.0001").unwrap(),
             loan_amount: Uint256::from(500000u64),
             pending_rewards: Decimal256::zero(),
+            rate_mode: RateMode::Variable,
+            stable_rate: Decimal256::zero(),
+            term_loan_amount: Uint256::zero(),
+            expired_term_loan_amount: Uint256::zero(),
         }
     );
 
     // Query to future blocks
-    // interest_factor is 100%
+    // interest_factor is ~100%
     let res = query(
         deps.as_ref(),
         mock_env(),
         QueryMsg::BorrowerInfo {
-            borrower: "addr0000".to_string(),
+            borrower: HumanAddr::from("addr0000"),
             block_height: Some(env.block.height + 100),
         },
     )
@@ -1051,10 +1254,14 @@ fn borrow_stable() {
         borrower_info,
         BorrowerInfoResponse {
             borrower: "addr0000".to_string(),
-            interest_index: Decimal256::from_uint256(4u128),
-            reward_index: Decimal256::from_str("0.00018").unwrap(),
-            loan_amount: Uint256::from(1000000u64),
-            pending_rewards: Decimal256::from_uint256(20u64),
+            interest_index: Decimal256::from_str("3.999999999996").unwrap(),
+            reward_index: Decimal256::from_str("0.000179999999999992").unwrap(),
+            loan_amount: Uint256::from(999999u64),
+            pending_rewards: Decimal256::from_str("19.999980000017999981").unwrap(),
+            rate_mode: RateMode::Variable,
+            stable_rate: Decimal256::zero(),
+            term_loan_amount: Uint256::zero(),
+            expired_term_loan_amount: Uint256::zero(),
         }
     );
 
@@ -1062,8 +1269,11 @@ fn borrow_stable() {
     let msg = ExecuteMsg::BorrowStable {
         borrow_amount: Uint256::from(500001u64),
         to: None,
+        on_behalf_of: None,
+        rate_mode: None,
+        term: None,
     };
-    let res = execute(deps.as_mut(), env, info, msg);
+    let res = handle(deps.as_mut(), env, info, msg);
     match res {
         Err(ContractError::BorrowExceedsLimit(1000000)) => (),
         _ => panic!("DO NOT ENTER HERE"),
@@ -1076,52 +1286,20 @@ fn assert_max_borrow_factor() {
         denom: "uusd".to_string(),
         amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
     }]);
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
-    );
-
-    let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        stable_denom: "uusd".to_string(),
-        aterra_code_id: 123u64,
-        orchai_epb_rate: Decimal256::one(),
-        max_borrow_factor: Decimal256::percent(1),
-    };
 
-    let info = mock_info(
-        "addr0000",
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-        }],
-    );
+    let msg = instantiate_msg(Decimal256::percent(1));
+    let info = mock_info("addr0000", &[]);
 
     // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     // Register anchor token contract
-    let mut token_inst_res = MsgInstantiateContractResponse::new();
-    token_inst_res.set_contract_address("at-uusd".to_string());
-    let reply_msg = Reply {
-        id: 1,
-        result: ContractResult::Ok(SubMsgExecutionResponse {
-            events: vec![],
-            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
-        }),
-    };
-    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
 
     // Register overseer contract
-    let msg = ExecuteMsg::RegisterContracts {
-        overseer_contract: "overseer".to_string(),
-        interest_model: "interest".to_string(),
-        distribution_model: "distribution".to_string(),
-        collector_contract: "collector".to_string(),
-        distributor_contract: "distributor".to_string(),
-    };
     let info = mock_info("addr0000", &[]);
-    let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let _res = handle(deps.as_mut(), mock_env(), info.clone(), register_contracts_msg()).unwrap();
 
     deps.querier
         .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
@@ -1130,32 +1308,27 @@ fn assert_max_borrow_factor() {
 
     store_state(
         deps.as_mut().storage,
-        &State {
-            total_liabilities: Decimal256::zero(),
-            total_reserves: Decimal256::zero(),
-            last_interest_updated: mock_env().block.height,
-            last_reward_updated: mock_env().block.height,
-            global_interest_index: Decimal256::one(),
-            global_reward_index: Decimal256::zero(),
-            orchai_epb_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
-        },
+        &mock_state(Decimal256::zero(), Decimal256::zero(), mock_env().block.height),
     )
     .unwrap();
 
     let msg = ExecuteMsg::BorrowStable {
         borrow_amount: Uint256::from(10000u64),
         to: None,
+        on_behalf_of: None,
+        rate_mode: None,
+        term: None,
     };
 
-    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let res = handle(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     assert_eq!(
         res.attributes,
         vec![
             attr("action", "borrow_stable"),
             attr("borrower", "addr0000"),
-            attr("borrow_amount", "10000")
+            attr("delegate", "addr0000"),
+            attr("borrow_amount", "10000"),
+            attr("origination_fee", "0"),
         ]
     );
 
@@ -1171,135 +1344,312 @@ fn assert_max_borrow_factor() {
     let msg = ExecuteMsg::BorrowStable {
         borrow_amount: Uint256::from(1u64),
         to: None,
+        on_behalf_of: None,
+        rate_mode: None,
+        term: None,
     };
-    let res = execute(deps.as_mut(), mock_env(), info, msg);
-    let _uusd_string = "uusd";
+    let res = handle(deps.as_mut(), mock_env(), info, msg);
     match res {
-        Err(ContractError::MaxBorrowFactorReached(_uusd_string)) => (),
+        Err(ContractError::MaxBorrowFactorReached {}) => (),
         _ => panic!("DO NOT ENTER HERE"),
     }
 }
 
 #[test]
-fn repay_stable() {
+fn term_loan_caps_and_rewards() {
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
         amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
     }]);
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
-    );
-
-    let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        stable_denom: "uusd".to_string(),
-        aterra_code_id: 123u64,
-        orchai_epb_rate: Decimal256::one(),
-        max_borrow_factor: Decimal256::one(),
-    };
-
-    let info = mock_info(
-        "addr0000",
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-        }],
-    );
 
-    // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-    // Register anchor token contract
-    let mut token_inst_res = MsgInstantiateContractResponse::new();
-    token_inst_res.set_contract_address("at-uusd".to_string());
-    let reply_msg = Reply {
-        id: 1,
-        result: ContractResult::Ok(SubMsgExecutionResponse {
-            events: vec![],
-            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
-        }),
-    };
-    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
 
-    // Register overseer contract
-    let msg = ExecuteMsg::RegisterContracts {
-        overseer_contract: "overseer".to_string(),
-        interest_model: "interest".to_string(),
-        distribution_model: "distribution".to_string(),
-        collector_contract: "collector".to_string(),
-        distributor_contract: "distributor".to_string(),
-    };
-    let mut env = mock_env();
-    let mut info = mock_info("addr0000", &[]);
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let info = mock_info("addr0000", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info.clone(), register_contracts_msg()).unwrap();
 
     deps.querier
-        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::zero())]);
     deps.querier
         .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
 
+    let mut env = mock_env();
     store_state(
         deps.as_mut().storage,
-        &State {
-            total_liabilities: Decimal256::from_uint256(1000000u128),
-            total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::one(),
-            global_reward_index: Decimal256::zero(),
-            orchai_epb_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
+        &mock_state(Decimal256::zero(), Decimal256::zero(), env.block.height),
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::UpdateBorrowerCap {
+        borrower: HumanAddr::from("addr0000"),
+        borrow_cap: Some(Uint256::from(600000u64)),
+    };
+    let _res = handle(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(300000u64),
+        to: None,
+        on_behalf_of: None,
+        rate_mode: None,
+        term: Some(BLOCKS_PER_YEAR / 10),
+    };
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let interest = res
+        .attributes
+        .iter()
+        .find(|attr| attr.key == "interest")
+        .map(|attr| Uint256::from_str(&attr.value).unwrap())
+        .unwrap();
+    let term_loan_amount = Uint256::from(300000u64) + interest;
+
+    // the term loan counts against the borrower cap
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Caps {
+            borrower: Some(HumanAddr::from("addr0000")),
+            block_height: None,
         },
     )
     .unwrap();
+    let caps: CapsResponse = from_binary(&res).unwrap();
+    let headroom = Uint256::from(600000u64) - term_loan_amount;
+    assert_eq!(caps.borrower_loan_amount, Some(term_loan_amount));
+    assert_eq!(caps.borrower_headroom, Some(headroom));
 
     let msg = ExecuteMsg::BorrowStable {
-        borrow_amount: Uint256::from(500000u64),
+        borrow_amount: headroom + Uint256::one(),
         to: None,
+        on_behalf_of: None,
+        rate_mode: None,
+        term: None,
     };
+    match handle(deps.as_mut(), env.clone(), info, msg) {
+        Err(ContractError::BorrowerCapExceeded(amount)) => {
+            assert_eq!(Uint256::from(amount), headroom)
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
 
+    // term loans do not earn rewards
     env.block.height += 100;
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::State {
+            block_height: Some(env.block.height),
+        },
+    )
+    .unwrap();
+    let state: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state.global_reward_index, Decimal256::zero());
+    assert_eq!(state.last_reward_updated, env.block.height);
+}
 
-    let msg = ExecuteMsg::RepayStable {};
-    info.funds = vec![Coin {
-        denom: "ukrw".to_string(),
-        amount: Uint128::from(100000u128),
-    }];
+#[test]
+fn borrow_stable_and_call() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
 
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
-    let _uusd_string = "uusd";
-    match res {
-        Err(ContractError::ZeroRepay(_uusd_string)) => (),
-        _ => panic!("DO NOT ENTER HERE"),
-    }
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
 
-    info.funds = vec![Coin {
-        denom: "uusd".to_string(),
-        amount: Uint128::zero(),
-    }];
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-    let res2 = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
-    match res2 {
-        Err(ContractError::ZeroRepay(_uusd_string)) => (),
-        _ => panic!("DO NOT ENTER HERE"),
-    }
+    // Register anchor token contract
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
 
-    deps.querier.update_balance(
-        MOCK_CONTRACT_ADDR.to_string(),
-        vec![Coin {
+    // Register overseer contract
+    let info = mock_info("addr0000", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info.clone(), register_contracts_msg()).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    store_state(
+        deps.as_mut().storage,
+        &mock_state(Decimal256::zero(), Decimal256::zero(), mock_env().block.height),
+    )
+    .unwrap();
+
+    // the market would execute the hook itself with a native stable,
+    // minting aTokens as the aToken minter
+    let msg = ExecuteMsg::BorrowStableAndCall {
+        borrow_amount: Uint256::from(10000u64),
+        contract: HumanAddr::from("at-uusd"),
+        msg: to_binary(&Cw20HandleMsg::Mint {
+            recipient: HumanAddr::from("addr0000"),
+            amount: Uint128::from(1000000000u128),
+        })
+        .unwrap(),
+    };
+    let res = handle(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::NativeStableCallNotSupported {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // a cw20 stable is sent through the receive hook of the contract
+    let mut deps = mock_dependencies(&[]);
+    let msg = InstantiateMsg {
+        stable_info: AssetInfo::Token {
+            contract_addr: HumanAddr::from("stable"),
+        },
+        ..instantiate_msg(Decimal256::one())
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
+
+    let info = mock_info("addr0000", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info.clone(), register_contracts_msg()).unwrap();
+
+    deps.querier.with_token_balances(&[(
+        &"stable".to_string(),
+        &[(
+            &MOCK_CONTRACT_ADDR.to_string(),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    store_state(
+        deps.as_mut().storage,
+        &mock_state(Decimal256::zero(), Decimal256::zero(), mock_env().block.height),
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::BorrowStableAndCall {
+        borrow_amount: Uint256::from(10000u64),
+        contract: HumanAddr::from("router"),
+        msg: to_binary(&"swap").unwrap(),
+    };
+    let res = handle(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("stable"),
+                send: vec![],
+                msg: to_binary(&Cw20HandleMsg::Send {
+                    contract: HumanAddr::from("router"),
+                    amount: Uint128::from(10000u128),
+                    msg: Some(to_binary(&"swap").unwrap()),
+                })
+                .unwrap(),
+            }),
+            update_borrower_debt_msg("addr0000", true),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                send: vec![],
+                msg: to_binary(&ExecuteMsg::AssertBorrowLimit {
+                    borrower: HumanAddr::from("addr0000"),
+                })
+                .unwrap(),
+            }),
+        ]
+    );
+}
+
+#[test]
+fn repay_stable() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
+
+    // Register overseer contract
+    let mut env = mock_env();
+    let mut info = mock_info("addr0000", &[]);
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), register_contracts_msg()).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &one_percent_per_block())]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    store_state(
+        deps.as_mut().storage,
+        &mock_state(
+            Decimal256::from_uint256(1000000u128),
+            Decimal256::zero(),
+            env.block.height,
+        ),
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+        on_behalf_of: None,
+        rate_mode: None,
+        term: None,
+    };
+
+    env.block.height += 100;
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::RepayStable {};
+    info.sent_funds = vec![Coin {
+        denom: "ukrw".to_string(),
+        amount: Uint128::from(100000u128),
+    }];
+
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res {
+        Err(ContractError::ZeroRepay {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    info.sent_funds = vec![Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::zero(),
+    }];
+
+    let res2 = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res2 {
+        Err(ContractError::ZeroRepay {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![Coin {
             denom: "uusd".to_string(),
             amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 100000u128),
         }],
     );
 
-    info.funds = vec![Coin {
+    info.sent_funds = vec![Coin {
         denom: "uusd".to_string(),
         amount: Uint128::from(100000u128),
     }];
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
     assert_eq!(
         res.attributes,
         vec![
@@ -1310,22 +1660,22 @@ fn repay_stable() {
     );
 
     //Loan amount and Total liability have decreased according to the repayment
-    let res_loan = read_borrower_infos(deps.as_ref(), None, None)
+    let res_loan = read_borrower_infos(deps.as_ref(), None, None, env.block.height)
         .unwrap()
-        .get(0)
+        .first()
         .unwrap()
         .loan_amount;
     assert_eq!(res_loan, Uint256::from(400000u128));
     assert_eq!(
         read_state(deps.as_ref().storage).unwrap().total_liabilities,
-        Decimal256::from_uint256(2400000u128)
+        Decimal256::from_str("2399999.999999").unwrap()
     );
 
-    info.funds = vec![Coin {
+    info.sent_funds = vec![Coin {
         denom: "uusd".to_string(),
         amount: Uint128::from(500000u128),
     }];
-    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    let res = handle(deps.as_mut(), env.clone(), info, msg).unwrap();
     assert_eq!(
         res.attributes,
         vec![
@@ -1336,30 +1686,30 @@ fn repay_stable() {
     );
 
     //Loan amount and Total liability have decreased according to the repayment
-    let res_loan = read_borrower_infos(deps.as_ref(), None, None)
+    let res_loan = read_borrower_infos(deps.as_ref(), None, None, env.block.height)
         .unwrap()
-        .get(0)
+        .first()
         .unwrap()
         .loan_amount;
     assert_eq!(res_loan, Uint256::zero());
     assert_eq!(
         read_state(deps.as_ref().storage).unwrap().total_liabilities,
-        Decimal256::from_uint256(2000000u128)
+        Decimal256::from_str("1999999.999999").unwrap()
     );
 
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "addr0000".to_string(),
-            amount: vec![deduct_tax(
-                deps.as_ref(),
-                Coin {
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("addr0000"),
+                amount: vec![Coin {
                     denom: "uusd".to_string(),
                     amount: Uint128::from(100000u128),
-                }
-            )
-            .unwrap()]
-        }))]
+                }],
+            }),
+            update_borrower_debt_msg("addr0000", false),
+        ]
     );
 }
 
@@ -1369,86 +1719,51 @@ fn repay_stable_from_liquidation() {
         denom: "uusd".to_string(),
         amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
     }]);
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
-    );
-
-    let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        stable_denom: "uusd".to_string(),
-        aterra_code_id: 123u64,
-        orchai_epb_rate: Decimal256::one(),
-        max_borrow_factor: Decimal256::one(),
-    };
 
-    let info = mock_info(
-        "addr0000",
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-        }],
-    );
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
 
     // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     // Register anchor token contract
-    let mut token_inst_res = MsgInstantiateContractResponse::new();
-    token_inst_res.set_contract_address("at-uusd".to_string());
-    let reply_msg = Reply {
-        id: 1,
-        result: ContractResult::Ok(SubMsgExecutionResponse {
-            events: vec![],
-            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
-        }),
-    };
-    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
 
     // Register overseer contract
-    let msg = ExecuteMsg::RegisterContracts {
-        overseer_contract: "overseer".to_string(),
-        interest_model: "interest".to_string(),
-        distribution_model: "distribution".to_string(),
-        collector_contract: "collector".to_string(),
-        distributor_contract: "distributor".to_string(),
-    };
     let mut env = mock_env();
     let info = mock_info("addr0000", &[]);
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), register_contracts_msg()).unwrap();
 
     deps.querier
-        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+        .with_borrow_rate(&[(&"interest".to_string(), &one_percent_per_block())]);
     deps.querier
         .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
 
     store_state(
         deps.as_mut().storage,
-        &State {
-            total_liabilities: Decimal256::from_uint256(1000000u128),
-            total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::one(),
-            global_reward_index: Decimal256::zero(),
-            orchai_epb_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
-        },
+        &mock_state(
+            Decimal256::from_uint256(1000000u128),
+            Decimal256::zero(),
+            env.block.height,
+        ),
     )
     .unwrap();
 
     let msg = ExecuteMsg::BorrowStable {
         borrow_amount: Uint256::from(500000u64),
         to: None,
+        on_behalf_of: None,
+        rate_mode: None,
+        term: None,
     };
 
     env.block.height += 100;
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     // update balance to make repay
     deps.querier.update_balance(
-        MOCK_CONTRACT_ADDR.to_string(),
+        MOCK_CONTRACT_ADDR,
         vec![
             Coin {
                 denom: "ukrw".to_string(),
@@ -1466,7 +1781,7 @@ fn repay_stable_from_liquidation() {
         prev_balance: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
     };
 
-    let res = execute(deps.as_mut(), env.clone(), info, msg.clone());
+    let res = handle(deps.as_mut(), env.clone(), info, msg.clone());
     match res {
         Err(ContractError::Unauthorized {}) => (),
         _ => panic!("DO NOT ENTER HERE"),
@@ -1474,63 +1789,64 @@ fn repay_stable_from_liquidation() {
 
     let info = mock_info("overseer", &[]);
 
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
-    let _uusd_string = "uusd";
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
     match res {
-        Err(ContractError::ZeroRepay(__uusd_string)) => (),
+        Err(ContractError::ZeroRepay {}) => (),
         _ => panic!("DO NOT ENTER HERE"),
     }
 
     // update balance to make repay
     deps.querier.update_balance(
-        MOCK_CONTRACT_ADDR.to_string(),
+        MOCK_CONTRACT_ADDR,
         vec![Coin {
             denom: "uusd".to_string(),
             amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 100000u128),
         }],
     );
 
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
     assert_eq!(
         res.attributes,
         vec![
             attr("action", "repay_stable"),
             attr("borrower", "addr0000"),
             attr("repay_amount", "100000"),
+            attr("term_repay_amount", "0"),
         ]
     );
 
     deps.querier.update_balance(
-        MOCK_CONTRACT_ADDR.to_string(),
+        MOCK_CONTRACT_ADDR,
         vec![Coin {
             denom: "uusd".to_string(),
             amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 500000u128),
         }],
     );
 
-    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    let res = handle(deps.as_mut(), env, info, msg).unwrap();
     assert_eq!(
         res.attributes,
         vec![
             attr("action", "repay_stable"),
             attr("borrower", "addr0000"),
             attr("repay_amount", "400000"),
+            attr("term_repay_amount", "0"),
         ]
     );
 
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "addr0000".to_string(),
-            amount: vec![deduct_tax(
-                deps.as_ref(),
-                Coin {
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("addr0000"),
+                amount: vec![Coin {
                     denom: "uusd".to_string(),
                     amount: Uint128::from(100000u128),
-                }
-            )
-            .unwrap()]
-        }))]
+                }],
+            }),
+            update_borrower_debt_msg("addr0000", false),
+        ]
     );
 }
 
@@ -1540,107 +1856,72 @@ fn claim_rewards() {
         denom: "uusd".to_string(),
         amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
     }]);
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
-    );
-
-    let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        stable_denom: "uusd".to_string(),
-        aterra_code_id: 123u64,
-        orchai_epb_rate: Decimal256::one(),
-        max_borrow_factor: Decimal256::one(),
-    };
 
-    let info = mock_info(
-        "addr0000",
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-        }],
-    );
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
 
     // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     // Register anchor token contract
-    let mut token_inst_res = MsgInstantiateContractResponse::new();
-    token_inst_res.set_contract_address("at-uusd".to_string());
-    let reply_msg = Reply {
-        id: 1,
-        result: ContractResult::Ok(SubMsgExecutionResponse {
-            events: vec![],
-            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
-        }),
-    };
-    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
 
     // Register overseer contract
-    let msg = ExecuteMsg::RegisterContracts {
-        overseer_contract: "overseer".to_string(),
-        interest_model: "interest".to_string(),
-        distribution_model: "distribution".to_string(),
-        collector_contract: "collector".to_string(),
-        distributor_contract: "distributor".to_string(),
-    };
     let info = mock_info("addr0000", &[]);
     let mut env = mock_env();
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), register_contracts_msg()).unwrap();
 
     deps.querier
-        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+        .with_borrow_rate(&[(&"interest".to_string(), &one_percent_per_block())]);
     deps.querier
         .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
 
     store_state(
         deps.as_mut().storage,
-        &State {
-            total_liabilities: Decimal256::from_uint256(1000000u128),
-            total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::one(),
-            global_reward_index: Decimal256::zero(),
-            orchai_epb_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
-        },
+        &mock_state(
+            Decimal256::from_uint256(1000000u128),
+            Decimal256::zero(),
+            env.block.height,
+        ),
     )
     .unwrap();
 
     // zero loan claim, will return empty messages
     let msg = ExecuteMsg::ClaimRewards { to: None };
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
     assert_eq!(res.messages.len(), 0);
 
     let msg = ExecuteMsg::BorrowStable {
         borrow_amount: Uint256::from(500000u64),
         to: None,
+        on_behalf_of: None,
+        rate_mode: None,
+        term: None,
     };
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     // zero block passed
     let msg = ExecuteMsg::ClaimRewards {
-        to: Some("addr0001".to_string()),
+        to: Some(HumanAddr::from("addr0001")),
     };
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
     assert_eq!(res.messages.len(), 0);
 
     // 100 blocks passed
     env.block.height += 100;
-    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    let res = handle(deps.as_mut(), env, info, msg).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: "distributor".to_string(),
-            funds: vec![],
-            msg: to_binary(&FaucetExecuteMsg::Spend {
-                recipient: "addr0001".to_string(),
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("orchai"),
+            send: vec![],
+            msg: to_binary(&Cw20HandleMsg::Transfer {
+                recipient: HumanAddr::from("addr0001"),
                 amount: Uint128::from(33u128),
             })
             .unwrap(),
-        }))]
+        })]
     );
 
     let res: BorrowerInfoResponse = from_binary(
@@ -1648,7 +1929,7 @@ fn claim_rewards() {
             deps.as_ref(),
             mock_env(),
             QueryMsg::BorrowerInfo {
-                borrower: "addr0000".to_string(),
+                borrower: HumanAddr::from("addr0000"),
                 block_height: None,
             },
         )
@@ -1657,7 +1938,7 @@ fn claim_rewards() {
     .unwrap();
     assert_eq!(
         res.pending_rewards,
-        Decimal256::from_str("0.333333333333").unwrap()
+        Decimal256::from_str("0.333300000016333316").unwrap()
     );
     assert_eq!(
         res.reward_index,
@@ -1671,72 +1952,34 @@ fn execute_epoch_operations() {
         denom: "uusd".to_string(),
         amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
     }]);
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
-    );
-
-    let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        stable_denom: "uusd".to_string(),
-        aterra_code_id: 123u64,
-        orchai_epb_rate: Decimal256::one(),
-        max_borrow_factor: Decimal256::one(),
-    };
 
-    let info = mock_info(
-        "addr0000",
-        &[Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-        }],
-    );
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
 
     // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     // Register anchor token contract
-    let mut token_inst_res = MsgInstantiateContractResponse::new();
-    token_inst_res.set_contract_address("at-uusd".to_string());
-    let reply_msg = Reply {
-        id: 1,
-        result: ContractResult::Ok(SubMsgExecutionResponse {
-            events: vec![],
-            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
-        }),
-    };
-    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
 
     // Register overseer contract
-    let msg = ExecuteMsg::RegisterContracts {
-        overseer_contract: "overseer".to_string(),
-        interest_model: "interest".to_string(),
-        distribution_model: "distribution".to_string(),
-        collector_contract: "collector".to_string(),
-        distributor_contract: "distributor".to_string(),
-    };
     let mut env = mock_env();
     let mut info = mock_info("addr0000", &[]);
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), register_contracts_msg()).unwrap();
 
     deps.querier
-        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+        .with_borrow_rate(&[(&"interest".to_string(), &one_percent_per_block())]);
     deps.querier
         .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
 
     store_state(
         deps.as_mut().storage,
-        &State {
-            total_liabilities: Decimal256::from_uint256(1000000u128),
-            total_reserves: Decimal256::from_uint256(3000u128),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::one(),
-            global_reward_index: Decimal256::zero(),
-            orchai_epb_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
-        },
+        &mock_state(
+            Decimal256::from_uint256(1000000u128),
+            Decimal256::from_uint256(3000u128),
+            env.block.height,
+        ),
     )
     .unwrap();
 
@@ -1751,45 +1994,41 @@ fn execute_epoch_operations() {
     };
 
     // only overseer can execute this
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
     match res {
         Err(ContractError::Unauthorized {}) => (),
         _ => panic!("DO NOT ENTER HERE"),
     }
 
-    info.sender = Addr::unchecked("overseer");
-    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    info.sender = HumanAddr::from("overseer");
+    let res = handle(deps.as_mut(), env.clone(), info, msg).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "collector".to_string(),
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("collector"),
             amount: vec![Coin {
                 denom: "uusd".to_string(),
-                amount: Uint128::from(2970u128), // 1% tax
+                amount: Uint128::from(3000u128),
             }],
-        }))]
+        })]
     );
 
     let state = read_state(deps.as_ref().storage).unwrap();
     assert_eq!(
         state,
         State {
-            total_liabilities: Decimal256::from_uint256(2000000u128),
-            total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::from_uint256(2u64),
+            total_liabilities: Decimal256::from_str("1999999.999999").unwrap(),
+            global_interest_index: Decimal256::from_str("1.999999999999").unwrap(),
             global_reward_index: Decimal256::from_str("0.0001").unwrap(),
-            orchai_epb_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
+            ..mock_state(Decimal256::zero(), Decimal256::zero(), env.block.height)
         }
     );
 
     // When there is not enough balance to cover reserve
     // no message will be sent and reserve will be left as same
     deps.querier.update_balance(
-        MOCK_CONTRACT_ADDR.to_string(),
+        MOCK_CONTRACT_ADDR,
         vec![Coin {
             denom: "uusd".to_string(),
             amount: Uint128::from(2999u128),
@@ -1800,17 +2039,11 @@ fn execute_epoch_operations() {
     let info = mock_info("overseer", &[]);
     store_state(
         deps.as_mut().storage,
-        &State {
-            total_liabilities: Decimal256::from_uint256(1000000u128),
-            total_reserves: Decimal256::from_uint256(3000u128),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::one(),
-            global_reward_index: Decimal256::zero(),
-            orchai_epb_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
-        },
+        &mock_state(
+            Decimal256::from_uint256(1000000u128),
+            Decimal256::from_uint256(3000u128),
+            env.block.height,
+        ),
     )
     .unwrap();
 
@@ -1824,26 +2057,701 @@ fn execute_epoch_operations() {
         distributed_interest: Uint256::zero(),
     };
 
-    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let res = handle(deps.as_mut(), env.clone(), info, msg).unwrap();
     assert_eq!(res.messages.len(), 0);
 
     let state = read_state(deps.as_ref().storage).unwrap();
     assert_eq!(
         state,
         State {
-            total_liabilities: Decimal256::from_uint256(2000000u128),
-            total_reserves: Decimal256::from_uint256(3000u128),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::from_uint256(2u64),
+            total_liabilities: Decimal256::from_str("1999999.999999").unwrap(),
+            global_interest_index: Decimal256::from_str("1.999999999999").unwrap(),
             global_reward_index: Decimal256::from_str("0.0001").unwrap(),
-            orchai_epb_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
+            ..mock_state(
+                Decimal256::zero(),
+                Decimal256::from_uint256(3000u128),
+                env.block.height,
+            )
         }
     );
 }
 
+#[test]
+fn withdraw_reserves() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
+
+    let mut env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = handle(deps.as_mut(), env.clone(), info, register_contracts_msg()).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &one_percent_per_block())]);
+
+    let owner_info = mock_info("owner", &[]);
+    let msg = ExecuteMsg::UpdateConfig(Box::new(UpdateConfigMsg {
+        reserve_factor: Some(Decimal256::percent(10)),
+        ..update_config_msg()
+    }));
+    let _res = handle(deps.as_mut(), env.clone(), owner_info.clone(), msg).unwrap();
+
+    store_state(
+        deps.as_mut().storage,
+        &mock_state(
+            Decimal256::from_uint256(1000000u128),
+            Decimal256::zero(),
+            env.block.height,
+        ),
+    )
+    .unwrap();
+
+    // reserve_factor of the accrued interest goes to reserves
+    // interest_accrued = 999999.999999
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::State {
+            block_height: Some(env.block.height + 100),
+        },
+    )
+    .unwrap();
+    let state: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        state.total_reserves,
+        Decimal256::from_str("99999.9999999").unwrap()
+    );
+
+    store_state(
+        deps.as_mut().storage,
+        &mock_state(
+            Decimal256::from_uint256(1000000u128),
+            Decimal256::from_uint256(3000u128),
+            env.block.height,
+        ),
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::WithdrawReserves {
+        amount: Some(Uint256::from(1000u64)),
+        recipient: HumanAddr::from("gov"),
+    };
+    let res = handle(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        owner_info.clone(),
+        ExecuteMsg::WithdrawReserves {
+            amount: Some(Uint256::from(4000u64)),
+            recipient: HumanAddr::from("gov"),
+        },
+    );
+    match res {
+        Err(ContractError::InsufficientReserves(3000)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(deps.as_mut(), env.clone(), owner_info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+            to_address: HumanAddr::from("gov"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000u128),
+            }],
+        })]
+    );
+
+    env.block.height += 1;
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::ReserveHistory {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let res: ReserveHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res.history,
+        vec![ReserveHistoryElem {
+            id: 1u64,
+            action: "withdraw".to_string(),
+            amount: Uint256::from(1000u64),
+            recipient: "gov".to_string(),
+            block_height: mock_env().block.height,
+            total_reserves: Uint256::from(2000u64),
+        }]
+    );
+}
+
+#[test]
+fn pause_operations() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
+
+    let info = mock_info("addr0000", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, register_contracts_msg()).unwrap();
+
+    let env = mock_env();
+    let owner_info = mock_info("owner", &[]);
+    let guardian_info = mock_info("guardian", &[]);
+
+    let msg = ExecuteMsg::UpdateGuardian {
+        guardian: Some(HumanAddr::from("guardian")),
+    };
+    let res = handle(deps.as_mut(), env.clone(), guardian_info.clone(), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let _res = handle(deps.as_mut(), env.clone(), owner_info.clone(), msg).unwrap();
+
+    // only the owner and the guardian can pause
+    let msg = ExecuteMsg::UpdatePauseFlags {
+        deposit: Some(true),
+        redeem: None,
+        borrow: Some(true),
+        repay: None,
+        claim: None,
+    };
+    let res = handle(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let _res = handle(deps.as_mut(), env.clone(), guardian_info.clone(), msg).unwrap();
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    let res = handle(deps.as_mut(), env.clone(), info, ExecuteMsg::DepositStable {});
+    match res {
+        Err(ContractError::OperationPaused(operation)) if operation == "deposit" => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(1000u64),
+        to: None,
+        on_behalf_of: None,
+        rate_mode: None,
+        term: None,
+    };
+    let res = handle(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg);
+    match res {
+        Err(ContractError::OperationPaused(operation)) if operation == "borrow" => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the guardian cannot unpause
+    let msg = ExecuteMsg::UpdatePauseFlags {
+        deposit: Some(false),
+        redeem: None,
+        borrow: None,
+        repay: None,
+        claim: None,
+    };
+    let res = handle(deps.as_mut(), env.clone(), guardian_info, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let _res = handle(deps.as_mut(), env, owner_info, msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config_res.guardian, Some("guardian".to_string()));
+    assert_eq!(
+        config_res.pause_flags,
+        PauseFlags {
+            deposit: false,
+            redeem: false,
+            borrow: true,
+            repay: false,
+            claim: false,
+        }
+    );
+}
+
+#[test]
+fn historical_states() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
+
+    let mut env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = handle(deps.as_mut(), env.clone(), info, register_contracts_msg()).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &one_percent_per_block())]);
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[(&"addr0000".to_string(), &Uint128::from(2000000u128))],
+    )]);
+
+    store_state(
+        deps.as_mut().storage,
+        &mock_state(
+            Decimal256::from_uint256(1000000u128),
+            Decimal256::zero(),
+            env.block.height,
+        ),
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::ExecuteEpochOperations {
+        deposit_rate: Decimal256::permille(5),
+        target_deposit_rate: Decimal256::permille(5),
+        threshold_deposit_rate: Decimal256::permille(3),
+        distributed_interest: Uint256::zero(),
+    };
+    let info = mock_info("overseer", &[]);
+
+    let mut heights: Vec<u64> = vec![];
+    let mut snapshots: Vec<HistoricalStateElem> = vec![];
+    for _ in 0..2 {
+        env.block.height += 100;
+        let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+
+        // each epoch snapshots the state it leaves behind
+        let state = read_state(deps.as_ref().storage).unwrap();
+        heights.push(env.block.height);
+        snapshots.push(HistoricalStateElem {
+            block_height: env.block.height,
+            exchange_rate: state.prev_exchange_rate,
+            total_liabilities: state.total_liabilities,
+            total_reserves: state.total_reserves,
+            borrow_rate: one_percent_per_block(),
+            deposit_rate: Decimal256::permille(5),
+            orchai_epb_rate: state.orchai_epb_rate,
+        });
+    }
+    assert!(snapshots[1].exchange_rate > snapshots[0].exchange_rate);
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::HistoricalStates {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let res: HistoricalStatesResponse = from_binary(&res).unwrap();
+    assert_eq!(res.states, snapshots);
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::HistoricalStates {
+            start_after: None,
+            limit: Some(1u32),
+        },
+    )
+    .unwrap();
+    let res: HistoricalStatesResponse = from_binary(&res).unwrap();
+    assert_eq!(res.states, vec![snapshots[0].clone()]);
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::HistoricalStates {
+            start_after: Some(heights[0]),
+            limit: None,
+        },
+    )
+    .unwrap();
+    let res: HistoricalStatesResponse = from_binary(&res).unwrap();
+    assert_eq!(res.states, vec![snapshots[1].clone()]);
+}
+
+#[test]
+fn borrow_with_delegation() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
+
+    let mut env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), register_contracts_msg()).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::zero())]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    store_state(
+        deps.as_mut().storage,
+        &mock_state(Decimal256::zero(), Decimal256::zero(), env.block.height),
+    )
+    .unwrap();
+
+    let expires = Expiration::AtHeight(env.block.height + 10);
+    let msg = ExecuteMsg::ApproveDelegation {
+        delegate: HumanAddr::from("vault"),
+        amount: Uint256::from(100000u64),
+        expires: Some(expires),
+    };
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let borrow_msg = |amount: u64| ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(amount),
+        to: None,
+        on_behalf_of: Some(HumanAddr::from("addr0000")),
+        rate_mode: None,
+        term: None,
+    };
+
+    let res = handle(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), borrow_msg(1000));
+    match res {
+        Err(ContractError::DelegationNotFound {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let vault_info = mock_info("vault", &[]);
+    let res = handle(deps.as_mut(), env.clone(), vault_info.clone(), borrow_msg(200000));
+    match res {
+        Err(ContractError::DelegationExceeded(100000)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the debt lands on the grantor, the stable goes to the delegate
+    let res = handle(deps.as_mut(), env.clone(), vault_info.clone(), borrow_msg(60000)).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                to_address: HumanAddr::from("vault"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(60000u128),
+                }],
+            }),
+            update_borrower_debt_msg("addr0000", true),
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowerInfo {
+            borrower: HumanAddr::from("addr0000"),
+            block_height: None,
+        },
+    )
+    .unwrap();
+    let borrower_info: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(borrower_info.loan_amount, Uint256::from(60000u64));
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::DelegationAllowance {
+            borrower: HumanAddr::from("addr0000"),
+            delegate: HumanAddr::from("vault"),
+        },
+    )
+    .unwrap();
+    let allowance: DelegationAllowanceResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        allowance,
+        DelegationAllowanceResponse {
+            borrower: "addr0000".to_string(),
+            delegate: "vault".to_string(),
+            allowance: Uint256::from(40000u64),
+            expires,
+        }
+    );
+
+    env.block.height += 10;
+    let res = handle(deps.as_mut(), env.clone(), vault_info.clone(), borrow_msg(1000));
+    match res {
+        Err(ContractError::DelegationExpired {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::RevokeDelegation {
+        delegate: HumanAddr::from("vault"),
+    };
+    let _res = handle(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let res = handle(deps.as_mut(), env, vault_info, borrow_msg(1000));
+    match res {
+        Err(ContractError::DelegationNotFound {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn write_off_bad_debt() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), register_contracts_msg()).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::zero())]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    store_state(
+        deps.as_mut().storage,
+        &mock_state(Decimal256::zero(), Decimal256::zero(), env.block.height),
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(10000u64),
+        to: None,
+        on_behalf_of: None,
+        rate_mode: None,
+        term: None,
+    };
+    let _res = handle(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let mut state = read_state(&deps.storage).unwrap();
+    state.total_reserves = Decimal256::from_uint256(3000u64);
+    store_state(&mut deps.storage, &state).unwrap();
+    deps.querier.update_balance(
+        "overseer",
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(5000u128),
+        }],
+    );
+
+    // only positions without collaterals can be written off
+    deps.querier.with_collaterals(&[(
+        &"addr0000".to_string(),
+        &vec![("bluna".to_string(), Uint256::from(1u64))],
+    )]);
+    let msg = ExecuteMsg::WriteOffBadDebt {
+        borrower: HumanAddr::from("addr0000"),
+    };
+    let keeper_info = mock_info("keeper", &[]);
+    let res = handle(deps.as_mut(), env.clone(), keeper_info.clone(), msg.clone());
+    match res {
+        Err(ContractError::BadDebtHasCollaterals {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // reserves absorb 3000, the overseer buffer 5000
+    // and depositors the remaining 2000
+    deps.querier.with_collaterals(&[]);
+    let res = handle(deps.as_mut(), env.clone(), keeper_info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("overseer"),
+            send: vec![],
+            msg: to_binary(&OverseerExecuteMsg::CoverBadDebt {
+                amount: Uint256::from(5000u64),
+            })
+            .unwrap(),
+        })]
+    );
+
+    let state = read_state(&deps.storage).unwrap();
+    assert_eq!(state.total_liabilities, Decimal256::zero());
+    assert_eq!(state.total_reserves, Decimal256::zero());
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BadDebtWriteOffs {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let res: BadDebtWriteOffsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res.write_offs,
+        vec![BadDebtWriteOffElem {
+            id: 1u64,
+            borrower: "addr0000".to_string(),
+            amount: Uint256::from(10000u64),
+            covered_by_reserves: Uint256::from(3000u64),
+            covered_by_buffer: Uint256::from(5000u64),
+            socialized: Uint256::from(2000u64),
+            block_height: env.block.height,
+        }]
+    );
+
+    // the loan is gone, nothing is left to write off
+    let res = handle(deps.as_mut(), env, keeper_info, msg);
+    match res {
+        Err(ContractError::ZeroBadDebt {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn simulate_actions() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = instantiate_msg(Decimal256::one());
+    let info = mock_info("addr0000", &[]);
+    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("at-uusd", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, ExecuteMsg::RegisterATerra {}).unwrap();
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = handle(deps.as_mut(), env.clone(), info, register_contracts_msg()).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::zero())]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[(&"addr0001".to_string(), &Uint128::from(1000000u128))],
+    )]);
+
+    // exchange_rate = (1000000 + 1000000) / 1000000 = 2
+    let state = mock_state(
+        Decimal256::from_uint256(1000000u128),
+        Decimal256::zero(),
+        env.block.height,
+    );
+    store_state(deps.as_mut().storage, &state).unwrap();
+
+    let simulate = |msg: QueryMsg| -> SimulationResponse {
+        from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap()
+    };
+
+    assert_eq!(
+        simulate(QueryMsg::SimulateDeposit {
+            amount: Uint256::from(100000u64),
+        }),
+        SimulationResponse {
+            exchange_rate: Decimal256::from_uint256(2u64),
+            aterra_amount: Uint256::from(50000u64),
+            stable_amount: Uint256::from(100000u64),
+            utilization_ratio: Decimal256::from_ratio(10, 21),
+            borrow_rate: Decimal256::zero(),
+            loan_amount: None,
+            origination_fee: Uint256::zero(),
+            within_limits: true,
+        }
+    );
+
+    assert_eq!(
+        simulate(QueryMsg::SimulateRedeem {
+            aterra_amount: Uint256::from(100000u64),
+        }),
+        SimulationResponse {
+            exchange_rate: Decimal256::from_uint256(2u64),
+            aterra_amount: Uint256::from(100000u64),
+            stable_amount: Uint256::from(200000u64),
+            utilization_ratio: Decimal256::from_ratio(10, 18),
+            borrow_rate: Decimal256::zero(),
+            loan_amount: None,
+            origination_fee: Uint256::zero(),
+            within_limits: true,
+        }
+    );
+
+    assert_eq!(
+        simulate(QueryMsg::SimulateBorrow {
+            borrower: HumanAddr::from("addr0000"),
+            amount: Uint256::from(300000u64),
+        }),
+        SimulationResponse {
+            exchange_rate: Decimal256::from_uint256(2u64),
+            aterra_amount: Uint256::zero(),
+            stable_amount: Uint256::from(300000u64),
+            utilization_ratio: Decimal256::percent(65),
+            borrow_rate: Decimal256::zero(),
+            loan_amount: Some(Uint256::from(300000u64)),
+            origination_fee: Uint256::zero(),
+            within_limits: true,
+        }
+    );
+
+    // above the overseer borrow limit
+    let res = simulate(QueryMsg::SimulateBorrow {
+        borrower: HumanAddr::from("addr0000"),
+        amount: Uint256::from(1000001u64),
+    });
+    assert!(!res.within_limits);
+
+    // nothing to repay without a loan
+    let res = simulate(QueryMsg::SimulateRepay {
+        borrower: HumanAddr::from("addr0000"),
+        amount: Uint256::from(1000u64),
+    });
+    assert_eq!(res.stable_amount, Uint256::zero());
+    assert_eq!(res.loan_amount, Some(Uint256::zero()));
+    assert!(!res.within_limits);
+
+    // simulations never touch the state
+    assert_eq!(read_state(&deps.storage).unwrap(), state);
+}
+
 // #[test]
 // fn borrow_repay_execute_operations() {
 //     let mut deps = mock_dependencies(
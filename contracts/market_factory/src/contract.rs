@@ -0,0 +1,457 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    attr, to_binary, Binary, CanonicalAddr, CosmosMsg, Deps, DepsMut, Env, HandleResponse,
+    HumanAddr, InitResponse, MessageInfo, MigrateResponse, StdResult, WasmMsg,
+};
+
+use crate::error::ContractError;
+use crate::querier::query_atoken_contract;
+use crate::state::{
+    read_config, read_market_info, read_market_infos, read_pending_market, remove_pending_market,
+    store_config, store_market_info, store_pending_market, Config, MarketInfo, PendingMarket,
+};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use moneymarket::market::{
+    ExecuteMsg as MarketExecuteMsg, InitHook, InstantiateMsg as MarketInstantiateMsg,
+    RedeemRateLock,
+};
+use moneymarket::market_factory::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MarketResponse, MarketsResponse, MigrateMsg,
+    QueryMsg,
+};
+use moneymarket::overseer::InstantiateMsg as OverseerInstantiateMsg;
+use oraiswap::asset::AssetInfo;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn init(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> StdResult<InitResponse> {
+    store_config(
+        deps.storage,
+        &Config {
+            owner: deps.api.canonical_address(&msg.owner)?,
+            market_code_id: msg.market_code_id,
+            overseer_code_id: msg.overseer_code_id,
+            atoken_code_id: msg.atoken_code_id,
+            oracle_contract: deps.api.canonical_address(&msg.oracle_contract)?,
+            liquidation_contract: deps.api.canonical_address(&msg.liquidation_contract)?,
+            distribution_model: deps.api.canonical_address(&msg.distribution_model)?,
+            interest_model: deps.api.canonical_address(&msg.interest_model)?,
+            collector_contract: deps.api.canonical_address(&msg.collector_contract)?,
+            distributor_contract: deps.api.canonical_address(&msg.distributor_contract)?,
+            orchai_token: deps.api.canonical_address(&msg.orchai_token)?,
+        },
+    )?;
+
+    Ok(InitResponse::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn handle(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<HandleResponse, ContractError> {
+    match msg {
+        ExecuteMsg::UpdateConfig {
+            owner,
+            market_code_id,
+            overseer_code_id,
+            atoken_code_id,
+            oracle_contract,
+            liquidation_contract,
+            distribution_model,
+            interest_model,
+            collector_contract,
+            distributor_contract,
+        } => update_config(
+            deps,
+            info,
+            owner,
+            market_code_id,
+            overseer_code_id,
+            atoken_code_id,
+            oracle_contract,
+            liquidation_contract,
+            distribution_model,
+            interest_model,
+            collector_contract,
+            distributor_contract,
+        ),
+        ExecuteMsg::CreateMarket {
+            stable_info,
+            atoken_name,
+            atoken_symbol,
+            orchai_epb_rate,
+            max_borrow_factor,
+            deposit_cap,
+            borrow_cap,
+            reserve_factor,
+            reserve_target,
+            redeem_rate_lock,
+        } => create_market(
+            deps,
+            env,
+            info,
+            stable_info,
+            atoken_name,
+            atoken_symbol,
+            orchai_epb_rate,
+            max_borrow_factor,
+            deposit_cap,
+            borrow_cap,
+            reserve_factor,
+            reserve_target,
+            redeem_rate_lock,
+        ),
+        ExecuteMsg::RegisterMarket {} => register_market(deps, env, info),
+        ExecuteMsg::RegisterOverseer { stable_info } => register_overseer(deps, info, stable_info),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner: Option<HumanAddr>,
+    market_code_id: Option<u64>,
+    overseer_code_id: Option<u64>,
+    atoken_code_id: Option<u64>,
+    oracle_contract: Option<HumanAddr>,
+    liquidation_contract: Option<HumanAddr>,
+    distribution_model: Option<HumanAddr>,
+    interest_model: Option<HumanAddr>,
+    collector_contract: Option<HumanAddr>,
+    distributor_contract: Option<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(owner) = owner {
+        config.owner = deps.api.canonical_address(&owner)?;
+    }
+
+    if let Some(market_code_id) = market_code_id {
+        config.market_code_id = market_code_id;
+    }
+
+    if let Some(overseer_code_id) = overseer_code_id {
+        config.overseer_code_id = overseer_code_id;
+    }
+
+    if let Some(atoken_code_id) = atoken_code_id {
+        config.atoken_code_id = atoken_code_id;
+    }
+
+    if let Some(oracle_contract) = oracle_contract {
+        config.oracle_contract = deps.api.canonical_address(&oracle_contract)?;
+    }
+
+    if let Some(liquidation_contract) = liquidation_contract {
+        config.liquidation_contract = deps.api.canonical_address(&liquidation_contract)?;
+    }
+
+    if let Some(distribution_model) = distribution_model {
+        config.distribution_model = deps.api.canonical_address(&distribution_model)?;
+    }
+
+    if let Some(interest_model) = interest_model {
+        config.interest_model = deps.api.canonical_address(&interest_model)?;
+    }
+
+    if let Some(collector_contract) = collector_contract {
+        config.collector_contract = deps.api.canonical_address(&collector_contract)?;
+    }
+
+    if let Some(distributor_contract) = distributor_contract {
+        config.distributor_contract = deps.api.canonical_address(&distributor_contract)?;
+    }
+
+    store_config(deps.storage, &config)?;
+    Ok(HandleResponse {
+        attributes: vec![attr("action", "update_config")],
+        messages: vec![],
+        data: None,
+    })
+}
+
+/// Instantiate a market for the stable asset, the market
+/// calls back `RegisterMarket` once it is instantiated
+#[allow(clippy::too_many_arguments)]
+pub fn create_market(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    stable_info: AssetInfo,
+    atoken_name: String,
+    atoken_symbol: String,
+    orchai_epb_rate: Decimal256,
+    max_borrow_factor: Decimal256,
+    deposit_cap: Option<Uint256>,
+    borrow_cap: Option<Uint256>,
+    reserve_factor: Decimal256,
+    reserve_target: Uint256,
+    redeem_rate_lock: RedeemRateLock,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let stable_info_raw = stable_info.to_raw(deps.api)?;
+    if read_market_info(deps.storage, &stable_info_raw)?.is_some() {
+        return Err(ContractError::MarketAlreadyExists {});
+    }
+
+    store_pending_market(
+        deps.storage,
+        &PendingMarket {
+            stable_info: stable_info_raw,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "create_market"),
+            attr("stable_info", &stable_info),
+            attr("atoken_symbol", &atoken_symbol),
+        ],
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Instantiate {
+            code_id: config.market_code_id,
+            send: vec![],
+            label: Some(format!("{} market", atoken_symbol)),
+            msg: to_binary(&MarketInstantiateMsg {
+                owner_addr: deps.api.human_address(&config.owner)?,
+                stable_info,
+                orchai_code_id: config.atoken_code_id,
+                orchai_epb_rate,
+                max_borrow_factor,
+                orchai_token: deps.api.human_address(&config.orchai_token)?,
+                deposit_cap,
+                borrow_cap,
+                reserve_factor,
+                reserve_target,
+                redeem_rate_lock,
+                atoken_name,
+                atoken_symbol,
+                init_hook: Some(InitHook {
+                    contract_addr: env.contract.address,
+                    msg: to_binary(&ExecuteMsg::RegisterMarket {})?,
+                }),
+            })?,
+        })],
+        data: None,
+    })
+}
+
+/// Store the market address and instantiate its overseer, liquidating
+/// through the shared liquidation queue. The overseer calls back
+/// `RegisterOverseer` once it is instantiated
+/// Executor: market
+pub fn register_market(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let pending_market = match read_pending_market(deps.storage)? {
+        Some(pending_market) => pending_market,
+        None => return Err(ContractError::NoPendingMarket {}),
+    };
+    remove_pending_market(deps.storage);
+
+    let market_contract = info.sender;
+    store_market_info(
+        deps.storage,
+        &pending_market.stable_info,
+        &MarketInfo {
+            stable_info: pending_market.stable_info.clone(),
+            market_contract: deps.api.canonical_address(&market_contract)?,
+            liquidation_contract: config.liquidation_contract.clone(),
+            overseer_contract: CanonicalAddr::from(vec![]),
+        },
+    )?;
+
+    let stable_info = pending_market.stable_info.to_normal(deps.api)?;
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "register_market"),
+            attr("stable_info", &stable_info),
+            attr("market_contract", &market_contract),
+        ],
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Instantiate {
+            code_id: config.overseer_code_id,
+            send: vec![],
+            label: Some(format!("{} overseer", stable_info)),
+            msg: to_binary(&OverseerInstantiateMsg {
+                owner_addr: deps.api.human_address(&config.owner)?,
+                oracle_contract: deps.api.human_address(&config.oracle_contract)?,
+                market_contract,
+                liquidation_contract: deps.api.human_address(&config.liquidation_contract)?,
+                collector_contract: deps.api.human_address(&config.collector_contract)?,
+                stable_info: stable_info.clone(),
+                epoch_period: None,
+                threshold_deposit_rate: None,
+                target_deposit_rate: None,
+                buffer_distribution_factor: None,
+                anc_purchase_factor: None,
+                price_timeframe: None,
+                dyn_rate_epoch: None,
+                dyn_rate_maxchange: None,
+                dyn_rate_yr_increase_expectation: None,
+                dyn_rate_min: None,
+                dyn_rate_max: None,
+                init_hook: Some(InitHook {
+                    contract_addr: env.contract.address,
+                    msg: to_binary(&ExecuteMsg::RegisterOverseer { stable_info })?,
+                }),
+            })?,
+        })],
+        data: None,
+    })
+}
+
+/// Store the overseer address and register the shared contracts to the market
+/// Executor: overseer
+pub fn register_overseer(
+    deps: DepsMut,
+    info: MessageInfo,
+    stable_info: AssetInfo,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let stable_info_raw = stable_info.to_raw(deps.api)?;
+    let mut market_info = match read_market_info(deps.storage, &stable_info_raw)? {
+        Some(market_info) => market_info,
+        None => return Err(ContractError::NoPendingMarket {}),
+    };
+
+    // overseer can be registered only once
+    if market_info.overseer_contract != CanonicalAddr::from(vec![]) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let overseer_contract = info.sender;
+    market_info.overseer_contract = deps.api.canonical_address(&overseer_contract)?;
+    store_market_info(deps.storage, &stable_info_raw, &market_info)?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "register_overseer"),
+            attr("stable_info", stable_info),
+            attr("overseer_contract", &overseer_contract),
+        ],
+        messages: vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&market_info.market_contract)?,
+            send: vec![],
+            msg: to_binary(&MarketExecuteMsg::RegisterContracts {
+                overseer_contract,
+                interest_model: deps.api.human_address(&config.interest_model)?,
+                distribution_model: deps.api.human_address(&config.distribution_model)?,
+                collector_contract: deps.api.human_address(&config.collector_contract)?,
+                distributor_contract: deps.api.human_address(&config.distributor_contract)?,
+            })?,
+        })],
+        data: None,
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Market { stable_info } => to_binary(&query_market(deps, stable_info)?),
+        QueryMsg::Markets { start_after, limit } => {
+            to_binary(&query_markets(deps, start_after, limit)?)
+        }
+    }
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(deps.storage)?;
+    Ok(ConfigResponse {
+        owner: deps.api.human_address(&config.owner)?.to_string(),
+        market_code_id: config.market_code_id,
+        overseer_code_id: config.overseer_code_id,
+        atoken_code_id: config.atoken_code_id,
+        oracle_contract: deps.api.human_address(&config.oracle_contract)?.to_string(),
+        liquidation_contract: deps
+            .api
+            .human_address(&config.liquidation_contract)?
+            .to_string(),
+        distribution_model: deps
+            .api
+            .human_address(&config.distribution_model)?
+            .to_string(),
+        interest_model: deps.api.human_address(&config.interest_model)?.to_string(),
+        collector_contract: deps
+            .api
+            .human_address(&config.collector_contract)?
+            .to_string(),
+        distributor_contract: deps
+            .api
+            .human_address(&config.distributor_contract)?
+            .to_string(),
+        orchai_token: deps.api.human_address(&config.orchai_token)?.to_string(),
+    })
+}
+
+pub fn query_market(deps: Deps, stable_info: AssetInfo) -> StdResult<MarketResponse> {
+    match read_market_info(deps.storage, &stable_info.to_raw(deps.api)?)? {
+        Some(market_info) => to_market_response(deps, market_info),
+        None => Err(cosmwasm_std::StdError::not_found("market")),
+    }
+}
+
+pub fn query_markets(
+    deps: Deps,
+    start_after: Option<AssetInfo>,
+    limit: Option<u32>,
+) -> StdResult<MarketsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(start_after.to_raw(deps.api)?)
+    } else {
+        None
+    };
+
+    let markets: Vec<MarketResponse> = read_market_infos(deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|market_info| to_market_response(deps, market_info))
+        .collect::<StdResult<Vec<MarketResponse>>>()?;
+
+    Ok(MarketsResponse { markets })
+}
+
+fn to_market_response(deps: Deps, market_info: MarketInfo) -> StdResult<MarketResponse> {
+    let market_contract = deps.api.human_address(&market_info.market_contract)?;
+    Ok(MarketResponse {
+        stable_info: market_info.stable_info.to_normal(deps.api)?,
+        atoken_contract: query_atoken_contract(deps, market_contract.clone())?,
+        market_contract: market_contract.to_string(),
+        overseer_contract: deps
+            .api
+            .human_address(&market_info.overseer_contract)?
+            .to_string(),
+        liquidation_contract: deps
+            .api
+            .human_address(&market_info.liquidation_contract)?
+            .to_string(),
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: MigrateMsg,
+) -> StdResult<MigrateResponse> {
+    Ok(MigrateResponse::default())
+}
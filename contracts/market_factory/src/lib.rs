@@ -0,0 +1,7 @@
+pub mod contract;
+pub mod error;
+pub mod querier;
+pub mod state;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,14 @@
+use cosmwasm_std::{to_binary, Deps, HumanAddr, QueryRequest, StdResult, WasmQuery};
+
+use moneymarket::market::{ConfigResponse as MarketConfigResponse, QueryMsg as MarketQueryMsg};
+
+/// Query the aToken registered to the market contract
+pub fn query_atoken_contract(deps: Deps, market_contract: HumanAddr) -> StdResult<String> {
+    let market_config: MarketConfigResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: market_contract,
+            msg: to_binary(&MarketQueryMsg::Config {})?,
+        }))?;
+
+    Ok(market_config.aterra_contract)
+}
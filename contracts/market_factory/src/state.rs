@@ -0,0 +1,110 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{CanonicalAddr, Order, StdResult, Storage};
+use cosmwasm_storage::{Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
+use oraiswap::asset::AssetInfoRaw;
+
+const KEY_CONFIG: &[u8] = b"config";
+const KEY_PENDING_MARKET: &[u8] = b"pending_market";
+
+const PREFIX_MARKET: &[u8] = b"market";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub owner: CanonicalAddr,
+    pub market_code_id: u64,
+    pub overseer_code_id: u64,
+    pub atoken_code_id: u64,
+    pub oracle_contract: CanonicalAddr,
+    pub liquidation_contract: CanonicalAddr,
+    pub distribution_model: CanonicalAddr,
+    pub interest_model: CanonicalAddr,
+    pub collector_contract: CanonicalAddr,
+    pub distributor_contract: CanonicalAddr,
+    pub orchai_token: CanonicalAddr,
+}
+
+/// Market waiting for its init hook,
+/// only lives during the CreateMarket transaction
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingMarket {
+    pub stable_info: AssetInfoRaw,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MarketInfo {
+    pub stable_info: AssetInfoRaw,
+    pub market_contract: CanonicalAddr,
+    pub liquidation_contract: CanonicalAddr,
+    pub overseer_contract: CanonicalAddr,
+}
+
+pub fn store_config(storage: &mut dyn Storage, data: &Config) -> StdResult<()> {
+    Singleton::new(storage, KEY_CONFIG).save(data)
+}
+
+pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
+    ReadonlySingleton::new(storage, KEY_CONFIG).load()
+}
+
+pub fn store_pending_market(storage: &mut dyn Storage, data: &PendingMarket) -> StdResult<()> {
+    Singleton::new(storage, KEY_PENDING_MARKET).save(data)
+}
+
+pub fn read_pending_market(storage: &dyn Storage) -> StdResult<Option<PendingMarket>> {
+    ReadonlySingleton::new(storage, KEY_PENDING_MARKET).may_load()
+}
+
+pub fn remove_pending_market(storage: &mut dyn Storage) {
+    Singleton::<PendingMarket>::new(storage, KEY_PENDING_MARKET).remove()
+}
+
+pub fn store_market_info(
+    storage: &mut dyn Storage,
+    stable_info: &AssetInfoRaw,
+    market_info: &MarketInfo,
+) -> StdResult<()> {
+    let mut market_bucket: Bucket<MarketInfo> = Bucket::new(storage, PREFIX_MARKET);
+    market_bucket.save(stable_info.as_bytes(), market_info)
+}
+
+pub fn read_market_info(
+    storage: &dyn Storage,
+    stable_info: &AssetInfoRaw,
+) -> StdResult<Option<MarketInfo>> {
+    let market_bucket: ReadonlyBucket<MarketInfo> = ReadonlyBucket::new(storage, PREFIX_MARKET);
+    market_bucket.may_load(stable_info.as_bytes())
+}
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+pub fn read_market_infos(
+    storage: &dyn Storage,
+    start_after: Option<AssetInfoRaw>,
+    limit: Option<u32>,
+) -> StdResult<Vec<MarketInfo>> {
+    let market_bucket: ReadonlyBucket<MarketInfo> = ReadonlyBucket::new(storage, PREFIX_MARKET);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    market_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (_, v) = elem?;
+            Ok(v)
+        })
+        .collect()
+}
+
+// this will set the first key after the provided key, by appending a 1 byte
+fn calc_range_start(start_after: Option<AssetInfoRaw>) -> Option<Vec<u8>> {
+    start_after.map(|stable_info| {
+        let mut v = stable_info.as_bytes().to_vec();
+        v.push(1);
+        v
+    })
+}
@@ -0,0 +1,338 @@
+use crate::contract::{handle, init, query};
+use crate::error::ContractError;
+use crate::state::read_market_info;
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{from_binary, to_binary, Api, CosmosMsg, HumanAddr, WasmMsg};
+use moneymarket::market::{
+    ExecuteMsg as MarketExecuteMsg, InitHook, InstantiateMsg as MarketInstantiateMsg,
+    RedeemRateLock,
+};
+use moneymarket::market_factory::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
+use moneymarket::overseer::InstantiateMsg as OverseerInstantiateMsg;
+use oraiswap::asset::AssetInfo;
+
+fn instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        owner: HumanAddr::from("owner0000"),
+        market_code_id: 1u64,
+        overseer_code_id: 2u64,
+        atoken_code_id: 3u64,
+        oracle_contract: HumanAddr::from("oracle"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        distribution_model: HumanAddr::from("distribution"),
+        interest_model: HumanAddr::from("interest"),
+        collector_contract: HumanAddr::from("collector"),
+        distributor_contract: HumanAddr::from("distributor"),
+        orchai_token: HumanAddr::from("orchai"),
+    }
+}
+
+fn create_market_msg(stable_info: AssetInfo) -> ExecuteMsg {
+    ExecuteMsg::CreateMarket {
+        stable_info,
+        atoken_name: "Orchai USDT".to_string(),
+        atoken_symbol: "aUSDT".to_string(),
+        orchai_epb_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::percent(95),
+        deposit_cap: Some(Uint256::from(1000000u64)),
+        borrow_cap: None,
+        reserve_factor: Decimal256::percent(10),
+        reserve_target: Uint256::from(100000u64),
+        redeem_rate_lock: RedeemRateLock::Fill,
+    }
+}
+
+fn usdt() -> AssetInfo {
+    AssetInfo::Token {
+        contract_addr: HumanAddr::from("usdt"),
+    }
+}
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("addr0000", &[]);
+    let res = init(deps.as_mut(), mock_env(), info, instantiate_msg()).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let value: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!("owner0000", value.owner.as_str());
+    assert_eq!(1u64, value.market_code_id);
+    assert_eq!(2u64, value.overseer_code_id);
+    assert_eq!(3u64, value.atoken_code_id);
+    assert_eq!("liquidation", value.liquidation_contract.as_str());
+    assert_eq!("oracle", value.oracle_contract.as_str());
+    assert_eq!("orchai", value.orchai_token.as_str());
+}
+
+#[test]
+fn update_config() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("addr0000", &[]);
+    let _res = init(deps.as_mut(), mock_env(), info, instantiate_msg()).unwrap();
+
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: Some(HumanAddr::from("owner0001")),
+        market_code_id: Some(10u64),
+        overseer_code_id: None,
+        atoken_code_id: None,
+        oracle_contract: None,
+        liquidation_contract: Some(HumanAddr::from("liquidation0001")),
+        distribution_model: None,
+        interest_model: None,
+        collector_contract: None,
+        distributor_contract: None,
+    };
+
+    // unauthorized
+    let info = mock_info("addr0000", &[]);
+    let res = handle(deps.as_mut(), mock_env(), info, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = mock_info("owner0000", &[]);
+    let _res = handle(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let value: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!("owner0001", value.owner.as_str());
+    assert_eq!(10u64, value.market_code_id);
+    assert_eq!("liquidation0001", value.liquidation_contract.as_str());
+}
+
+#[test]
+fn create_market() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("addr0000", &[]);
+    let _res = init(deps.as_mut(), mock_env(), info, instantiate_msg()).unwrap();
+
+    // only owner can create markets
+    let info = mock_info("addr0000", &[]);
+    let res = handle(deps.as_mut(), mock_env(), info, create_market_msg(usdt()));
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = mock_info("owner0000", &[]);
+    let res = handle(deps.as_mut(), mock_env(), info, create_market_msg(usdt())).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Instantiate {
+            code_id: 1u64,
+            send: vec![],
+            label: Some("aUSDT market".to_string()),
+            msg: to_binary(&MarketInstantiateMsg {
+                owner_addr: HumanAddr::from("owner0000"),
+                stable_info: usdt(),
+                orchai_code_id: 3u64,
+                orchai_epb_rate: Decimal256::one(),
+                max_borrow_factor: Decimal256::percent(95),
+                orchai_token: HumanAddr::from("orchai"),
+                deposit_cap: Some(Uint256::from(1000000u64)),
+                borrow_cap: None,
+                reserve_factor: Decimal256::percent(10),
+                reserve_target: Uint256::from(100000u64),
+                redeem_rate_lock: RedeemRateLock::Fill,
+                atoken_name: "Orchai USDT".to_string(),
+                atoken_symbol: "aUSDT".to_string(),
+                init_hook: Some(InitHook {
+                    contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                    msg: to_binary(&ExecuteMsg::RegisterMarket {}).unwrap(),
+                }),
+            })
+            .unwrap(),
+        })]
+    );
+
+    // market init hook, the overseer liquidates through the shared queue
+    let info = mock_info("market0000", &[]);
+    let res = handle(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::RegisterMarket {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Instantiate {
+            code_id: 2u64,
+            send: vec![],
+            label: Some("usdt overseer".to_string()),
+            msg: to_binary(&OverseerInstantiateMsg {
+                owner_addr: HumanAddr::from("owner0000"),
+                oracle_contract: HumanAddr::from("oracle"),
+                market_contract: HumanAddr::from("market0000"),
+                liquidation_contract: HumanAddr::from("liquidation"),
+                collector_contract: HumanAddr::from("collector"),
+                stable_info: usdt(),
+                epoch_period: None,
+                threshold_deposit_rate: None,
+                target_deposit_rate: None,
+                buffer_distribution_factor: None,
+                anc_purchase_factor: None,
+                price_timeframe: None,
+                dyn_rate_epoch: None,
+                dyn_rate_maxchange: None,
+                dyn_rate_yr_increase_expectation: None,
+                dyn_rate_min: None,
+                dyn_rate_max: None,
+                init_hook: Some(InitHook {
+                    contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                    msg: to_binary(&ExecuteMsg::RegisterOverseer {
+                        stable_info: usdt(),
+                    })
+                    .unwrap(),
+                }),
+            })
+            .unwrap(),
+        })]
+    );
+
+    // pending market is consumed
+    let res = handle(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::RegisterMarket {},
+    );
+    match res {
+        Err(ContractError::NoPendingMarket {}) => {}
+        _ => panic!("Must return no pending market error"),
+    }
+
+    // overseer init hook
+    let info = mock_info("overseer0000", &[]);
+    let msg = ExecuteMsg::RegisterOverseer {
+        stable_info: usdt(),
+    };
+    let res = handle(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("market0000"),
+            send: vec![],
+            msg: to_binary(&MarketExecuteMsg::RegisterContracts {
+                overseer_contract: HumanAddr::from("overseer0000"),
+                interest_model: HumanAddr::from("interest"),
+                distribution_model: HumanAddr::from("distribution"),
+                collector_contract: HumanAddr::from("collector"),
+                distributor_contract: HumanAddr::from("distributor"),
+            })
+            .unwrap(),
+        })]
+    );
+
+    // overseer can be registered only once
+    let res = handle(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let market_info = read_market_info(
+        deps.as_ref().storage,
+        &usdt().to_raw(deps.as_ref().api).unwrap(),
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        market_info.market_contract,
+        deps.api
+            .canonical_address(&HumanAddr::from("market0000"))
+            .unwrap()
+    );
+    assert_eq!(
+        market_info.liquidation_contract,
+        deps.api
+            .canonical_address(&HumanAddr::from("liquidation"))
+            .unwrap()
+    );
+    assert_eq!(
+        market_info.overseer_contract,
+        deps.api
+            .canonical_address(&HumanAddr::from("overseer0000"))
+            .unwrap()
+    );
+
+    // second market for the same stable is rejected
+    let info = mock_info("owner0000", &[]);
+    let res = handle(deps.as_mut(), mock_env(), info, create_market_msg(usdt()));
+    match res {
+        Err(ContractError::MarketAlreadyExists {}) => {}
+        _ => panic!("Must return market already exists error"),
+    }
+}
+
+#[test]
+fn create_native_market() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("addr0000", &[]);
+    let _res = init(deps.as_mut(), mock_env(), info, instantiate_msg()).unwrap();
+
+    let orai = AssetInfo::NativeToken {
+        denom: "orai".to_string(),
+    };
+    for (stable_info, market, overseer) in [
+        (usdt(), "market0000", "overseer0000"),
+        (orai.clone(), "market0001", "overseer0001"),
+    ] {
+        let info = mock_info("owner0000", &[]);
+        handle(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            create_market_msg(stable_info.clone()),
+        )
+        .unwrap();
+
+        let info = mock_info(market, &[]);
+        handle(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::RegisterMarket {},
+        )
+        .unwrap();
+
+        let info = mock_info(overseer, &[]);
+        let msg = ExecuteMsg::RegisterOverseer { stable_info };
+        handle(deps.as_mut(), mock_env(), info, msg).unwrap();
+    }
+
+    // both markets share the liquidation queue
+    let market_info = read_market_info(
+        deps.as_ref().storage,
+        &orai.to_raw(deps.as_ref().api).unwrap(),
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        market_info.market_contract,
+        deps.api
+            .canonical_address(&HumanAddr::from("market0001"))
+            .unwrap()
+    );
+    assert_eq!(
+        market_info.liquidation_contract,
+        deps.api
+            .canonical_address(&HumanAddr::from("liquidation"))
+            .unwrap()
+    );
+
+    let info = mock_info("owner0000", &[]);
+    let res = handle(deps.as_mut(), mock_env(), info, create_market_msg(orai));
+    match res {
+        Err(ContractError::MarketAlreadyExists {}) => {}
+        _ => panic!("Must return market already exists error"),
+    }
+}
@@ -4,8 +4,10 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use moneymarket::overseer::{
-    AllCollateralsResponse, BorrowLimitResponse, CollateralsResponse, ConfigResponse,
-    InstantiateMsg, MigrateMsg, WhitelistResponse, WhitelistResponseElem, ExecuteMsg, QueryMsg
+    AllCollateralsResponse, BorrowLimitResponse, BorrowerCategoryResponse, BorrowerHealthResponse,
+    CategoriesResponse, CollateralsResponse, ConfigResponse, Cw20HookMsg, EpochStateResponse,
+    InstantiateMsg, LiquidatablePositionsResponse, MigrateMsg, WhitelistResponse,
+    WhitelistResponseElem, ExecuteMsg, QueryMsg
 };
 
 fn main() {
@@ -16,9 +18,15 @@ fn main() {
 
     export_schema(&schema_for!(AllCollateralsResponse), &out_dir);
     export_schema(&schema_for!(BorrowLimitResponse), &out_dir);
+    export_schema(&schema_for!(BorrowerCategoryResponse), &out_dir);
+    export_schema(&schema_for!(BorrowerHealthResponse), &out_dir);
+    export_schema(&schema_for!(CategoriesResponse), &out_dir);
     export_schema(&schema_for!(CollateralsResponse), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(Cw20HookMsg), &out_dir);
+    export_schema(&schema_for!(EpochStateResponse), &out_dir);
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
+    export_schema(&schema_for!(LiquidatablePositionsResponse), &out_dir);
     export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(WhitelistResponse), &out_dir);
     export_schema(&schema_for!(WhitelistResponseElem), &out_dir);
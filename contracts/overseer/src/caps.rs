@@ -0,0 +1,369 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, CanonicalAddr, Deps, DepsMut, Env, HandleResponse, HumanAddr, MessageInfo, StdResult,
+    Storage,
+};
+
+use crate::category::{collateral_ltv_ratios, read_eligible_category};
+use crate::collateral::compute_borrow_limit;
+use crate::error::ContractError;
+use crate::querier::query_borrower_info;
+use crate::state::{
+    read_collateral_seeding, read_collateral_total, read_collaterals, read_collaterals_page,
+    read_config, read_debt_attribution, read_whitelist_elem, remove_collateral_seeding,
+    store_collateral_seeding, store_collateral_total, store_debt_attribution, store_whitelist_elem,
+    CollateralSeeding, CollateralTotal, Config, WhitelistElem,
+};
+
+use moneymarket::common::format_cap;
+use moneymarket::market::BorrowerInfoResponse;
+use moneymarket::overseer::DebtCeilingsResponse;
+use moneymarket::tokens::Tokens;
+
+pub fn update_collateral_caps(
+    deps: DepsMut,
+    info: MessageInfo,
+    collateral_token: HumanAddr,
+    collateral_cap: Option<Uint256>,
+    debt_ceiling: Option<Uint256>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let mut whitelist_elem: WhitelistElem =
+        read_whitelist_elem(deps.storage, &collateral_token_raw)?;
+    whitelist_elem.collateral_cap = collateral_cap;
+    whitelist_elem.debt_ceiling = debt_ceiling;
+    store_whitelist_elem(deps.storage, &collateral_token_raw, &whitelist_elem)?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "update_collateral_caps"),
+            attr("collateral_token", collateral_token),
+            attr("collateral_cap", format_cap(collateral_cap)),
+            attr("debt_ceiling", format_cap(debt_ceiling)),
+        ],
+        messages: vec![],
+        data: None,
+    })
+}
+
+/// Refresh the debt attributed to the collaterals of a borrower,
+/// the market calls it after every borrow and repayment
+pub fn update_borrower_debt(
+    deps: DepsMut,
+    env: Env,
+    borrower: HumanAddr,
+    enforce_ceilings: bool,
+) -> Result<HandleResponse, ContractError> {
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let collaterals: Tokens = read_collaterals(deps.storage, &borrower_raw);
+    let loan_amount = query_loan_amount(deps.as_ref(), &env, borrower.clone())?;
+    update_debt_attribution(
+        deps,
+        &env,
+        &borrower_raw,
+        &collaterals,
+        loan_amount,
+        enforce_ceilings,
+    )?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "update_borrower_debt"),
+            attr("borrower", borrower),
+            attr("loan_amount", loan_amount),
+        ],
+        messages: vec![],
+        data: None,
+    })
+}
+
+/// Count the positions opened before the collateral totals were tracked,
+/// limit positions at a time from where the previous call stopped. The
+/// seeding is started by the migration and ends with the last position
+pub fn seed_collateral_totals(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut seeding: CollateralSeeding = match read_collateral_seeding(deps.storage)? {
+        Some(seeding) => seeding,
+        None => return Err(ContractError::NoCollateralSeeding {}),
+    };
+
+    let positions = read_collaterals_page(deps.as_ref(), seeding.last_seeded.clone(), limit)?;
+    for (borrower, collaterals) in positions.iter() {
+        for collateral in collaterals.iter() {
+            let mut collateral_total: CollateralTotal =
+                read_collateral_total(deps.storage, &collateral.0)?;
+            collateral_total.total_collateral += collateral.1;
+            store_collateral_total(deps.storage, &collateral.0, &collateral_total)?;
+        }
+
+        let loan_amount =
+            query_loan_amount(deps.as_ref(), &env, deps.api.human_address(borrower)?)?;
+        update_debt_attribution(
+            deps.branch(),
+            &env,
+            borrower,
+            collaterals,
+            loan_amount,
+            false,
+        )?;
+    }
+
+    let done = match positions.last() {
+        Some((borrower, _)) => {
+            seeding.last_seeded = Some(borrower.clone());
+            store_collateral_seeding(deps.storage, &seeding)?;
+            false
+        }
+        None => {
+            remove_collateral_seeding(deps.storage);
+            true
+        }
+    };
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "seed_collateral_totals"),
+            attr("seeded_positions", positions.len()),
+            attr("done", done),
+        ],
+        messages: vec![],
+        data: None,
+    })
+}
+
+/// Whether the position of a borrower is part of the collateral totals,
+/// which is not the case before the seeding reached it
+pub(crate) fn is_position_counted(
+    storage: &dyn Storage,
+    borrower: &CanonicalAddr,
+) -> StdResult<bool> {
+    Ok(match read_collateral_seeding(storage)? {
+        Some(seeding) => match seeding.last_seeded {
+            Some(last_seeded) => borrower.as_slice() <= last_seeded.as_slice(),
+            None => false,
+        },
+        None => true,
+    })
+}
+
+pub(crate) fn query_loan_amount(deps: Deps, env: &Env, borrower: HumanAddr) -> StdResult<Uint256> {
+    let config: Config = read_config(deps.storage)?;
+    let borrower_info: BorrowerInfoResponse = query_borrower_info(
+        deps,
+        deps.api.human_address(&config.market_contract)?,
+        borrower,
+        env.block.height,
+    )?;
+
+    Ok(borrower_info.loan_amount + borrower_info.term_loan_amount)
+}
+
+/// Add locked collaterals to the totals, rejecting any
+/// collateral going over its collateral cap
+#[allow(clippy::ptr_arg)]
+pub(crate) fn increase_total_collaterals(
+    deps: DepsMut,
+    borrower: &CanonicalAddr,
+    collaterals: &Tokens,
+) -> Result<(), ContractError> {
+    if !is_position_counted(deps.storage, borrower)? {
+        return Ok(());
+    }
+
+    for collateral in collaterals.iter() {
+        let whitelist_elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
+        let mut collateral_total: CollateralTotal =
+            read_collateral_total(deps.storage, &collateral.0)?;
+        collateral_total.total_collateral += collateral.1;
+
+        if let Some(collateral_cap) = whitelist_elem.collateral_cap {
+            if collateral_total.total_collateral > collateral_cap {
+                return Err(ContractError::CollateralCapExceeded(
+                    whitelist_elem.symbol,
+                    collateral_cap.into(),
+                ));
+            }
+        }
+
+        store_collateral_total(deps.storage, &collateral.0, &collateral_total)?;
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::ptr_arg)]
+pub(crate) fn decrease_total_collaterals(
+    deps: DepsMut,
+    borrower: &CanonicalAddr,
+    collaterals: &Tokens,
+) -> StdResult<()> {
+    if !is_position_counted(deps.storage, borrower)? {
+        return Ok(());
+    }
+
+    for collateral in collaterals.iter() {
+        let mut collateral_total: CollateralTotal =
+            read_collateral_total(deps.storage, &collateral.0)?;
+        collateral_total.total_collateral = collateral_total.total_collateral
+            - std::cmp::min(collateral_total.total_collateral, collateral.1);
+        store_collateral_total(deps.storage, &collateral.0, &collateral_total)?;
+    }
+
+    Ok(())
+}
+
+/// Attribute the loan to the collaterals of a borrower pro rata to their
+/// contribution to the borrow limit and update the total debt of each
+/// collateral. With enforce_ceilings, a collateral whose attributed
+/// debt increases cannot go over its debt ceiling.
+///
+/// The attribution is refreshed on lock, unlock, liquidate, borrow and
+/// repay.
+#[allow(clippy::ptr_arg)]
+pub(crate) fn update_debt_attribution(
+    deps: DepsMut,
+    env: &Env,
+    borrower: &CanonicalAddr,
+    collaterals: &Tokens,
+    loan_amount: Uint256,
+    enforce_ceilings: bool,
+) -> Result<(), ContractError> {
+    let mut attribution =
+        compute_debt_attribution(deps.as_ref(), env, borrower, collaterals, loan_amount)?;
+    if enforce_ceilings {
+        assert_debt_ceilings(deps.as_ref(), borrower, &attribution)?;
+    }
+
+    let prev_attribution: Tokens = read_debt_attribution(deps.storage, borrower);
+    for (collateral_token, prev_debt) in prev_attribution.iter() {
+        if attribution.iter().all(|debt| debt.0 != *collateral_token) {
+            attribution.push((collateral_token.clone(), Uint256::zero()));
+        }
+
+        let mut collateral_total: CollateralTotal =
+            read_collateral_total(deps.storage, collateral_token)?;
+        collateral_total.total_debt =
+            collateral_total.total_debt - std::cmp::min(collateral_total.total_debt, *prev_debt);
+        store_collateral_total(deps.storage, collateral_token, &collateral_total)?;
+    }
+
+    for (collateral_token, debt) in attribution.iter() {
+        let mut collateral_total: CollateralTotal =
+            read_collateral_total(deps.storage, collateral_token)?;
+        collateral_total.total_debt += *debt;
+        store_collateral_total(deps.storage, collateral_token, &collateral_total)?;
+    }
+
+    attribution.retain(|debt| !debt.1.is_zero());
+    store_debt_attribution(deps.storage, borrower, &attribution)?;
+
+    Ok(())
+}
+
+/// Split the loan between the collaterals pro rata to their
+/// contribution to the borrow limit
+#[allow(clippy::ptr_arg)]
+pub(crate) fn compute_debt_attribution(
+    deps: Deps,
+    env: &Env,
+    borrower: &CanonicalAddr,
+    collaterals: &Tokens,
+    loan_amount: Uint256,
+) -> StdResult<Tokens> {
+    let (_, collateral_prices) =
+        compute_borrow_limit(deps, borrower, collaterals, Some(env.block.time))?;
+    let category = read_eligible_category(deps, borrower, collaterals, Some(env.block.time))?;
+
+    let mut contributions: Vec<Uint256> = vec![];
+    for (collateral, price) in collaterals.iter().zip(collateral_prices) {
+        let whitelist_elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
+        let (max_ltv, _) =
+            collateral_ltv_ratios(&whitelist_elem, category.as_ref(), Some(env.block.time));
+        contributions.push(collateral.1 * price * max_ltv);
+    }
+
+    let total_contribution = contributions
+        .iter()
+        .fold(Uint256::zero(), |total, contribution| total + *contribution);
+    let mut attribution: Tokens = vec![];
+    if !total_contribution.is_zero() {
+        for (collateral, contribution) in collaterals.iter().zip(contributions) {
+            let debt = loan_amount * Decimal256::from_uint256(contribution)
+                / Decimal256::from_uint256(total_contribution);
+            attribution.push((collateral.0.clone(), debt));
+        }
+    }
+
+    Ok(attribution)
+}
+
+/// A collateral whose attributed debt increases
+/// cannot go over its debt ceiling
+#[allow(clippy::ptr_arg)]
+pub(crate) fn assert_debt_ceilings(
+    deps: Deps,
+    borrower: &CanonicalAddr,
+    attribution: &Tokens,
+) -> Result<(), ContractError> {
+    let prev_attribution: Tokens = read_debt_attribution(deps.storage, borrower);
+    for (collateral_token, debt) in attribution.iter() {
+        let prev_debt = prev_attribution
+            .iter()
+            .find(|prev_debt| prev_debt.0 == *collateral_token)
+            .map_or_else(Uint256::zero, |prev_debt| prev_debt.1);
+        if *debt <= prev_debt {
+            continue;
+        }
+
+        let whitelist_elem: WhitelistElem = read_whitelist_elem(deps.storage, collateral_token)?;
+        if let Some(debt_ceiling) = whitelist_elem.debt_ceiling {
+            let collateral_total: CollateralTotal =
+                read_collateral_total(deps.storage, collateral_token)?;
+            let total_debt = collateral_total.total_debt
+                - std::cmp::min(collateral_total.total_debt, prev_debt)
+                + *debt;
+            if total_debt > debt_ceiling {
+                return Err(ContractError::DebtCeilingExceeded(
+                    whitelist_elem.symbol,
+                    debt_ceiling.into(),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether a borrower loan of loan_amount stays under
+/// the debt ceilings of its collaterals
+pub fn query_debt_ceilings(
+    deps: Deps,
+    env: Env,
+    borrower: HumanAddr,
+    loan_amount: Uint256,
+) -> StdResult<DebtCeilingsResponse> {
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let collaterals: Tokens = read_collaterals(deps.storage, &borrower_raw);
+    let attribution =
+        compute_debt_attribution(deps, &env, &borrower_raw, &collaterals, loan_amount)?;
+
+    Ok(DebtCeilingsResponse {
+        borrower: borrower.to_string(),
+        loan_amount,
+        within_ceilings: assert_debt_ceilings(deps, &borrower_raw, &attribution).is_ok(),
+    })
+}
@@ -0,0 +1,257 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    attr, CanonicalAddr, Deps, DepsMut, Env, HandleResponse, HumanAddr, MessageInfo, StdResult,
+};
+
+use crate::caps::query_loan_amount;
+use crate::collateral::compute_borrow_limit;
+use crate::contract::assert_liquidation_threshold;
+use crate::error::ContractError;
+use crate::state::{
+    read_borrower_category, read_categories, read_category, read_collaterals, read_config,
+    read_whitelist_elem, store_borrower_category, store_category, store_whitelist_elem, Category,
+    Config, WhitelistElem,
+};
+
+use moneymarket::overseer::{
+    BorrowerCategoryResponse, CategoriesResponse, CategoryExitSchedule, CategoryResponse,
+};
+use moneymarket::tokens::Tokens;
+
+/// Create or update a category, its LTV ratios apply to borrowers
+/// whose collaterals all belong to the category
+pub fn update_category(
+    deps: DepsMut,
+    info: MessageInfo,
+    category_id: u8,
+    name: String,
+    max_ltv: Decimal256,
+    liquidation_threshold: Decimal256,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    assert_liquidation_threshold(max_ltv, liquidation_threshold)?;
+
+    store_category(
+        deps.storage,
+        category_id,
+        &Category {
+            name: name.clone(),
+            max_ltv,
+            liquidation_threshold,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "update_category"),
+            attr("category_id", category_id),
+            attr("name", name),
+            attr("LTV", max_ltv.to_string()),
+            attr("liquidation_threshold", liquidation_threshold.to_string()),
+        ],
+        messages: vec![],
+        data: None,
+    })
+}
+
+/// Move a collateral to another category, the borrowers of the
+/// category it leaves ramp down to the collateral LTV ratios over
+/// ramp_period
+pub fn set_collateral_category(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collateral_token: HumanAddr,
+    category_id: Option<u8>,
+    ramp_period: u64,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(category_id) = category_id {
+        read_category(deps.storage, category_id)?;
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let mut whitelist_elem: WhitelistElem =
+        read_whitelist_elem(deps.storage, &collateral_token_raw)?;
+
+    // moving back to the category being exited cancels its ramp
+    if let Some(exit) = &whitelist_elem.category_exit {
+        if category_id == Some(exit.category_id) {
+            whitelist_elem.category_exit = None;
+        }
+    }
+
+    // a ramp in progress cannot be replaced by another one
+    if let Some(prev_category_id) = whitelist_elem.category_id {
+        if category_id != Some(prev_category_id) {
+            if let Some(exit) = &whitelist_elem.category_exit {
+                if env.block.time < exit.end_time {
+                    return Err(ContractError::CategoryExitInProgress(whitelist_elem.symbol));
+                }
+            }
+
+            whitelist_elem.category_exit = Some(CategoryExitSchedule {
+                category_id: prev_category_id,
+                start_time: env.block.time,
+                end_time: env.block.time + ramp_period,
+            });
+        }
+    }
+
+    whitelist_elem.category_id = category_id;
+    store_whitelist_elem(deps.storage, &collateral_token_raw, &whitelist_elem)?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "set_collateral_category"),
+            attr("collateral_token", collateral_token),
+            attr("category_id", format_category(category_id)),
+        ],
+        messages: vec![],
+        data: None,
+    })
+}
+
+/// Opt in or out of a category, all the collaterals of the borrower
+/// must belong to the category and the loan must stay under the
+/// resulting borrow limit
+pub fn set_category(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    category_id: Option<u8>,
+) -> Result<HandleResponse, ContractError> {
+    let borrower = info.sender;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let collaterals: Tokens = read_collaterals(deps.storage, &borrower_raw);
+
+    if let Some(category_id) = category_id {
+        read_category(deps.storage, category_id)?;
+        assert_category_collaterals(deps.as_ref(), category_id, &collaterals)?;
+    }
+
+    store_borrower_category(deps.storage, &borrower_raw, category_id)?;
+
+    let (borrow_limit, _) = compute_borrow_limit(
+        deps.as_ref(),
+        &borrower_raw,
+        &collaterals,
+        Some(env.block.time),
+    )?;
+    if borrow_limit < query_loan_amount(deps.as_ref(), &env, borrower.clone())? {
+        return Err(ContractError::CategoryChangeExceedsLimit(
+            borrow_limit.into(),
+        ));
+    }
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "set_category"),
+            attr("borrower", borrower),
+            attr("category_id", format_category(category_id)),
+        ],
+        messages: vec![],
+        data: None,
+    })
+}
+
+fn format_category(category_id: Option<u8>) -> String {
+    category_id.map_or_else(|| "none".to_string(), |category_id| category_id.to_string())
+}
+
+#[allow(clippy::ptr_arg)]
+pub(crate) fn assert_category_collaterals(
+    deps: Deps,
+    category_id: u8,
+    collaterals: &Tokens,
+) -> Result<(), ContractError> {
+    for collateral in collaterals.iter() {
+        let whitelist_elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
+        if whitelist_elem.category_id != Some(category_id) {
+            return Err(ContractError::CategoryMismatch(whitelist_elem.symbol));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the category of the borrower if all its collaterals still
+/// belong to it, the owner can move a collateral out of a category
+#[allow(clippy::ptr_arg)]
+pub(crate) fn read_eligible_category(
+    deps: Deps,
+    borrower: &CanonicalAddr,
+    collaterals: &Tokens,
+    block_time: Option<u64>,
+) -> StdResult<Option<(u8, Category)>> {
+    let category_id = match read_borrower_category(deps.storage, borrower) {
+        Some(category_id) => category_id,
+        None => return Ok(None),
+    };
+
+    // a collateral exiting the category stays eligible until its ramp ends
+    for collateral in collaterals.iter() {
+        let whitelist_elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
+        if !whitelist_elem.is_in_category(category_id, block_time) {
+            return Ok(None);
+        }
+    }
+
+    Ok(Some((
+        category_id,
+        read_category(deps.storage, category_id)?,
+    )))
+}
+
+/// LTV ratios of a collateral for a borrower, a delisted
+/// collateral always follows its ramp
+pub(crate) fn collateral_ltv_ratios(
+    whitelist_elem: &WhitelistElem,
+    category: Option<&(u8, Category)>,
+    block_time: Option<u64>,
+) -> (Decimal256, Decimal256) {
+    match category {
+        Some((category_id, category)) if whitelist_elem.delisting.is_none() => {
+            whitelist_elem.category_ltv_ratios(*category_id, category, block_time)
+        }
+        _ => whitelist_elem.ltv_ratios(block_time),
+    }
+}
+
+pub fn query_categories(deps: Deps) -> StdResult<CategoriesResponse> {
+    let categories = read_categories(deps.storage)?
+        .into_iter()
+        .map(|(category_id, category)| CategoryResponse {
+            category_id,
+            name: category.name,
+            max_ltv: category.max_ltv,
+            liquidation_threshold: category.liquidation_threshold,
+        })
+        .collect();
+
+    Ok(CategoriesResponse { categories })
+}
+
+pub fn query_borrower_category(
+    deps: Deps,
+    env: Env,
+    borrower: HumanAddr,
+) -> StdResult<BorrowerCategoryResponse> {
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let collaterals: Tokens = read_collaterals(deps.storage, &borrower_raw);
+
+    Ok(BorrowerCategoryResponse {
+        borrower: borrower.to_string(),
+        category_id: read_borrower_category(deps.storage, &borrower_raw),
+        eligible: read_eligible_category(deps, &borrower_raw, &collaterals, Some(env.block.time))?
+            .is_some(),
+    })
+}
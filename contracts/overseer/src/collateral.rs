@@ -1,14 +1,21 @@
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    attr, to_binary, CosmosMsg, Deps, DepsMut, Env, HandleResponse, HumanAddr, MessageInfo,
-    StdResult, Uint128, WasmMsg,
+    attr, to_binary, CanonicalAddr, CosmosMsg, Deps, DepsMut, Env, HandleResponse, HumanAddr,
+    MessageInfo, StdResult, WasmMsg,
 };
 
+use crate::caps::{
+    decrease_total_collaterals, increase_total_collaterals, query_loan_amount,
+    update_debt_attribution,
+};
+use crate::category::{assert_category_collaterals, collateral_ltv_ratios, read_eligible_category};
 use crate::error::ContractError;
-use crate::querier::{query_borrower_info, query_liquidation_amount};
+use crate::querier::{
+    query_borrower_info, query_exchange_rate, query_liquidation_amount, query_stable_balance,
+};
 use crate::state::{
-    read_all_collaterals, read_collaterals, read_config, read_whitelist_elem, store_collaterals,
-    Config, WhitelistElem,
+    read_all_collaterals, read_borrower_category, read_collaterals, read_collaterals_page,
+    read_config, read_whitelist_elem, store_collaterals, Config, WhitelistElem,
 };
 
 use cw20::Cw20HandleMsg;
@@ -16,12 +23,16 @@ use moneymarket::custody::ExecuteMsg as CustodyExecuteMsg;
 use moneymarket::liquidation::LiquidationAmountResponse;
 use moneymarket::market::{BorrowerInfoResponse, ExecuteMsg as MarketExecuteMsg};
 use moneymarket::oracle::PriceResponse;
-use moneymarket::overseer::{AllCollateralsResponse, BorrowLimitResponse, CollateralsResponse};
-use moneymarket::querier::{deduct_tax, query_balance, query_price, TimeConstraints};
+use moneymarket::overseer::{
+    AllCollateralsResponse, BorrowLimitResponse, BorrowerHealthResponse, CollateralHealthElem,
+    CollateralsResponse, LiquidatablePositionElem, LiquidatablePositionsResponse, PriceSource,
+};
+use moneymarket::querier::{query_price, TimeConstraints};
 use moneymarket::tokens::{Tokens, TokensHuman, TokensMath, TokensToHuman, TokensToRaw};
 
 pub fn lock_collateral(
-    deps: DepsMut,
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     collaterals_human: TokensHuman,
 ) -> Result<HandleResponse, ContractError> {
@@ -31,10 +42,34 @@ pub fn lock_collateral(
     let mut cur_collaterals: Tokens = read_collaterals(deps.storage, &borrower_raw);
 
     let collaterals: Tokens = collaterals_human.to_raw(deps.as_ref())?;
+    for collateral in collaterals.iter() {
+        let whitelist_elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
+        if whitelist_elem.delisting.is_some() {
+            return Err(ContractError::CollateralDelisted(whitelist_elem.symbol));
+        }
+    }
+
+    // a borrower in a category can only lock collaterals of the category
+    if let Some(category_id) = read_borrower_category(deps.storage, &borrower_raw) {
+        assert_category_collaterals(deps.as_ref(), category_id, &collaterals)?;
+    }
 
     cur_collaterals.add(collaterals.clone());
     store_collaterals(deps.storage, &borrower_raw, &cur_collaterals)?;
 
+    // locking never fails on a debt ceiling,
+    // it can only make a position safer
+    increase_total_collaterals(deps.branch(), &borrower_raw, &collaterals)?;
+    let loan_amount = query_loan_amount(deps.as_ref(), &env, info.sender.clone())?;
+    update_debt_attribution(
+        deps.branch(),
+        &env,
+        &borrower_raw,
+        &cur_collaterals,
+        loan_amount,
+        false,
+    )?;
+
     let mut messages: Vec<CosmosMsg> = vec![];
     for collateral in collaterals {
         let whitelist_elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
@@ -67,7 +102,7 @@ pub fn lock_collateral(
 }
 
 pub fn unlock_collateral(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     collaterals_human: TokensHuman,
@@ -86,15 +121,29 @@ pub fn unlock_collateral(
     }
 
     // Compute borrow limit with collaterals except unlock target collaterals
-    let (borrow_limit, _) =
-        compute_borrow_limit(deps.as_ref(), &cur_collaterals, Some(env.block.time))?;
+    let (borrow_limit, _) = compute_borrow_limit(
+        deps.as_ref(),
+        &borrower_raw,
+        &cur_collaterals,
+        Some(env.block.time),
+    )?;
     let borrow_amount_res: BorrowerInfoResponse =
         query_borrower_info(deps.as_ref(), market, borrower.clone(), env.block.height)?;
-    if borrow_limit < borrow_amount_res.loan_amount {
+    let loan_amount = borrow_amount_res.loan_amount + borrow_amount_res.term_loan_amount;
+    if borrow_limit < loan_amount {
         return Err(ContractError::UnlockTooLarge(borrow_limit.into()));
     }
 
     store_collaterals(deps.storage, &borrower_raw, &cur_collaterals)?;
+    decrease_total_collaterals(deps.branch(), &borrower_raw, &collaterals)?;
+    update_debt_attribution(
+        deps.branch(),
+        &env,
+        &borrower_raw,
+        &cur_collaterals,
+        loan_amount,
+        true,
+    )?;
 
     let mut messages: Vec<CosmosMsg> = vec![];
     for collateral in collaterals {
@@ -128,37 +177,37 @@ pub fn unlock_collateral(
 }
 
 pub fn liquidate_collateral(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     borrower: HumanAddr,
 ) -> Result<HandleResponse, ContractError> {
     let config: Config = read_config(deps.storage)?;
-    let market = deps.api.human_address(&config.market_contract)?;
 
     let borrower_raw = deps.api.canonical_address(&borrower)?;
     let mut cur_collaterals: Tokens = read_collaterals(deps.storage, &borrower_raw);
 
-    // Compute borrow limit with collaterals except unlock target collaterals
-    let (borrow_limit, collateral_prices) =
-        compute_borrow_limit(deps.as_ref(), &cur_collaterals, Some(env.block.time))?;
-    let borrow_amount_res: BorrowerInfoResponse =
-        query_borrower_info(deps.as_ref(), market, borrower.clone(), env.block.height)?;
-    let borrow_amount = borrow_amount_res.loan_amount;
+    let position = compute_position_limits(
+        deps.as_ref(),
+        &config,
+        &env,
+        borrower.clone(),
+        &cur_collaterals,
+    )?;
 
-    // borrow limit is equal or bigger than loan amount
+    // liquidation limit is equal or bigger than loan amount
     // cannot liquidation collaterals
-    if borrow_limit >= borrow_amount {
+    if !position.is_liquidatable() {
         return Err(ContractError::CannotLiquidateSafeLoan {});
     }
 
     let liquidation_amount_res: LiquidationAmountResponse = query_liquidation_amount(
         deps.as_ref(),
         deps.api.human_address(&config.liquidation_contract)?,
-        borrow_amount,
-        borrow_limit,
+        position.borrow_amount,
+        position.borrow_limit,
         &cur_collaterals.to_human(deps.as_ref())?,
-        collateral_prices,
+        position.collateral_prices,
     )?;
 
     let liquidation_amount = liquidation_amount_res.collaterals.to_raw(deps.as_ref())?;
@@ -166,13 +215,19 @@ pub fn liquidate_collateral(
     // Store left collaterals
     cur_collaterals.sub(liquidation_amount.clone())?;
     store_collaterals(deps.storage, &borrower_raw, &cur_collaterals)?;
+    decrease_total_collaterals(deps.branch(), &borrower_raw, &liquidation_amount)?;
+    update_debt_attribution(
+        deps.branch(),
+        &env,
+        &borrower_raw,
+        &cur_collaterals,
+        position.borrow_amount,
+        false,
+    )?;
 
     let market_contract = deps.api.human_address(&config.market_contract)?;
-    let prev_balance: Uint256 = query_balance(
-        deps.as_ref(),
-        market_contract.clone(),
-        deps.api.human_address(&config.stable_addr)?,
-    )?;
+    let prev_balance: Uint256 =
+        query_stable_balance(deps.as_ref(), &config, market_contract.clone())?;
 
     let mut liquidation_messages: Vec<CosmosMsg> = liquidation_amount
         .iter()
@@ -251,43 +306,154 @@ pub fn query_all_collaterals(
     Ok(AllCollateralsResponse { all_collaterals })
 }
 
+/// Loan and limits of a position evaluated with fresh prices
+struct PositionLimits {
+    borrow_amount: Uint256,
+    borrow_limit: Uint256,
+    liquidation_limit: Uint256,
+    collateral_prices: Vec<Decimal256>,
+}
+
+impl PositionLimits {
+    fn is_liquidatable(&self) -> bool {
+        self.liquidation_limit < self.borrow_amount
+    }
+}
+
+/// Liquidation is triggered by the liquidation limit, the liquidated
+/// amount is computed to bring the loan back under the borrow limit
+#[allow(clippy::ptr_arg)]
+fn compute_position_limits(
+    deps: Deps,
+    config: &Config,
+    env: &Env,
+    borrower: HumanAddr,
+    collaterals: &Tokens,
+) -> StdResult<PositionLimits> {
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let (liquidation_limit, _) =
+        compute_liquidation_limit(deps, &borrower_raw, collaterals, Some(env.block.time))?;
+    let (borrow_limit, collateral_prices) =
+        compute_borrow_limit(deps, &borrower_raw, collaterals, Some(env.block.time))?;
+    let borrow_amount_res: BorrowerInfoResponse = query_borrower_info(
+        deps,
+        deps.api.human_address(&config.market_contract)?,
+        borrower,
+        env.block.height,
+    )?;
+
+    Ok(PositionLimits {
+        borrow_amount: borrow_amount_res.loan_amount + borrow_amount_res.term_loan_amount,
+        borrow_limit: exclude_expired_term_loans(borrow_limit, &borrow_amount_res),
+        liquidation_limit: exclude_expired_term_loans(liquidation_limit, &borrow_amount_res),
+        collateral_prices,
+    })
+}
+
+/// Term loans past maturity and grace period are liquidatable
+/// regardless of LTV, they are excluded from the limit
+fn exclude_expired_term_loans(limit: Uint256, borrower_info: &BorrowerInfoResponse) -> Uint256 {
+    if borrower_info.expired_term_loan_amount.is_zero() {
+        return limit;
+    }
+
+    let unexpired_amount = borrower_info.loan_amount + borrower_info.term_loan_amount
+        - borrower_info.expired_term_loan_amount;
+    std::cmp::min(limit, unexpired_amount)
+}
+
 #[allow(clippy::ptr_arg)]
 pub(crate) fn compute_borrow_limit(
     deps: Deps,
+    borrower: &CanonicalAddr,
+    collaterals: &Tokens,
+    block_time: Option<u64>,
+) -> StdResult<(Uint256, Vec<Decimal256>)> {
+    let category = read_eligible_category(deps, borrower, collaterals, block_time)?;
+    compute_collaterals_limit(deps, collaterals, block_time, |elem| {
+        collateral_ltv_ratios(elem, category.as_ref(), block_time).0
+    })
+}
+
+/// Returns the loan amount above which the collaterals are
+/// liquidatable, computed with the liquidation thresholds
+#[allow(clippy::ptr_arg)]
+pub(crate) fn compute_liquidation_limit(
+    deps: Deps,
+    borrower: &CanonicalAddr,
     collaterals: &Tokens,
     block_time: Option<u64>,
+) -> StdResult<(Uint256, Vec<Decimal256>)> {
+    let category = read_eligible_category(deps, borrower, collaterals, block_time)?;
+    compute_collaterals_limit(deps, collaterals, block_time, |elem| {
+        collateral_ltv_ratios(elem, category.as_ref(), block_time).1
+    })
+}
+
+#[allow(clippy::ptr_arg)]
+fn compute_collaterals_limit(
+    deps: Deps,
+    collaterals: &Tokens,
+    block_time: Option<u64>,
+    ltv: impl Fn(&WhitelistElem) -> Decimal256,
 ) -> StdResult<(Uint256, Vec<Decimal256>)> {
     let config: Config = read_config(deps.storage)?;
     let oracle_contract = deps.api.human_address(&config.oracle_contract)?;
+    let stable_info = config.stable_info.to_normal(deps.api)?;
 
     let mut borrow_limit: Uint256 = Uint256::zero();
+    let mut atoken_borrow_limit: Uint256 = Uint256::zero();
+    let mut exchange_rate: Option<Decimal256> = None;
     let mut collateral_prices: Vec<Decimal256> = vec![];
     for collateral in collaterals.iter() {
         let collateral_token = collateral.0.clone();
         let collateral_amount = collateral.1;
-        // FIX CUNG NGUYEN NGU PRICE
-        // let price: PriceResponse = query_price(
-        //     deps,
-        //     oracle_contract.clone(),
-        //     (deps.api.human_address(&collateral_token)?).to_string(),
-        //     config.stable_denom.to_string(),
-        //     block_time.map(|block_time| TimeConstraints {
-        //         block_time,
-        //         valid_timeframe: config.price_timeframe,
-        //     }),
-        // )?;
-        let price = PriceResponse {
-            rate: Decimal256::from_ratio(3, 1),
-            last_updated_base: 1,
-            last_updated_quote: 1,
-        };
-
         let elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
+
+        // aToken is valued at the market exchange rate
+        if elem.price_source == PriceSource::ExchangeRate {
+            let rate = match exchange_rate {
+                Some(rate) => rate,
+                None => {
+                    let rate = query_exchange_rate(
+                        deps,
+                        deps.api.human_address(&config.market_contract)?,
+                    )?;
+                    exchange_rate = Some(rate);
+                    rate
+                }
+            };
+
+            atoken_borrow_limit += collateral_amount * rate * ltv(&elem);
+            collateral_prices.push(rate);
+            continue;
+        }
+
+        let price: PriceResponse = query_price(
+            deps,
+            oracle_contract.clone(),
+            deps.api.human_address(&collateral_token)?.to_string(),
+            stable_info.to_string(),
+            block_time.map(|block_time| TimeConstraints {
+                block_time,
+                valid_timeframe: config.price_timeframe,
+            }),
+        )?;
+
         let collateral_value = collateral_amount * price.rate;
-        borrow_limit += collateral_value * elem.max_ltv;
+        borrow_limit += collateral_value * ltv(&elem);
         collateral_prices.push(price.rate);
     }
 
+    // aTokens can provide at most atoken_borrow_limit_share of
+    // the borrow limit to prevent recursive borrowing
+    let share = config.atoken_borrow_limit_share;
+    if share < Decimal256::one() {
+        let atoken_borrow_limit_cap = borrow_limit * share / (Decimal256::one() - share);
+        atoken_borrow_limit = std::cmp::min(atoken_borrow_limit, atoken_borrow_limit_cap);
+    }
+    borrow_limit += atoken_borrow_limit;
+
     // returns borrow_limit with collaterals value in stable denom
     Ok((borrow_limit, collateral_prices))
 }
@@ -297,13 +463,227 @@ pub fn query_borrow_limit(
     borrower: HumanAddr,
     block_time: Option<u64>,
 ) -> StdResult<BorrowLimitResponse> {
-    let collaterals = read_collaterals(deps.storage, &deps.api.canonical_address(&borrower)?);
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let collaterals = read_collaterals(deps.storage, &borrower_raw);
 
     // Compute borrow limit with collaterals
-    let (borrow_limit, _) = compute_borrow_limit(deps, &collaterals, block_time)?;
+    let (borrow_limit, _) = compute_borrow_limit(deps, &borrower_raw, &collaterals, block_time)?;
 
     Ok(BorrowLimitResponse {
         borrower: borrower.to_string(),
         borrow_limit,
     })
 }
+
+pub fn query_borrower_health(
+    deps: Deps,
+    env: Env,
+    borrower: HumanAddr,
+) -> StdResult<BorrowerHealthResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let collaterals = read_collaterals(deps.storage, &borrower_raw);
+
+    let (borrow_limit, collateral_prices) =
+        compute_borrow_limit(deps, &borrower_raw, &collaterals, Some(env.block.time))?;
+    let (liquidation_limit, _) =
+        compute_liquidation_limit(deps, &borrower_raw, &collaterals, Some(env.block.time))?;
+    let category = read_eligible_category(deps, &borrower_raw, &collaterals, Some(env.block.time))?;
+    let borrower_info: BorrowerInfoResponse = query_borrower_info(
+        deps,
+        deps.api.human_address(&config.market_contract)?,
+        borrower.clone(),
+        env.block.height,
+    )?;
+    let loan_amount = borrower_info.loan_amount + borrower_info.term_loan_amount;
+
+    // liquidation limit contributions before the aToken share cap
+    let mut elems: Vec<(bool, Decimal256, Decimal256, Uint256)> = vec![];
+    let mut atoken_limit = Uint256::zero();
+    let mut non_atoken_limit = Uint256::zero();
+    for (collateral, price) in collaterals.iter().zip(collateral_prices.iter()) {
+        let elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
+        let (max_ltv, liquidation_threshold) =
+            collateral_ltv_ratios(&elem, category.as_ref(), Some(env.block.time));
+        let is_atoken = elem.price_source == PriceSource::ExchangeRate;
+        let contribution = collateral.1 * *price * liquidation_threshold;
+        if is_atoken {
+            atoken_limit += contribution;
+        } else {
+            non_atoken_limit += contribution;
+        }
+
+        elems.push((is_atoken, max_ltv, liquidation_threshold, contribution));
+    }
+
+    let mut collateral_elems: Vec<CollateralHealthElem> = vec![];
+    for ((collateral, price), (is_atoken, max_ltv, liquidation_threshold, contribution)) in
+        collaterals.iter().zip(collateral_prices).zip(elems)
+    {
+        let value = collateral.1 * price;
+        let (others_atoken_limit, others_non_atoken_limit) = if is_atoken {
+            (atoken_limit - contribution, non_atoken_limit)
+        } else {
+            (atoken_limit, non_atoken_limit - contribution)
+        };
+
+        collateral_elems.push(CollateralHealthElem {
+            collateral_token: deps.api.human_address(&collateral.0)?.to_string(),
+            amount: collateral.1,
+            price,
+            value,
+            borrow_limit: value * max_ltv,
+            liquidation_price: compute_liquidation_price(
+                loan_amount,
+                collateral.1 * liquidation_threshold,
+                is_atoken,
+                others_atoken_limit,
+                others_non_atoken_limit,
+                config.atoken_borrow_limit_share,
+            ),
+        });
+    }
+
+    let liquidation_limit = exclude_expired_term_loans(liquidation_limit, &borrower_info);
+    let health_factor = if loan_amount.is_zero() {
+        None
+    } else {
+        Some(Decimal256::from_uint256(liquidation_limit) / Decimal256::from_uint256(loan_amount))
+    };
+
+    Ok(BorrowerHealthResponse {
+        borrower: borrower.to_string(),
+        collaterals: collateral_elems,
+        borrow_limit: exclude_expired_term_loans(borrow_limit, &borrower_info),
+        liquidation_limit,
+        loan_amount,
+        health_factor,
+    })
+}
+
+/// Price of a collateral at which the liquidation limit falls to the loan.
+/// With the aToken share cap the limit is the minimum of the uncapped
+/// limit and non_atoken_limit / (1 - share), so a collateral price has
+/// to keep both above the loan. None when no price of the collateral
+/// moves the position across the limit.
+fn compute_liquidation_price(
+    loan_amount: Uint256,
+    liquidation_weight: Uint256,
+    is_atoken: bool,
+    others_atoken_limit: Uint256,
+    others_non_atoken_limit: Uint256,
+    atoken_borrow_limit_share: Decimal256,
+) -> Option<Decimal256> {
+    if liquidation_weight.is_zero() {
+        return None;
+    }
+
+    // the capped limit is at most non_atoken_limit / cap_factor
+    let cap_factor = if atoken_borrow_limit_share < Decimal256::one() {
+        Some(Decimal256::one() - atoken_borrow_limit_share)
+    } else {
+        None
+    };
+
+    let others_limit = others_atoken_limit + others_non_atoken_limit;
+    let mut needed_limit = loan_amount - std::cmp::min(loan_amount, others_limit);
+    if let Some(cap_factor) = cap_factor {
+        let capped_loan = loan_amount * cap_factor;
+        if is_atoken {
+            // the aToken cannot lift the limit above the cap
+            if capped_loan > others_non_atoken_limit {
+                return None;
+            }
+        } else {
+            needed_limit = std::cmp::max(
+                needed_limit,
+                capped_loan - std::cmp::min(capped_loan, others_non_atoken_limit),
+            );
+        }
+    }
+
+    if needed_limit.is_zero() {
+        None
+    } else {
+        Some(Decimal256::from_uint256(needed_limit) / Decimal256::from_uint256(liquidation_weight))
+    }
+}
+
+/// Scan a bounded page of borrowers and return the liquidatable positions,
+/// `last_scanned` is the cursor for the next page even when no position
+/// of the page is liquidatable
+pub fn query_liquidatable_positions(
+    deps: Deps,
+    env: Env,
+    start_after: Option<HumanAddr>,
+    limit: Option<u32>,
+) -> StdResult<LiquidatablePositionsResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let liquidation_contract = deps.api.human_address(&config.liquidation_contract)?;
+
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.canonical_address(&start_after)?)
+    } else {
+        None
+    };
+
+    let mut positions: Vec<LiquidatablePositionElem> = vec![];
+    let mut failed: Vec<String> = vec![];
+    let mut last_scanned: Option<String> = None;
+    for (borrower_raw, collaterals) in read_collaterals_page(deps, start_after, limit)? {
+        let borrower = deps.api.human_address(&borrower_raw)?;
+        last_scanned = Some(borrower.to_string());
+
+        // a position that cannot be evaluated must not hide the rest of the page
+        match compute_liquidatable_position(
+            deps,
+            &config,
+            &env,
+            liquidation_contract.clone(),
+            borrower.clone(),
+            &collaterals,
+        ) {
+            Ok(Some(position)) => positions.push(position),
+            Ok(None) => (),
+            Err(_) => failed.push(borrower.to_string()),
+        }
+    }
+
+    Ok(LiquidatablePositionsResponse {
+        positions,
+        last_scanned,
+        failed,
+    })
+}
+
+fn compute_liquidatable_position(
+    deps: Deps,
+    config: &Config,
+    env: &Env,
+    liquidation_contract: HumanAddr,
+    borrower: HumanAddr,
+    collaterals: &Tokens,
+) -> StdResult<Option<LiquidatablePositionElem>> {
+    let position = compute_position_limits(deps, config, env, borrower.clone(), collaterals)?;
+    if !position.is_liquidatable() {
+        return Ok(None);
+    }
+
+    let liquidation_amount_res: LiquidationAmountResponse = query_liquidation_amount(
+        deps,
+        liquidation_contract,
+        position.borrow_amount,
+        position.borrow_limit,
+        &collaterals.to_human(deps)?,
+        position.collateral_prices,
+    )?;
+
+    Ok(Some(LiquidatablePositionElem {
+        borrower: borrower.to_string(),
+        loan_amount: position.borrow_amount,
+        borrow_limit: position.borrow_limit,
+        liquidation_limit: position.liquidation_limit,
+        shortfall: position.borrow_amount - position.liquidation_limit,
+        collaterals_to_seize: liquidation_amount_res.collaterals,
+    }))
+}
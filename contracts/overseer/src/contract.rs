@@ -1,24 +1,33 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, to_binary, BankMsg, Binary, CanonicalAddr, Coin, CosmosMsg, Deps, DepsMut, Env,
-    HandleResponse, HumanAddr, InitResponse, MessageInfo, MigrateResponse, StdResult, Uint128,
-    WasmMsg,
+    attr, from_binary, to_binary, Binary, CosmosMsg, Deps, DepsMut, Env, HandleResponse, HumanAddr,
+    InitResponse, MessageInfo, MigrateResponse, StdResult, Uint128, WasmMsg,
 };
 use std::cmp::{max, min};
 
+use crate::caps::{
+    query_debt_ceilings, seed_collateral_totals, update_borrower_debt, update_collateral_caps,
+};
+use crate::category::{
+    query_borrower_category, query_categories, set_category, set_collateral_category,
+    update_category,
+};
 use crate::collateral::{
     liquidate_collateral, lock_collateral, query_all_collaterals, query_borrow_limit,
-    query_collaterals, unlock_collateral,
+    query_borrower_health, query_collaterals, query_liquidatable_positions, unlock_collateral,
 };
+use crate::deleverage::{assert_position_healthier, repay_with_collateral, update_swap_route};
 use crate::error::ContractError;
-use crate::querier::query_epoch_state;
+use crate::migration::{migrate_config, migrate_epoch_state, migrate_whitelist};
+use crate::querier::{query_atoken_contract, query_epoch_state, query_stable_balance};
 
 use crate::state::{
-    read_config, read_dynrate_config, read_dynrate_state, read_epoch_state, read_whitelist,
-    read_whitelist_elem, store_config, store_dynrate_config, store_dynrate_state,
-    store_epoch_state, store_whitelist_elem, Config, DynrateConfig, DynrateState, EpochState,
-    WhitelistElem,
+    read_collateral_total, read_config, read_dynrate_config, read_dynrate_state, read_epoch_state,
+    read_pause_info, read_whitelist, read_whitelist_elem, remove_collateral_total,
+    remove_whitelist_elem, store_collateral_seeding, store_config, store_dynrate_config,
+    store_dynrate_state, store_epoch_state, store_pause_info, store_whitelist_elem,
+    CollateralSeeding, Config, DynrateConfig, DynrateState, EpochState, PauseInfo, WhitelistElem,
 };
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
@@ -26,12 +35,14 @@ use moneymarket::custody::ExecuteMsg as CustodyExecuteMsg;
 use moneymarket::market::EpochStateResponse;
 use moneymarket::market::ExecuteMsg as MarketExecuteMsg;
 use moneymarket::overseer::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, WhitelistResponse,
-    WhitelistResponseElem,
+    BufferDistributionPolicy, ConfigResponse, Cw20HookMsg, DelistingSchedule,
+    EpochStateResponse as OverseerEpochStateResponse, ExecuteMsg, InstantiateMsg, MigrateMsg,
+    PriceSource, QueryMsg, WhitelistResponse, WhitelistResponseElem,
 };
 // use moneymarket::querier::{deduct_tax, query_balance};
-use cw20::Cw20HandleMsg;
-use moneymarket::querier::{deduct_tax, query_balance};
+use cw20::Cw20ReceiveMsg;
+use moneymarket::querier::{asset_transfer_msg, deduct_asset_tax};
+use oraiswap::asset::AssetInfoRaw;
 
 pub const BLOCKS_PER_YEAR: u64 = 6300000;
 
@@ -61,13 +72,18 @@ pub fn init(
             market_contract: deps.api.canonical_address(&msg.market_contract)?,
             liquidation_contract: deps.api.canonical_address(&msg.liquidation_contract)?,
             collector_contract: deps.api.canonical_address(&msg.collector_contract)?,
-            stable_addr: deps.api.canonical_address(&msg.stable_addr)?,
+            stable_info: msg.stable_info.to_raw(deps.api)?,
             epoch_period: epoch_period, //msg.epoch_period,
             threshold_deposit_rate: threshold_deposit_rate, // msg.threshold_deposit_rate,
             target_deposit_rate: target_deposit_rate, // msg.target_deposit_rate,
             buffer_distribution_factor: buffer_distribution_factor, // msg.buffer_distribution_factor,
             anc_purchase_factor: anc_purchase_factor,               // msg.anc_purchase_factor,
             price_timeframe: price_timeframe,                       //  msg.price_timeframe,
+            atoken_borrow_limit_share: Decimal256::percent(50u64),
+            buffer_distribution_policy: BufferDistributionPolicy::AllToDepositors,
+            min_interest_buffer: Uint256::zero(),
+            keeper_bounty_rate: Decimal256::zero(),
+            keeper_bounty_cap: Uint256::zero(),
         },
     )?;
 
@@ -90,6 +106,7 @@ pub fn init(
             prev_interest_buffer: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
             last_executed_height: env.block.height,
+            total_keeper_bounty: Uint256::zero(),
         },
     )?;
 
@@ -101,7 +118,19 @@ pub fn init(
         },
     )?;
 
-    Ok(InitResponse::default())
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if let Some(init_hook) = msg.init_hook {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: init_hook.contract_addr,
+            msg: init_hook.msg,
+            send: vec![],
+        }));
+    }
+
+    Ok(InitResponse {
+        attributes: vec![],
+        messages,
+    })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -110,7 +139,16 @@ pub fn migrate(
     env: Env,
     _info: MessageInfo,
     msg: MigrateMsg,
-) -> StdResult<MigrateResponse> {
+) -> Result<MigrateResponse, ContractError> {
+    // a legacy contract has no collateral totals yet,
+    // the owner seeds them with SeedCollateralTotals
+    if read_config(deps.storage).is_err() {
+        migrate_config(deps.storage)?;
+        migrate_whitelist(deps.storage)?;
+        migrate_epoch_state(deps.storage)?;
+        store_collateral_seeding(deps.storage, &CollateralSeeding { last_seeded: None })?;
+    }
+
     store_dynrate_config(
         deps.storage,
         &DynrateConfig {
@@ -122,11 +160,8 @@ pub fn migrate(
         },
     )?;
     let mut config = read_config(deps.storage)?;
-    let prev_yield_reserve = query_balance(
-        deps.as_ref(),
-        env.contract.address.clone(),
-        deps.api.human_address(&config.stable_addr)?,
-    )?;
+    let prev_yield_reserve =
+        query_stable_balance(deps.as_ref(), &config, env.contract.address.clone())?;
     store_dynrate_state(
         deps.storage,
         &DynrateState {
@@ -141,6 +176,7 @@ pub fn migrate(
     config.threshold_deposit_rate = new_rate;
     config.target_deposit_rate = new_rate;
     store_config(deps.storage, &config)?;
+
     Ok(MigrateResponse::default())
 }
 
@@ -152,6 +188,7 @@ pub fn handle(
     msg: ExecuteMsg,
 ) -> Result<HandleResponse, ContractError> {
     match msg {
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, info, msg),
         ExecuteMsg::UpdateConfig {
             owner_addr,
             oracle_contract,
@@ -168,6 +205,11 @@ pub fn handle(
             dyn_rate_min,
             dyn_rate_max,
             market_contract,
+            atoken_borrow_limit_share,
+            buffer_distribution_policy,
+            min_interest_buffer,
+            keeper_bounty_rate,
+            keeper_bounty_cap,
         } => update_config(
             deps,
             info,
@@ -186,6 +228,11 @@ pub fn handle(
             dyn_rate_min,
             dyn_rate_max,
             market_contract,
+            atoken_borrow_limit_share,
+            buffer_distribution_policy,
+            min_interest_buffer,
+            keeper_bounty_rate,
+            keeper_bounty_cap,
         ),
         ExecuteMsg::Whitelist {
             name,
@@ -193,45 +240,138 @@ pub fn handle(
             collateral_token,
             custody_contract,
             max_ltv,
-        } => {
-            let api = deps.api;
-            register_whitelist(
-                deps,
-                info,
-                name,
-                symbol,
-                collateral_token,
-                custody_contract,
-                max_ltv,
-            )
-        }
+            liquidation_threshold,
+        } => register_whitelist(
+            deps,
+            info,
+            name,
+            symbol,
+            collateral_token,
+            custody_contract,
+            max_ltv,
+            liquidation_threshold,
+        ),
+        ExecuteMsg::WhitelistAToken {
+            name,
+            symbol,
+            custody_contract,
+            max_ltv,
+            liquidation_threshold,
+        } => register_atoken_whitelist(
+            deps,
+            info,
+            name,
+            symbol,
+            custody_contract,
+            max_ltv,
+            liquidation_threshold,
+        ),
         ExecuteMsg::UpdateWhitelist {
             collateral_token,
             custody_contract,
             max_ltv,
-        } => {
-            let api = deps.api;
-            update_whitelist(deps, info, collateral_token, custody_contract, max_ltv)
+            liquidation_threshold,
+        } => update_whitelist(
+            deps,
+            info,
+            collateral_token,
+            custody_contract,
+            max_ltv,
+            liquidation_threshold,
+        ),
+        ExecuteMsg::UpdateCollateralCaps {
+            collateral_token,
+            collateral_cap,
+            debt_ceiling,
+        } => update_collateral_caps(deps, info, collateral_token, collateral_cap, debt_ceiling),
+        ExecuteMsg::SeedCollateralTotals { limit } => {
+            seed_collateral_totals(deps, env, info, limit)
         }
-        ExecuteMsg::ExecuteEpochOperations {} => execute_epoch_operations(deps, env),
+        ExecuteMsg::DelistCollateral {
+            collateral_token,
+            final_ltv,
+            ramp_period,
+        } => delist_collateral(deps, env, info, collateral_token, final_ltv, ramp_period),
+        ExecuteMsg::RemoveCollateral { collateral_token } => {
+            remove_collateral(deps, info, collateral_token)
+        }
+        ExecuteMsg::UpdateCategory {
+            category_id,
+            name,
+            max_ltv,
+            liquidation_threshold,
+        } => update_category(
+            deps,
+            info,
+            category_id,
+            name,
+            max_ltv,
+            liquidation_threshold,
+        ),
+        ExecuteMsg::SetCollateralCategory {
+            collateral_token,
+            category_id,
+            ramp_period,
+        } => set_collateral_category(deps, env, info, collateral_token, category_id, ramp_period),
+        ExecuteMsg::UpdateSwapRoute {
+            collateral_token,
+            swap_route,
+        } => update_swap_route(deps, info, collateral_token, swap_route),
+        ExecuteMsg::SetCategory { category_id } => set_category(deps, env, info, category_id),
+        ExecuteMsg::UpdateBorrowerDebt {
+            borrower,
+            enforce_ceilings,
+        } => update_borrower_debt(deps, env, borrower, enforce_ceilings),
+        ExecuteMsg::UpdateGuardian { guardian } => update_guardian(deps, info, guardian),
+        ExecuteMsg::UpdatePauseFlags {
+            lock,
+            unlock,
+            liquidate,
+        } => update_pause_flags(deps, info, lock, unlock, liquidate),
+        ExecuteMsg::ExecuteEpochOperations {} => execute_epoch_operations(deps, env, info),
         ExecuteMsg::UpdateEpochState {
             interest_buffer,
             distributed_interest,
         } => update_epoch_state(deps, env, info, interest_buffer, distributed_interest),
-        ExecuteMsg::LockCollateral { collaterals } => lock_collateral(deps, info, collaterals),
+        ExecuteMsg::LockCollateral { collaterals } => {
+            assert_not_paused(read_pause_info(deps.storage)?.flags.lock, "lock")?;
+            lock_collateral(deps, env, info, collaterals)
+        }
         ExecuteMsg::UnlockCollateral { collaterals } => {
+            assert_not_paused(read_pause_info(deps.storage)?.flags.unlock, "unlock")?;
             unlock_collateral(deps, env, info, collaterals)
         }
+        ExecuteMsg::RepayWithCollateral {
+            collateral_token,
+            amount,
+            min_stable_out,
+        } => {
+            assert_not_paused(read_pause_info(deps.storage)?.flags.unlock, "unlock")?;
+            repay_with_collateral(deps, env, info, collateral_token, amount, min_stable_out)
+        }
+        ExecuteMsg::AssertPositionHealthier {
+            borrower,
+            prev_loan_amount,
+            prev_borrow_limit,
+        } => assert_position_healthier(
+            deps,
+            env,
+            info,
+            borrower,
+            prev_loan_amount,
+            prev_borrow_limit,
+        ),
         ExecuteMsg::LiquidateCollateral { borrower } => {
-            let api = deps.api;
-            liquidate_collateral(
-                deps,
-                env,
-                info,
-                api.human_address(&CanonicalAddr(to_binary(&borrower)?))?,
-            )
+            assert_not_paused(read_pause_info(deps.storage)?.flags.liquidate, "liquidate")?;
+            liquidate_collateral(deps, env, info, HumanAddr::from(borrower))
         }
-        ExecuteMsg::FundReserve {} => fund_reserve(deps, info),
+        ExecuteMsg::FundReserve {} => {
+            // only a native stable can be sent along with the message,
+            // the cw20 stable funds the reserve through Receive
+            let amount = native_stable_amount(deps.as_ref(), &info)?;
+            fund_reserve(deps, amount.into())
+        }
+        ExecuteMsg::CoverBadDebt { amount } => cover_bad_debt(deps, env, info, amount),
     }
 }
 
@@ -254,6 +394,11 @@ pub fn update_config(
     dyn_rate_min: Option<Decimal256>,
     dyn_rate_max: Option<Decimal256>,
     market_contract: Option<HumanAddr>,
+    atoken_borrow_limit_share: Option<Decimal256>,
+    buffer_distribution_policy: Option<BufferDistributionPolicy>,
+    min_interest_buffer: Option<Uint256>,
+    keeper_bounty_rate: Option<Decimal256>,
+    keeper_bounty_cap: Option<Uint256>,
 ) -> Result<HandleResponse, ContractError> {
     let mut config: Config = read_config(deps.storage)?;
     let mut dynrate_config: DynrateConfig = read_dynrate_config(deps.storage)?;
@@ -322,6 +467,40 @@ pub fn update_config(
         config.market_contract = deps.api.canonical_address(&market_contract)?;
     }
 
+    if let Some(atoken_borrow_limit_share) = atoken_borrow_limit_share {
+        if atoken_borrow_limit_share > Decimal256::one() {
+            return Err(ContractError::InvalidATokenBorrowLimitShare {});
+        }
+
+        config.atoken_borrow_limit_share = atoken_borrow_limit_share;
+    }
+
+    if let Some(buffer_distribution_policy) = buffer_distribution_policy {
+        if let BufferDistributionPolicy::FixedRatio { ratio } = buffer_distribution_policy {
+            if ratio > Decimal256::one() {
+                return Err(ContractError::InvalidBufferDistributionRatio {});
+            }
+        }
+
+        config.buffer_distribution_policy = buffer_distribution_policy;
+    }
+
+    if let Some(min_interest_buffer) = min_interest_buffer {
+        config.min_interest_buffer = min_interest_buffer;
+    }
+
+    if let Some(keeper_bounty_rate) = keeper_bounty_rate {
+        if keeper_bounty_rate > Decimal256::one() {
+            return Err(ContractError::InvalidKeeperBountyRate {});
+        }
+
+        config.keeper_bounty_rate = keeper_bounty_rate;
+    }
+
+    if let Some(keeper_bounty_cap) = keeper_bounty_cap {
+        config.keeper_bounty_cap = keeper_bounty_cap;
+    }
+
     store_config(deps.storage, &config)?;
     store_dynrate_config(deps.storage, &dynrate_config)?;
     Ok(HandleResponse {
@@ -331,6 +510,20 @@ pub fn update_config(
     })
 }
 
+/// Liquidation threshold must leave a margin above max LTV,
+/// so a borrower at the limit is not liquidated on the next price tick
+pub(crate) fn assert_liquidation_threshold(
+    max_ltv: Decimal256,
+    liquidation_threshold: Decimal256,
+) -> Result<(), ContractError> {
+    if max_ltv >= liquidation_threshold || liquidation_threshold >= Decimal256::one() {
+        return Err(ContractError::InvalidLiquidationThreshold {});
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn register_whitelist(
     deps: DepsMut,
     info: MessageInfo,
@@ -339,12 +532,15 @@ pub fn register_whitelist(
     collateral_token: HumanAddr,
     custody_contract: HumanAddr,
     max_ltv: Decimal256,
+    liquidation_threshold: Decimal256,
 ) -> Result<HandleResponse, ContractError> {
     let config: Config = read_config(deps.storage)?;
     if deps.api.canonical_address(&info.sender)? != config.owner_addr {
         return Err(ContractError::Unauthorized {});
     }
 
+    assert_liquidation_threshold(max_ltv, liquidation_threshold)?;
+
     let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
     if read_whitelist_elem(deps.storage, &collateral_token_raw).is_ok() {
         return Err(ContractError::TokenAlreadyRegistered {});
@@ -358,6 +554,14 @@ pub fn register_whitelist(
             symbol: symbol.to_string(),
             custody_contract: deps.api.canonical_address(&custody_contract)?,
             max_ltv,
+            liquidation_threshold,
+            price_source: PriceSource::Oracle,
+            collateral_cap: None,
+            debt_ceiling: None,
+            delisting: None,
+            category_id: None,
+            category_exit: None,
+            swap_route: None,
         },
     )?;
     let res = HandleResponse {
@@ -368,6 +572,7 @@ pub fn register_whitelist(
             attr("collateral_token", collateral_token),
             attr("custody_contract", custody_contract),
             attr("LTV", max_ltv.to_string()),
+            attr("liquidation_threshold", liquidation_threshold.to_string()),
         ],
         messages: vec![],
         data: None,
@@ -375,12 +580,74 @@ pub fn register_whitelist(
     Ok(res)
 }
 
+/// The collateral token is always the aToken of the market,
+/// an aToken is never priced by the oracle
+pub fn register_atoken_whitelist(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+    symbol: String,
+    custody_contract: HumanAddr,
+    max_ltv: Decimal256,
+    liquidation_threshold: Decimal256,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    assert_liquidation_threshold(max_ltv, liquidation_threshold)?;
+
+    let collateral_token = query_atoken_contract(
+        deps.as_ref(),
+        deps.api.human_address(&config.market_contract)?,
+    )?;
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    if read_whitelist_elem(deps.storage, &collateral_token_raw).is_ok() {
+        return Err(ContractError::TokenAlreadyRegistered {});
+    }
+
+    store_whitelist_elem(
+        deps.storage,
+        &collateral_token_raw,
+        &WhitelistElem {
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            custody_contract: deps.api.canonical_address(&custody_contract)?,
+            max_ltv,
+            liquidation_threshold,
+            price_source: PriceSource::ExchangeRate,
+            collateral_cap: None,
+            debt_ceiling: None,
+            delisting: None,
+            category_id: None,
+            category_exit: None,
+            swap_route: None,
+        },
+    )?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "register_atoken_whitelist"),
+            attr("name", name),
+            attr("symbol", symbol),
+            attr("collateral_token", collateral_token),
+            attr("custody_contract", custody_contract),
+            attr("LTV", max_ltv.to_string()),
+            attr("liquidation_threshold", liquidation_threshold.to_string()),
+        ],
+        messages: vec![],
+        data: None,
+    })
+}
+
 pub fn update_whitelist(
     deps: DepsMut,
     info: MessageInfo,
     collateral_token: HumanAddr,
     custody_contract: Option<HumanAddr>,
     max_ltv: Option<Decimal256>,
+    liquidation_threshold: Option<Decimal256>,
 ) -> Result<HandleResponse, ContractError> {
     let config: Config = read_config(deps.storage)?;
     if deps.api.canonical_address(&info.sender)? != config.owner_addr {
@@ -391,6 +658,12 @@ pub fn update_whitelist(
     let mut whitelist_elem: WhitelistElem =
         read_whitelist_elem(deps.storage, &collateral_token_raw)?;
 
+    // the LTV ratios of a delisted collateral follow the ramp
+    if whitelist_elem.delisting.is_some() && (max_ltv.is_some() || liquidation_threshold.is_some())
+    {
+        return Err(ContractError::CollateralDelisted(whitelist_elem.symbol));
+    }
+
     if let Some(custody_contract) = custody_contract {
         whitelist_elem.custody_contract = deps.api.canonical_address(&custody_contract)?;
     }
@@ -399,6 +672,12 @@ pub fn update_whitelist(
         whitelist_elem.max_ltv = max_ltv;
     }
 
+    if let Some(liquidation_threshold) = liquidation_threshold {
+        whitelist_elem.liquidation_threshold = liquidation_threshold;
+    }
+
+    assert_liquidation_threshold(whitelist_elem.max_ltv, whitelist_elem.liquidation_threshold)?;
+
     store_whitelist_elem(deps.storage, &collateral_token_raw, &whitelist_elem)?;
 
     let res = HandleResponse {
@@ -410,6 +689,10 @@ pub fn update_whitelist(
                 deps.api.human_address(&whitelist_elem.custody_contract)?,
             ),
             attr("LTV", whitelist_elem.max_ltv.to_string()),
+            attr(
+                "liquidation_threshold",
+                whitelist_elem.liquidation_threshold.to_string(),
+            ),
         ],
         messages: vec![],
         data: None,
@@ -417,6 +700,169 @@ pub fn update_whitelist(
     Ok(res)
 }
 
+pub fn delist_collateral(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collateral_token: HumanAddr,
+    final_ltv: Decimal256,
+    ramp_period: u64,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let mut whitelist_elem: WhitelistElem =
+        read_whitelist_elem(deps.storage, &collateral_token_raw)?;
+    if whitelist_elem.delisting.is_some() {
+        return Err(ContractError::CollateralDelisted(whitelist_elem.symbol));
+    }
+
+    if final_ltv > whitelist_elem.max_ltv {
+        return Err(ContractError::InvalidDelistingLtv {});
+    }
+
+    let delisting = DelistingSchedule {
+        start_time: env.block.time,
+        end_time: env.block.time + ramp_period,
+        final_ltv,
+    };
+    whitelist_elem.delisting = Some(delisting.clone());
+    store_whitelist_elem(deps.storage, &collateral_token_raw, &whitelist_elem)?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "delist_collateral"),
+            attr("collateral_token", collateral_token),
+            attr("final_ltv", final_ltv),
+            attr("start_time", delisting.start_time),
+            attr("end_time", delisting.end_time),
+        ],
+        messages: vec![],
+        data: None,
+    })
+}
+
+pub fn remove_collateral(
+    deps: DepsMut,
+    info: MessageInfo,
+    collateral_token: HumanAddr,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let whitelist_elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral_token_raw)?;
+    if whitelist_elem.delisting.is_none() {
+        return Err(ContractError::CollateralNotDelisted {});
+    }
+
+    let collateral_total = read_collateral_total(deps.storage, &collateral_token_raw)?;
+    if !collateral_total.total_collateral.is_zero() {
+        return Err(ContractError::CollateralStillLocked(
+            collateral_total.total_collateral.into(),
+        ));
+    }
+
+    remove_whitelist_elem(deps.storage, &collateral_token_raw);
+    remove_collateral_total(deps.storage, &collateral_token_raw);
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "remove_collateral"),
+            attr("collateral_token", collateral_token),
+        ],
+        messages: vec![],
+        data: None,
+    })
+}
+
+pub fn update_guardian(
+    deps: DepsMut,
+    info: MessageInfo,
+    guardian: Option<HumanAddr>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut pause_info: PauseInfo = read_pause_info(deps.storage)?;
+    pause_info.guardian = if let Some(guardian) = guardian.as_ref() {
+        Some(deps.api.canonical_address(guardian)?)
+    } else {
+        None
+    };
+    store_pause_info(deps.storage, &pause_info)?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "update_guardian"),
+            attr(
+                "guardian",
+                guardian.map_or_else(|| "none".to_string(), |g| g.to_string()),
+            ),
+        ],
+        messages: vec![],
+        data: None,
+    })
+}
+
+/// The guardian can only pause operations,
+/// the owner can both pause and unpause
+pub fn update_pause_flags(
+    deps: DepsMut,
+    info: MessageInfo,
+    lock: Option<bool>,
+    unlock: Option<bool>,
+    liquidate: Option<bool>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut pause_info: PauseInfo = read_pause_info(deps.storage)?;
+
+    let sender_raw = deps.api.canonical_address(&info.sender)?;
+    if sender_raw != config.owner_addr {
+        if pause_info.guardian != Some(sender_raw) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        if [lock, unlock, liquidate].contains(&Some(false)) {
+            return Err(ContractError::Unauthorized {});
+        }
+    }
+
+    if let Some(lock) = lock {
+        pause_info.flags.lock = lock;
+    }
+
+    if let Some(unlock) = unlock {
+        pause_info.flags.unlock = unlock;
+    }
+
+    if let Some(liquidate) = liquidate {
+        pause_info.flags.liquidate = liquidate;
+    }
+
+    store_pause_info(deps.storage, &pause_info)?;
+    Ok(HandleResponse {
+        attributes: vec![attr("action", "update_pause_flags")],
+        messages: vec![],
+        data: None,
+    })
+}
+
+fn assert_not_paused(paused: bool, operation: &str) -> Result<(), ContractError> {
+    if paused {
+        return Err(ContractError::OperationPaused(operation.to_string()));
+    }
+
+    Ok(())
+}
+
 fn update_deposit_rate(deps: DepsMut, env: Env) -> StdResult<()> {
     let dynrate_config: DynrateConfig = read_dynrate_config(deps.storage)?;
     let dynrate_state: DynrateState = read_dynrate_state(deps.storage)?;
@@ -425,11 +871,8 @@ fn update_deposit_rate(deps: DepsMut, env: Env) -> StdResult<()> {
     // check whether its time to re-evaluate rate
     if env.block.height >= dynrate_state.last_executed_height + dynrate_config.dyn_rate_epoch {
         // retrieve interest buffer
-        let interest_buffer = query_balance(
-            deps.as_ref(),
-            env.contract.address.clone(),
-            deps.api.human_address(&config.stable_addr)?,
-        )?;
+        let interest_buffer =
+            query_stable_balance(deps.as_ref(), &config, env.contract.address.clone())?;
         // convert block rate into yearly rate
         let blocks_per_year = Decimal256::from_ratio(Uint256::from(BLOCKS_PER_YEAR), 1);
         let current_rate = config.threshold_deposit_rate * blocks_per_year;
@@ -501,9 +944,13 @@ fn update_deposit_rate(deps: DepsMut, env: Env) -> StdResult<()> {
     Ok(())
 }
 
-pub fn execute_epoch_operations(deps: DepsMut, env: Env) -> Result<HandleResponse, ContractError> {
+pub fn execute_epoch_operations(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<HandleResponse, ContractError> {
     let config: Config = read_config(deps.storage)?;
-    let state: EpochState = read_epoch_state(deps.storage)?;
+    let mut state: EpochState = read_epoch_state(deps.storage)?;
     if env.block.height < state.last_executed_height + config.epoch_period {
         return Err(ContractError::EpochNotPassed(state.last_executed_height));
     }
@@ -527,11 +974,8 @@ pub fn execute_epoch_operations(deps: DepsMut, env: Env) -> Result<HandleRespons
         (effective_deposit_rate - Decimal256::one()) / Decimal256::from_uint256(blocks);
 
     let mut messages: Vec<CosmosMsg> = vec![];
-    let mut interest_buffer = query_balance(
-        deps.as_ref(),
-        env.contract.address.clone(),
-        deps.api.human_address(&config.stable_addr)?,
-    )?;
+    let mut interest_buffer =
+        query_stable_balance(deps.as_ref(), &config, env.contract.address.clone())?;
 
     // Send accrued_buffer * config.anc_purchase_factor amount stable token to collector
     let accrued_buffer = interest_buffer - state.prev_interest_buffer;
@@ -551,70 +995,87 @@ pub fn execute_epoch_operations(deps: DepsMut, env: Env) -> Result<HandleRespons
                                                                            // }
                                                                            // FIX CUNG NGUYEN NGU
     if !anc_purchase_amount.is_zero() {
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: deps.api.human_address(&config.stable_addr)?,
-            send: vec![],
-            msg: to_binary(&Cw20HandleMsg::Transfer {
-                recipient: deps.api.human_address(&config.collector_contract)?,
-                amount: deduct_tax(deps.as_ref(), anc_purchase_amount.into())?,
-            })?,
-        }));
+        messages.push(stable_transfer_msg(
+            deps.as_ref(),
+            &env,
+            &config,
+            deps.api.human_address(&config.collector_contract)?,
+            anc_purchase_amount,
+        )?);
     }
 
     // Deduct anc_purchase_amount from the interest_buffer
     interest_buffer = interest_buffer - anc_purchase_amount;
 
-    // Distribute Interest Buffer to depositor
-    // Only executed when deposit rate < threshold_deposit_rate
-    // let mut distributed_interest: Uint256 = Uint256::zero();
-    // if deposit_rate < config.threshold_deposit_rate {
-    //     // missing_deposit_rate(_per_block)
-    //     let missing_deposit_rate = config.threshold_deposit_rate - deposit_rate;
-    //     let prev_deposits = state.prev_aterra_supply * state.prev_exchange_rate;
-
-    //     // missing_deposits = prev_deposits * missing_deposit_rate(_per_block) * blocks
-    //     let missing_deposits = prev_deposits * blocks * missing_deposit_rate;
-    //     let distribution_buffer = interest_buffer * config.buffer_distribution_factor;
-
-    //     // When there was not enough deposits happens,
-    //     // distribute interest to market contract
-    //     distributed_interest = std::cmp::min(missing_deposits, distribution_buffer);
-    //     interest_buffer = interest_buffer - distributed_interest;
-
-    //     if !distributed_interest.is_zero() {
-    //         // deduct tax
-    //         distributed_interest =
-    //             Uint256::from(deduct_tax(deps.as_ref(), distributed_interest.into())?);
-
-    //         // Send some portion of interest buffer to Market contract
-    //         messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-    //             contract_addr: deps.api.human_address(&config.stable_addr)?,
-    //             send: vec![],
-    //             msg: to_binary(&Cw20HandleMsg::Transfer {
-    //                 recipient: market_contract,
-    //                 amount: distributed_interest.into(),
-    //             })?,
-    //         }));
-    //     }
-    // }
-
-    // Distribute all reward from proof of stake for market
-    let mut distributed_interest: Uint256 = interest_buffer.clone();
+    // Only the interest buffer above min_interest_buffer
+    // is paid out, to the keeper first and then to depositors
+    let mut distributable_buffer =
+        interest_buffer - min(interest_buffer, config.min_interest_buffer);
+
+    // Pay the keeper a single bounty per call, covering the epoch
+    // and the custody DistributeRewards it triggers
+    let keeper_bounty = min(
+        distributable_buffer * config.keeper_bounty_rate,
+        config.keeper_bounty_cap,
+    );
+    if !keeper_bounty.is_zero() {
+        interest_buffer = interest_buffer - keeper_bounty;
+        distributable_buffer = distributable_buffer - keeper_bounty;
+        state.total_keeper_bounty += keeper_bounty;
+        store_epoch_state(deps.storage, &state)?;
+
+        messages.push(stable_transfer_msg(
+            deps.as_ref(),
+            &env,
+            &config,
+            info.sender.clone(),
+            keeper_bounty,
+        )?);
+    }
+
+    // Distribute to depositors according to the distribution policy
+    let mut distributed_interest: Uint256 = match config.buffer_distribution_policy {
+        BufferDistributionPolicy::AllToDepositors => distributable_buffer,
+        BufferDistributionPolicy::ThresholdTopUp => {
+            // Only executed when deposit rate < threshold_deposit_rate
+            if deposit_rate < config.threshold_deposit_rate {
+                // missing_deposit_rate(_per_block)
+                let missing_deposit_rate = config.threshold_deposit_rate - deposit_rate;
+                let prev_deposits = state.prev_aterra_supply * state.prev_exchange_rate;
+
+                // missing_deposits = prev_deposits * missing_deposit_rate(_per_block) * blocks
+                let missing_deposits = prev_deposits * blocks * missing_deposit_rate;
+                let distribution_buffer = interest_buffer * config.buffer_distribution_factor;
+
+                min(
+                    missing_deposits,
+                    min(distribution_buffer, distributable_buffer),
+                )
+            } else {
+                Uint256::zero()
+            }
+        }
+        BufferDistributionPolicy::FixedRatio { ratio } => {
+            min(interest_buffer * ratio, distributable_buffer)
+        }
+    };
     interest_buffer = interest_buffer - distributed_interest;
     if !distributed_interest.is_zero() {
-        // deduct tax
-        distributed_interest =
-            Uint256::from(deduct_tax(deps.as_ref(), distributed_interest.into())?);
-
         // Send some portion of interest buffer to Market contract
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: deps.api.human_address(&config.stable_addr)?,
-            send: vec![],
-            msg: to_binary(&Cw20HandleMsg::Transfer {
-                recipient: market_contract,
-                amount: distributed_interest.into(),
-            })?,
-        }));
+        messages.push(stable_transfer_msg(
+            deps.as_ref(),
+            &env,
+            &config,
+            market_contract,
+            distributed_interest,
+        )?);
+
+        // the market receives the amount after tax
+        distributed_interest = Uint256::from(deduct_asset_tax(
+            deps.as_ref(),
+            &config.stable_info.to_normal(deps.api)?,
+            distributed_interest.into(),
+        )?);
     }
 
     // Execute DistributeRewards
@@ -646,6 +1107,8 @@ pub fn execute_epoch_operations(deps: DepsMut, env: Env) -> Result<HandleRespons
             attr("aterra_supply", epoch_state.aterra_supply),
             attr("distributed_interest", distributed_interest),
             attr("anc_purchase_amount", anc_purchase_amount),
+            attr("keeper", info.sender),
+            attr("keeper_bounty", keeper_bounty),
         ],
         messages: messages,
         data: None,
@@ -696,6 +1159,7 @@ pub fn update_epoch_state(
             prev_exchange_rate: market_epoch_state.exchange_rate,
             prev_interest_buffer: interest_buffer,
             deposit_rate,
+            total_keeper_bounty: overseer_epoch_state.total_keeper_bounty,
         },
     )?;
 
@@ -731,24 +1195,61 @@ pub fn update_epoch_state(
     Ok(res)
 }
 
-pub fn fund_reserve(deps: DepsMut, info: MessageInfo) -> Result<HandleResponse, ContractError> {
-    let sent_uusd = match info.sent_funds.iter().find(|x| x.denom == "uusd") {
-        Some(coin) => coin.amount,
-        None => Uint128::zero(),
-    };
+pub fn receive_cw20(
+    deps: DepsMut,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<HandleResponse, ContractError> {
+    let contract_addr = info.sender;
+
+    match cw20_msg.msg.map(|msg| from_binary(&msg)) {
+        Some(Ok(Cw20HookMsg::FundReserve {})) => {
+            // only the cw20 stable token can fund the reserve
+            let config: Config = read_config(deps.storage)?;
+            match config.stable_info {
+                AssetInfoRaw::Token {
+                    contract_addr: stable_addr,
+                } if stable_addr == deps.api.canonical_address(&contract_addr)? => {}
+                _ => return Err(ContractError::Unauthorized {}),
+            }
+
+            fund_reserve(deps, cw20_msg.amount.into())
+        }
+        _ => Err(ContractError::MissingFundReserveHook {}),
+    }
+}
+
+/// Amount of the native stable sent along with the message
+fn native_stable_amount(deps: Deps, info: &MessageInfo) -> Result<Uint128, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    match config.stable_info {
+        AssetInfoRaw::NativeToken { denom } => Ok(info
+            .sent_funds
+            .iter()
+            .find(|c| c.denom == denom)
+            .map(|c| c.amount)
+            .unwrap_or_else(Uint128::zero)),
+        AssetInfoRaw::Token { .. } => Err(ContractError::NativeStableNotConfigured {}),
+    }
+}
+
+pub fn fund_reserve(deps: DepsMut, amount: Uint256) -> Result<HandleResponse, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::ZeroFundReserve {});
+    }
 
     let mut overseer_epoch_state: EpochState = read_epoch_state(deps.storage)?;
-    overseer_epoch_state.prev_interest_buffer += Uint256::from(sent_uusd);
+    overseer_epoch_state.prev_interest_buffer += amount;
     store_epoch_state(deps.storage, &overseer_epoch_state)?;
 
     let mut dyn_rate_state: DynrateState = read_dynrate_state(deps.storage)?;
-    dyn_rate_state.prev_yield_reserve += Decimal256::from_ratio(Uint256::from(sent_uusd), 1);
+    dyn_rate_state.prev_yield_reserve += Decimal256::from_uint256(amount);
     store_dynrate_state(deps.storage, &dyn_rate_state)?;
 
     let res = HandleResponse {
         attributes: vec![
             attr("action", "fund_reserve"),
-            attr("funded_amount", sent_uusd.to_string()),
+            attr("funded_amount", amount),
         ],
         messages: vec![],
         data: None,
@@ -756,8 +1257,71 @@ pub fn fund_reserve(deps: DepsMut, info: MessageInfo) -> Result<HandleResponse,
     Ok(res)
 }
 
+pub fn cover_bad_debt(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint256,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.market_contract {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let interest_buffer =
+        query_stable_balance(deps.as_ref(), &config, env.contract.address.clone())?;
+    let covered_amount = min(amount, interest_buffer);
+
+    // Keep prev_interest_buffer below the remaining buffer,
+    // so the next epoch only sees the newly accrued buffer
+    let mut overseer_epoch_state: EpochState = read_epoch_state(deps.storage)?;
+    overseer_epoch_state.prev_interest_buffer = min(
+        overseer_epoch_state.prev_interest_buffer,
+        interest_buffer - covered_amount,
+    );
+    store_epoch_state(deps.storage, &overseer_epoch_state)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !covered_amount.is_zero() {
+        messages.push(stable_transfer_msg(
+            deps.as_ref(),
+            &env,
+            &config,
+            info.sender,
+            covered_amount,
+        )?);
+    }
+
+    Ok(HandleResponse {
+        messages,
+        attributes: vec![
+            attr("action", "cover_bad_debt"),
+            attr("covered_amount", covered_amount),
+        ],
+        data: None,
+    })
+}
+
+/// Build the message sending `amount` of the stable asset
+/// from the overseer to `recipient`, taxed if the stable is native
+pub(crate) fn stable_transfer_msg(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    recipient: HumanAddr,
+    amount: Uint256,
+) -> StdResult<CosmosMsg> {
+    asset_transfer_msg(
+        deps,
+        &config.stable_info.to_normal(deps.api)?,
+        env.contract.address.clone(),
+        recipient,
+        amount,
+    )
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::EpochState {} => to_binary(&query_state(deps)?),
@@ -779,6 +1343,20 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             borrower,
             block_time,
         } => to_binary(&query_borrow_limit(deps, borrower, block_time)?),
+        QueryMsg::BorrowerHealth { borrower } => {
+            to_binary(&query_borrower_health(deps, env, borrower)?)
+        }
+        QueryMsg::Categories {} => to_binary(&query_categories(deps)?),
+        QueryMsg::BorrowerCategory { borrower } => {
+            to_binary(&query_borrower_category(deps, env, borrower)?)
+        }
+        QueryMsg::DebtCeilings {
+            borrower,
+            loan_amount,
+        } => to_binary(&query_debt_ceilings(deps, env, borrower, loan_amount)?),
+        QueryMsg::LiquidatablePositions { start_after, limit } => to_binary(
+            &query_liquidatable_positions(deps, env, start_after, limit)?,
+        ),
         QueryMsg::DynrateState {} => to_binary(&query_dynrate_state(deps)?),
     }
 }
@@ -786,6 +1364,7 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
 pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let config: Config = read_config(deps.storage)?;
     let dynrate_config: DynrateConfig = read_dynrate_config(deps.storage)?;
+    let pause_info: PauseInfo = read_pause_info(deps.storage)?;
     Ok(ConfigResponse {
         owner_addr: deps.api.human_address(&config.owner_addr)?.to_string(),
         oracle_contract: deps.api.human_address(&config.oracle_contract)?.to_string(),
@@ -798,7 +1377,7 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
             .api
             .human_address(&config.collector_contract)?
             .to_string(),
-        stable_addr: deps.api.human_address(&config.stable_addr)?.to_string(),
+        stable_info: config.stable_info.to_normal(deps.api)?,
         epoch_period: config.epoch_period,
         threshold_deposit_rate: config.threshold_deposit_rate,
         target_deposit_rate: config.target_deposit_rate,
@@ -810,11 +1389,33 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         dyn_rate_yr_increase_expectation: dynrate_config.dyn_rate_yr_increase_expectation,
         dyn_rate_min: dynrate_config.dyn_rate_min,
         dyn_rate_max: dynrate_config.dyn_rate_max,
+        atoken_borrow_limit_share: config.atoken_borrow_limit_share,
+        buffer_distribution_policy: config.buffer_distribution_policy,
+        min_interest_buffer: config.min_interest_buffer,
+        keeper_bounty_rate: config.keeper_bounty_rate,
+        keeper_bounty_cap: config.keeper_bounty_cap,
+        guardian: if let Some(guardian) = pause_info.guardian {
+            Some(deps.api.human_address(&guardian)?.to_string())
+        } else {
+            None
+        },
+        pause_flags: pause_info.flags,
     })
 }
 
-pub fn query_state(deps: Deps) -> StdResult<EpochState> {
-    read_epoch_state(deps.storage)
+pub fn query_state(deps: Deps) -> StdResult<OverseerEpochStateResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let state: EpochState = read_epoch_state(deps.storage)?;
+    Ok(OverseerEpochStateResponse {
+        deposit_rate: state.deposit_rate,
+        prev_aterra_supply: state.prev_aterra_supply,
+        prev_exchange_rate: state.prev_exchange_rate,
+        prev_interest_buffer: state.prev_interest_buffer,
+        last_executed_height: state.last_executed_height,
+        buffer_distribution_policy: config.buffer_distribution_policy,
+        min_interest_buffer: config.min_interest_buffer,
+        total_keeper_bounty: state.total_keeper_bounty,
+    })
 }
 
 pub fn query_dynrate_state(deps: Deps) -> StdResult<DynrateState> {
@@ -828,17 +1429,27 @@ pub fn query_whitelist(
     limit: Option<u32>,
 ) -> StdResult<WhitelistResponse> {
     if let Some(collateral_token) = collateral_token {
-        let whitelist_elem: WhitelistElem = read_whitelist_elem(
-            deps.storage,
-            &deps.api.canonical_address(&collateral_token)?,
-        )?;
+        let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+        let whitelist_elem: WhitelistElem =
+            read_whitelist_elem(deps.storage, &collateral_token_raw)?;
+        let collateral_total = read_collateral_total(deps.storage, &collateral_token_raw)?;
         Ok(WhitelistResponse {
             elems: vec![WhitelistResponseElem {
                 name: whitelist_elem.name,
                 symbol: whitelist_elem.symbol,
                 max_ltv: whitelist_elem.max_ltv,
+                liquidation_threshold: whitelist_elem.liquidation_threshold,
                 custody_contract: deps.api.human_address(&whitelist_elem.custody_contract)?,
                 collateral_token: collateral_token,
+                price_source: whitelist_elem.price_source,
+                collateral_cap: whitelist_elem.collateral_cap,
+                debt_ceiling: whitelist_elem.debt_ceiling,
+                total_collateral: collateral_total.total_collateral,
+                total_debt: collateral_total.total_debt,
+                delisting: whitelist_elem.delisting,
+                category_id: whitelist_elem.category_id,
+                category_exit: whitelist_elem.category_exit,
+                swap_route: whitelist_elem.swap_route,
             }],
         })
     } else {
@@ -0,0 +1,244 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, to_binary, CosmosMsg, DepsMut, Env, HandleResponse, HumanAddr, MessageInfo, WasmMsg,
+};
+
+use crate::caps::{decrease_total_collaterals, query_loan_amount, update_debt_attribution};
+use crate::collateral::compute_borrow_limit;
+use crate::error::ContractError;
+use crate::querier::query_stable_balance;
+use crate::state::{
+    read_collaterals, read_config, read_whitelist_elem, store_collaterals, store_whitelist_elem,
+    Config, WhitelistElem,
+};
+
+use moneymarket::custody::ExecuteMsg as CustodyExecuteMsg;
+use moneymarket::market::ExecuteMsg as MarketExecuteMsg;
+use moneymarket::overseer::{ExecuteMsg, SwapRoute};
+use moneymarket::tokens::{Tokens, TokensMath};
+use oraiswap::asset::AssetInfo;
+use oraiswap::router::{Cw20HookMsg as RouterHookMsg, SwapOperation};
+
+pub fn update_swap_route(
+    deps: DepsMut,
+    info: MessageInfo,
+    collateral_token: HumanAddr,
+    swap_route: Option<SwapRoute>,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.canonical_address(&info.sender)? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(swap_route) = &swap_route {
+        assert_swap_route(
+            swap_route,
+            &collateral_token,
+            &config.stable_info.to_normal(deps.api)?,
+        )?;
+    }
+
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let mut whitelist_elem: WhitelistElem =
+        read_whitelist_elem(deps.storage, &collateral_token_raw)?;
+    whitelist_elem.swap_route = swap_route;
+    store_whitelist_elem(deps.storage, &collateral_token_raw, &whitelist_elem)?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "update_swap_route"),
+            attr("collateral_token", collateral_token),
+            attr(
+                "swap_route",
+                whitelist_elem.swap_route.map_or_else(
+                    || "none".to_string(),
+                    |swap_route| swap_route.router_contract.to_string(),
+                ),
+            ),
+        ],
+        messages: vec![],
+        data: None,
+    })
+}
+
+/// The operations must chain from the collateral token to the stable asset
+fn assert_swap_route(
+    swap_route: &SwapRoute,
+    collateral_token: &HumanAddr,
+    stable_info: &AssetInfo,
+) -> Result<(), ContractError> {
+    let mut asset_info = AssetInfo::Token {
+        contract_addr: collateral_token.clone(),
+    };
+    for operation in swap_route.operations.iter() {
+        match operation {
+            SwapOperation::OraiSwap {
+                offer_asset_info,
+                ask_asset_info,
+            } => {
+                if *offer_asset_info != asset_info {
+                    return Err(ContractError::InvalidSwapRoute {});
+                }
+                asset_info = ask_asset_info.clone();
+            }
+        }
+    }
+
+    if swap_route.operations.is_empty() || asset_info != *stable_info {
+        return Err(ContractError::InvalidSwapRoute {});
+    }
+
+    Ok(())
+}
+
+/// Sell locked collateral through its swap route and repay the loan
+/// with the proceeds, the stable surplus is refunded by the market
+pub fn repay_with_collateral(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collateral_token: HumanAddr,
+    amount: Uint256,
+    min_stable_out: Uint256,
+) -> Result<HandleResponse, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let market = deps.api.human_address(&config.market_contract)?;
+
+    let borrower = info.sender;
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let collateral_token_raw = deps.api.canonical_address(&collateral_token)?;
+    let whitelist_elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral_token_raw)?;
+    let swap_route = match whitelist_elem.swap_route {
+        Some(swap_route) => swap_route,
+        None => return Err(ContractError::SwapRouteNotConfigured(whitelist_elem.symbol)),
+    };
+
+    let loan_amount = query_loan_amount(deps.as_ref(), &env, borrower.clone())?;
+    if loan_amount.is_zero() {
+        return Err(ContractError::NoLoanToRepay {});
+    }
+
+    let mut cur_collaterals: Tokens = read_collaterals(deps.storage, &borrower_raw);
+    let (prev_borrow_limit, _) = compute_borrow_limit(
+        deps.as_ref(),
+        &borrower_raw,
+        &cur_collaterals,
+        Some(env.block.time),
+    )?;
+
+    let collaterals: Tokens = vec![(collateral_token_raw, amount)];
+    if amount.is_zero() || cur_collaterals.sub(collaterals.clone()).is_err() {
+        return Err(ContractError::UnlockExceedsLocked {});
+    }
+
+    store_collaterals(deps.storage, &borrower_raw, &cur_collaterals)?;
+    decrease_total_collaterals(deps.branch(), &borrower_raw, &collaterals)?;
+    update_debt_attribution(
+        deps.branch(),
+        &env,
+        &borrower_raw,
+        &cur_collaterals,
+        loan_amount,
+        false,
+    )?;
+
+    let prev_balance: Uint256 = query_stable_balance(deps.as_ref(), &config, market.clone())?;
+
+    let messages: Vec<CosmosMsg> = vec![
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(&whitelist_elem.custody_contract)?,
+            send: vec![],
+            msg: to_binary(&CustodyExecuteMsg::SendCollateral {
+                borrower: borrower.clone(),
+                amount,
+                contract: swap_route.router_contract,
+                msg: to_binary(&RouterHookMsg::ExecuteSwapOperations {
+                    operations: swap_route.operations,
+                    minimum_receive: Some(min_stable_out.into()),
+                    to: Some(market.to_string()),
+                })?,
+            })?,
+        }),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: market,
+            send: vec![],
+            msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
+                borrower: borrower.to_string(),
+                prev_balance,
+            })?,
+        }),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address,
+            send: vec![],
+            msg: to_binary(&ExecuteMsg::AssertPositionHealthier {
+                borrower: borrower.clone(),
+                prev_loan_amount: loan_amount,
+                prev_borrow_limit,
+            })?,
+        }),
+    ];
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "repay_with_collateral"),
+            attr("borrower", borrower),
+            attr("collateral_token", collateral_token),
+            attr("amount", amount),
+            attr("min_stable_out", min_stable_out),
+        ],
+        messages,
+        data: None,
+    })
+}
+
+/// Executed after the repayment, the loan to borrow limit
+/// ratio of the borrower must have decreased
+pub fn assert_position_healthier(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrower: HumanAddr,
+    prev_loan_amount: Uint256,
+    prev_borrow_limit: Uint256,
+) -> Result<HandleResponse, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let borrower_raw = deps.api.canonical_address(&borrower)?;
+    let collaterals: Tokens = read_collaterals(deps.storage, &borrower_raw);
+    let (borrow_limit, _) = compute_borrow_limit(
+        deps.as_ref(),
+        &borrower_raw,
+        &collaterals,
+        Some(env.block.time),
+    )?;
+    let loan_amount = query_loan_amount(deps.as_ref(), &env, borrower.clone())?;
+
+    let healthier = if loan_amount.is_zero() {
+        true
+    } else if borrow_limit.is_zero() {
+        false
+    } else if prev_borrow_limit.is_zero() {
+        loan_amount < prev_loan_amount
+    } else {
+        Decimal256::from_ratio(loan_amount, borrow_limit)
+            < Decimal256::from_ratio(prev_loan_amount, prev_borrow_limit)
+    };
+    if !healthier {
+        return Err(ContractError::PositionNotHealthier {});
+    }
+
+    update_debt_attribution(deps, &env, &borrower_raw, &collaterals, loan_amount, false)?;
+
+    Ok(HandleResponse {
+        attributes: vec![
+            attr("action", "assert_position_healthier"),
+            attr("borrower", borrower),
+            attr("loan_amount", loan_amount),
+            attr("borrow_limit", borrow_limit),
+        ],
+        messages: vec![],
+        data: None,
+    })
+}
@@ -15,9 +15,72 @@ pub enum ContractError {
     #[error("Cannot liquidate safely collateralized loan")]
     CannotLiquidateSafeLoan {},
 
+    #[error("Category change makes the loan exceed the borrow limit: {0}")]
+    CategoryChangeExceedsLimit(u128),
+
+    #[error("Collateral is still exiting its previous category: {0}")]
+    CategoryExitInProgress(String),
+
+    #[error("Collateral does not belong to the borrower category: {0}")]
+    CategoryMismatch(String),
+
+    #[error("Collateral is delisted: {0}")]
+    CollateralDelisted(String),
+
+    #[error("Collateral must be delisted before removal")]
+    CollateralNotDelisted {},
+
+    #[error("Collateral is still locked by borrowers: {0}")]
+    CollateralStillLocked(u128),
+
+    #[error("Collateral cap of {0} exceeded; cap: {1}")]
+    CollateralCapExceeded(String, u128),
+
+    #[error("Debt ceiling of {0} exceeded; ceiling: {1}")]
+    DebtCeilingExceeded(String, u128),
+
     #[error("An epoch has not passed yet; last executed height: {0}")]
     EpochNotPassed(u64),
 
+    #[error("aToken borrow limit share must be less than or equal to 1")]
+    InvalidATokenBorrowLimitShare {},
+
+    #[error("Buffer distribution ratio must be less than or equal to 1")]
+    InvalidBufferDistributionRatio {},
+
+    #[error("Final LTV must be less than or equal to max LTV")]
+    InvalidDelistingLtv {},
+
+    #[error("Keeper bounty rate must be less than or equal to 1")]
+    InvalidKeeperBountyRate {},
+
+    #[error("Liquidation threshold must be bigger than max LTV and less than 1")]
+    InvalidLiquidationThreshold {},
+
+    #[error("Swap route must chain from the collateral token to the stable token")]
+    InvalidSwapRoute {},
+
+    #[error("Missing fund reserve hook")]
+    MissingFundReserveHook {},
+
+    #[error("Native stable is not configured, fund the reserve with the cw20 stable")]
+    NativeStableNotConfigured {},
+
+    #[error("Collateral totals are not being seeded")]
+    NoCollateralSeeding {},
+
+    #[error("Borrower has no loan to repay")]
+    NoLoanToRepay {},
+
+    #[error("Operation paused: {0}")]
+    OperationPaused(String),
+
+    #[error("Position is not healthier after the repayment")]
+    PositionNotHealthier {},
+
+    #[error("Swap route is not configured for collateral: {0}")]
+    SwapRouteNotConfigured(String),
+
     #[error("Token is already registered as collateral")]
     TokenAlreadyRegistered {},
 
@@ -26,4 +89,7 @@ pub enum ContractError {
 
     #[error("Unlock amount too high; Loan liability becomes greater than borrow limit: {0}")]
     UnlockTooLarge(u128),
+
+    #[error("Fund reserve amount must be greater than 0")]
+    ZeroFundReserve {},
 }
@@ -1,6 +1,10 @@
+pub mod caps;
+pub mod category;
 pub mod collateral;
 pub mod contract;
+pub mod deleverage;
 pub mod error;
+pub mod migration;
 pub mod querier;
 pub mod state;
 
@@ -0,0 +1,141 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::state::{
+    store_config, store_epoch_state, store_whitelist_elem, Config, EpochState, WhitelistElem,
+    KEY_CONFIG, KEY_EPOCH_STATE, PREFIX_WHITELIST,
+};
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{CanonicalAddr, Order, StdResult, Storage};
+use cosmwasm_storage::{ReadonlyBucket, ReadonlySingleton};
+use moneymarket::overseer::{BufferDistributionPolicy, PriceSource};
+use oraiswap::asset::AssetInfoRaw;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyConfig {
+    pub owner_addr: CanonicalAddr,
+    pub oracle_contract: CanonicalAddr,
+    pub market_contract: CanonicalAddr,
+    pub liquidation_contract: CanonicalAddr,
+    pub collector_contract: CanonicalAddr,
+    pub stable_addr: CanonicalAddr,
+    pub epoch_period: u64,
+    pub threshold_deposit_rate: Decimal256,
+    pub target_deposit_rate: Decimal256,
+    pub buffer_distribution_factor: Decimal256,
+    pub anc_purchase_factor: Decimal256,
+    pub price_timeframe: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyEpochState {
+    pub deposit_rate: Decimal256,
+    pub prev_aterra_supply: Uint256,
+    pub prev_exchange_rate: Decimal256,
+    pub prev_interest_buffer: Uint256,
+    pub last_executed_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyWhitelistElem {
+    pub name: String,
+    pub symbol: String,
+    pub max_ltv: Decimal256,
+    pub custody_contract: CanonicalAddr,
+}
+
+fn read_legacy_config(storage: &dyn Storage) -> StdResult<LegacyConfig> {
+    ReadonlySingleton::new(storage, KEY_CONFIG).load()
+}
+
+fn read_legacy_epoch_state(storage: &dyn Storage) -> StdResult<LegacyEpochState> {
+    ReadonlySingleton::new(storage, KEY_EPOCH_STATE).load()
+}
+
+/// There are only a few whitelisted collaterals
+fn read_legacy_whitelist(
+    storage: &dyn Storage,
+) -> StdResult<Vec<(CanonicalAddr, LegacyWhitelistElem)>> {
+    let whitelist_bucket: ReadonlyBucket<LegacyWhitelistElem> =
+        ReadonlyBucket::new(storage, PREFIX_WHITELIST);
+    whitelist_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+/// The settings the legacy config did not have take their init values
+pub fn migrate_config(storage: &mut dyn Storage) -> StdResult<()> {
+    let legacy_config: LegacyConfig = read_legacy_config(storage)?;
+
+    store_config(
+        storage,
+        &Config {
+            owner_addr: legacy_config.owner_addr,
+            oracle_contract: legacy_config.oracle_contract,
+            market_contract: legacy_config.market_contract,
+            liquidation_contract: legacy_config.liquidation_contract,
+            collector_contract: legacy_config.collector_contract,
+            stable_info: AssetInfoRaw::Token {
+                contract_addr: legacy_config.stable_addr,
+            },
+            epoch_period: legacy_config.epoch_period,
+            threshold_deposit_rate: legacy_config.threshold_deposit_rate,
+            target_deposit_rate: legacy_config.target_deposit_rate,
+            buffer_distribution_factor: legacy_config.buffer_distribution_factor,
+            anc_purchase_factor: legacy_config.anc_purchase_factor,
+            price_timeframe: legacy_config.price_timeframe,
+            atoken_borrow_limit_share: Decimal256::percent(50u64),
+            buffer_distribution_policy: BufferDistributionPolicy::AllToDepositors,
+            min_interest_buffer: Uint256::zero(),
+            keeper_bounty_rate: Decimal256::zero(),
+            keeper_bounty_cap: Uint256::zero(),
+        },
+    )
+}
+
+pub fn migrate_epoch_state(storage: &mut dyn Storage) -> StdResult<()> {
+    let legacy_epoch_state: LegacyEpochState = read_legacy_epoch_state(storage)?;
+
+    store_epoch_state(
+        storage,
+        &EpochState {
+            deposit_rate: legacy_epoch_state.deposit_rate,
+            prev_aterra_supply: legacy_epoch_state.prev_aterra_supply,
+            prev_exchange_rate: legacy_epoch_state.prev_exchange_rate,
+            prev_interest_buffer: legacy_epoch_state.prev_interest_buffer,
+            last_executed_height: legacy_epoch_state.last_executed_height,
+            total_keeper_bounty: Uint256::zero(),
+        },
+    )
+}
+
+/// Legacy collaterals were liquidated at their max LTV
+/// and priced by the oracle
+pub fn migrate_whitelist(storage: &mut dyn Storage) -> StdResult<()> {
+    for (collateral_token, legacy_elem) in read_legacy_whitelist(storage)? {
+        store_whitelist_elem(
+            storage,
+            &collateral_token,
+            &WhitelistElem {
+                name: legacy_elem.name,
+                symbol: legacy_elem.symbol,
+                max_ltv: legacy_elem.max_ltv,
+                liquidation_threshold: legacy_elem.max_ltv,
+                custody_contract: legacy_elem.custody_contract,
+                price_source: PriceSource::Oracle,
+                collateral_cap: None,
+                debt_ceiling: None,
+                delisting: None,
+                category_id: None,
+                category_exit: None,
+                swap_route: None,
+            },
+        )?;
+    }
+
+    Ok(())
+}
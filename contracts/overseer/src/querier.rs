@@ -3,10 +3,24 @@ use cosmwasm_std::{to_binary, Deps, HumanAddr, QueryRequest, StdResult, WasmQuer
 
 use moneymarket::liquidation::{LiquidationAmountResponse, QueryMsg as LiquidationQueryMsg};
 use moneymarket::market::{
-    BorrowerInfoResponse, EpochStateResponse, QueryMsg as MarketQueryMsg, StateResponse,
+    BorrowerInfoResponse, ConfigResponse as MarketConfigResponse, EpochStateResponse,
+    QueryMsg as MarketQueryMsg, StateResponse,
 };
+use moneymarket::querier::query_asset_balance;
 use moneymarket::tokens::TokensHuman;
 
+use crate::state::Config;
+
+/// Query the stable balance of `account_addr`,
+/// either a native denom or a cw20 token
+pub fn query_stable_balance(
+    deps: Deps,
+    config: &Config,
+    account_addr: HumanAddr,
+) -> StdResult<Uint256> {
+    query_asset_balance(deps, account_addr, &config.stable_info.to_normal(deps.api)?)
+}
+
 pub fn query_market_state(
     deps: Deps,
     market_addr: HumanAddr,
@@ -40,6 +54,32 @@ pub fn query_epoch_state(
     Ok(epoch_state)
 }
 
+/// Query the aToken exchange rate of the market contract
+/// as of its last interest update
+pub fn query_exchange_rate(deps: Deps, market_addr: HumanAddr) -> StdResult<Decimal256> {
+    let epoch_state: EpochStateResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: market_addr,
+            msg: to_binary(&MarketQueryMsg::EpochState {
+                block_height: None,
+                distributed_interest: None,
+            })?,
+        }))?;
+
+    Ok(epoch_state.exchange_rate)
+}
+
+/// Query the aToken registered to the market contract
+pub fn query_atoken_contract(deps: Deps, market_addr: HumanAddr) -> StdResult<HumanAddr> {
+    let market_config: MarketConfigResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: market_addr,
+            msg: to_binary(&MarketQueryMsg::Config {})?,
+        }))?;
+
+    Ok(HumanAddr(market_config.aterra_contract))
+}
+
 /// Query borrow amount from the market contract
 pub fn query_borrower_info(
     deps: Deps,
@@ -5,16 +5,26 @@ use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{CanonicalAddr, Deps, Order, StdError, StdResult, Storage, HumanAddr};
 use cosmwasm_storage::{Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
 
-use moneymarket::overseer::{CollateralsResponse, WhitelistResponseElem};
+use moneymarket::overseer::{
+    BufferDistributionPolicy, CategoryExitSchedule, CollateralsResponse, DelistingSchedule,
+    PauseFlags, PriceSource, SwapRoute, WhitelistResponseElem,
+};
 use moneymarket::tokens::Tokens;
+use oraiswap::asset::AssetInfoRaw;
 
-const KEY_CONFIG: &[u8] = b"config";
+pub const KEY_CONFIG: &[u8] = b"config";
 const KEY_DYNRATE_CONFIG: &[u8] = b"dynrate_config";
-const KEY_EPOCH_STATE: &[u8] = b"epoch_state";
+pub const KEY_EPOCH_STATE: &[u8] = b"epoch_state";
 const KEY_DYNRATE_STATE: &[u8] = b"dynrate_state";
+const KEY_PAUSE_INFO: &[u8] = b"pause_info";
+const KEY_COLLATERAL_SEEDING: &[u8] = b"collateral_seeding";
 
-const PREFIX_WHITELIST: &[u8] = b"whitelist";
+pub const PREFIX_WHITELIST: &[u8] = b"whitelist";
 const PREFIX_COLLATERALS: &[u8] = b"collateral";
+const PREFIX_COLLATERAL_TOTAL: &[u8] = b"collateral_total";
+const PREFIX_DEBT_ATTRIBUTION: &[u8] = b"debt_attribution";
+const PREFIX_CATEGORY: &[u8] = b"category";
+const PREFIX_BORROWER_CATEGORY: &[u8] = b"borrower_category";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -23,13 +33,24 @@ pub struct Config {
     pub market_contract: CanonicalAddr,
     pub liquidation_contract: CanonicalAddr,
     pub collector_contract: CanonicalAddr,
-    pub stable_addr: CanonicalAddr,
+    pub stable_info: AssetInfoRaw,
     pub epoch_period: u64,
     pub threshold_deposit_rate: Decimal256,
     pub target_deposit_rate: Decimal256,
     pub buffer_distribution_factor: Decimal256,
     pub anc_purchase_factor: Decimal256,
     pub price_timeframe: u64,
+    /// Maximum part of a borrow limit coming from aToken collaterals
+    pub atoken_borrow_limit_share: Decimal256,
+    pub buffer_distribution_policy: BufferDistributionPolicy,
+    /// Part of the interest buffer never paid out to keepers or depositors
+    pub min_interest_buffer: Uint256,
+    /// Ratio of the interest buffer above min_interest_buffer paid to
+    /// the keeper per call, covering the epoch and the custody
+    /// DistributeRewards it triggers
+    pub keeper_bounty_rate: Decimal256,
+    /// Maximum bounty paid per call
+    pub keeper_bounty_cap: Uint256,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -49,6 +70,8 @@ pub struct EpochState {
     pub prev_exchange_rate: Decimal256,
     pub prev_interest_buffer: Uint256,
     pub last_executed_height: u64,
+    /// Total bounty paid to keepers
+    pub total_keeper_bounty: Uint256,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -62,7 +85,130 @@ pub struct WhitelistElem {
     pub name: String,
     pub symbol: String,
     pub max_ltv: Decimal256,
+    pub liquidation_threshold: Decimal256,
     pub custody_contract: CanonicalAddr,
+    pub price_source: PriceSource,
+    /// Maximum total amount that can be locked
+    pub collateral_cap: Option<Uint256>,
+    /// Maximum total debt attributable to the collateral
+    pub debt_ceiling: Option<Uint256>,
+    pub delisting: Option<DelistingSchedule>,
+    pub category_id: Option<u8>,
+    /// collaterals stored before category exits were ramped never left a category
+    #[serde(default)]
+    pub category_exit: Option<CategoryExitSchedule>,
+    pub swap_route: Option<SwapRoute>,
+}
+
+impl WhitelistElem {
+    /// Max LTV and liquidation threshold at block_time, ramped down by
+    /// the delisting schedule. Without block_time the ramp is considered
+    /// complete
+    pub fn ltv_ratios(&self, block_time: Option<u64>) -> (Decimal256, Decimal256) {
+        let delisting = match &self.delisting {
+            Some(delisting) => delisting,
+            None => return (self.max_ltv, self.liquidation_threshold),
+        };
+
+        let progress = ramp_progress(delisting.start_time, delisting.end_time, block_time);
+
+        let final_threshold = if self.max_ltv.is_zero() {
+            Decimal256::zero()
+        } else {
+            self.liquidation_threshold * delisting.final_ltv / self.max_ltv
+        };
+
+        (
+            self.max_ltv - (self.max_ltv - delisting.final_ltv) * progress,
+            self.liquidation_threshold - (self.liquidation_threshold - final_threshold) * progress,
+        )
+    }
+
+    /// Whether the borrowers of category_id are still eligible to
+    /// the category with this collateral at block_time
+    pub fn is_in_category(&self, category_id: u8, block_time: Option<u64>) -> bool {
+        if self.category_id == Some(category_id) {
+            return true;
+        }
+
+        match (&self.category_exit, block_time) {
+            (Some(exit), Some(block_time)) => {
+                exit.category_id == category_id && block_time < exit.end_time
+            }
+            _ => false,
+        }
+    }
+
+    /// LTV ratios of the collateral for the borrowers of category_id,
+    /// ramped down to the collateral ratios while it exits the category
+    pub fn category_ltv_ratios(
+        &self,
+        category_id: u8,
+        category: &Category,
+        block_time: Option<u64>,
+    ) -> (Decimal256, Decimal256) {
+        let exit = match &self.category_exit {
+            Some(exit) if self.category_id != Some(category_id) => exit,
+            _ => return (category.max_ltv, category.liquidation_threshold),
+        };
+
+        let progress = ramp_progress(exit.start_time, exit.end_time, block_time);
+        (
+            ramp(category.max_ltv, self.max_ltv, progress),
+            ramp(
+                category.liquidation_threshold,
+                self.liquidation_threshold,
+                progress,
+            ),
+        )
+    }
+}
+
+/// Progress of a ramp between start_time and end_time, without
+/// block_time the ramp is considered complete
+fn ramp_progress(start_time: u64, end_time: u64, block_time: Option<u64>) -> Decimal256 {
+    match block_time {
+        Some(block_time) if block_time <= start_time => Decimal256::zero(),
+        Some(block_time) if block_time < end_time => {
+            Decimal256::from_ratio(block_time - start_time, end_time - start_time)
+        }
+        _ => Decimal256::one(),
+    }
+}
+
+fn ramp(from: Decimal256, to: Decimal256, progress: Decimal256) -> Decimal256 {
+    if from > to {
+        from - (from - to) * progress
+    } else {
+        from + (to - from) * progress
+    }
+}
+
+/// Correlated collaterals borrowing with higher LTV ratios
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Category {
+    pub name: String,
+    pub max_ltv: Decimal256,
+    pub liquidation_threshold: Decimal256,
+}
+
+/// Progress of the collateral totals seeding started by the migration,
+/// the positions after last_seeded are not counted in the totals yet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralSeeding {
+    pub last_seeded: Option<CanonicalAddr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralTotal {
+    pub total_collateral: Uint256,
+    pub total_debt: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct PauseInfo {
+    pub guardian: Option<CanonicalAddr>,
+    pub flags: PauseFlags,
 }
 
 pub fn store_config(storage: &mut dyn Storage, data: &Config) -> StdResult<()> {
@@ -73,6 +219,16 @@ pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
     ReadonlySingleton::new(storage, KEY_CONFIG).load()
 }
 
+pub fn store_pause_info(storage: &mut dyn Storage, data: &PauseInfo) -> StdResult<()> {
+    Singleton::new(storage, KEY_PAUSE_INFO).save(data)
+}
+
+pub fn read_pause_info(storage: &dyn Storage) -> StdResult<PauseInfo> {
+    Ok(ReadonlySingleton::new(storage, KEY_PAUSE_INFO)
+        .may_load()?
+        .unwrap_or_default())
+}
+
 pub fn store_dynrate_config(storage: &mut dyn Storage, data: &DynrateConfig) -> StdResult<()> {
     Singleton::new(storage, KEY_DYNRATE_CONFIG).save(data)
 }
@@ -108,6 +264,11 @@ pub fn store_whitelist_elem(
     Ok(())
 }
 
+pub fn remove_whitelist_elem(storage: &mut dyn Storage, collateral_token: &CanonicalAddr) {
+    let mut whitelist_bucket: Bucket<WhitelistElem> = Bucket::new(storage, PREFIX_WHITELIST);
+    whitelist_bucket.remove(collateral_token.as_slice());
+}
+
 pub fn read_whitelist_elem(
     storage: &dyn Storage,
     collateral_token: &CanonicalAddr,
@@ -138,14 +299,26 @@ pub fn read_whitelist(
         .take(limit)
         .map(|elem| {
             let (k, v) = elem?;
-            let collateral_token = deps.api.human_address(&CanonicalAddr::from(k))?;
+            let collateral_token_raw = CanonicalAddr::from(k);
+            let collateral_token = deps.api.human_address(&collateral_token_raw)?;
             let custody_contract = deps.api.human_address(&v.custody_contract)?;
+            let collateral_total = read_collateral_total(deps.storage, &collateral_token_raw)?;
             Ok(WhitelistResponseElem {
                 name: v.name,
                 symbol: v.symbol,
                 collateral_token,
                 custody_contract,
                 max_ltv: v.max_ltv,
+                liquidation_threshold: v.liquidation_threshold,
+                price_source: v.price_source,
+                collateral_cap: v.collateral_cap,
+                debt_ceiling: v.debt_ceiling,
+                total_collateral: collateral_total.total_collateral,
+                total_debt: collateral_total.total_debt,
+                delisting: v.delisting,
+                category_id: v.category_id,
+                category_exit: v.category_exit,
+                swap_route: v.swap_route,
             })
         })
         .collect()
@@ -170,12 +343,133 @@ pub fn store_collaterals(
 pub fn read_collaterals(storage: &dyn Storage, borrower: &CanonicalAddr) -> Tokens {
     let collaterals_bucket: ReadonlyBucket<Tokens> =
         ReadonlyBucket::new(storage, PREFIX_COLLATERALS);
-    match collaterals_bucket.load(borrower.as_slice()) {
-        Ok(v) => v,
-        _ => vec![],
+    collaterals_bucket
+        .load(borrower.as_slice())
+        .unwrap_or_default()
+}
+
+pub fn store_collateral_seeding(
+    storage: &mut dyn Storage,
+    data: &CollateralSeeding,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_COLLATERAL_SEEDING).save(data)
+}
+
+pub fn remove_collateral_seeding(storage: &mut dyn Storage) {
+    Singleton::<CollateralSeeding>::new(storage, KEY_COLLATERAL_SEEDING).remove()
+}
+
+pub fn read_collateral_seeding(storage: &dyn Storage) -> StdResult<Option<CollateralSeeding>> {
+    ReadonlySingleton::new(storage, KEY_COLLATERAL_SEEDING).may_load()
+}
+
+pub fn store_collateral_total(
+    storage: &mut dyn Storage,
+    collateral_token: &CanonicalAddr,
+    collateral_total: &CollateralTotal,
+) -> StdResult<()> {
+    let mut collateral_total_bucket: Bucket<CollateralTotal> =
+        Bucket::new(storage, PREFIX_COLLATERAL_TOTAL);
+    collateral_total_bucket.save(collateral_token.as_slice(), collateral_total)
+}
+
+pub fn remove_collateral_total(storage: &mut dyn Storage, collateral_token: &CanonicalAddr) {
+    let mut collateral_total_bucket: Bucket<CollateralTotal> =
+        Bucket::new(storage, PREFIX_COLLATERAL_TOTAL);
+    collateral_total_bucket.remove(collateral_token.as_slice());
+}
+
+pub fn read_collateral_total(
+    storage: &dyn Storage,
+    collateral_token: &CanonicalAddr,
+) -> StdResult<CollateralTotal> {
+    let collateral_total_bucket: ReadonlyBucket<CollateralTotal> =
+        ReadonlyBucket::new(storage, PREFIX_COLLATERAL_TOTAL);
+    Ok(collateral_total_bucket
+        .may_load(collateral_token.as_slice())?
+        .unwrap_or(CollateralTotal {
+            total_collateral: Uint256::zero(),
+            total_debt: Uint256::zero(),
+        }))
+}
+
+#[allow(clippy::ptr_arg)]
+pub fn store_debt_attribution(
+    storage: &mut dyn Storage,
+    borrower: &CanonicalAddr,
+    attribution: &Tokens,
+) -> StdResult<()> {
+    let mut attribution_bucket: Bucket<Tokens> = Bucket::new(storage, PREFIX_DEBT_ATTRIBUTION);
+    if attribution.is_empty() {
+        attribution_bucket.remove(borrower.as_slice());
+    } else {
+        attribution_bucket.save(borrower.as_slice(), attribution)?;
+    }
+
+    Ok(())
+}
+
+pub fn read_debt_attribution(storage: &dyn Storage, borrower: &CanonicalAddr) -> Tokens {
+    let attribution_bucket: ReadonlyBucket<Tokens> =
+        ReadonlyBucket::new(storage, PREFIX_DEBT_ATTRIBUTION);
+    attribution_bucket
+        .load(borrower.as_slice())
+        .unwrap_or_default()
+}
+
+pub fn store_category(
+    storage: &mut dyn Storage,
+    category_id: u8,
+    category: &Category,
+) -> StdResult<()> {
+    let mut category_bucket: Bucket<Category> = Bucket::new(storage, PREFIX_CATEGORY);
+    category_bucket.save(&[category_id], category)
+}
+
+pub fn read_category(storage: &dyn Storage, category_id: u8) -> StdResult<Category> {
+    let category_bucket: ReadonlyBucket<Category> = ReadonlyBucket::new(storage, PREFIX_CATEGORY);
+    match category_bucket.load(&[category_id]) {
+        Ok(v) => Ok(v),
+        _ => Err(StdError::generic_err("Category is not registered")),
     }
 }
 
+/// There are at most 256 categories
+pub fn read_categories(storage: &dyn Storage) -> StdResult<Vec<(u8, Category)>> {
+    let category_bucket: ReadonlyBucket<Category> = ReadonlyBucket::new(storage, PREFIX_CATEGORY);
+    category_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((k[0], v))
+        })
+        .collect()
+}
+
+pub fn store_borrower_category(
+    storage: &mut dyn Storage,
+    borrower: &CanonicalAddr,
+    category_id: Option<u8>,
+) -> StdResult<()> {
+    let mut borrower_category_bucket: Bucket<u8> = Bucket::new(storage, PREFIX_BORROWER_CATEGORY);
+    if let Some(category_id) = category_id {
+        borrower_category_bucket.save(borrower.as_slice(), &category_id)?;
+    } else {
+        borrower_category_bucket.remove(borrower.as_slice());
+    }
+
+    Ok(())
+}
+
+pub fn read_borrower_category(storage: &dyn Storage, borrower: &CanonicalAddr) -> Option<u8> {
+    let borrower_category_bucket: ReadonlyBucket<u8> =
+        ReadonlyBucket::new(storage, PREFIX_BORROWER_CATEGORY);
+    borrower_category_bucket
+        .may_load(borrower.as_slice())
+        .ok()
+        .flatten()
+}
+
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
@@ -209,6 +503,31 @@ pub fn read_all_collaterals(
         .collect()
 }
 
+// every scanned position costs queries to the market and the liquidation queue
+const MAX_SCAN_LIMIT: u32 = 10;
+const DEFAULT_SCAN_LIMIT: u32 = 5;
+
+pub fn read_collaterals_page(
+    deps: Deps,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(CanonicalAddr, Tokens)>> {
+    let collaterals_bucket: ReadonlyBucket<Tokens> =
+        ReadonlyBucket::new(deps.storage, PREFIX_COLLATERALS);
+
+    let limit = limit.unwrap_or(DEFAULT_SCAN_LIMIT).min(MAX_SCAN_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    collaterals_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
 // this will set the first key after the provided key, by appending a 1 byte
 fn calc_range_start(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
     start_after.map(|addr| {
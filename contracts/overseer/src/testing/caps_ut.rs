@@ -0,0 +1,450 @@
+use crate::caps::update_debt_attribution;
+use crate::contract::{handle, init, migrate};
+use crate::error::ContractError;
+use crate::migration::{LegacyConfig, LegacyEpochState, LegacyWhitelistElem};
+use crate::state::{
+    read_collateral_total, read_config, read_debt_attribution, read_whitelist_elem,
+    store_collaterals, KEY_CONFIG, KEY_EPOCH_STATE, PREFIX_WHITELIST,
+};
+use crate::testing::mock_querier::mock_dependencies;
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{attr, Api, HumanAddr};
+use cosmwasm_storage::{Bucket, Singleton};
+
+use moneymarket::overseer::{ExecuteMsg, InstantiateMsg, MigrateMsg, PriceSource};
+use moneymarket::tokens::Tokens;
+use oraiswap::asset::{AssetInfo, AssetInfoRaw};
+
+#[test]
+fn proper_update_debt_attribution() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("owner", &[]);
+    let env = mock_env();
+    let msg = InstantiateMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        epoch_period: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        price_timeframe: None,
+        dyn_rate_epoch: None,
+        dyn_rate_maxchange: None,
+        dyn_rate_yr_increase_expectation: None,
+        dyn_rate_min: None,
+        dyn_rate_max: None,
+        init_hook: None,
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(Decimal256::from_ratio(3, 1), env.block.time, env.block.time),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(Decimal256::from_ratio(3, 1), env.block.time, env.block.time),
+        ),
+    ]);
+
+    // store whitelist elems
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
+    };
+
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "batom".to_string(),
+        symbol: "batom".to_string(),
+        collateral_token: HumanAddr::from("batom"),
+        custody_contract: HumanAddr::from("custody_batom"),
+        max_ltv: Decimal256::percent(50),
+        liquidation_threshold: Decimal256::percent(70),
+    };
+
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let bluna_raw = deps
+        .api
+        .canonical_address(&HumanAddr::from("bluna"))
+        .unwrap();
+    let batom_raw = deps
+        .api
+        .canonical_address(&HumanAddr::from("batom"))
+        .unwrap();
+    let borrower_raw = deps
+        .api
+        .canonical_address(&HumanAddr::from("addr0000"))
+        .unwrap();
+    let collaterals: Tokens = vec![
+        (bluna_raw.clone(), Uint256::from(1000u128)),
+        (batom_raw.clone(), Uint256::from(2000u128)),
+    ];
+
+    // the collaterals are priced at 3
+    // contributions = 1000 * 3 * 0.6 : 2000 * 3 * 0.5 = 1800 : 3000
+    update_debt_attribution(
+        deps.as_mut(),
+        &env,
+        &borrower_raw,
+        &collaterals,
+        Uint256::from(480u128),
+        true,
+    )
+    .unwrap();
+    assert_eq!(
+        read_debt_attribution(deps.as_ref().storage, &borrower_raw),
+        vec![
+            (bluna_raw.clone(), Uint256::from(180u128)),
+            (batom_raw.clone(), Uint256::from(300u128)),
+        ]
+    );
+    assert_eq!(
+        read_collateral_total(deps.as_ref().storage, &bluna_raw)
+            .unwrap()
+            .total_debt,
+        Uint256::from(180u128)
+    );
+    assert_eq!(
+        read_collateral_total(deps.as_ref().storage, &batom_raw)
+            .unwrap()
+            .total_debt,
+        Uint256::from(300u128)
+    );
+
+    // the previous attribution is replaced, the unlocked batom drops out
+    let collaterals: Tokens = vec![(bluna_raw.clone(), Uint256::from(1000u128))];
+    update_debt_attribution(
+        deps.as_mut(),
+        &env,
+        &borrower_raw,
+        &collaterals,
+        Uint256::from(240u128),
+        true,
+    )
+    .unwrap();
+    assert_eq!(
+        read_debt_attribution(deps.as_ref().storage, &borrower_raw),
+        vec![(bluna_raw.clone(), Uint256::from(240u128))]
+    );
+    assert_eq!(
+        read_collateral_total(deps.as_ref().storage, &bluna_raw)
+            .unwrap()
+            .total_debt,
+        Uint256::from(240u128)
+    );
+    assert_eq!(
+        read_collateral_total(deps.as_ref().storage, &batom_raw)
+            .unwrap()
+            .total_debt,
+        Uint256::zero()
+    );
+
+    let msg = ExecuteMsg::UpdateCollateralCaps {
+        collateral_token: HumanAddr::from("bluna"),
+        collateral_cap: None,
+        debt_ceiling: Some(Uint256::from(300u128)),
+    };
+    let _res = handle(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // 240 + 100 goes over the bluna debt ceiling
+    let borrower2_raw = deps
+        .api
+        .canonical_address(&HumanAddr::from("addr0001"))
+        .unwrap();
+    let res = update_debt_attribution(
+        deps.as_mut(),
+        &env,
+        &borrower2_raw,
+        &collaterals,
+        Uint256::from(100u128),
+        true,
+    );
+    match res {
+        Err(ContractError::DebtCeilingExceeded(symbol, 300)) if symbol == "bluna" => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    assert_eq!(
+        read_collateral_total(deps.as_ref().storage, &bluna_raw)
+            .unwrap()
+            .total_debt,
+        Uint256::from(240u128)
+    );
+
+    // without enforce_ceilings the debt is still attributed
+    update_debt_attribution(
+        deps.as_mut(),
+        &env,
+        &borrower2_raw,
+        &collaterals,
+        Uint256::from(100u128),
+        false,
+    )
+    .unwrap();
+    assert_eq!(
+        read_collateral_total(deps.as_ref().storage, &bluna_raw)
+            .unwrap()
+            .total_debt,
+        Uint256::from(340u128)
+    );
+
+    // decreasing debt is allowed over the ceiling
+    update_debt_attribution(
+        deps.as_mut(),
+        &env,
+        &borrower_raw,
+        &collaterals,
+        Uint256::from(220u128),
+        true,
+    )
+    .unwrap();
+    assert_eq!(
+        read_collateral_total(deps.as_ref().storage, &bluna_raw)
+            .unwrap()
+            .total_debt,
+        Uint256::from(320u128)
+    );
+
+    // a repaid loan clears the attribution
+    update_debt_attribution(
+        deps.as_mut(),
+        &env,
+        &borrower_raw,
+        &collaterals,
+        Uint256::zero(),
+        true,
+    )
+    .unwrap();
+    assert_eq!(
+        read_debt_attribution(deps.as_ref().storage, &borrower_raw),
+        vec![]
+    );
+    assert_eq!(
+        read_collateral_total(deps.as_ref().storage, &bluna_raw)
+            .unwrap()
+            .total_debt,
+        Uint256::from(100u128)
+    );
+}
+
+#[test]
+fn proper_seed_collateral_totals() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+
+    let owner_raw = deps
+        .api
+        .canonical_address(&HumanAddr::from("owner"))
+        .unwrap();
+    Singleton::new(&mut deps.storage, KEY_CONFIG)
+        .save(&LegacyConfig {
+            owner_addr: owner_raw,
+            oracle_contract: deps
+                .api
+                .canonical_address(&HumanAddr::from("oracle"))
+                .unwrap(),
+            market_contract: deps
+                .api
+                .canonical_address(&HumanAddr::from("market"))
+                .unwrap(),
+            liquidation_contract: deps
+                .api
+                .canonical_address(&HumanAddr::from("liquidation"))
+                .unwrap(),
+            collector_contract: deps
+                .api
+                .canonical_address(&HumanAddr::from("collector"))
+                .unwrap(),
+            stable_addr: deps
+                .api
+                .canonical_address(&HumanAddr::from("stable"))
+                .unwrap(),
+            epoch_period: 5u64,
+            threshold_deposit_rate: Decimal256::permille(3),
+            target_deposit_rate: Decimal256::permille(5),
+            buffer_distribution_factor: Decimal256::one(),
+            anc_purchase_factor: Decimal256::percent(15),
+            price_timeframe: 60u64,
+        })
+        .unwrap();
+    Singleton::new(&mut deps.storage, KEY_EPOCH_STATE)
+        .save(&LegacyEpochState {
+            deposit_rate: Decimal256::zero(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            prev_interest_buffer: Uint256::zero(),
+            last_executed_height: env.block.height,
+        })
+        .unwrap();
+
+    let bluna_raw = deps
+        .api
+        .canonical_address(&HumanAddr::from("bluna"))
+        .unwrap();
+    Bucket::new(&mut deps.storage, PREFIX_WHITELIST)
+        .save(
+            bluna_raw.as_slice(),
+            &LegacyWhitelistElem {
+                name: "bluna".to_string(),
+                symbol: "bluna".to_string(),
+                max_ltv: Decimal256::percent(60),
+                custody_contract: deps
+                    .api
+                    .canonical_address(&HumanAddr::from("custody_bluna"))
+                    .unwrap(),
+            },
+        )
+        .unwrap();
+
+    // positions opened before the collateral totals were tracked
+    for (borrower, amount) in [("addr0000", 100u64), ("addr0001", 200u64)] {
+        let borrower_raw = deps
+            .api
+            .canonical_address(&HumanAddr::from(borrower))
+            .unwrap();
+        store_collaterals(
+            &mut deps.storage,
+            &borrower_raw,
+            &vec![(bluna_raw.clone(), Uint256::from(amount))],
+        )
+        .unwrap();
+    }
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "stable".to_string()),
+        &(Decimal256::from_ratio(3, 1), env.block.time, env.block.time),
+    )]);
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(60u64))]);
+
+    let msg = MigrateMsg {
+        dyn_rate_epoch: 5u64,
+        dyn_rate_maxchange: Decimal256::percent(100),
+        dyn_rate_yr_increase_expectation: Decimal256::zero(),
+        dyn_rate_current: Decimal256::permille(3),
+        dyn_rate_min: Decimal256::zero(),
+        dyn_rate_max: Decimal256::permille(5),
+    };
+    migrate(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    let config = read_config(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        config.stable_info,
+        AssetInfoRaw::Token {
+            contract_addr: deps
+                .api
+                .canonical_address(&HumanAddr::from("stable"))
+                .unwrap(),
+        }
+    );
+    let whitelist_elem = read_whitelist_elem(deps.as_ref().storage, &bluna_raw).unwrap();
+    assert_eq!(
+        whitelist_elem.liquidation_threshold,
+        Decimal256::percent(60)
+    );
+    assert_eq!(whitelist_elem.price_source, PriceSource::Oracle);
+    assert_eq!(
+        read_collateral_total(deps.as_ref().storage, &bluna_raw)
+            .unwrap()
+            .total_collateral,
+        Uint256::zero()
+    );
+
+    // only the owner seeds the totals
+    let msg = ExecuteMsg::SeedCollateralTotals { limit: Some(1) };
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "seed_collateral_totals"),
+            attr("seeded_positions", 1),
+            attr("done", false),
+        ]
+    );
+
+    // locking before the position is seeded is counted by the seeding
+    let lock_msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(10u64))],
+    };
+    handle(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        lock_msg.clone(),
+    )
+    .unwrap();
+    handle(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0001", &[]),
+        lock_msg,
+    )
+    .unwrap();
+
+    handle(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "seed_collateral_totals"),
+            attr("seeded_positions", 0),
+            attr("done", true),
+        ]
+    );
+
+    let collateral_total = read_collateral_total(deps.as_ref().storage, &bluna_raw).unwrap();
+    assert_eq!(collateral_total.total_collateral, Uint256::from(320u64));
+    assert_eq!(collateral_total.total_debt, Uint256::from(60u64));
+
+    let res = handle(deps.as_mut(), env, mock_info("owner", &[]), msg);
+    match res {
+        Err(ContractError::NoCollateralSeeding {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
@@ -1,15 +1,14 @@
 use crate::collateral::compute_borrow_limit;
-use crate::contract::{execute, instantiate};
+use crate::contract::{handle, init};
 use crate::testing::mock_querier::mock_dependencies;
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, mock_info};
-use cosmwasm_std::Api;
+use cosmwasm_std::{Api, HumanAddr};
 
 use moneymarket::overseer::{ExecuteMsg, InstantiateMsg};
 use moneymarket::tokens::{Token, Tokens};
-
-use std::str::FromStr;
+use oraiswap::asset::AssetInfo;
 
 #[test]
 fn proper_compute_borrow_limit() {
@@ -18,86 +17,92 @@ fn proper_compute_borrow_limit() {
     let info = mock_info("owner", &[]);
     let env = mock_env();
     let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        oracle_contract: "oracle".to_string(),
-        market_contract: "market".to_string(),
-        liquidation_contract: "liquidation".to_string(),
-        collector_contract: "collector".to_string(),
-        stable_denom: "uusd".to_string(),
-        epoch_period: 86400u64,
-        threshold_deposit_rate: Decimal256::permille(3),
-        target_deposit_rate: Decimal256::permille(5),
-        buffer_distribution_factor: Decimal256::percent(20),
-        anc_purchase_factor: Decimal256::percent(20),
-        price_timeframe: 60u64,
-        dyn_rate_epoch: 86400u64,
-        dyn_rate_maxchange: Decimal256::from_str("0.03").unwrap(),
-        dyn_rate_yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
-        dyn_rate_min: Decimal256::zero(),
-        dyn_rate_max: Decimal256::one(),
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        epoch_period: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        price_timeframe: None,
+        dyn_rate_epoch: None,
+        dyn_rate_maxchange: None,
+        dyn_rate_yr_increase_expectation: None,
+        dyn_rate_min: None,
+        dyn_rate_max: None,
+        init_hook: None,
     };
 
     // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let _res = init(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(Decimal256::from_ratio(3, 1), env.block.time, env.block.time),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(Decimal256::from_ratio(3, 1), env.block.time, env.block.time),
+        ),
+    ]);
 
     // store whitelist elems
     let msg = ExecuteMsg::Whitelist {
         name: "bluna".to_string(),
         symbol: "bluna".to_string(),
-        collateral_token: "bluna".to_string(),
-        custody_contract: "custody_bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
         max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
     };
 
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     let msg = ExecuteMsg::Whitelist {
         name: "batom".to_string(),
         symbol: "batom".to_string(),
-        collateral_token: "batom".to_string(),
-        custody_contract: "custody_batom".to_string(),
-        max_ltv: Decimal256::percent(60),
+        collateral_token: HumanAddr::from("batom"),
+        custody_contract: HumanAddr::from("custody_batom"),
+        max_ltv: Decimal256::percent(50),
+        liquidation_threshold: Decimal256::percent(70),
     };
 
-    let _res = execute(deps.as_mut(), env.clone(), info, msg);
-
-    deps.querier.with_oracle_price(&[
-        (
-            &("bluna".to_string(), "uusd".to_string()),
-            &(
-                Decimal256::from_uint256(1000u128),
-                env.block.time.seconds(),
-                env.block.time.seconds(),
-            ),
-        ),
-        (
-            &("batom".to_string(), "uusd".to_string()),
-            &(
-                Decimal256::from_uint256(2000u128),
-                env.block.time.seconds(),
-                env.block.time.seconds(),
-            ),
-        ),
-    ]);
+    let _res = handle(deps.as_mut(), env, info, msg).unwrap();
 
+    let borrower_raw = deps
+        .api
+        .canonical_address(&HumanAddr::from("addr0000"))
+        .unwrap();
     let mut collaterals: Tokens = vec![];
     let token1: Token = (
-        deps.api.addr_canonicalize("bluna").unwrap(),
+        deps.api
+            .canonical_address(&HumanAddr::from("bluna"))
+            .unwrap(),
         Uint256::from(1000u128),
     );
     collaterals.push(token1);
     let token2: Token = (
-        deps.api.addr_canonicalize("batom").unwrap(),
-        Uint256::from(1000u128),
+        deps.api
+            .canonical_address(&HumanAddr::from("batom"))
+            .unwrap(),
+        Uint256::from(2000u128),
     );
     collaterals.push(token2);
 
-    let res = compute_borrow_limit(deps.as_ref(), &collaterals, None).unwrap();
+    // the collaterals are priced at 3
+    // borrow_limit = 1000 * 3 * 0.6 + 2000 * 3 * 0.5
+    let res = compute_borrow_limit(deps.as_ref(), &borrower_raw, &collaterals, None).unwrap();
     let vec: Vec<Decimal256> = vec![
-        Decimal256::from_uint256(1000u128),
-        Decimal256::from_uint256(2000u128),
+        Decimal256::from_uint256(3u128),
+        Decimal256::from_uint256(3u128),
     ];
 
-    let res2 = (Uint256::from(1800000u128), vec);
+    let res2 = (Uint256::from(4800u128), vec);
     assert_eq!(res, res2);
 }
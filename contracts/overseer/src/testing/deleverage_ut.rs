@@ -0,0 +1,141 @@
+use crate::contract::{handle, init};
+use crate::error::ContractError;
+use crate::state::read_debt_attribution;
+use crate::testing::mock_querier::mock_dependencies;
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{attr, Api, HumanAddr};
+
+use moneymarket::overseer::{ExecuteMsg, InstantiateMsg};
+use oraiswap::asset::AssetInfo;
+
+#[test]
+fn assert_position_healthier() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("owner", &[]);
+    let env = mock_env();
+    let msg = InstantiateMsg {
+        owner_addr: HumanAddr::from("owner"),
+        oracle_contract: HumanAddr::from("oracle"),
+        market_contract: HumanAddr::from("market"),
+        liquidation_contract: HumanAddr::from("liquidation"),
+        collector_contract: HumanAddr::from("collector"),
+        stable_info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        epoch_period: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        price_timeframe: None,
+        dyn_rate_epoch: None,
+        dyn_rate_maxchange: None,
+        dyn_rate_yr_increase_expectation: None,
+        dyn_rate_min: None,
+        dyn_rate_max: None,
+        init_hook: None,
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = init(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(Decimal256::from_ratio(3, 1), env.block.time, env.block.time),
+    )]);
+
+    // store whitelist elems
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
+    };
+
+    let _res = handle(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(1000u64))],
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // the collaterals are priced at 3
+    // borrow_limit = 1000 * 3 * 0.6 = 1800
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(900u64))]);
+
+    // only contract itself can assert the position
+    let msg = ExecuteMsg::AssertPositionHealthier {
+        borrower: HumanAddr::from("addr0000"),
+        prev_loan_amount: Uint256::from(1000u64),
+        prev_borrow_limit: Uint256::from(1800u64),
+    };
+    let res = handle(deps.as_mut(), env.clone(), info, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // same loan to borrow limit ratio
+    let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::AssertPositionHealthier {
+            borrower: HumanAddr::from("addr0000"),
+            prev_loan_amount: Uint256::from(900u64),
+            prev_borrow_limit: Uint256::from(1800u64),
+        },
+    );
+    match res {
+        Err(ContractError::PositionNotHealthier {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // without a previous borrow limit the loan must decrease
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::AssertPositionHealthier {
+            borrower: HumanAddr::from("addr0000"),
+            prev_loan_amount: Uint256::from(900u64),
+            prev_borrow_limit: Uint256::zero(),
+        },
+    );
+    match res {
+        Err(ContractError::PositionNotHealthier {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "assert_position_healthier"),
+            attr("borrower", "addr0000"),
+            attr("loan_amount", "900"),
+            attr("borrow_limit", "1800"),
+        ]
+    );
+
+    // the remaining loan is attributed to the collateral
+    let borrower_raw = deps
+        .api
+        .canonical_address(&HumanAddr::from("addr0000"))
+        .unwrap();
+    let bluna_raw = deps
+        .api
+        .canonical_address(&HumanAddr::from("bluna"))
+        .unwrap();
+    assert_eq!(
+        read_debt_attribution(deps.as_ref().storage, &borrower_raw),
+        vec![(bluna_raw, Uint256::from(900u64))]
+    );
+}
@@ -4,37 +4,36 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, from_slice, to_binary, Coin, ContractResult, Decimal, OwnedDeps, Querier,
+    from_binary, from_slice, to_binary, Coin, ContractResult, Empty, HumanAddr, OwnedDeps, Querier,
     QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
 };
 use std::collections::HashMap;
 
+use cw20::BalanceResponse;
 use moneymarket::liquidation::LiquidationAmountResponse;
-use moneymarket::market::{BorrowerInfoResponse, EpochStateResponse, StateResponse};
+use moneymarket::market::{
+    BorrowerInfoResponse, ConfigResponse as MarketConfigResponse, EpochStateResponse, FeeRecipient,
+    OriginationFeeMode, PauseFlags, RateMode, RedeemRateLock,
+};
 use moneymarket::oracle::PriceResponse;
 use moneymarket::tokens::TokensHuman;
-
-use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
-
-use std::str::FromStr;
+use oraiswap::asset::AssetInfo;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    /// Market state to market contract
-    State { block_height: Option<u64> },
     /// Query epoch state to market contract
     EpochState {
         block_height: Option<u64>,
         distributed_interest: Option<Uint256>,
     },
+    /// Query config to market contract
+    Config {},
     /// Query loan amount to market contract
     BorrowerInfo {
-        borrower: String,
+        borrower: HumanAddr,
         block_height: Option<u64>,
     },
-    /// Query oracle price to oracle contract
-    Price { base: String, quote: String },
     /// Query liquidation amount to liquidation model contract
     LiquidationAmount {
         borrow_amount: Uint256,
@@ -42,6 +41,10 @@ pub enum QueryMsg {
         collaterals: TokensHuman,
         collateral_prices: Vec<Decimal256>,
     },
+    /// Query collateral price to oracle contract
+    Price { base: String, quote: String },
+    /// Query cw20 Token Balance
+    Balance { address: HumanAddr },
 }
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
@@ -49,8 +52,10 @@ pub enum QueryMsg {
 pub fn mock_dependencies(
     contract_balance: &[Coin],
 ) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
-    let custom_querier: WasmMockQuerier =
-        WasmMockQuerier::new(MockQuerier::new(&[(MOCK_CONTRACT_ADDR, contract_balance)]));
+    let custom_querier: WasmMockQuerier = WasmMockQuerier::new(MockQuerier::new(&[(
+        &HumanAddr::from(MOCK_CONTRACT_ADDR),
+        contract_balance,
+    )]));
 
     OwnedDeps {
         storage: MockStorage::default(),
@@ -60,63 +65,11 @@ pub fn mock_dependencies(
 }
 
 pub struct WasmMockQuerier {
-    base: MockQuerier<TerraQueryWrapper>,
-    tax_querier: TaxQuerier,
+    base: MockQuerier<Empty>,
     epoch_state_querier: EpochStateQuerier,
-    oracle_price_querier: OraclePriceQuerier,
     loan_amount_querier: LoanAmountQuerier,
     liquidation_percent_querier: LiquidationPercentQuerier,
-}
-
-#[derive(Clone, Default)]
-pub struct TaxQuerier {
-    rate: Decimal,
-    // this lets us iterate over all pairs that match the first string
-    caps: HashMap<String, Uint128>,
-}
-
-impl TaxQuerier {
-    pub fn new(rate: Decimal, caps: &[(&String, &Uint128)]) -> Self {
-        TaxQuerier {
-            rate,
-            caps: caps_to_map(caps),
-        }
-    }
-}
-
-pub(crate) fn caps_to_map(caps: &[(&String, &Uint128)]) -> HashMap<String, Uint128> {
-    let mut owner_map: HashMap<String, Uint128> = HashMap::new();
-    for (denom, cap) in caps.iter() {
-        owner_map.insert(denom.to_string(), **cap);
-    }
-    owner_map
-}
-
-#[derive(Clone, Default)]
-pub struct OraclePriceQuerier {
-    // this lets us iterate over all pairs that match the first string
-    oracle_price: HashMap<(String, String), (Decimal256, u64, u64)>,
-}
-
-#[allow(clippy::type_complexity)]
-impl OraclePriceQuerier {
-    pub fn new(oracle_price: &[(&(String, String), &(Decimal256, u64, u64))]) -> Self {
-        OraclePriceQuerier {
-            oracle_price: oracle_price_to_map(oracle_price),
-        }
-    }
-}
-
-#[allow(clippy::type_complexity)]
-pub(crate) fn oracle_price_to_map(
-    oracle_price: &[(&(String, String), &(Decimal256, u64, u64))],
-) -> HashMap<(String, String), (Decimal256, u64, u64)> {
-    let mut oracle_price_map: HashMap<(String, String), (Decimal256, u64, u64)> = HashMap::new();
-    for (base_quote, oracle_price) in oracle_price.iter() {
-        oracle_price_map.insert((*base_quote).clone(), **oracle_price);
-    }
-
-    oracle_price_map
+    oracle_price_querier: OraclePriceQuerier,
 }
 
 #[derive(Clone, Default)]
@@ -167,22 +120,6 @@ pub(crate) fn borrower_amount_to_map(
     borrower_amount_map
 }
 
-impl Querier for WasmMockQuerier {
-    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
-        // MockQuerier doesn't support Custom, so we ignore it completely here
-        let request: QueryRequest<TerraQueryWrapper> = match from_slice(bin_request) {
-            Ok(v) => v,
-            Err(e) => {
-                return SystemResult::Err(SystemError::InvalidRequest {
-                    error: format!("Parsing query request: {}", e),
-                    request: bin_request.into(),
-                })
-            }
-        };
-        self.handle_query(&request)
-    }
-}
-
 #[derive(Clone, Default)]
 pub struct LiquidationPercentQuerier {
     // this lets us iterate over all pairs that match the first string
@@ -207,63 +144,64 @@ pub(crate) fn liquidation_percent_to_map(
     liquidation_percent_map
 }
 
+/// (base, quote) of an oracle price
+type OraclePair = (String, String);
+/// (rate, last_updated_base, last_updated_quote) of an oracle price
+type OraclePrice = (Decimal256, u64, u64);
+
+#[derive(Clone, Default)]
+pub struct OraclePriceQuerier {
+    // this lets us iterate over all pairs that match the first string
+    oracle_price: HashMap<OraclePair, OraclePrice>,
+}
+
+impl OraclePriceQuerier {
+    pub fn new(oracle_price: &[(&OraclePair, &OraclePrice)]) -> Self {
+        OraclePriceQuerier {
+            oracle_price: oracle_price_to_map(oracle_price),
+        }
+    }
+}
+
+pub(crate) fn oracle_price_to_map(
+    oracle_price: &[(&OraclePair, &OraclePrice)],
+) -> HashMap<OraclePair, OraclePrice> {
+    let mut oracle_price_map: HashMap<OraclePair, OraclePrice> = HashMap::new();
+    for (base_quote, oracle_price) in oracle_price.iter() {
+        oracle_price_map.insert((*base_quote).clone(), **oracle_price);
+    }
+    oracle_price_map
+}
+
+impl Querier for WasmMockQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        // MockQuerier doesn't support Custom, so we ignore it completely here
+        let request: QueryRequest<Empty> = match from_slice(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return SystemResult::Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", e),
+                    request: bin_request.into(),
+                })
+            }
+        };
+        self.handle_query(&request)
+    }
+}
+
 impl WasmMockQuerier {
-    pub fn handle_query(&self, request: &QueryRequest<TerraQueryWrapper>) -> QuerierResult {
+    pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
         match &request {
-            QueryRequest::Custom(TerraQueryWrapper { route, query_data }) => {
-                if &TerraRoute::Treasury == route {
-                    match query_data {
-                        TerraQuery::TaxRate {} => {
-                            let res = TaxRateResponse {
-                                rate: self.tax_querier.rate,
-                            };
-                            SystemResult::Ok(ContractResult::from(to_binary(&res)))
-                        }
-                        TerraQuery::TaxCap { denom } => {
-                            let cap = self
-                                .tax_querier
-                                .caps
-                                .get(denom)
-                                .copied()
-                                .unwrap_or_default();
-                            let res = TaxCapResponse { cap };
-                            SystemResult::Ok(ContractResult::from(to_binary(&res)))
-                        }
-                        _ => panic!("DO NOT ENTER HERE"),
-                    }
-                } else {
-                    panic!("DO NOT ENTER HERE")
-                }
-            }
             QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
                 match from_binary(msg).unwrap() {
-                    QueryMsg::State { block_height: _ } => {
-                        match self.epoch_state_querier.epoch_state.get(contract_addr) {
-                            // TODO:
-                            Some(_v) => {
-                                SystemResult::Ok(ContractResult::from(to_binary(&StateResponse {
-                                    total_liabilities: Decimal256::zero(),
-                                    total_reserves: Decimal256::zero(),
-                                    last_interest_updated: 0,
-                                    last_reward_updated: 0,
-                                    global_interest_index: Decimal256::from_str("1000000.0")
-                                        .unwrap(),
-                                    global_reward_index: Decimal256::zero(),
-                                    orchai_epb_rate: Decimal256::zero(),
-                                    prev_aterra_supply: Uint256::zero(),
-                                    prev_exchange_rate: Decimal256::zero(),
-                                })))
-                            }
-                            None => SystemResult::Err(SystemError::InvalidRequest {
-                                error: "No epoch state exists".to_string(),
-                                request: msg.as_slice().into(),
-                            }),
-                        }
-                    }
                     QueryMsg::EpochState {
                         block_height: _,
                         distributed_interest: _,
-                    } => match self.epoch_state_querier.epoch_state.get(contract_addr) {
+                    } => match self
+                        .epoch_state_querier
+                        .epoch_state
+                        .get(contract_addr.as_str())
+                    {
                         Some(v) => {
                             SystemResult::Ok(ContractResult::from(to_binary(&EpochStateResponse {
                                 aterra_supply: v.0,
@@ -275,39 +213,57 @@ impl WasmMockQuerier {
                             request: msg.as_slice().into(),
                         }),
                     },
+                    // the market registers "aterra" as its aToken
+                    QueryMsg::Config {} => {
+                        SystemResult::Ok(ContractResult::from(to_binary(&MarketConfigResponse {
+                            owner_addr: "owner".to_string(),
+                            aterra_contract: "aterra".to_string(),
+                            interest_model: "interest".to_string(),
+                            distribution_model: "distribution".to_string(),
+                            overseer_contract: MOCK_CONTRACT_ADDR.to_string(),
+                            collector_contract: "collector".to_string(),
+                            distributor_contract: "distributor".to_string(),
+                            stable_info: AssetInfo::NativeToken {
+                                denom: "uusd".to_string(),
+                            },
+                            orchai_token: "orchai".to_string(),
+                            max_borrow_factor: Decimal256::one(),
+                            deposit_cap: None,
+                            borrow_cap: None,
+                            reserve_factor: Decimal256::zero(),
+                            reserve_target: Uint256::zero(),
+                            redeem_rate_lock: RedeemRateLock::Request,
+                            origination_fee_rate: Decimal256::zero(),
+                            origination_fee_mode: OriginationFeeMode::AddToLoan,
+                            origination_fee_recipient: FeeRecipient::Reserves,
+                            stable_rate_premium: Decimal256::zero(),
+                            rebalance_utilization: Decimal256::one(),
+                            rebalance_min_duration: 0u64,
+                            term_grace_period: 0u64,
+                            guardian: None,
+                            pause_flags: PauseFlags::default(),
+                        })))
+                    }
+                    // the market returns an empty loan to unknown borrowers
                     QueryMsg::BorrowerInfo {
                         borrower,
                         block_height: _,
-                    } => match self.loan_amount_querier.borrower_amount.get(&borrower) {
-                        Some(v) => SystemResult::Ok(ContractResult::from(to_binary(
-                            &BorrowerInfoResponse {
-                                borrower,
-                                interest_index: Decimal256::one(),
-                                reward_index: Decimal256::zero(),
-                                loan_amount: *v,
-                                pending_rewards: Decimal256::zero(),
-                            },
-                        ))),
-                        None => SystemResult::Err(SystemError::InvalidRequest {
-                            error: "No borrow amount exists".to_string(),
-                            request: msg.as_slice().into(),
-                        }),
-                    },
-                    QueryMsg::Price { base, quote } => {
-                        match self.oracle_price_querier.oracle_price.get(&(base, quote)) {
-                            Some(v) => {
-                                SystemResult::Ok(ContractResult::from(to_binary(&PriceResponse {
-                                    rate: v.0,
-                                    last_updated_base: v.1,
-                                    last_updated_quote: v.2,
-                                })))
-                            }
-                            None => SystemResult::Err(SystemError::InvalidRequest {
-                                error: "No oracle price exists".to_string(),
-                                request: msg.as_slice().into(),
-                            }),
-                        }
-                    }
+                    } => SystemResult::Ok(ContractResult::from(to_binary(&BorrowerInfoResponse {
+                        borrower: borrower.to_string(),
+                        interest_index: Decimal256::one(),
+                        reward_index: Decimal256::zero(),
+                        loan_amount: self
+                            .loan_amount_querier
+                            .borrower_amount
+                            .get(borrower.as_str())
+                            .copied()
+                            .unwrap_or_else(Uint256::zero),
+                        pending_rewards: Decimal256::zero(),
+                        rate_mode: RateMode::Variable,
+                        stable_rate: Decimal256::zero(),
+                        term_loan_amount: Uint256::zero(),
+                        expired_term_loan_amount: Uint256::zero(),
+                    }))),
                     QueryMsg::LiquidationAmount {
                         borrow_amount,
                         borrow_limit,
@@ -317,7 +273,7 @@ impl WasmMockQuerier {
                         match self
                             .liquidation_percent_querier
                             .liquidation_percent
-                            .get(contract_addr)
+                            .get(contract_addr.as_str())
                         {
                             Some(v) => {
                                 if borrow_amount > borrow_limit {
@@ -344,6 +300,27 @@ impl WasmMockQuerier {
                             }),
                         }
                     }
+                    QueryMsg::Price { base, quote } => {
+                        match self.oracle_price_querier.oracle_price.get(&(base, quote)) {
+                            Some(v) => {
+                                SystemResult::Ok(ContractResult::from(to_binary(&PriceResponse {
+                                    rate: v.0,
+                                    last_updated_base: v.1,
+                                    last_updated_quote: v.2,
+                                })))
+                            }
+                            None => SystemResult::Err(SystemError::InvalidRequest {
+                                error: "No oracle price exists".to_string(),
+                                request: msg.as_slice().into(),
+                            }),
+                        }
+                    }
+                    // cw20 stables are never held in the tests
+                    QueryMsg::Balance { address: _ } => {
+                        SystemResult::Ok(ContractResult::from(to_binary(&BalanceResponse {
+                            balance: Uint128::zero(),
+                        })))
+                    }
                 }
             }
             _ => self.base.handle_query(request),
@@ -352,34 +329,20 @@ impl WasmMockQuerier {
 }
 
 impl WasmMockQuerier {
-    pub fn new(base: MockQuerier<TerraQueryWrapper>) -> Self {
+    pub fn new(base: MockQuerier<Empty>) -> Self {
         WasmMockQuerier {
             base,
-            tax_querier: TaxQuerier::default(),
             epoch_state_querier: EpochStateQuerier::default(),
-            oracle_price_querier: OraclePriceQuerier::default(),
             loan_amount_querier: LoanAmountQuerier::default(),
             liquidation_percent_querier: LiquidationPercentQuerier::default(),
+            oracle_price_querier: OraclePriceQuerier::default(),
         }
     }
 
-    // configure the tax mock querier
-    pub fn with_tax(&mut self, rate: Decimal, caps: &[(&String, &Uint128)]) {
-        self.tax_querier = TaxQuerier::new(rate, caps);
-    }
-
     pub fn with_epoch_state(&mut self, epoch_state: &[(&String, &(Uint256, Decimal256))]) {
         self.epoch_state_querier = EpochStateQuerier::new(epoch_state);
     }
 
-    #[allow(clippy::type_complexity)]
-    pub fn with_oracle_price(
-        &mut self,
-        oracle_price: &[(&(String, String), &(Decimal256, u64, u64))],
-    ) {
-        self.oracle_price_querier = OraclePriceQuerier::new(oracle_price);
-    }
-
     pub fn with_loan_amount(&mut self, loan_amount: &[(&String, &Uint256)]) {
         self.loan_amount_querier = LoanAmountQuerier::new(loan_amount);
     }
@@ -387,4 +350,8 @@ impl WasmMockQuerier {
     pub fn with_liquidation_percent(&mut self, liquidation_percent: &[(&String, &Decimal256)]) {
         self.liquidation_percent_querier = LiquidationPercentQuerier::new(liquidation_percent);
     }
+
+    pub fn with_oracle_price(&mut self, oracle_price: &[(&OraclePair, &OraclePrice)]) {
+        self.oracle_price_querier = OraclePriceQuerier::new(oracle_price);
+    }
 }
@@ -1,3 +1,6 @@
+mod caps_ut;
 mod collateral_ut;
+mod deleverage_ut;
 mod mock_querier;
+mod state_ut;
 mod tests;
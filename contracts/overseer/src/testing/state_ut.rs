@@ -0,0 +1,93 @@
+use crate::state::WhitelistElem;
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::CanonicalAddr;
+use moneymarket::overseer::{DelistingSchedule, PriceSource};
+
+use std::str::FromStr;
+
+fn whitelist_elem(delisting: Option<DelistingSchedule>) -> WhitelistElem {
+    WhitelistElem {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
+        custody_contract: CanonicalAddr::default(),
+        price_source: PriceSource::Oracle,
+        collateral_cap: None,
+        debt_ceiling: None,
+        delisting,
+        category_id: None,
+        category_exit: None,
+        swap_route: None,
+    }
+}
+
+#[test]
+fn ltv_ratios_without_delisting() {
+    let elem = whitelist_elem(None);
+    assert_eq!(
+        elem.ltv_ratios(Some(1500u64)),
+        (Decimal256::percent(60), Decimal256::percent(70))
+    );
+    assert_eq!(
+        elem.ltv_ratios(None),
+        (Decimal256::percent(60), Decimal256::percent(70))
+    );
+}
+
+#[test]
+fn ltv_ratios_ramp_down() {
+    let elem = whitelist_elem(Some(DelistingSchedule {
+        start_time: 1000u64,
+        end_time: 2000u64,
+        final_ltv: Decimal256::percent(30),
+    }));
+
+    // the ramp has not started yet
+    assert_eq!(
+        elem.ltv_ratios(Some(1000u64)),
+        (Decimal256::percent(60), Decimal256::percent(70))
+    );
+
+    // halfway, the liquidation threshold keeps its ratio to the max LTV
+    // final_threshold = 0.7 * 0.3 / 0.6 = 0.35
+    assert_eq!(
+        elem.ltv_ratios(Some(1500u64)),
+        (
+            Decimal256::percent(45),
+            Decimal256::from_str("0.525").unwrap()
+        )
+    );
+
+    // the ramp is complete
+    assert_eq!(
+        elem.ltv_ratios(Some(2000u64)),
+        (Decimal256::percent(30), Decimal256::percent(35))
+    );
+    assert_eq!(
+        elem.ltv_ratios(Some(3000u64)),
+        (Decimal256::percent(30), Decimal256::percent(35))
+    );
+
+    // without block time the ramp is considered complete
+    assert_eq!(
+        elem.ltv_ratios(None),
+        (Decimal256::percent(30), Decimal256::percent(35))
+    );
+}
+
+#[test]
+fn ltv_ratios_zero_max_ltv() {
+    let mut elem = whitelist_elem(Some(DelistingSchedule {
+        start_time: 1000u64,
+        end_time: 2000u64,
+        final_ltv: Decimal256::zero(),
+    }));
+    elem.max_ltv = Decimal256::zero();
+
+    assert_eq!(
+        elem.ltv_ratios(None),
+        (Decimal256::zero(), Decimal256::zero())
+    );
+}
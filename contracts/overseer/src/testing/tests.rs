@@ -1,49 +1,122 @@
-use crate::contract::{handle, init, query};
+use crate::contract::{handle, init, query, BLOCKS_PER_YEAR};
 use crate::error::ContractError;
 use crate::querier::query_epoch_state;
 use crate::state::{
-    read_epoch_state, store_dynrate_state, store_epoch_state, DynrateState, EpochState,
+    read_dynrate_state, read_epoch_state, store_dynrate_state, store_epoch_state, DynrateState,
+    EpochState,
 };
 use crate::testing::mock_querier::mock_dependencies;
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, from_binary, to_binary, HumanAddr, Api, BankMsg, CanonicalAddr, Coin, CosmosMsg, Decimal,
-    DepsMut, SubMsg, Uint128, WasmMsg,
+    attr, from_binary, to_binary, BankMsg, Coin, CosmosMsg, DepsMut, Env, HumanAddr, OwnedDeps,
+    Uint128,
+    WasmMsg,
 };
+use cw20::Cw20ReceiveMsg;
 use moneymarket::custody::ExecuteMsg as CustodyExecuteMsg;
 use moneymarket::market::ExecuteMsg as MarketExecuteMsg;
 use moneymarket::overseer::{
-    AllCollateralsResponse, BorrowLimitResponse, CollateralsResponse, ConfigResponse, ExecuteMsg,
-    InstantiateMsg, QueryMsg, WhitelistResponse, WhitelistResponseElem,
+    AllCollateralsResponse, BorrowLimitResponse, BorrowerCategoryResponse, BorrowerHealthResponse,
+    BufferDistributionPolicy, CollateralHealthElem, CollateralsResponse, ConfigResponse,
+    Cw20HookMsg, EpochStateResponse, ExecuteMsg, InstantiateMsg, LiquidatablePositionElem,
+    LiquidatablePositionsResponse, PauseFlags, PriceSource, QueryMsg, WhitelistResponse,
+    WhitelistResponseElem,
 };
-use moneymarket::querier::deduct_tax;
+use oraiswap::asset::AssetInfo;
 
 use std::str::FromStr;
 
-#[test]
-fn proper_initialization() {
-    let mut deps = mock_dependencies(&[]);
-
-    let msg = InstantiateMsg {
+fn instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
         owner_addr: HumanAddr::from("owner"),
         oracle_contract: HumanAddr::from("oracle"),
         market_contract: HumanAddr::from("market"),
         liquidation_contract: HumanAddr::from("liquidation"),
         collector_contract: HumanAddr::from("collector"),
-        stable_denom: "uusd".to_string(),
-        epoch_period: 86400u64,
-        threshold_deposit_rate: Decimal256::permille(3),
-        target_deposit_rate: Decimal256::permille(5),
-        buffer_distribution_factor: Decimal256::percent(20),
-        anc_purchase_factor: Decimal256::percent(20),
-        price_timeframe: 60u64,
-        dyn_rate_epoch: 8600u64,
-        dyn_rate_maxchange: Decimal256::permille(5),
-        dyn_rate_yr_increase_expectation: Decimal256::permille(1),
-        dyn_rate_min: Decimal256::from_ratio(1000000000000u64, 1000000000000000000u64),
-        dyn_rate_max: Decimal256::from_ratio(1200000000000u64, 1000000000000000000u64),
+        stable_info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        epoch_period: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        price_timeframe: None,
+        dyn_rate_epoch: None,
+        dyn_rate_maxchange: None,
+        dyn_rate_yr_increase_expectation: None,
+        dyn_rate_min: None,
+        dyn_rate_max: None,
+        init_hook: None,
+    }
+}
+
+/// init stores hardcoded epoch and rate parameters,
+/// the owner sets the ones the tests rely on
+fn init_overseer(
+    mut deps: DepsMut,
+    env: Env,
+    threshold_deposit_rate: Decimal256,
+    target_deposit_rate: Decimal256,
+) {
+    let info = mock_info("owner", &[]);
+    init(deps.branch(), env.clone(), info.clone(), instantiate_msg()).unwrap();
+
+    let msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: Some(threshold_deposit_rate),
+        target_deposit_rate: Some(target_deposit_rate),
+        buffer_distribution_factor: Some(Decimal256::percent(20)),
+        anc_purchase_factor: Some(Decimal256::percent(20)),
+        epoch_period: Some(86400u64),
+        price_timeframe: Some(60u64),
+        dyn_rate_epoch: Some(86400u64),
+        dyn_rate_maxchange: Some(Decimal256::from_str("0.03").unwrap()),
+        dyn_rate_yr_increase_expectation: Some(Decimal256::from_str("0.01").unwrap()),
+        dyn_rate_min: Some(Decimal256::zero()),
+        dyn_rate_max: Some(Decimal256::one()),
+        market_contract: None,
+        atoken_borrow_limit_share: None,
+        buffer_distribution_policy: None,
+        min_interest_buffer: None,
+        keeper_bounty_rate: None,
+        keeper_bounty_cap: None,
+    };
+    handle(deps, env, info, msg).unwrap();
+}
+
+fn stable_send(recipient: &str, amount: u128) -> CosmosMsg {
+    CosmosMsg::Bank(BankMsg::Send {
+        from_address: HumanAddr::from(MOCK_CONTRACT_ADDR),
+        to_address: HumanAddr::from(recipient),
+        amount: vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(amount),
+        }],
+    })
+}
+
+fn distribute_rewards(custody_contract: &str) -> CosmosMsg {
+    CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: HumanAddr::from(custody_contract),
+        send: vec![],
+        msg: to_binary(&CustodyExecuteMsg::DistributeRewards {}).unwrap(),
+    })
+}
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        epoch_period: Some(86400u64),
+        threshold_deposit_rate: Some(Decimal256::permille(3)),
+        target_deposit_rate: Some(Decimal256::permille(5)),
+        ..instantiate_msg()
     };
 
     let info = mock_info("addr0000", &[]);
@@ -51,6 +124,7 @@ fn proper_initialization() {
     // we can just call .unwrap() to assert this was a success
     let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
 
+    // the epoch and rate parameters are not taken from the message
     let query_res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
     let config_res: ConfigResponse = from_binary(&query_res).unwrap();
     assert_eq!(
@@ -61,31 +135,43 @@ fn proper_initialization() {
             market_contract: "market".to_string(),
             liquidation_contract: "liquidation".to_string(),
             collector_contract: "collector".to_string(),
-            stable_denom: "uusd".to_string(),
-            epoch_period: 86400u64,
-            threshold_deposit_rate: Decimal256::permille(3),
-            target_deposit_rate: Decimal256::permille(5),
-            buffer_distribution_factor: Decimal256::percent(20),
-            anc_purchase_factor: Decimal256::percent(20),
-            price_timeframe: 60u64,
-            dyn_rate_epoch: 8600u64,
-            dyn_rate_maxchange: Decimal256::permille(5),
-            dyn_rate_yr_increase_expectation: Decimal256::permille(1),
-            dyn_rate_min: Decimal256::from_ratio(1000000000000u64, 1000000000000000000u64),
-            dyn_rate_max: Decimal256::from_ratio(1200000000000u64, 1000000000000000000u64),
+            stable_info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            epoch_period: 5u64,
+            threshold_deposit_rate: Decimal256::from_ratio(1500u64, BLOCKS_PER_YEAR * 100),
+            target_deposit_rate: Decimal256::from_ratio(1500u64, BLOCKS_PER_YEAR * 100),
+            buffer_distribution_factor: Decimal256::one(),
+            anc_purchase_factor: Decimal256::percent(15),
+            price_timeframe: 10000000u64,
+            dyn_rate_epoch: 5u64,
+            dyn_rate_maxchange: Decimal256::one(),
+            dyn_rate_yr_increase_expectation: Decimal256::zero(),
+            dyn_rate_min: Decimal256::zero(),
+            dyn_rate_max: Decimal256::from_ratio(1500u64, BLOCKS_PER_YEAR * 100),
+            atoken_borrow_limit_share: Decimal256::percent(50),
+            buffer_distribution_policy: BufferDistributionPolicy::AllToDepositors,
+            min_interest_buffer: Uint256::zero(),
+            keeper_bounty_rate: Decimal256::zero(),
+            keeper_bounty_cap: Uint256::zero(),
+            guardian: None,
+            pause_flags: PauseFlags::default(),
         }
     );
 
     let query_res = query(deps.as_ref(), mock_env(), QueryMsg::EpochState {}).unwrap();
-    let epoch_state: EpochState = from_binary(&query_res).unwrap();
+    let epoch_state: EpochStateResponse = from_binary(&query_res).unwrap();
     assert_eq!(
         epoch_state,
-        EpochState {
+        EpochStateResponse {
             deposit_rate: Decimal256::zero(),
             last_executed_height: mock_env().block.height,
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
             prev_interest_buffer: Uint256::zero(),
+            buffer_distribution_policy: BufferDistributionPolicy::AllToDepositors,
+            min_interest_buffer: Uint256::zero(),
+            total_keeper_bounty: Uint256::zero(),
         }
     );
 }
@@ -95,28 +181,9 @@ fn update_config() {
     let mut deps = mock_dependencies(&[]);
 
     let info = mock_info("addr0000", &[]);
-    let msg = InstantiateMsg {
-        owner_addr: HumanAddr::from("owner"),
-        oracle_contract: HumanAddr::from("oracle"),
-        market_contract: HumanAddr::from("market"),
-        liquidation_contract: HumanAddr::from("liquidation"),
-        collector_contract: HumanAddr::from("collector"),
-        stable_denom: "uusd".to_string(),
-        epoch_period: 86400u64,
-        threshold_deposit_rate: Decimal256::permille(3),
-        target_deposit_rate: Decimal256::permille(5),
-        buffer_distribution_factor: Decimal256::percent(20),
-        anc_purchase_factor: Decimal256::percent(20),
-        price_timeframe: 60u64,
-        dyn_rate_epoch: 86400u64,
-        dyn_rate_maxchange: Decimal256::from_str("0.03").unwrap(),
-        dyn_rate_yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
-        dyn_rate_min: Decimal256::zero(),
-        dyn_rate_max: Decimal256::one(),
-    };
 
     // we can just call .unwrap() to assert this was a success
-    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let _res = init(deps.as_mut(), mock_env(), info, instantiate_msg()).unwrap();
 
     // update owner
     let info = mock_info("owner", &[]);
@@ -135,6 +202,12 @@ fn update_config() {
         dyn_rate_yr_increase_expectation: None,
         dyn_rate_min: None,
         dyn_rate_max: None,
+        market_contract: None,
+        atoken_borrow_limit_share: None,
+        buffer_distribution_policy: None,
+        min_interest_buffer: None,
+        keeper_bounty_rate: None,
+        keeper_bounty_cap: None,
     };
 
     let res = handle(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -149,7 +222,7 @@ fn update_config() {
     let info = mock_info("owner1", &[]);
     let msg = ExecuteMsg::UpdateConfig {
         owner_addr: None,
-        oracle_contract: Some( HumanAddr::from("oracle1")),
+        oracle_contract: Some(HumanAddr::from("oracle1")),
         liquidation_contract: Some(HumanAddr::from("liquidation1")),
         threshold_deposit_rate: Some(Decimal256::permille(1)),
         target_deposit_rate: Some(Decimal256::permille(2)),
@@ -168,6 +241,12 @@ fn update_config() {
             1200000000000u64,
             1000000000000000000u64,
         )),
+        market_contract: None,
+        atoken_borrow_limit_share: None,
+        buffer_distribution_policy: None,
+        min_interest_buffer: None,
+        keeper_bounty_rate: None,
+        keeper_bounty_cap: None,
     };
 
     let res = handle(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -188,6 +267,20 @@ fn update_config() {
     assert_eq!(Decimal256::percent(10), config_res.anc_purchase_factor);
     assert_eq!(100000u64, config_res.epoch_period);
     assert_eq!(120u64, config_res.price_timeframe);
+    assert_eq!(8600u64, config_res.dyn_rate_epoch);
+    assert_eq!(Decimal256::permille(5), config_res.dyn_rate_maxchange);
+    assert_eq!(
+        Decimal256::permille(1),
+        config_res.dyn_rate_yr_increase_expectation
+    );
+    assert_eq!(
+        Decimal256::from_ratio(1000000000000u64, 1000000000000000000u64),
+        config_res.dyn_rate_min
+    );
+    assert_eq!(
+        Decimal256::from_ratio(1200000000000u64, 1000000000000000000u64),
+        config_res.dyn_rate_max
+    );
 
     // Unauthorized err
     let info = mock_info("owner", &[]);
@@ -206,6 +299,12 @@ fn update_config() {
         dyn_rate_yr_increase_expectation: None,
         dyn_rate_min: None,
         dyn_rate_max: None,
+        market_contract: None,
+        atoken_borrow_limit_share: None,
+        buffer_distribution_policy: None,
+        min_interest_buffer: None,
+        keeper_bounty_rate: None,
+        keeper_bounty_cap: None,
     };
 
     let res = handle(deps.as_mut(), mock_env(), info, msg);
@@ -220,28 +319,9 @@ fn whitelist() {
     let mut deps = mock_dependencies(&[]);
 
     let info = mock_info("addr0000", &[]);
-    let msg = InstantiateMsg {
-        owner_addr: HumanAddr::from("owner"),
-        oracle_contract: HumanAddr::from("oracle"),
-        market_contract: HumanAddr::from("market"),
-        liquidation_contract: HumanAddr::from("liquidation"),
-        collector_contract: HumanAddr::from("collector"),
-        stable_denom: "uusd".to_string(),
-        epoch_period: 86400u64,
-        threshold_deposit_rate: Decimal256::permille(3),
-        target_deposit_rate: Decimal256::permille(5),
-        buffer_distribution_factor: Decimal256::percent(20),
-        anc_purchase_factor: Decimal256::percent(20),
-        price_timeframe: 60u64,
-        dyn_rate_epoch: 86400u64,
-        dyn_rate_maxchange: Decimal256::from_str("0.03").unwrap(),
-        dyn_rate_yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
-        dyn_rate_min: Decimal256::zero(),
-        dyn_rate_max: Decimal256::one(),
-    };
 
     // we can just call .unwrap() to assert this was a success
-    let _res = init(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let _res = init(deps.as_mut(), mock_env(), info, instantiate_msg()).unwrap();
 
     let msg = ExecuteMsg::Whitelist {
         name: "bluna".to_string(),
@@ -249,6 +329,7 @@ fn whitelist() {
         collateral_token: HumanAddr::from("bluna"),
         custody_contract: HumanAddr::from("custody"),
         max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
     };
 
     let info = mock_info("addr0000", &[]);
@@ -258,7 +339,26 @@ fn whitelist() {
         _ => panic!("DO NOT ENTER HERE"),
     };
 
+    // liquidation threshold must be above max LTV
     let info = mock_info("owner", &[]);
+    let res = handle(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::Whitelist {
+            name: "bluna".to_string(),
+            symbol: "bluna".to_string(),
+            collateral_token: HumanAddr::from("bluna"),
+            custody_contract: HumanAddr::from("custody"),
+            max_ltv: Decimal256::percent(60),
+            liquidation_threshold: Decimal256::percent(60),
+        },
+    );
+    match res {
+        Err(ContractError::InvalidLiquidationThreshold {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    };
+
     let res = handle(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(
         res.attributes,
@@ -269,6 +369,7 @@ fn whitelist() {
             attr("collateral_token", "bluna"),
             attr("custody_contract", "custody"),
             attr("LTV", "0.6"),
+            attr("liquidation_threshold", "0.7"),
         ]
     );
 
@@ -289,9 +390,19 @@ fn whitelist() {
             elems: vec![WhitelistResponseElem {
                 name: "bluna".to_string(),
                 symbol: "bluna".to_string(),
-                collateral_token: "bluna".to_string(),
-                custody_contract: "custody".to_string(),
+                collateral_token: HumanAddr::from("bluna"),
+                custody_contract: HumanAddr::from("custody"),
                 max_ltv: Decimal256::percent(60),
+                liquidation_threshold: Decimal256::percent(70),
+                price_source: PriceSource::Oracle,
+                collateral_cap: None,
+                debt_ceiling: None,
+                total_collateral: Uint256::zero(),
+                total_debt: Uint256::zero(),
+                delisting: None,
+                category_id: None,
+                category_exit: None,
+                swap_route: None,
             }]
         }
     );
@@ -303,6 +414,7 @@ fn whitelist() {
         collateral_token: HumanAddr::from("bluna"),
         custody_contract: HumanAddr::from("custody"),
         max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
     };
 
     let info = mock_info("owner", &[]);
@@ -316,6 +428,7 @@ fn whitelist() {
         collateral_token: HumanAddr::from("bluna"),
         custody_contract: Some(HumanAddr::from("custody2")),
         max_ltv: Some(Decimal256::percent(30)),
+        liquidation_threshold: None,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -334,6 +447,7 @@ fn whitelist() {
             attr("collateral_token", "bluna"),
             attr("custody_contract", "custody2"),
             attr("LTV", "0.3"),
+            attr("liquidation_threshold", "0.7"),
         ]
     );
 
@@ -354,9 +468,19 @@ fn whitelist() {
             elems: vec![WhitelistResponseElem {
                 name: "bluna".to_string(),
                 symbol: "bluna".to_string(),
-                collateral_token: "bluna".to_string(),
-                custody_contract: "custody2".to_string(),
+                collateral_token: HumanAddr::from("bluna"),
+                custody_contract: HumanAddr::from("custody2"),
                 max_ltv: Decimal256::percent(30),
+                liquidation_threshold: Decimal256::percent(70),
+                price_source: PriceSource::Oracle,
+                collateral_cap: None,
+                debt_ceiling: None,
+                total_collateral: Uint256::zero(),
+                total_debt: Uint256::zero(),
+                delisting: None,
+                category_id: None,
+                category_exit: None,
+                swap_route: None,
             }]
         }
     );
@@ -371,65 +495,60 @@ fn execute_epoch_operations() {
 
     let mut env = mock_env();
     let info = mock_info("owner", &[]);
-    let msg = InstantiateMsg {
-        owner_addr: HumanAddr::from("owner"),
-        oracle_contract: HumanAddr::from("oracle"),
-        market_contract: HumanAddr::from("market"),
-        liquidation_contract: HumanAddr::from("liquidation"),
-        collector_contract: HumanAddr::from("collector"),
-        stable_denom: "uusd".to_string(),
-        epoch_period: 86400u64,
-        threshold_deposit_rate: Decimal256::from_ratio(1u64, 1000000u64),
-        target_deposit_rate: Decimal256::from_ratio(1u64, 1000000u64),
-        buffer_distribution_factor: Decimal256::percent(20),
-        anc_purchase_factor: Decimal256::percent(20),
-        price_timeframe: 60u64,
-        dyn_rate_epoch: 86400u64,
-        dyn_rate_maxchange: Decimal256::from_str("0.03").unwrap(),
-        dyn_rate_yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
-        dyn_rate_min: Decimal256::zero(),
-        dyn_rate_max: Decimal256::one(),
-    };
-
-    // we can just call .unwrap() to assert this was a success
-    let _res = init(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
-
-    let batom_collat_token = deps
-        .api
-        .human_address(&CanonicalAddr::from(vec![
-            1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        ]))
-        .unwrap();
+    init_overseer(
+        deps.as_mut(),
+        env.clone(),
+        Decimal256::from_ratio(1u64, 1000000u64),
+        Decimal256::from_ratio(1u64, 1000000u64),
+    );
 
-    let bluna_collat_token = deps
-        .api
-        .human_address(&CanonicalAddr::from(vec![
-            1, 1, 1, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        ]))
-        .unwrap();
+    // only top the deposit rate up to the threshold
+    let msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        dyn_rate_epoch: None,
+        dyn_rate_maxchange: None,
+        dyn_rate_yr_increase_expectation: None,
+        dyn_rate_min: None,
+        dyn_rate_max: None,
+        market_contract: None,
+        atoken_borrow_limit_share: None,
+        buffer_distribution_policy: Some(BufferDistributionPolicy::ThresholdTopUp),
+        min_interest_buffer: None,
+        keeper_bounty_rate: None,
+        keeper_bounty_cap: None,
+    };
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     // store whitelist elems
     let msg = ExecuteMsg::Whitelist {
         name: "bluna".to_string(),
         symbol: "bluna".to_string(),
-        collateral_token: bluna_collat_token,
+        collateral_token: HumanAddr::from("bluna"),
         custody_contract: HumanAddr::from("custody_bluna"),
         max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
     };
 
-    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     let msg = ExecuteMsg::Whitelist {
         name: "batom".to_string(),
         symbol: "batom".to_string(),
-        collateral_token: batom_collat_token,
+        collateral_token: HumanAddr::from("batom"),
         custody_contract: HumanAddr::from("custody_batom"),
         max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
     };
 
-    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     let msg = ExecuteMsg::ExecuteEpochOperations {};
     let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone());
@@ -454,37 +573,18 @@ fn execute_epoch_operations() {
     assert_eq!(
         res.messages,
         vec![
-            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-                from_address: HumanAddr::from("overseer"),
-                to_address: HumanAddr::from("collector"),
-                amount: vec![deduct_tax(
-                    deps.as_ref(),
-                    Coin {
-                        denom: "uusd".to_string(),
-                        amount: Uint128::from(2_000_000_000u128),
-                    }
-                )
-                .unwrap()],
-            })),
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "custody_batom".to_string(),
-                funds: vec![],
-                msg: to_binary(&CustodyExecuteMsg::DistributeRewards {}).unwrap(),
-            })),
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "custody_bluna".to_string(),
-                funds: vec![],
-                msg: to_binary(&CustodyExecuteMsg::DistributeRewards {}).unwrap(),
-            })),
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
-                funds: vec![],
+            stable_send("collector", 2_000_000_000),
+            distribute_rewards("custody_bluna"),
+            distribute_rewards("custody_batom"),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                send: vec![],
                 msg: to_binary(&ExecuteMsg::UpdateEpochState {
                     interest_buffer: Uint256::from(8_000_000_000u128),
                     distributed_interest: Uint256::zero(),
                 })
                 .unwrap(),
-            }))
+            })
         ]
     );
 
@@ -497,6 +597,8 @@ fn execute_epoch_operations() {
             attr("aterra_supply", "1000000"),
             attr("distributed_interest", "0"),
             attr("anc_purchase_amount", "2000000000"),
+            attr("keeper", "owner"),
+            attr("keeper_bounty", "0"),
         ]
     );
 
@@ -509,6 +611,7 @@ fn execute_epoch_operations() {
             prev_aterra_supply: Uint256::from_str("1000000").unwrap(),
             prev_interest_buffer: Uint256::from_str("9999000000").unwrap(),
             deposit_rate: Decimal256::from_str("0.000002314814814814").unwrap(),
+            total_keeper_bounty: Uint256::zero(),
         },
     )
     .unwrap();
@@ -519,62 +622,30 @@ fn execute_epoch_operations() {
         &(Uint256::from(1000000u64), Decimal256::percent(125)),
     )]);
 
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
-    );
-
     env.block.height += 86400u64;
 
     // accrued_buffer = 1,000,000
     // interest_buffer = 9,999,000,000
     // (125 / 120 - 1) / 86400
     // deposit rate = 0.000000482253086419
+    // missing_deposits = 1,200,000 * 86400 * (0.000001 - 0.000000482253086419)
     let res = handle(deps.as_mut(), env, info, msg).unwrap();
     assert_eq!(
         res.messages,
         vec![
-            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-                to_address: "collector".to_string(),
-                amount: vec![deduct_tax(
-                    deps.as_ref(),
-                    Coin {
-                        denom: "uusd".to_string(),
-                        amount: Uint128::from(200_000u128),
-                    }
-                )
-                .unwrap()]
-            })),
-            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-                to_address: "market".to_string(),
-                amount: vec![deduct_tax(
-                    deps.as_ref(),
-                    Coin {
-                        denom: "uusd".to_string(),
-                        amount: Uint128::from(53680u128),
-                    }
-                )
-                .unwrap()]
-            })),
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "custody_batom".to_string(),
-                funds: vec![],
-                msg: to_binary(&CustodyExecuteMsg::DistributeRewards {}).unwrap(),
-            })),
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "custody_bluna".to_string(),
-                funds: vec![],
-                msg: to_binary(&CustodyExecuteMsg::DistributeRewards {}).unwrap(),
-            })),
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
-                funds: vec![],
+            stable_send("collector", 200_000),
+            stable_send("market", 53680),
+            distribute_rewards("custody_bluna"),
+            distribute_rewards("custody_batom"),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                send: vec![],
                 msg: to_binary(&ExecuteMsg::UpdateEpochState {
                     interest_buffer: Uint256::from(9999746320u128),
-                    distributed_interest: Uint256::from(53148u128),
+                    distributed_interest: Uint256::from(53680u128),
                 })
                 .unwrap(),
-            }))
+            })
         ]
     );
 
@@ -585,63 +656,134 @@ fn execute_epoch_operations() {
             attr("deposit_rate", "0.000000482253086419"),
             attr("exchange_rate", "1.25"),
             attr("aterra_supply", "1000000"),
-            attr("distributed_interest", "53148"),
-            attr("anc_purchase_amount", "200000")
+            attr("distributed_interest", "53680"),
+            attr("anc_purchase_amount", "200000"),
+            attr("keeper", "owner"),
+            attr("keeper_bounty", "0"),
         ]
     );
 }
 
 #[test]
-fn update_epoch_state() {
+fn keeper_bounty() {
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
         amount: Uint128::from(10000000000u128),
     }]);
 
+    let mut env = mock_env();
     let info = mock_info("owner", &[]);
-    let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        oracle_contract: "oracle".to_string(),
-        market_contract: "market".to_string(),
-        liquidation_contract: "liquidation".to_string(),
-        collector_contract: "collector".to_string(),
-        stable_denom: "uusd".to_string(),
-        epoch_period: 86400u64,
-        threshold_deposit_rate: Decimal256::from_ratio(1u64, 1000000u64),
-        target_deposit_rate: Decimal256::permille(5),
-        buffer_distribution_factor: Decimal256::percent(20),
-        anc_purchase_factor: Decimal256::percent(20),
-        price_timeframe: 60u64,
-        dyn_rate_epoch: 86400u64,
-        dyn_rate_maxchange: Decimal256::from_str("0.03").unwrap(),
-        dyn_rate_yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
-        dyn_rate_min: Decimal256::zero(),
-        dyn_rate_max: Decimal256::one(),
+    init_overseer(
+        deps.as_mut(),
+        env.clone(),
+        Decimal256::from_ratio(1u64, 1000000u64),
+        Decimal256::from_ratio(1u64, 1000000u64),
+    );
+
+    let msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        dyn_rate_epoch: None,
+        dyn_rate_maxchange: None,
+        dyn_rate_yr_increase_expectation: None,
+        dyn_rate_min: None,
+        dyn_rate_max: None,
+        market_contract: None,
+        atoken_borrow_limit_share: None,
+        buffer_distribution_policy: None,
+        min_interest_buffer: Some(Uint256::from(7_990_000_000u128)),
+        keeper_bounty_rate: Some(Decimal256::percent(50)),
+        keeper_bounty_cap: Some(Uint256::from(1_000_000_000u128)),
     };
+    let _res = handle(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-    // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    env.block.height += 86400u64;
+    deps.querier.with_epoch_state(&[(
+        &"market".to_string(),
+        &(Uint256::from(1000000u64), Decimal256::percent(120)),
+    )]);
+
+    // interest_buffer = 10,000,000,000 - 2,000,000,000 (anc purchase)
+    // distributable_buffer = 8,000,000,000 - 7,990,000,000 = 10,000,000
+    // keeper_bounty = 10,000,000 * 0.5, the buffer above
+    // min_interest_buffer is not enough to reach the cap
+    let info = mock_info("keeper0000", &[]);
+    let res = handle(
+        deps.as_mut(),
+        env,
+        info,
+        ExecuteMsg::ExecuteEpochOperations {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            stable_send("collector", 2_000_000_000),
+            stable_send("keeper0000", 5_000_000),
+            stable_send("market", 5_000_000),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                send: vec![],
+                msg: to_binary(&ExecuteMsg::UpdateEpochState {
+                    interest_buffer: Uint256::from(7_990_000_000u128),
+                    distributed_interest: Uint256::from(5_000_000u128),
+                })
+                .unwrap(),
+            })
+        ]
+    );
+    assert_eq!(
+        read_epoch_state(deps.as_ref().storage)
+            .unwrap()
+            .total_keeper_bounty,
+        Uint256::from(5_000_000u128)
+    );
+}
+
+#[test]
+fn update_epoch_state() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(10000000000u128),
+    }]);
+
+    let info = mock_info("owner", &[]);
+    init_overseer(
+        deps.as_mut(),
+        mock_env(),
+        Decimal256::from_ratio(1u64, 1000000u64),
+        Decimal256::permille(5),
+    );
 
     // store whitelist elems
     let msg = ExecuteMsg::Whitelist {
         name: "bluna".to_string(),
         symbol: "bluna".to_string(),
         collateral_token: HumanAddr::from("bluna"),
-        custody_contract: "custody_bluna".to_string(),
+        custody_contract: HumanAddr::from("custody_bluna"),
         max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
     };
 
-    let _res = handle(deps.as_mut(), mock_env(), info.clone(), msg);
+    let _res = handle(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
     let msg = ExecuteMsg::Whitelist {
         name: "batom".to_string(),
         symbol: "batom".to_string(),
-        collateral_token: "batom".to_string(),
-        custody_contract: "custody_batom".to_string(),
+        collateral_token: HumanAddr::from("batom"),
+        custody_contract: HumanAddr::from("custody_batom"),
         max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
     };
 
-    let _res = handle(deps.as_mut(), mock_env(), info.clone(), msg);
+    let _res = handle(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
     // only contract itself can execute update_epoch_state
     let msg = ExecuteMsg::UpdateEpochState {
@@ -667,9 +809,9 @@ fn update_epoch_state() {
     let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: "market".to_string(),
-            funds: vec![],
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("market"),
+            send: vec![],
             msg: to_binary(&MarketExecuteMsg::ExecuteEpochOperations {
                 deposit_rate: Decimal256::from_str("0.000002314814814814").unwrap(),
                 target_deposit_rate: Decimal256::permille(5),
@@ -677,7 +819,7 @@ fn update_epoch_state() {
                 distributed_interest: Uint256::from(1000000u128),
             })
             .unwrap(),
-        }))]
+        })]
     );
     assert_eq!(
         res.attributes,
@@ -696,21 +838,23 @@ fn update_epoch_state() {
         &(Uint256::from(1000000u64), Decimal256::percent(125)),
     )]);
 
+    // the first update moved the rates by dyn_rate_maxchange
+    // 0.000001 + 0.03 / 6300000 = 0.000001004761904761
     env.block.height += 86400u64;
     let res = handle(deps.as_mut(), env.clone(), info, msg).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: "market".to_string(),
-            funds: vec![],
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("market"),
+            send: vec![],
             msg: to_binary(&MarketExecuteMsg::ExecuteEpochOperations {
                 deposit_rate: Decimal256::from_str("0.000000482253086419").unwrap(),
-                target_deposit_rate: Decimal256::from_str("0.000001006442178229").unwrap(),
-                threshold_deposit_rate: Decimal256::from_str("0.000001006442178229").unwrap(),
+                target_deposit_rate: Decimal256::from_str("0.000001004761904761").unwrap(),
+                threshold_deposit_rate: Decimal256::from_str("0.000001004761904761").unwrap(),
                 distributed_interest: Uint256::from(1000000u128),
             })
             .unwrap(),
-        }))]
+        })]
     );
     assert_eq!(
         res.attributes,
@@ -725,7 +869,7 @@ fn update_epoch_state() {
 
     let epoch_state_response = query_epoch_state(
         deps.as_ref(),
-        Addr::unchecked("market"),
+        HumanAddr::from("market"),
         env.block.height,
         None,
     )
@@ -741,6 +885,7 @@ fn update_epoch_state() {
             prev_exchange_rate: epoch_state_response.exchange_rate,
             prev_interest_buffer: Uint256::from(10000000000u128),
             last_executed_height: env.block.height,
+            total_keeper_bounty: Uint256::zero(),
         }
     )
 }
@@ -750,70 +895,50 @@ fn lock_collateral() {
     let mut deps = mock_dependencies(&[]);
 
     let info = mock_info("owner", &[]);
-    let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        oracle_contract: "oracle".to_string(),
-        market_contract: "market".to_string(),
-        liquidation_contract: "liquidation".to_string(),
-        collector_contract: "collector".to_string(),
-        stable_denom: "uusd".to_string(),
-        epoch_period: 86400u64,
-        threshold_deposit_rate: Decimal256::permille(3),
-        target_deposit_rate: Decimal256::permille(5),
-        buffer_distribution_factor: Decimal256::percent(20),
-        anc_purchase_factor: Decimal256::percent(20),
-        price_timeframe: 60u64,
-        dyn_rate_epoch: 86400u64,
-        dyn_rate_maxchange: Decimal256::from_str("0.03").unwrap(),
-        dyn_rate_yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
-        dyn_rate_min: Decimal256::zero(),
-        dyn_rate_max: Decimal256::one(),
-    };
-
-    // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
-
-    let batom_collat_token = deps
-        .api
-        .human_address(&CanonicalAddr::from(vec![
-            1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        ]))
-        .unwrap();
-
-    let bluna_collat_token = deps
-        .api
-        .human_address(&CanonicalAddr::from(vec![
-            1, 1, 1, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        ]))
-        .unwrap();
+    init_overseer(
+        deps.as_mut(),
+        mock_env(),
+        Decimal256::permille(3),
+        Decimal256::permille(5),
+    );
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(Decimal256::from_ratio(3, 1), mock_env().block.time, mock_env().block.time),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(Decimal256::from_ratio(3, 1), mock_env().block.time, mock_env().block.time),
+        ),
+    ]);
 
     // store whitelist elems
     let msg = ExecuteMsg::Whitelist {
         name: "bluna".to_string(),
         symbol: "bluna".to_string(),
-        collateral_token: bluna_collat_token.clone(),
+        collateral_token: HumanAddr::from("bluna"),
         custody_contract: HumanAddr::from("custody_bluna"),
         max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
     };
 
-    let _res = handle(deps.as_mut(), mock_env(), info.clone(), msg);
+    let _res = handle(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
     let msg = ExecuteMsg::Whitelist {
         name: "batom".to_string(),
         symbol: "batom".to_string(),
-        collateral_token: batom_collat_token.clone(),
+        collateral_token: HumanAddr::from("batom"),
         custody_contract: HumanAddr::from("custody_batom"),
         max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
     };
 
-    let _res = handle(deps.as_mut(), mock_env(), info, msg);
+    let _res = handle(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     let msg = ExecuteMsg::LockCollateral {
         collaterals: vec![
-            (bluna_collat_token.to_string().clone(), Uint256::from(1000000u64)),
-            (batom_collat_token.to_string().clone(), Uint256::from(10000000u64)),
+            ("bluna".to_string(), Uint256::from(1000000u64)),
+            ("batom".to_string(), Uint256::from(10000000u64)),
         ],
     };
     let info = mock_info("addr0000", &[]);
@@ -821,24 +946,24 @@ fn lock_collateral() {
     assert_eq!(
         res.messages,
         vec![
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "custody_bluna".to_string(),
-                funds: vec![],
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_bluna"),
+                send: vec![],
                 msg: to_binary(&CustodyExecuteMsg::LockCollateral {
-                    borrower: "addr0000".to_string(),
+                    borrower: HumanAddr::from("addr0000"),
                     amount: Uint256::from(1000000u64),
                 })
                 .unwrap(),
-            })),
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "custody_batom".to_string(),
-                funds: vec![],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_batom"),
+                send: vec![],
                 msg: to_binary(&CustodyExecuteMsg::LockCollateral {
-                    borrower: "addr0000".to_string(),
+                    borrower: HumanAddr::from("addr0000"),
                     amount: Uint256::from(10000000u64),
                 })
                 .unwrap(),
-            }))
+            })
         ]
     );
 
@@ -847,13 +972,7 @@ fn lock_collateral() {
         vec![
             attr("action", "lock_collateral"),
             attr("borrower", "addr0000"),
-            attr(
-                "collaterals",
-                format!(
-                    "1000000{},10000000{}",
-                    bluna_collat_token, batom_collat_token
-                )
-            ),
+            attr("collaterals", "1000000bluna,10000000batom"),
         ]
     );
 
@@ -861,7 +980,7 @@ fn lock_collateral() {
         deps.as_ref(),
         mock_env(),
         QueryMsg::Collaterals {
-            borrower: "addr0000".to_string(),
+            borrower: HumanAddr::from("addr0000"),
         },
     )
     .unwrap();
@@ -871,8 +990,8 @@ fn lock_collateral() {
         CollateralsResponse {
             borrower: "addr0000".to_string(),
             collaterals: vec![
-                (batom_collat_token.clone(), Uint256::from(10000000u64)),
-                (bluna_collat_token.clone(), Uint256::from(1000000u64)),
+                ("bluna".to_string(), Uint256::from(1000000u64)),
+                ("batom".to_string(), Uint256::from(10000000u64)),
             ]
         }
     );
@@ -893,8 +1012,8 @@ fn lock_collateral() {
             all_collaterals: vec![CollateralsResponse {
                 borrower: "addr0000".to_string(),
                 collaterals: vec![
-                    (batom_collat_token, Uint256::from(10000000u64)),
-                    (bluna_collat_token, Uint256::from(1000000u64)),
+                    ("bluna".to_string(), Uint256::from(1000000u64)),
+                    ("batom".to_string(), Uint256::from(10000000u64)),
                 ]
             }]
         }
@@ -907,49 +1026,45 @@ fn unlock_collateral() {
 
     let info = mock_info("owner", &[]);
     let env = mock_env();
-    let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        oracle_contract: "oracle".to_string(),
-        market_contract: "market".to_string(),
-        liquidation_contract: "liquidation".to_string(),
-        collector_contract: "collector".to_string(),
-        stable_denom: "uusd".to_string(),
-        epoch_period: 86400u64,
-        threshold_deposit_rate: Decimal256::permille(3),
-        target_deposit_rate: Decimal256::permille(5),
-        buffer_distribution_factor: Decimal256::percent(20),
-        anc_purchase_factor: Decimal256::percent(20),
-        price_timeframe: 60u64,
-        dyn_rate_epoch: 86400u64,
-        dyn_rate_maxchange: Decimal256::from_str("0.03").unwrap(),
-        dyn_rate_yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
-        dyn_rate_min: Decimal256::zero(),
-        dyn_rate_max: Decimal256::one(),
-    };
-
-    // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    init_overseer(
+        deps.as_mut(),
+        env.clone(),
+        Decimal256::permille(3),
+        Decimal256::permille(5),
+    );
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(Decimal256::from_ratio(3, 1), mock_env().block.time, mock_env().block.time),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(Decimal256::from_ratio(3, 1), mock_env().block.time, mock_env().block.time),
+        ),
+    ]);
 
     // store whitelist elems
     let msg = ExecuteMsg::Whitelist {
         name: "bluna".to_string(),
         symbol: "bluna".to_string(),
         collateral_token: HumanAddr::from("bluna"),
-        custody_contract: "custody_bluna".to_string(),
+        custody_contract: HumanAddr::from("custody_bluna"),
         max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
     };
 
-    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     let msg = ExecuteMsg::Whitelist {
         name: "batom".to_string(),
         symbol: "batom".to_string(),
-        collateral_token: "batom".to_string(),
-        custody_contract: "custody_batom".to_string(),
+        collateral_token: HumanAddr::from("batom"),
+        custody_contract: HumanAddr::from("custody_batom"),
         max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
     };
 
-    let _res = handle(deps.as_mut(), env.clone(), info, msg);
+    let _res = handle(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     let msg = ExecuteMsg::LockCollateral {
         collaterals: vec![
@@ -973,29 +1088,11 @@ fn unlock_collateral() {
         _ => panic!("DO NOT ENTER HERE"),
     }
 
-    deps.querier.with_oracle_price(&[
-        (
-            &("bluna".to_string(), "uusd".to_string()),
-            &(
-                Decimal256::from_ratio(1000u64, 1u64),
-                env.block.time.seconds(),
-                env.block.time.seconds(),
-            ),
-        ),
-        (
-            &("batom".to_string(), "uusd".to_string()),
-            &(
-                Decimal256::from_ratio(2000u64, 1u64),
-                env.block.time.seconds(),
-                env.block.time.seconds(),
-            ),
-        ),
-    ]);
-
-    // borrow_limit = 1000 * 1000000 * 0.6 + 2000 * 10000000 * 0.6
-    // = 12,600,000,000 uusd
+    // the collaterals are priced at 3
+    // borrow_limit = 3 * 1000000 * 0.6 + 3 * 10000000 * 0.6
+    // = 19,800,000 uusd
     deps.querier
-        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(12600000000u64))]);
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(19800000u64))]);
 
     // cannot unlock any tokens
     // Failed to unlock more than locked amount
@@ -1004,7 +1101,7 @@ fn unlock_collateral() {
     };
     let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
     match res {
-        Err(ContractError::UnlockTooLarge(12599999400)) => (),
+        Err(ContractError::UnlockTooLarge(19799998)) => (),
         _ => panic!("DO NOT ENTER HERE"),
     }
 
@@ -1013,25 +1110,25 @@ fn unlock_collateral() {
     };
     let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
     match res {
-        Err(ContractError::UnlockTooLarge(12599998800)) => (),
+        Err(ContractError::UnlockTooLarge(19799998)) => (),
         _ => panic!("DO NOT ENTER HERE"),
     }
 
-    // borrow_limit = 1000 * 1000000 * 0.6 + 2000 * 10000000 * 0.6
-    // = 12,600,000,000 uusd
+    // borrow_limit = 3 * 1000000 * 0.6 + 3 * 10000000 * 0.6
+    // = 19,800,000 uusd
     deps.querier
-        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(12599999400u64))]);
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(19799998u64))]);
     let res = query(
         deps.as_ref(),
         env.clone(),
         QueryMsg::BorrowLimit {
-            borrower: "addr0000".to_string(),
+            borrower: HumanAddr::from("addr0000"),
             block_time: None,
         },
     )
     .unwrap();
     let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
-    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(12600000000u64),);
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(19800000u64),);
 
     // Cannot unlock 2bluna
     let msg = ExecuteMsg::UnlockCollateral {
@@ -1039,7 +1136,7 @@ fn unlock_collateral() {
     };
     let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
     match res {
-        Err(ContractError::UnlockTooLarge(12599998800)) => (),
+        Err(ContractError::UnlockTooLarge(19799996)) => (),
         _ => panic!("DO NOT ENTER HERE"),
     }
 
@@ -1050,15 +1147,15 @@ fn unlock_collateral() {
     let res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: "custody_bluna".to_string(),
-            funds: vec![],
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("custody_bluna"),
+            send: vec![],
             msg: to_binary(&CustodyExecuteMsg::UnlockCollateral {
-                borrower: "addr0000".to_string(),
+                borrower: HumanAddr::from("addr0000"),
                 amount: Uint256::one(),
             })
             .unwrap(),
-        }))]
+        })]
     );
 
     assert_eq!(
@@ -1072,7 +1169,7 @@ fn unlock_collateral() {
 
     //testing for unlocking more collaterals
     deps.querier
-        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(125999900u128))]);
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(197999u128))]);
 
     let msg = ExecuteMsg::UnlockCollateral {
         collaterals: vec![
@@ -1084,24 +1181,24 @@ fn unlock_collateral() {
     assert_eq!(
         res.messages,
         vec![
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "custody_bluna".to_string(),
-                funds: vec![],
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_bluna"),
+                send: vec![],
                 msg: to_binary(&CustodyExecuteMsg::UnlockCollateral {
-                    borrower: "addr0000".to_string(),
+                    borrower: HumanAddr::from("addr0000"),
                     amount: Uint256::from(1u128),
                 })
                 .unwrap(),
-            })),
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "custody_batom".to_string(),
-                funds: vec![],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_batom"),
+                send: vec![],
                 msg: to_binary(&CustodyExecuteMsg::UnlockCollateral {
-                    borrower: "addr0000".to_string(),
+                    borrower: HumanAddr::from("addr0000"),
                     amount: Uint256::from(1u128),
                 })
                 .unwrap(),
-            }))
+            })
         ]
     );
     assert_eq!(
@@ -1122,100 +1219,60 @@ fn liquidate_collateral() {
 
     let info = mock_info("owner", &[]);
     let env = mock_env();
-    let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        oracle_contract: "oracle".to_string(),
-        market_contract: "market".to_string(),
-        liquidation_contract: "liquidation".to_string(),
-        collector_contract: "collector".to_string(),
-        stable_denom: "uusd".to_string(),
-        epoch_period: 86400u64,
-        threshold_deposit_rate: Decimal256::permille(3),
-        target_deposit_rate: Decimal256::permille(5),
-        buffer_distribution_factor: Decimal256::percent(20),
-        anc_purchase_factor: Decimal256::percent(20),
-        price_timeframe: 60u64,
-        dyn_rate_epoch: 86400u64,
-        dyn_rate_maxchange: Decimal256::from_str("0.03").unwrap(),
-        dyn_rate_yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
-        dyn_rate_min: Decimal256::zero(),
-        dyn_rate_max: Decimal256::one(),
-    };
-
-    // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
-
-    let batom_collat_token = deps
-        .api
-        .human_address(&CanonicalAddr::from(vec![
-            1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        ]))
-        .unwrap()
-        .to_string();
-
-    let bluna_collat_token = deps
-        .api
-        .human_address(&CanonicalAddr::from(vec![
-            1, 1, 1, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        ]))
-        .unwrap()
-        .to_string();
+    init_overseer(
+        deps.as_mut(),
+        env.clone(),
+        Decimal256::permille(3),
+        Decimal256::permille(5),
+    );
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(Decimal256::from_ratio(3, 1), mock_env().block.time, mock_env().block.time),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(Decimal256::from_ratio(3, 1), mock_env().block.time, mock_env().block.time),
+        ),
+    ]);
 
     // store whitelist elems
     let msg = ExecuteMsg::Whitelist {
         name: "bluna".to_string(),
         symbol: "bluna".to_string(),
-        collateral_token: bluna_collat_token.clone(),
-        custody_contract: "custody_bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
         max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
     };
 
-    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     let msg = ExecuteMsg::Whitelist {
         name: "batom".to_string(),
         symbol: "batom".to_string(),
-        collateral_token: batom_collat_token.clone(),
-        custody_contract: "custody_batom".to_string(),
+        collateral_token: HumanAddr::from("batom"),
+        custody_contract: HumanAddr::from("custody_batom"),
         max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
     };
 
-    let _res = handle(deps.as_mut(), env.clone(), info, msg);
+    let _res = handle(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     let msg = ExecuteMsg::LockCollateral {
         collaterals: vec![
-            (bluna_collat_token.clone(), Uint256::from(1000000u64)),
-            (batom_collat_token.clone(), Uint256::from(10000000u64)),
+            ("bluna".to_string(), Uint256::from(1000000u64)),
+            ("batom".to_string(), Uint256::from(10000000u64)),
         ],
     };
     let info = mock_info("addr0000", &[]);
     let _res = handle(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-    deps.querier.with_oracle_price(&[
-        (
-            &(bluna_collat_token.clone(), "uusd".to_string()),
-            &(
-                Decimal256::from_ratio(1000u64, 1u64),
-                env.block.time.seconds(),
-                env.block.time.seconds(),
-            ),
-        ),
-        (
-            &(batom_collat_token.clone(), "uusd".to_string()),
-            &(
-                Decimal256::from_ratio(2000u64, 1u64),
-                env.block.time.seconds(),
-                env.block.time.seconds(),
-            ),
-        ),
-    ]);
-
-    // borrow_limit = 1000 * 1000000 * 0.6 + 2000 * 10000000 * 0.6
-    // = 12,600,000,000 uusd
+    // the collaterals are priced at 3
+    // liquidation_limit = 3 * 1000000 * 0.7 + 3 * 10000000 * 0.7
+    // = 23,100,000 uusd
     deps.querier
-        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(12600000000u64))]);
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(23100000u64))]);
 
     let msg = ExecuteMsg::LiquidateCollateral {
         borrower: "addr0000".to_string(),
@@ -1228,40 +1285,40 @@ fn liquidate_collateral() {
     }
 
     deps.querier
-        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(12600000001u64))]);
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(23100001u64))]);
     let res = handle(deps.as_mut(), env.clone(), info, msg).unwrap();
     assert_eq!(
         res.messages,
         vec![
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "custody_batom".to_string(),
-                funds: vec![],
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_bluna"),
+                send: vec![],
                 msg: to_binary(&CustodyExecuteMsg::LiquidateCollateral {
-                    liquidator: "addr0001".to_string(),
-                    borrower: "addr0000".to_string(),
-                    amount: Uint256::from(100000u64),
+                    liquidator: HumanAddr::from("addr0001"),
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(10000u64),
                 })
                 .unwrap(),
-            })),
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "custody_bluna".to_string(),
-                funds: vec![],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("custody_batom"),
+                send: vec![],
                 msg: to_binary(&CustodyExecuteMsg::LiquidateCollateral {
-                    liquidator: "addr0001".to_string(),
-                    borrower: "addr0000".to_string(),
-                    amount: Uint256::from(10000u64),
+                    liquidator: HumanAddr::from("addr0001"),
+                    borrower: HumanAddr::from("addr0000"),
+                    amount: Uint256::from(100000u64),
                 })
                 .unwrap(),
-            })),
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "market".to_string(),
-                funds: vec![],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from("market"),
+                send: vec![],
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
                     borrower: "addr0000".to_string(),
                     prev_balance: Uint256::zero(),
                 })
                 .unwrap(),
-            }))
+            })
         ]
     );
 
@@ -1269,7 +1326,7 @@ fn liquidate_collateral() {
         deps.as_ref(),
         env,
         QueryMsg::Collaterals {
-            borrower: "addr0000".to_string(),
+            borrower: HumanAddr::from("addr0000"),
         },
     )
     .unwrap();
@@ -1279,13 +1336,807 @@ fn liquidate_collateral() {
         CollateralsResponse {
             borrower: "addr0000".to_string(),
             collaterals: vec![
-                (batom_collat_token, Uint256::from(9900000u64)),
-                (bluna_collat_token, Uint256::from(990000u64)),
+                ("bluna".to_string(), Uint256::from(990000u64)),
+                ("batom".to_string(), Uint256::from(9900000u64)),
             ]
         }
     );
 }
 
+#[test]
+fn liquidatable_positions() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_liquidation_percent(&[(&"liquidation".to_string(), &Decimal256::percent(1))]);
+
+    let info = mock_info("owner", &[]);
+    let env = mock_env();
+    init_overseer(
+        deps.as_mut(),
+        env.clone(),
+        Decimal256::permille(3),
+        Decimal256::permille(5),
+    );
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(Decimal256::from_ratio(3, 1), env.block.time, env.block.time),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(Decimal256::from_ratio(3, 1), env.block.time, env.block.time),
+        ),
+    ]);
+
+    for symbol in ["bluna", "batom"] {
+        let msg = ExecuteMsg::Whitelist {
+            name: symbol.to_string(),
+            symbol: symbol.to_string(),
+            collateral_token: HumanAddr::from(symbol),
+            custody_contract: HumanAddr::from(format!("custody_{}", symbol)),
+            max_ltv: Decimal256::percent(60),
+            liquidation_threshold: Decimal256::percent(70),
+        };
+        let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    }
+
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(1000000u64))],
+    };
+    let _res = handle(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("batom".to_string(), Uint256::from(1000000u64))],
+    };
+    let _res = handle(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), msg).unwrap();
+
+    // liquidation_limit = 3 * 1000000 * 0.7 = 2,100,000 uusd
+    deps.querier.with_loan_amount(&[
+        (&"addr0000".to_string(), &Uint256::from(2100001u64)),
+        (&"addr0001".to_string(), &Uint256::from(2100001u64)),
+    ]);
+
+    // batom lost its price, the batom borrower is reported
+    // without hiding the bluna borrower
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(Decimal256::from_ratio(3, 1), env.block.time, env.block.time),
+    )]);
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::LiquidatablePositions {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let res: LiquidatablePositionsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res.positions,
+        vec![LiquidatablePositionElem {
+            borrower: "addr0000".to_string(),
+            loan_amount: Uint256::from(2100001u64),
+            borrow_limit: Uint256::from(1800000u64),
+            liquidation_limit: Uint256::from(2100000u64),
+            shortfall: Uint256::one(),
+            collaterals_to_seize: vec![("bluna".to_string(), Uint256::from(10000u64))],
+        }]
+    );
+    assert_eq!(res.failed, vec!["addr0001".to_string()]);
+}
+
+#[test]
+fn collateral_category_exit() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("owner", &[]);
+    let mut env = mock_env();
+    init_overseer(
+        deps.as_mut(),
+        env.clone(),
+        Decimal256::permille(3),
+        Decimal256::permille(5),
+    );
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(Decimal256::from_ratio(3, 1), u64::MAX, u64::MAX),
+    )]);
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
+    };
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::UpdateCategory {
+        category_id: 1u8,
+        name: "luna".to_string(),
+        max_ltv: Decimal256::percent(90),
+        liquidation_threshold: Decimal256::percent(95),
+    };
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::SetCollateralCategory {
+        collateral_token: HumanAddr::from("bluna"),
+        category_id: Some(1u8),
+        ramp_period: 0u64,
+    };
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let borrower_info = mock_info("addr0000", &[]);
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(1000000u64))],
+    };
+    let _res = handle(deps.as_mut(), env.clone(), borrower_info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::SetCategory {
+        category_id: Some(1u8),
+    };
+    let _res = handle(deps.as_mut(), env.clone(), borrower_info, msg).unwrap();
+
+    let query_limit = |deps: &OwnedDeps<_, _, _>, env: &Env| -> Uint256 {
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BorrowLimit {
+                borrower: HumanAddr::from("addr0000"),
+                block_time: Some(env.block.time),
+            },
+        )
+        .unwrap();
+        let res: BorrowLimitResponse = from_binary(&res).unwrap();
+        res.borrow_limit
+    };
+
+    // 3 * 1000000 * 0.9
+    assert_eq!(query_limit(&deps, &env), Uint256::from(2700000u64));
+
+    // moving the collateral out of the category ramps
+    // the category LTV down to the collateral LTV
+    let msg = ExecuteMsg::SetCollateralCategory {
+        collateral_token: HumanAddr::from("bluna"),
+        category_id: None,
+        ramp_period: 100u64,
+    };
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(query_limit(&deps, &env), Uint256::from(2700000u64));
+
+    // halfway through the ramp, 3 * 1000000 * 0.75
+    env.block.time += 50;
+    assert_eq!(query_limit(&deps, &env), Uint256::from(2250000u64));
+
+    // joining another category keeps the ramp going,
+    // leaving it would start a second one
+    let msg = ExecuteMsg::UpdateCategory {
+        category_id: 2u8,
+        name: "staked".to_string(),
+        max_ltv: Decimal256::percent(80),
+        liquidation_threshold: Decimal256::percent(85),
+    };
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::SetCollateralCategory {
+        collateral_token: HumanAddr::from("bluna"),
+        category_id: Some(2u8),
+        ramp_period: 100u64,
+    };
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(query_limit(&deps, &env), Uint256::from(2250000u64));
+
+    let msg = ExecuteMsg::SetCollateralCategory {
+        collateral_token: HumanAddr::from("bluna"),
+        category_id: None,
+        ramp_period: 100u64,
+    };
+    let res = handle(deps.as_mut(), env.clone(), info, msg);
+    match res {
+        Err(ContractError::CategoryExitInProgress(symbol)) if symbol == "bluna" => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // once the ramp is over the borrower is no longer eligible
+    env.block.time += 50;
+    assert_eq!(query_limit(&deps, &env), Uint256::from(1800000u64));
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::BorrowerCategory {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let res: BorrowerCategoryResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        BorrowerCategoryResponse {
+            borrower: "addr0000".to_string(),
+            category_id: Some(1u8),
+            eligible: false,
+        }
+    );
+}
+
+#[test]
+fn atoken_collateral() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_epoch_state(&[(
+        &"market".to_string(),
+        &(Uint256::from(10000000u64), Decimal256::from_str("1.2").unwrap()),
+    )]);
+
+    let info = mock_info("owner", &[]);
+    let env = mock_env();
+    init_overseer(
+        deps.as_mut(),
+        env.clone(),
+        Decimal256::permille(3),
+        Decimal256::permille(5),
+    );
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(Decimal256::from_ratio(3, 1), env.block.time, env.block.time),
+    )]);
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
+    };
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::WhitelistAToken {
+        name: "aterra".to_string(),
+        symbol: "aUST".to_string(),
+        custody_contract: HumanAddr::from("custody_aterra"),
+        max_ltv: Decimal256::percent(50),
+        liquidation_threshold: Decimal256::percent(60),
+    };
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "register_atoken_whitelist"),
+            attr("name", "aterra"),
+            attr("symbol", "aUST"),
+            attr("collateral_token", "aterra"),
+            attr("custody_contract", "custody_aterra"),
+            attr("LTV", "0.5"),
+            attr("liquidation_threshold", "0.6"),
+        ]
+    );
+
+    let res = handle(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(ContractError::TokenAlreadyRegistered {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Whitelist {
+            collateral_token: Some(HumanAddr::from("aterra")),
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let whitelist_res: WhitelistResponse = from_binary(&res).unwrap();
+    assert_eq!(whitelist_res.elems[0].price_source, PriceSource::ExchangeRate);
+
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![
+            ("bluna".to_string(), Uint256::from(1000000u64)),
+            ("aterra".to_string(), Uint256::from(1000000u64)),
+        ],
+    };
+    let _res = handle(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // bluna: 1000000 * 3 * 0.6 = 1,800,000
+    // aterra: 1000000 * 1.2 * 0.5 = 600,000, below the 50% share cap
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowLimit {
+            borrower: HumanAddr::from("addr0000"),
+            block_time: None,
+        },
+    )
+    .unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(2400000u64));
+
+    // aterra: 6000000 * 1.2 * 0.5 = 3,600,000, capped at
+    // 1,800,000 * 0.5 / (1 - 0.5) = 1,800,000
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("aterra".to_string(), Uint256::from(5000000u64))],
+    };
+    let _res = handle(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowLimit {
+            borrower: HumanAddr::from("addr0000"),
+            block_time: None,
+        },
+    )
+    .unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(3600000u64));
+
+    // aterra capped at 1,800,000 * 0.2 / (1 - 0.2) = 450,000
+    let msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        dyn_rate_epoch: None,
+        dyn_rate_maxchange: None,
+        dyn_rate_yr_increase_expectation: None,
+        dyn_rate_min: None,
+        dyn_rate_max: None,
+        market_contract: None,
+        atoken_borrow_limit_share: Some(Decimal256::percent(20)),
+        buffer_distribution_policy: None,
+        min_interest_buffer: None,
+        keeper_bounty_rate: None,
+        keeper_bounty_cap: None,
+    };
+    let _res = handle(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::BorrowLimit {
+            borrower: HumanAddr::from("addr0000"),
+            block_time: None,
+        },
+    )
+    .unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(2250000u64));
+}
+
+
+#[test]
+fn borrower_health() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("owner", &[]);
+    let env = mock_env();
+    init_overseer(
+        deps.as_mut(),
+        env.clone(),
+        Decimal256::permille(3),
+        Decimal256::permille(5),
+    );
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(Decimal256::from_ratio(3, 1), env.block.time, env.block.time),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(Decimal256::from_ratio(2, 1), env.block.time, env.block.time),
+        ),
+    ]);
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: HumanAddr::from("bluna"),
+        custody_contract: HumanAddr::from("custody_bluna"),
+        max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
+    };
+    let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::Whitelist {
+        name: "batom".to_string(),
+        symbol: "batom".to_string(),
+        collateral_token: HumanAddr::from("batom"),
+        custody_contract: HumanAddr::from("custody_batom"),
+        max_ltv: Decimal256::percent(50),
+        liquidation_threshold: Decimal256::percent(60),
+    };
+    let _res = handle(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![
+            ("bluna".to_string(), Uint256::from(1000000u64)),
+            ("batom".to_string(), Uint256::from(1000000u64)),
+        ],
+    };
+    let _res = handle(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // no loan, no health factor and no liquidation price
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowerHealth {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let health_res: BorrowerHealthResponse = from_binary(&res).unwrap();
+    assert_eq!(health_res.loan_amount, Uint256::zero());
+    assert_eq!(health_res.health_factor, None);
+    assert!(health_res
+        .collaterals
+        .iter()
+        .all(|elem| elem.liquidation_price.is_none()));
+
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(2400000u64))]);
+
+    // borrow_limit = 3,000,000 * 0.6 + 2,000,000 * 0.5 = 2,800,000
+    // liquidation_limit = 3,000,000 * 0.7 + 2,000,000 * 0.6 = 3,300,000
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::BorrowerHealth {
+            borrower: HumanAddr::from("addr0000"),
+        },
+    )
+    .unwrap();
+    let health_res: BorrowerHealthResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        health_res,
+        BorrowerHealthResponse {
+            borrower: "addr0000".to_string(),
+            collaterals: vec![
+                // (2,400,000 - 1,200,000) / (1,000,000 * 0.7)
+                CollateralHealthElem {
+                    collateral_token: "bluna".to_string(),
+                    amount: Uint256::from(1000000u64),
+                    price: Decimal256::from_ratio(3, 1),
+                    value: Uint256::from(3000000u64),
+                    borrow_limit: Uint256::from(1800000u64),
+                    liquidation_price: Some(Decimal256::from_ratio(12, 7)),
+                },
+                // (2,400,000 - 2,100,000) / (1,000,000 * 0.6)
+                CollateralHealthElem {
+                    collateral_token: "batom".to_string(),
+                    amount: Uint256::from(1000000u64),
+                    price: Decimal256::from_ratio(2, 1),
+                    value: Uint256::from(2000000u64),
+                    borrow_limit: Uint256::from(1000000u64),
+                    liquidation_price: Some(Decimal256::percent(50)),
+                },
+            ],
+            borrow_limit: Uint256::from(2800000u64),
+            liquidation_limit: Uint256::from(3300000u64),
+            loan_amount: Uint256::from(2400000u64),
+            health_factor: Some(Decimal256::from_str("1.375").unwrap()),
+        }
+    );
+}
+
+
+#[test]
+fn fund_reserve() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("owner", &[]);
+    let env = mock_env();
+    init_overseer(
+        deps.as_mut(),
+        env.clone(),
+        Decimal256::permille(3),
+        Decimal256::permille(5),
+    );
+
+    let msg = ExecuteMsg::FundReserve {};
+    let res = handle(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg.clone());
+    match res {
+        Err(ContractError::ZeroFundReserve {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u128),
+            }],
+        ),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "fund_reserve"),
+            attr("funded_amount", "1000000"),
+        ]
+    );
+
+    // the native stable is not taken through Receive
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(to_binary(&Cw20HookMsg::FundReserve {}).unwrap()),
+    });
+    let res = handle(deps.as_mut(), env.clone(), mock_info("uusd", &[]), msg);
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // reinitialize with a cw20 stable
+    let mut deps = mock_dependencies(&[]);
+    let mut msg = instantiate_msg();
+    msg.stable_info = AssetInfo::Token {
+        contract_addr: HumanAddr::from("stable"),
+    };
+    init(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let epoch_state = read_epoch_state(deps.as_ref().storage).unwrap();
+    let dynrate_state = read_dynrate_state(deps.as_ref().storage).unwrap();
+
+    let msg = ExecuteMsg::FundReserve {};
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u128),
+            }],
+        ),
+        msg,
+    );
+    match res {
+        Err(ContractError::NativeStableNotConfigured {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: HumanAddr::from("addr0000"),
+        amount: Uint128::from(1000000u128),
+        msg: Some(to_binary(&Cw20HookMsg::FundReserve {}).unwrap()),
+    });
+    let res = handle(deps.as_mut(), env.clone(), mock_info("other", &[]), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("stable", &[]),
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: HumanAddr::from("addr0000"),
+            amount: Uint128::from(1000000u128),
+            msg: None,
+        }),
+    );
+    match res {
+        Err(ContractError::MissingFundReserveHook {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = handle(deps.as_mut(), env, mock_info("stable", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "fund_reserve"),
+            attr("funded_amount", "1000000"),
+        ]
+    );
+
+    // the funded amount is not counted as accrued interest
+    assert_eq!(
+        read_epoch_state(deps.as_ref().storage)
+            .unwrap()
+            .prev_interest_buffer,
+        epoch_state.prev_interest_buffer + Uint256::from(1000000u64)
+    );
+    assert_eq!(
+        read_dynrate_state(deps.as_ref().storage)
+            .unwrap()
+            .prev_yield_reserve,
+        dynrate_state.prev_yield_reserve + Decimal256::from_uint256(1000000u64)
+    );
+}
+
+
+#[test]
+fn buffer_distribution_policy() {
+    fn set_policy(
+        deps: DepsMut,
+        env: Env,
+        policy: BufferDistributionPolicy,
+        min_interest_buffer: u128,
+    ) -> Result<(), ContractError> {
+        let msg = ExecuteMsg::UpdateConfig {
+            owner_addr: None,
+            oracle_contract: None,
+            liquidation_contract: None,
+            threshold_deposit_rate: None,
+            target_deposit_rate: None,
+            buffer_distribution_factor: None,
+            anc_purchase_factor: None,
+            epoch_period: None,
+            price_timeframe: None,
+            dyn_rate_epoch: None,
+            dyn_rate_maxchange: None,
+            dyn_rate_yr_increase_expectation: None,
+            dyn_rate_min: None,
+            dyn_rate_max: None,
+            market_contract: None,
+            atoken_borrow_limit_share: None,
+            buffer_distribution_policy: Some(policy),
+            min_interest_buffer: Some(Uint256::from(min_interest_buffer)),
+            keeper_bounty_rate: None,
+            keeper_bounty_cap: None,
+        };
+        handle(deps, env, mock_info("owner", &[]), msg).map(|_| ())
+    }
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(10000000000u128),
+    }]);
+
+    let mut env = mock_env();
+    init_overseer(
+        deps.as_mut(),
+        env.clone(),
+        Decimal256::from_ratio(1u64, 1000000u64),
+        Decimal256::from_ratio(1u64, 1000000u64),
+    );
+
+    let res = set_policy(
+        deps.as_mut(),
+        env.clone(),
+        BufferDistributionPolicy::FixedRatio {
+            ratio: Decimal256::percent(110),
+        },
+        0,
+    );
+    match res {
+        Err(ContractError::InvalidBufferDistributionRatio {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    set_policy(
+        deps.as_mut(),
+        env.clone(),
+        BufferDistributionPolicy::FixedRatio {
+            ratio: Decimal256::percent(10),
+        },
+        7_000_000_000,
+    )
+    .unwrap();
+
+    let query_res = query(deps.as_ref(), env.clone(), QueryMsg::EpochState {}).unwrap();
+    let epoch_state: EpochStateResponse = from_binary(&query_res).unwrap();
+    assert_eq!(
+        epoch_state.buffer_distribution_policy,
+        BufferDistributionPolicy::FixedRatio {
+            ratio: Decimal256::percent(10),
+        }
+    );
+    assert_eq!(
+        epoch_state.min_interest_buffer,
+        Uint256::from(7_000_000_000u128)
+    );
+
+    env.block.height += 86400u64;
+    deps.querier.with_epoch_state(&[(
+        &"market".to_string(),
+        &(Uint256::from(1000000u64), Decimal256::percent(120)),
+    )]);
+
+    // anc_purchase_amount = 10,000,000,000 * 0.2 = 2,000,000,000
+    // interest_buffer = 8,000,000,000
+    // 8,000,000,000 * 0.1 = 800,000,000 < 1,000,000,000 above the minimum
+    let msg = ExecuteMsg::ExecuteEpochOperations {};
+    let res = handle(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            stable_send("collector", 2_000_000_000),
+            stable_send("market", 800_000_000),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                send: vec![],
+                msg: to_binary(&ExecuteMsg::UpdateEpochState {
+                    interest_buffer: Uint256::from(7_200_000_000u128),
+                    distributed_interest: Uint256::from(800_000_000u128),
+                })
+                .unwrap(),
+            })
+        ]
+    );
+
+    // the minimum limits the ratio to 8,000,000,000 - 7,900,000,000
+    set_policy(
+        deps.as_mut(),
+        env.clone(),
+        BufferDistributionPolicy::FixedRatio {
+            ratio: Decimal256::percent(10),
+        },
+        7_900_000_000,
+    )
+    .unwrap();
+
+    let res = handle(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            stable_send("collector", 2_000_000_000),
+            stable_send("market", 100_000_000),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                send: vec![],
+                msg: to_binary(&ExecuteMsg::UpdateEpochState {
+                    interest_buffer: Uint256::from(7_900_000_000u128),
+                    distributed_interest: Uint256::from(100_000_000u128),
+                })
+                .unwrap(),
+            })
+        ]
+    );
+
+    // everything above the minimum is distributed
+    set_policy(
+        deps.as_mut(),
+        env.clone(),
+        BufferDistributionPolicy::AllToDepositors,
+        7_000_000_000,
+    )
+    .unwrap();
+
+    let res = handle(deps.as_mut(), env, mock_info("owner", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            stable_send("collector", 2_000_000_000),
+            stable_send("market", 1_000_000_000),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: HumanAddr::from(MOCK_CONTRACT_ADDR),
+                send: vec![],
+                msg: to_binary(&ExecuteMsg::UpdateEpochState {
+                    interest_buffer: Uint256::from(7_000_000_000u128),
+                    distributed_interest: Uint256::from(1_000_000_000u128),
+                })
+                .unwrap(),
+            })
+        ]
+    );
+}
+
+
 #[test]
 fn dynamic_rate_model() {
     let mut deps = mock_dependencies(&[Coin {
@@ -1294,49 +2145,65 @@ fn dynamic_rate_model() {
     }]);
 
     let info = mock_info("owner", &[]);
-    let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        oracle_contract: "oracle".to_string(),
-        market_contract: "market".to_string(),
-        liquidation_contract: "liquidation".to_string(),
-        collector_contract: "collector".to_string(),
-        stable_denom: "uusd".to_string(),
-        epoch_period: 86400u64,
-        threshold_deposit_rate: Decimal256::from_ratio(1u64, 1000000u64),
-        target_deposit_rate: Decimal256::permille(5),
-        buffer_distribution_factor: Decimal256::percent(20),
-        anc_purchase_factor: Decimal256::percent(20),
-        price_timeframe: 60u64,
-        dyn_rate_epoch: 8600u64,
-        dyn_rate_maxchange: Decimal256::permille(5),
-        dyn_rate_yr_increase_expectation: Decimal256::permille(1),
-        dyn_rate_min: Decimal256::from_ratio(1000000000000u64, 1000000000000000000u64),
-        dyn_rate_max: Decimal256::from_ratio(1200000000000u64, 1000000000000000000u64),
-    };
+    init_overseer(
+        deps.as_mut(),
+        mock_env(),
+        Decimal256::from_ratio(1u64, 1000000u64),
+        Decimal256::permille(5),
+    );
 
-    // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        dyn_rate_epoch: Some(8600u64),
+        dyn_rate_maxchange: Some(Decimal256::permille(5)),
+        dyn_rate_yr_increase_expectation: Some(Decimal256::permille(1)),
+        dyn_rate_min: Some(Decimal256::from_ratio(
+            1000000000000u64,
+            1000000000000000000u64,
+        )),
+        dyn_rate_max: Some(Decimal256::from_ratio(
+            1200000000000u64,
+            1000000000000000000u64,
+        )),
+        market_contract: None,
+        atoken_borrow_limit_share: None,
+        buffer_distribution_policy: None,
+        min_interest_buffer: None,
+        keeper_bounty_rate: None,
+        keeper_bounty_cap: None,
+    };
+    let _res = handle(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
     // store whitelist elems
     let msg = ExecuteMsg::Whitelist {
         name: "bluna".to_string(),
         symbol: "bluna".to_string(),
         collateral_token: HumanAddr::from("bluna"),
-        custody_contract: "custody_bluna".to_string(),
+        custody_contract: HumanAddr::from("custody_bluna"),
         max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
     };
 
-    let _res = handle(deps.as_mut(), mock_env(), info.clone(), msg);
+    let _res = handle(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
     let msg = ExecuteMsg::Whitelist {
         name: "batom".to_string(),
         symbol: "batom".to_string(),
-        collateral_token: "batom".to_string(),
-        custody_contract: "custody_batom".to_string(),
+        collateral_token: HumanAddr::from("batom"),
+        custody_contract: HumanAddr::from("custody_batom"),
         max_ltv: Decimal256::percent(60),
+        liquidation_threshold: Decimal256::percent(70),
     };
 
-    let _res = handle(deps.as_mut(), mock_env(), info.clone(), msg);
+    let _res = handle(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
     // only contract itself can execute update_epoch_state
     let msg = ExecuteMsg::UpdateEpochState {
@@ -1362,9 +2229,9 @@ fn dynamic_rate_model() {
     let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: "market".to_string(),
-            funds: vec![],
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("market"),
+            send: vec![],
             msg: to_binary(&MarketExecuteMsg::ExecuteEpochOperations {
                 deposit_rate: Decimal256::from_str("0.000002314814814814").unwrap(),
                 target_deposit_rate: Decimal256::permille(5),
@@ -1372,7 +2239,7 @@ fn dynamic_rate_model() {
                 distributed_interest: Uint256::from(1000000u128),
             })
             .unwrap(),
-        }))]
+        })]
     );
     assert_eq!(
         res.attributes,
@@ -1394,19 +2261,20 @@ fn dynamic_rate_model() {
     env.block.height += 86400u64;
     let res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
 
+    // Rate increase: 0.005 / 6300000 (bpy) = 793650793
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: "market".to_string(),
-            funds: vec![],
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: HumanAddr::from("market"),
+            send: vec![],
             msg: to_binary(&MarketExecuteMsg::ExecuteEpochOperations {
                 deposit_rate: Decimal256::from_str("0.000000482253086419").unwrap(),
-                target_deposit_rate: Decimal256::from_str("0.000001001073696371").unwrap(),
-                threshold_deposit_rate: Decimal256::from_str("0.000001001073696371").unwrap(),
+                target_deposit_rate: Decimal256::from_str("0.000001000793650793").unwrap(),
+                threshold_deposit_rate: Decimal256::from_str("0.000001000793650793").unwrap(),
                 distributed_interest: Uint256::from(1000000u128),
             })
             .unwrap(),
-        }))]
+        })]
     );
     assert_eq!(
         res.attributes,
@@ -1421,7 +2289,7 @@ fn dynamic_rate_model() {
 
     let epoch_state_response = query_epoch_state(
         deps.as_ref(),
-        Addr::unchecked("market"),
+        HumanAddr::from("market"),
         env.block.height,
         None,
     )
@@ -1436,18 +2304,19 @@ fn dynamic_rate_model() {
             prev_exchange_rate: epoch_state_response.exchange_rate,
             prev_interest_buffer: Uint256::from(10000000000u128),
             last_executed_height: env.block.height,
+            total_keeper_bounty: Uint256::zero(),
         }
     );
 
     // Case 1: YR unchanged, expected drop in rate due to dyn_rate_yr_increase_expectation
-    // Rate drop: 1001073696371 - 1000858957096 = 214739275
-    // 214739275 * 4656810 (bpy) = 1e15 = dyn_rate_yr_increase_expectation
+    // Rate drop: 1000793650793 - 1000634920634 = 158730159
+    // 158730159 * 6300000 (bpy) = 1e15 = dyn_rate_yr_increase_expectation
     validate_deposit_rates(
         deps.as_mut(),
-        Decimal256::from_ratio(1000858957096u64, 1000000000000000000u64),
+        Decimal256::from_ratio(1000634920634u64, 1000000000000000000u64),
     );
     // Case 2: Stillk unchanged, repeating behavior
-    // Rate drop: 1000858957096 - 1000644217821 = 214739275 = dyn_rate_yr_increase_expectation
+    // Rate drop: 1000634920634 - 1000476190475 = 158730159 = dyn_rate_yr_increase_expectation
     store_dynrate_state(
         deps.as_mut().storage,
         &DynrateState {
@@ -1460,11 +2329,11 @@ fn dynamic_rate_model() {
     let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
     validate_deposit_rates(
         deps.as_mut(),
-        Decimal256::from_ratio(1000644217821u64, 1000000000000000000u64),
+        Decimal256::from_ratio(1000476190475u64, 1000000000000000000u64),
     );
 
     // ----- YR increasing dramarically, 10x
-    // Rate increase: (1001717914192 - 1000644217821) * 4656810 = 5e15 = dyn_rate_maxchange
+    // Rate increase: (1001269841268 - 1000476190475) * 6300000 = 5e15 = dyn_rate_maxchange
     store_dynrate_state(
         deps.as_mut().storage,
         &DynrateState {
@@ -1477,11 +2346,11 @@ fn dynamic_rate_model() {
     let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
     validate_deposit_rates(
         deps.as_mut(),
-        Decimal256::from_ratio(1001717914192u64, 1000000000000000000u64),
+        Decimal256::from_ratio(1001269841268u64, 1000000000000000000u64),
     );
 
     // ----- YR increasing just a little, rate will still drop to compensate for dyn_rate_yr_increase_expectation
-    // (1001717914192 - 1001503174896) * 4656810 = 1.0000001e15
+    // (1001269841268 - 1001111111093) * 6300000 = 1.0000001e15
     store_dynrate_state(
         deps.as_mut().storage,
         &DynrateState {
@@ -1494,7 +2363,7 @@ fn dynamic_rate_model() {
     let _res = handle(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
     validate_deposit_rates(
         deps.as_mut(),
-        Decimal256::from_ratio(1001503174896u64, 1000000000000000000u64),
+        Decimal256::from_ratio(1001111111093u64, 1000000000000000000u64),
     );
 
     // lets hit lower threshold
@@ -1507,8 +2376,8 @@ fn dynamic_rate_model() {
         Decimal256::from_ratio(1000000000000u64, 1000000000000000000u64),
     );
 
-    // lets hit upper threshold
-    for _i in 1..200 {
+    // lets hit upper threshold, 0.0000002 / 793650793 takes 253 updates
+    for _i in 1..300 {
         store_dynrate_state(
             deps.as_mut().storage,
             &DynrateState {
@@ -1537,7 +2406,9 @@ fn validate_deposit_rates(deps: DepsMut, rate: Decimal256) {
             market_contract: "market".to_string(),
             liquidation_contract: "liquidation".to_string(),
             collector_contract: "collector".to_string(),
-            stable_denom: "uusd".to_string(),
+            stable_info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
             epoch_period: 86400u64,
             threshold_deposit_rate: rate,
             target_deposit_rate: rate,
@@ -1549,6 +2420,13 @@ fn validate_deposit_rates(deps: DepsMut, rate: Decimal256) {
             dyn_rate_yr_increase_expectation: Decimal256::permille(1),
             dyn_rate_min: Decimal256::from_ratio(1000000000000u64, 1000000000000000000u64),
             dyn_rate_max: Decimal256::from_ratio(1200000000000u64, 1000000000000000000u64),
+            atoken_borrow_limit_share: Decimal256::percent(50),
+            buffer_distribution_policy: BufferDistributionPolicy::AllToDepositors,
+            min_interest_buffer: Uint256::zero(),
+            keeper_bounty_rate: Decimal256::zero(),
+            keeper_bounty_cap: Uint256::zero(),
+            guardian: None,
+            pause_flags: PauseFlags::default(),
         }
     );
 }
@@ -1,3 +1,4 @@
+use cosmwasm_bignumber::Uint256;
 use cosmwasm_std::{Api, StdResult, HumanAddr,CanonicalAddr};
 
 pub fn optional_addr_validate(api: &dyn Api, addr: Option<String>) -> StdResult<Option<CanonicalAddr>> {
@@ -10,3 +11,8 @@ pub fn optional_addr_validate(api: &dyn Api, addr: Option<String>) -> StdResult<
 
     Ok(addr)
 }
+
+/// Cap attribute value, an unset cap means unlimited
+pub fn format_cap(cap: Option<Uint256>) -> String {
+    cap.map_or_else(|| "unlimited".to_string(), |cap| cap.to_string())
+}
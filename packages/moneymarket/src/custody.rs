@@ -2,7 +2,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_bignumber::Uint256;
-use cosmwasm_std::HumanAddr;
+use cosmwasm_std::{Binary, HumanAddr};
 use cw20::Cw20ReceiveMsg;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -10,14 +10,15 @@ use cw20::Cw20ReceiveMsg;
 pub struct InstantiateMsg {
     /// owner address
     pub owner: HumanAddr,
-    /// bAsset token address
+    /// bAsset or market aToken address
     pub collateral_token: HumanAddr,
     /// overseer contract address
     pub overseer_contract: HumanAddr,
     /// market contract address
     pub market_contract: HumanAddr,
-    /// bAsset rewrad contract
-    pub reward_contract: HumanAddr,
+    /// bAsset rewrad contract, none for a market aToken
+    /// which accrues interest through the exchange rate
+    pub reward_contract: Option<HumanAddr>,
     /// liquidation contract address
     pub liquidation_contract: HumanAddr,
     /// oraiswap contract address
@@ -47,6 +48,16 @@ pub enum ExecuteMsg {
         reward_contract: Option<HumanAddr>,
         swap_contract: Option<HumanAddr>,
     },
+    /// Set or remove the guardian allowed to pause operations
+    UpdateGuardian {
+        guardian: Option<HumanAddr>,
+    },
+    /// Update per-operation pause flags,
+    /// the guardian can only pause, unpausing is owner-only
+    UpdatePauseFlags {
+        deposit: Option<bool>,
+        withdraw: Option<bool>,
+    },
     /// Make specified amount of tokens unspendable
     LockCollateral {
         borrower: HumanAddr,
@@ -68,6 +79,15 @@ pub enum ExecuteMsg {
         amount: Uint256,
     },
 
+    /// Send locked collateral to a contract with a hook message,
+    /// used to repay a loan with collateral
+    SendCollateral {
+        borrower: HumanAddr,
+        amount: Uint256,
+        contract: HumanAddr,
+        msg: Binary,
+    },
+
     ////////////////////
     /// User operations
     ////////////////////
@@ -115,10 +135,19 @@ pub struct ConfigResponse {
     pub collateral_token: String,
     pub overseer_contract: String,
     pub market_contract: String,
-    pub reward_contract: String,
+    pub reward_contract: Option<String>,
     pub liquidation_contract: String,
     pub stable_addr: String,
     pub basset_info: BAssetInfo,
+    pub guardian: Option<String>,
+    pub pause_flags: PauseFlags,
+}
+
+/// Operations the guardian or the owner can pause
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct PauseFlags {
+    pub deposit: bool,
+    pub withdraw: bool,
 }
 
 // We define a custom struct for each query response
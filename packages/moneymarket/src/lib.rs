@@ -6,6 +6,7 @@ pub mod interest_model;
 pub mod liquidation;
 pub mod liquidation_queue;
 pub mod market;
+pub mod market_factory;
 pub mod oracle;
 pub mod overseer;
 pub mod querier;
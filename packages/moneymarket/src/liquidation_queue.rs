@@ -5,6 +5,7 @@ use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{Uint128, HumanAddr};
 use cw20::Cw20ReceiveMsg;
 
+use crate::market::InitHook;
 use crate::tokens::TokensHuman;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -31,6 +32,8 @@ pub struct InstantiateMsg {
     pub waiting_period: u64,
     pub overseer: HumanAddr,
     pub oraiswap_oracle: HumanAddr,
+    /// Executed once the queue is instantiated
+    pub init_hook: Option<InitHook>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -60,6 +63,18 @@ pub enum ExecuteMsg {
         bid_threshold: Option<Uint256>,
         max_slot: Option<u8>,
     },
+    /// Set or remove the guardian allowed to pause operations
+    UpdateGuardian {
+        guardian: Option<HumanAddr>,
+    },
+    /// Update per-operation pause flags,
+    /// the guardian can only pause, unpausing is owner-only
+    UpdatePauseFlags {
+        submit: Option<bool>,
+        retract: Option<bool>,
+        execute: Option<bool>,
+        claim: Option<bool>,
+    },
     /// Withdraw a bid
     RetractBid {
         bid_idx: Uint128,
@@ -138,6 +153,17 @@ pub struct ConfigResponse {
     pub price_timeframe: u64,
     pub waiting_period: u64,
     pub overseer: String,
+    pub guardian: Option<String>,
+    pub pause_flags: PauseFlags,
+}
+
+/// Operations the guardian or the owner can pause
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct PauseFlags {
+    pub submit: bool,
+    pub retract: bool,
+    pub execute: bool,
+    pub claim: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -4,15 +4,17 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{Attribute, Binary, HumanAddr, Uint128};
 use cw20::Cw20ReceiveMsg;
-use cw20::{Cw20Coin, MinterResponse};
+use cw20::{Cw20Coin, Expiration, MinterResponse};
+use oraiswap::asset::AssetInfo;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InstantiateMsg {
     /// Owner address for config update
     pub owner_addr: HumanAddr,
-    /// stable coin denom used to borrow & repay
-    pub stable_addr: HumanAddr,
+    /// stable asset used to borrow & repay,
+    /// either a native denom or a cw20 token
+    pub stable_info: AssetInfo,
     /// Anchor token code ID used to instantiate
     pub orchai_code_id: u64,
     /// Anchor token distribution speed
@@ -20,9 +22,59 @@ pub struct InstantiateMsg {
     /// Maximum allowed borrow rate over deposited stable balance
     pub max_borrow_factor: Decimal256,
     pub orchai_token: HumanAddr,
+    /// Maximum total deposits (in stable) the market accepts, unlimited if not set
+    pub deposit_cap: Option<Uint256>,
+    /// Maximum total liabilities the market allows, unlimited if not set
+    pub borrow_cap: Option<Uint256>,
+    /// Portion of the accrued interest kept as reserves
+    pub reserve_factor: Decimal256,
+    /// Reserves kept in the market as a buffer,
+    /// the excess is sent to the collector every epoch
+    pub reserve_target: Uint256,
+    /// Whether queued redemptions use the exchange rate
+    /// at request time or at fill time
+    pub redeem_rate_lock: RedeemRateLock,
+    /// aToken name and symbol
+    pub atoken_name: String,
+    pub atoken_symbol: String,
+    /// Hook executed with the market address after instantiation
+    pub init_hook: Option<InitHook>,
     // pub hook_msg: HookMsg,
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RedeemRateLock {
+    Request,
+    Fill,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OriginationFeeMode {
+    /// The fee is added to the borrower loan
+    AddToLoan,
+    /// The fee is deducted from the borrowed stable
+    DeductFromBorrow,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeRecipient {
+    Reserves,
+    Collector,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RateMode {
+    /// Follows the market borrow rate through global_interest_index
+    #[default]
+    Variable,
+    /// Locked at borrow time, repriced only by a rebalance
+    Stable,
+}
+
 /// InstantiateMsg Hook
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct HookMsg {
@@ -42,6 +94,25 @@ pub struct TokenInstantiateMsg {
     pub init_hook: Option<InitHook>,
 }
 
+/// Config values to update, unset values are kept
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UpdateConfigMsg {
+    pub owner_addr: Option<HumanAddr>,
+    pub max_borrow_factor: Option<Decimal256>,
+    pub interest_model: Option<HumanAddr>,
+    pub distribution_model: Option<HumanAddr>,
+    pub reserve_factor: Option<Decimal256>,
+    pub reserve_target: Option<Uint256>,
+    pub redeem_rate_lock: Option<RedeemRateLock>,
+    pub origination_fee_rate: Option<Decimal256>,
+    pub origination_fee_mode: Option<OriginationFeeMode>,
+    pub origination_fee_recipient: Option<FeeRecipient>,
+    pub stable_rate_premium: Option<Decimal256>,
+    pub rebalance_utilization: Option<Decimal256>,
+    pub rebalance_min_duration: Option<u64>,
+    pub term_grace_period: Option<u64>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
@@ -66,11 +137,44 @@ pub enum ExecuteMsg {
     },
 
     /// Update config values
-    UpdateConfig {
-        owner_addr: Option<HumanAddr>,
-        max_borrow_factor: Option<Decimal256>,
-        interest_model: Option<HumanAddr>,
-        distribution_model: Option<HumanAddr>,
+    UpdateConfig(Box<UpdateConfigMsg>),
+
+    /// Set or remove the guardian allowed to pause operations
+    UpdateGuardian {
+        guardian: Option<HumanAddr>,
+    },
+
+    /// Withdraw reserves to the recipient,
+    /// withdraws all reserves if amount is not set
+    WithdrawReserves {
+        amount: Option<Uint256>,
+        recipient: HumanAddr,
+    },
+
+    /// Update market wide supply caps,
+    /// an unset cap means unlimited
+    UpdateCaps {
+        deposit_cap: Option<Uint256>,
+        borrow_cap: Option<Uint256>,
+    },
+
+    /// Set or remove the borrow cap of a single borrower
+    UpdateBorrowerCap {
+        borrower: HumanAddr,
+        borrow_cap: Option<Uint256>,
+    },
+
+    ////////////////////
+    /// Guardian operations
+    ////////////////////
+    /// Update per-operation pause flags,
+    /// the guardian can only pause, unpausing is owner-only
+    UpdatePauseFlags {
+        deposit: Option<bool>,
+        redeem: Option<bool>,
+        borrow: Option<bool>,
+        repay: Option<bool>,
+        claim: Option<bool>,
     },
 
     ////////////////////
@@ -95,23 +199,95 @@ pub enum ExecuteMsg {
     ////////////////////
     /// User operations
     ////////////////////
-    /// Deposit stable asset to get interest
-    // DepositStable {},
+    /// Deposit native stable asset to get interest,
+    /// cw20 stables are deposited through Receive
+    DepositStable {},
 
-    /// Borrow stable asset with collaterals in overseer contract
+    /// Borrow stable asset with collaterals in overseer contract,
+    /// a delegate can borrow against the collaterals of on_behalf_of
+    /// within the allowance granted through ApproveDelegation
     BorrowStable {
         borrow_amount: Uint256,
         to: Option<HumanAddr>,
+        on_behalf_of: Option<HumanAddr>,
+        /// Rate mode of the loan, defaults to the mode of the existing loan
+        rate_mode: Option<RateMode>,
+        /// Open a fixed-term loan maturing after term blocks instead,
+        /// the total interest is fixed at the current stable rate
+        term: Option<u64>,
     },
 
-    /// Repay stable asset to decrease liability
-    // RepayStable {},
+    /// Borrow the cw20 stable and send it to contract along with msg
+    /// through cw20 Send, native stables are rejected. The borrow limit
+    /// is checked again once the hook is executed.
+    BorrowStableAndCall {
+        borrow_amount: Uint256,
+        contract: HumanAddr,
+        msg: Binary,
+    },
+
+    /// Switch the sender loan between variable and stable rate
+    SwapRateMode {},
+
+    /// Allow the delegate to borrow up to amount on behalf of the sender,
+    /// origination fees included, replaces any existing allowance
+    ApproveDelegation {
+        delegate: HumanAddr,
+        amount: Uint256,
+        expires: Option<Expiration>,
+    },
+
+    /// Remove the allowance granted to the delegate
+    RevokeDelegation {
+        delegate: HumanAddr,
+    },
+
+    /// Repay native stable asset to decrease liability,
+    /// cw20 stables are repaid through Receive
+    RepayStable {},
+
+    /// Repay native stable asset to a term loan of the sender
+    RepayTermLoan {
+        loan_id: u64,
+    },
 
     /// Claim distributed ANC rewards
     ClaimRewards {
         to: Option<HumanAddr>,
     },
 
+    /// Cancel a queued redemption and get the remaining aToken back
+    CancelRedeemRequest {
+        request_id: u64,
+    },
+
+    /////////////////////////////
+    /// Permissionless operations
+    /////////////////////////////
+    /// Write off the remaining loans of a borrower without collaterals,
+    /// term loans included, the shortfall is absorbed by reserves, then
+    /// by the overseer interest buffer and finally by depositors
+    WriteOffBadDebt {
+        borrower: HumanAddr,
+    },
+
+    /// Reprice a stable loan at the current stable rate,
+    /// allowed once utilization has stayed above rebalance_utilization
+    /// for rebalance_min_duration blocks and the loan rate is below
+    /// the current variable rate
+    RebalanceStableRate {
+        borrower: HumanAddr,
+    },
+
+    ////////////////////
+    /// Internal operations
+    ////////////////////
+    /// Fail if the borrower loan exceeds the borrow limit,
+    /// executed by the market after a BorrowStableAndCall hook
+    AssertBorrowLimit {
+        borrower: HumanAddr,
+    },
+
     RegisterATerra {},
 }
 
@@ -193,10 +369,16 @@ pub struct Event {
 #[serde(rename_all = "snake_case")]
 pub enum Cw20HookMsg {
     /// Return stable coins to a user
-    /// according to exchange rate
-    RedeemStable {},
+    /// according to exchange rate,
+    /// queue the redemption if queue is set and liquidity is exhausted
+    RedeemStable {
+        queue: Option<bool>,
+    },
     DepositStabe {},
     RepayStable {},
+    RepayTermLoan {
+        loan_id: u64,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -218,6 +400,57 @@ pub enum QueryMsg {
         start_after: Option<HumanAddr>,
         limit: Option<u32>,
     },
+    /// Term loans of a borrower ordered by maturity
+    TermLoans {
+        borrower: HumanAddr,
+        block_height: Option<u64>,
+    },
+    DelegationAllowance {
+        borrower: HumanAddr,
+        delegate: HumanAddr,
+    },
+    Caps {
+        borrower: Option<HumanAddr>,
+        block_height: Option<u64>,
+    },
+    ReserveHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Market snapshots taken every epoch, keyed by block height
+    HistoricalStates {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    BadDebtWriteOffs {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    RedeemRequest {
+        request_id: u64,
+    },
+    RedeemQueue {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Simulate a deposit at the current block
+    SimulateDeposit {
+        amount: Uint256,
+    },
+    /// Simulate a redemption at the current block
+    SimulateRedeem {
+        aterra_amount: Uint256,
+    },
+    /// Simulate a borrow at the current block
+    SimulateBorrow {
+        borrower: HumanAddr,
+        amount: Uint256,
+    },
+    /// Simulate a repayment at the current block
+    SimulateRepay {
+        borrower: HumanAddr,
+        amount: Uint256,
+    },
 }
 
 // We define a custom struct for each query response
@@ -230,15 +463,46 @@ pub struct ConfigResponse {
     pub overseer_contract: String,
     pub collector_contract: String,
     pub distributor_contract: String,
-    pub stable_addr: String,
+    pub stable_info: AssetInfo,
     pub orchai_token: String,
     pub max_borrow_factor: Decimal256,
+    pub deposit_cap: Option<Uint256>,
+    pub borrow_cap: Option<Uint256>,
+    pub reserve_factor: Decimal256,
+    pub reserve_target: Uint256,
+    pub redeem_rate_lock: RedeemRateLock,
+    pub origination_fee_rate: Decimal256,
+    pub origination_fee_mode: OriginationFeeMode,
+    pub origination_fee_recipient: FeeRecipient,
+    pub stable_rate_premium: Decimal256,
+    pub rebalance_utilization: Decimal256,
+    pub rebalance_min_duration: u64,
+    pub term_grace_period: u64,
+    pub guardian: Option<String>,
+    pub pause_flags: PauseFlags,
+}
+
+/// Operations the guardian or the owner can pause
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct PauseFlags {
+    pub deposit: bool,
+    pub redeem: bool,
+    pub borrow: bool,
+    pub repay: bool,
+    pub claim: bool,
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct StateResponse {
     pub total_liabilities: Decimal256,
+    /// Stable rate part of total_liabilities
+    pub total_stable_liabilities: Decimal256,
+    pub avg_stable_rate: Decimal256,
+    /// Term loan part of total_liabilities, principal and accrued interest
+    pub total_term_liabilities: Decimal256,
+    /// Annual interest of the term loans before maturity
+    pub term_interest_rate: Decimal256,
     pub total_reserves: Decimal256,
     pub last_interest_updated: u64,
     pub last_reward_updated: u64,
@@ -247,6 +511,8 @@ pub struct StateResponse {
     pub orchai_epb_rate: Decimal256,
     pub prev_aterra_supply: Uint256,
     pub prev_exchange_rate: Decimal256,
+    /// Block from which utilization is above rebalance_utilization
+    pub high_utilization_since: Option<u64>,
 }
 
 // We define a custom struct for each query response
@@ -264,6 +530,46 @@ pub struct BorrowerInfoResponse {
     pub reward_index: Decimal256,
     pub loan_amount: Uint256,
     pub pending_rewards: Decimal256,
+    pub rate_mode: RateMode,
+    pub stable_rate: Decimal256,
+    /// Amount owed on term loans, not part of loan_amount
+    pub term_loan_amount: Uint256,
+    /// Part of term_loan_amount past maturity and grace period
+    pub expired_term_loan_amount: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TermLoanElem {
+    pub loan_id: u64,
+    /// Principal left to repay
+    pub principal: Uint256,
+    /// Fixed interest left to repay
+    pub interest: Uint256,
+    /// principal + interest
+    pub loan_amount: Uint256,
+    pub rate: Decimal256,
+    pub start_height: u64,
+    pub maturity: u64,
+    /// Past maturity and grace period, the overseer
+    /// can liquidate the borrower regardless of LTV
+    pub expired: bool,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TermLoansResponse {
+    pub borrower: String,
+    pub loans: Vec<TermLoanElem>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DelegationAllowanceResponse {
+    pub borrower: String,
+    pub delegate: String,
+    pub allowance: Uint256,
+    pub expires: Expiration,
 }
 
 // We define a custom struct for each query response
@@ -272,6 +578,137 @@ pub struct BorrowerInfosResponse {
     pub borrower_infos: Vec<BorrowerInfoResponse>,
 }
 
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CapsResponse {
+    pub total_deposits: Uint256,
+    pub deposit_cap: Option<Uint256>,
+    /// Remaining deposit amount before hitting the cap, unlimited if not set
+    pub deposit_headroom: Option<Uint256>,
+    pub total_liabilities: Uint256,
+    pub borrow_cap: Option<Uint256>,
+    /// Remaining borrow amount before hitting the cap, unlimited if not set
+    pub borrow_headroom: Option<Uint256>,
+    pub borrower_loan_amount: Option<Uint256>,
+    pub borrower_cap: Option<Uint256>,
+    pub borrower_headroom: Option<Uint256>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReserveHistoryElem {
+    pub id: u64,
+    /// "withdraw" for governance withdrawals,
+    /// "distribute" for the excess sent to the collector
+    pub action: String,
+    pub amount: Uint256,
+    pub recipient: String,
+    pub block_height: u64,
+    /// Reserves left after the operation
+    pub total_reserves: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReserveHistoryResponse {
+    pub history: Vec<ReserveHistoryElem>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HistoricalStateElem {
+    pub block_height: u64,
+    pub exchange_rate: Decimal256,
+    pub total_liabilities: Decimal256,
+    pub total_reserves: Decimal256,
+    pub borrow_rate: Decimal256,
+    pub deposit_rate: Decimal256,
+    pub orchai_epb_rate: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HistoricalStatesResponse {
+    pub states: Vec<HistoricalStateElem>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BadDebtWriteOffElem {
+    pub id: u64,
+    pub borrower: String,
+    pub amount: Uint256,
+    pub covered_by_reserves: Uint256,
+    pub covered_by_buffer: Uint256,
+    /// Shortfall left to depositors through a lower exchange rate
+    pub socialized: Uint256,
+    pub block_height: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BadDebtWriteOffsResponse {
+    pub write_offs: Vec<BadDebtWriteOffElem>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RedeemRequestResponse {
+    pub request_id: u64,
+    pub redeemer: String,
+    /// aToken left to redeem
+    pub aterra_amount: Uint256,
+    /// Exchange rate locked at request time, if any
+    pub locked_rate: Option<Decimal256>,
+    pub block_height: u64,
+    /// Number of requests ahead in the queue,
+    /// including the slots of cancelled requests behind the front
+    pub position: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RedeemRequestElem {
+    pub request_id: u64,
+    pub redeemer: String,
+    pub aterra_amount: Uint256,
+    pub locked_rate: Option<Decimal256>,
+    pub block_height: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RedeemQueueResponse {
+    pub requests: Vec<RedeemRequestElem>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulationResponse {
+    /// Exchange rate after the action
+    pub exchange_rate: Decimal256,
+    /// aToken minted by a deposit or burned by a redemption
+    pub aterra_amount: Uint256,
+    /// Stable deposited, redeemed, borrowed or repaid
+    pub stable_amount: Uint256,
+    pub utilization_ratio: Decimal256,
+    pub borrow_rate: Decimal256,
+    /// Borrower loan after a borrow or a repayment, term loans included
+    pub loan_amount: Option<Uint256>,
+    /// Origination fee charged by a borrow
+    pub origination_fee: Uint256,
+    /// Whether the action passes the market caps, the available
+    /// liquidity, the overseer borrow limit and debt ceilings,
+    /// a redemption waiting in the redeem queue is not within limits
+    pub within_limits: bool,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    /// Settings the legacy config did not have,
+    /// ignored once the config is already migrated
+    pub reserve_factor: Decimal256,
+    pub reserve_target: Uint256,
+    pub redeem_rate_lock: RedeemRateLock,
+}
@@ -0,0 +1,133 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::HumanAddr;
+
+use crate::market::RedeemRateLock;
+use oraiswap::asset::AssetInfo;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct InstantiateMsg {
+    /// Owner address, also set as the owner of every created market and overseer
+    pub owner: HumanAddr,
+    /// Market contract code ID used to instantiate
+    pub market_code_id: u64,
+    /// Overseer contract code ID used to instantiate
+    pub overseer_code_id: u64,
+    /// aToken code ID the market uses to instantiate its aToken
+    pub atoken_code_id: u64,
+    /// Shared oracle contract
+    pub oracle_contract: HumanAddr,
+    /// Shared liquidation queue contract
+    pub liquidation_contract: HumanAddr,
+    /// Shared ORCHAI distribution model contract
+    pub distribution_model: HumanAddr,
+    /// Shared borrow interest model contract
+    pub interest_model: HumanAddr,
+    /// Shared collector contract
+    pub collector_contract: HumanAddr,
+    /// Shared distributor contract
+    pub distributor_contract: HumanAddr,
+    /// ORCHAI token address
+    pub orchai_token: HumanAddr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    ////////////////////
+    /// Owner operations
+    ////////////////////
+
+    /// Update config values
+    UpdateConfig {
+        owner: Option<HumanAddr>,
+        market_code_id: Option<u64>,
+        overseer_code_id: Option<u64>,
+        atoken_code_id: Option<u64>,
+        oracle_contract: Option<HumanAddr>,
+        liquidation_contract: Option<HumanAddr>,
+        distribution_model: Option<HumanAddr>,
+        interest_model: Option<HumanAddr>,
+        collector_contract: Option<HumanAddr>,
+        distributor_contract: Option<HumanAddr>,
+    },
+
+    /// Instantiate market, overseer and aToken set for a stable asset,
+    /// either a native denom or a cw20 token
+    CreateMarket {
+        stable_info: AssetInfo,
+        atoken_name: String,
+        atoken_symbol: String,
+        orchai_epb_rate: Decimal256,
+        max_borrow_factor: Decimal256,
+        deposit_cap: Option<Uint256>,
+        borrow_cap: Option<Uint256>,
+        reserve_factor: Decimal256,
+        reserve_target: Uint256,
+        redeem_rate_lock: RedeemRateLock,
+    },
+
+    ////////////////////
+    /// Init hook operations
+    ////////////////////
+
+    /// Register the newly instantiated market
+    /// and instantiate the market overseer
+    RegisterMarket {},
+
+    /// Register the newly instantiated overseer
+    /// and register shared contracts to the market
+    RegisterOverseer { stable_info: AssetInfo },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    Market {
+        stable_info: AssetInfo,
+    },
+    Markets {
+        start_after: Option<AssetInfo>,
+        limit: Option<u32>,
+    },
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner: String,
+    pub market_code_id: u64,
+    pub overseer_code_id: u64,
+    pub atoken_code_id: u64,
+    pub oracle_contract: String,
+    pub liquidation_contract: String,
+    pub distribution_model: String,
+    pub interest_model: String,
+    pub collector_contract: String,
+    pub distributor_contract: String,
+    pub orchai_token: String,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MarketResponse {
+    pub stable_info: AssetInfo,
+    pub market_contract: String,
+    pub overseer_contract: String,
+    pub liquidation_contract: String,
+    pub atoken_contract: String,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MarketsResponse {
+    pub markets: Vec<MarketResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MigrateMsg {}
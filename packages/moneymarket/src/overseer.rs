@@ -1,9 +1,13 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::market::InitHook;
 use crate::tokens::TokensHuman;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{HumanAddr, CanonicalAddr};
+use cw20::Cw20ReceiveMsg;
+use oraiswap::asset::AssetInfo;
+use oraiswap::router::SwapOperation;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -18,9 +22,9 @@ pub struct InstantiateMsg {
     pub liquidation_contract: HumanAddr,
     /// Collector contract address which is purchasing ANC token
     pub collector_contract: HumanAddr,
-    /// The base denomination used when fetching oracle price,
+    /// The base asset used when fetching oracle price,
     /// reward distribution, and borrow
-    pub stable_addr: HumanAddr,
+    pub stable_info: AssetInfo,
     /// # of blocks per epoch period
     pub epoch_period: Option<u64>,
     /// Distribute interest buffer to market contract,
@@ -45,12 +49,17 @@ pub struct InstantiateMsg {
     /// clamps for dyn rate
     pub dyn_rate_min: Option<Decimal256>,
     pub dyn_rate_max: Option<Decimal256>,
+    /// Hook executed with the overseer address after instantiation
+    pub init_hook: Option<InitHook>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 #[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
+    /// CW20 token receiver
+    Receive(Cw20ReceiveMsg),
+
     ////////////////////
     /// Owner operations
     ////////////////////
@@ -72,20 +81,121 @@ pub enum ExecuteMsg {
         dyn_rate_min: Option<Decimal256>,
         dyn_rate_max: Option<Decimal256>,
         market_contract: Option<HumanAddr>,
+        atoken_borrow_limit_share: Option<Decimal256>,
+        buffer_distribution_policy: Option<BufferDistributionPolicy>,
+        min_interest_buffer: Option<Uint256>,
+        keeper_bounty_rate: Option<Decimal256>,
+        keeper_bounty_cap: Option<Uint256>,
     },
     /// Create new custody contract for the given collateral token
     Whitelist {
         name: String,                // bAsset name
         symbol: String,              // bAsset symbol
-        collateral_token: HumanAddr, // bAsset token contract
-        custody_contract: HumanAddr, // bAsset custody contract
-        max_ltv: Decimal256,         // Loan To Value ratio
+        collateral_token: HumanAddr,       // bAsset token contract
+        custody_contract: HumanAddr,       // bAsset custody contract
+        max_ltv: Decimal256,               // Loan To Value ratio
+        liquidation_threshold: Decimal256, // Loan To Value ratio triggering liquidation
+    },
+    /// Whitelist the market aToken as collateral, valued at the
+    /// market exchange rate instead of the oracle price
+    WhitelistAToken {
+        name: String,
+        symbol: String,
+        custody_contract: HumanAddr,       // custody contract without a reward contract
+        max_ltv: Decimal256,               // Loan To Value ratio dedicated to the aToken
+        liquidation_threshold: Decimal256, // Loan To Value ratio triggering liquidation
     },
     /// Update registered whitelist info
     UpdateWhitelist {
-        collateral_token: HumanAddr,         // bAsset token contract
-        custody_contract: Option<HumanAddr>, // bAsset custody contract
-        max_ltv: Option<Decimal256>,         // Loan To Value ratio
+        collateral_token: HumanAddr,               // bAsset token contract
+        custody_contract: Option<HumanAddr>,       // bAsset custody contract
+        max_ltv: Option<Decimal256>,               // Loan To Value ratio
+        liquidation_threshold: Option<Decimal256>, // Loan To Value ratio triggering liquidation
+    },
+
+    /// Set the supply cap and the debt ceiling of a collateral,
+    /// None removes the cap
+    UpdateCollateralCaps {
+        collateral_token: HumanAddr,
+        collateral_cap: Option<Uint256>,
+        debt_ceiling: Option<Uint256>,
+    },
+
+    /// Count limit more of the positions opened before the collateral
+    /// totals were tracked, until the seeding started by the migration ends
+    SeedCollateralTotals { limit: Option<u32> },
+
+    /// Freeze new locks of a collateral and ramp its max LTV down
+    /// linearly to final_ltv over ramp_period seconds
+    DelistCollateral {
+        collateral_token: HumanAddr,
+        final_ltv: Decimal256,
+        ramp_period: u64,
+    },
+
+    /// Remove a delisted collateral no borrower holds anymore
+    RemoveCollateral {
+        collateral_token: HumanAddr,
+    },
+
+    /// Create or update a category of correlated collaterals
+    /// with higher LTV ratios
+    UpdateCategory {
+        category_id: u8,
+        name: String,
+        max_ltv: Decimal256,
+        liquidation_threshold: Decimal256,
+    },
+
+    /// Set or remove the category of a collateral
+    SetCollateralCategory {
+        collateral_token: HumanAddr,
+        category_id: Option<u8>,
+        /// Seconds over which the borrowers of the previous
+        /// category ramp down to the collateral LTV ratios
+        ramp_period: u64,
+    },
+
+    /// Set or remove the route used to swap a collateral to the
+    /// stable token when a borrower repays with collateral
+    UpdateSwapRoute {
+        collateral_token: HumanAddr,
+        swap_route: Option<SwapRoute>,
+    },
+
+    ////////////////////
+    /// User operations
+    ////////////////////
+
+    /// Opt in or out of a category, the category LTV ratios apply
+    /// while all the collaterals of the borrower belong to it
+    SetCategory {
+        category_id: Option<u8>,
+    },
+
+    /// Refresh the debt attributed to the collaterals of a borrower,
+    /// the market calls it after every borrow and repayment. Debt
+    /// ceilings are only enforced after a borrow so that a repayment
+    /// never fails on them
+    UpdateBorrowerDebt {
+        borrower: HumanAddr,
+        enforce_ceilings: bool,
+    },
+
+    /// Set or remove the guardian allowed to pause operations
+    UpdateGuardian {
+        guardian: Option<HumanAddr>,
+    },
+
+    ////////////////////
+    /// Guardian operations
+    ////////////////////
+    /// Update per-operation pause flags,
+    /// the guardian can only pause, unpausing is owner-only
+    UpdatePauseFlags {
+        lock: Option<bool>,
+        unlock: Option<bool>,
+        liquidate: Option<bool>,
     },
 
     /// Claims all staking rewards from the bAsset contracts
@@ -93,12 +203,24 @@ pub enum ExecuteMsg {
     /// 1. Distribute interest buffers to depositors
     /// 2. Invoke [Custody] DistributeRewards
     /// 3. Update epoch state
+    ///
+    /// The caller receives a keeper bounty from the interest buffer,
+    /// capped per call
     ExecuteEpochOperations {},
     UpdateEpochState {
         interest_buffer: Uint256,
         distributed_interest: Uint256,
     },
 
+    ////////////////////
+    /// Market operations
+    ////////////////////
+    /// Send up to amount of the interest buffer
+    /// to the market to cover a bad debt write-off
+    CoverBadDebt {
+        amount: Uint256,
+    },
+
     ////////////////////
     /// User operations
     ////////////////////
@@ -108,6 +230,24 @@ pub enum ExecuteMsg {
     UnlockCollateral {
         collaterals: TokensHuman, // <(Collateral Token, Amount)>
     },
+    /// Swap locked collateral to stable through the collateral
+    /// swap route and repay the loan, without liquidation fee
+    RepayWithCollateral {
+        collateral_token: HumanAddr,
+        amount: Uint256,
+        min_stable_out: Uint256,
+    },
+
+    ////////////////////
+    /// Internal operations
+    ////////////////////
+    /// Check the loan to borrow limit ratio decreased
+    /// after a repayment with collateral
+    AssertPositionHealthier {
+        borrower: HumanAddr,
+        prev_loan_amount: Uint256,
+        prev_borrow_limit: Uint256,
+    },
 
     /////////////////////////////
     /// Permissionless operations
@@ -116,6 +256,16 @@ pub enum ExecuteMsg {
         borrower: String,
     },
 
+    /// Add the sent native stable to the interest buffer, rejected
+    /// unless the stable is native; the cw20 stable funds the reserve
+    /// through Receive
+    FundReserve {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    /// Add the received stable to the interest buffer
     FundReserve {},
 }
 
@@ -155,6 +305,23 @@ pub enum QueryMsg {
         borrower: HumanAddr,
         block_time: Option<u64>,
     },
+    BorrowerHealth {
+        borrower: HumanAddr,
+    },
+    LiquidatablePositions {
+        start_after: Option<HumanAddr>,
+        limit: Option<u32>,
+    },
+    Categories {},
+    BorrowerCategory {
+        borrower: HumanAddr,
+    },
+    /// Whether a loan of loan_amount would stay under the
+    /// debt ceilings of the borrower collaterals
+    DebtCeilings {
+        borrower: HumanAddr,
+        loan_amount: Uint256,
+    },
 }
 
 // We define a custom struct for each query response
@@ -169,7 +336,7 @@ pub struct ConfigResponse {
     pub target_deposit_rate: Decimal256,
     pub buffer_distribution_factor: Decimal256,
     pub anc_purchase_factor: Decimal256,
-    pub stable_addr: String,
+    pub stable_info: AssetInfo,
     pub epoch_period: u64,
     pub price_timeframe: u64,
     pub dyn_rate_epoch: u64,
@@ -177,6 +344,48 @@ pub struct ConfigResponse {
     pub dyn_rate_yr_increase_expectation: Decimal256,
     pub dyn_rate_min: Decimal256,
     pub dyn_rate_max: Decimal256,
+    pub atoken_borrow_limit_share: Decimal256,
+    pub buffer_distribution_policy: BufferDistributionPolicy,
+    pub min_interest_buffer: Uint256,
+    pub keeper_bounty_rate: Decimal256,
+    pub keeper_bounty_cap: Uint256,
+    pub guardian: Option<String>,
+    pub pause_flags: PauseFlags,
+}
+
+/// How the interest buffer above min_interest_buffer
+/// is distributed to depositors every epoch
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BufferDistributionPolicy {
+    /// Distribute the whole buffer
+    AllToDepositors,
+    /// Top the deposit rate up to threshold_deposit_rate,
+    /// using at most buffer_distribution_factor of the buffer
+    ThresholdTopUp,
+    /// Distribute a fixed ratio of the buffer
+    FixedRatio { ratio: Decimal256 },
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochStateResponse {
+    pub deposit_rate: Decimal256,
+    pub prev_aterra_supply: Uint256,
+    pub prev_exchange_rate: Decimal256,
+    pub prev_interest_buffer: Uint256,
+    pub last_executed_height: u64,
+    pub buffer_distribution_policy: BufferDistributionPolicy,
+    pub min_interest_buffer: Uint256,
+    pub total_keeper_bounty: Uint256,
+}
+
+/// Operations the guardian or the owner can pause
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct PauseFlags {
+    pub lock: bool,
+    pub unlock: bool,
+    pub liquidate: bool,
 }
 
 // We define a custom struct for each query response
@@ -185,8 +394,55 @@ pub struct WhitelistResponseElem {
     pub name: String,
     pub symbol: String,
     pub max_ltv: Decimal256,
+    pub liquidation_threshold: Decimal256,
     pub custody_contract: HumanAddr,
     pub collateral_token: HumanAddr,
+    pub price_source: PriceSource,
+    pub collateral_cap: Option<Uint256>,
+    pub debt_ceiling: Option<Uint256>,
+    pub total_collateral: Uint256,
+    pub total_debt: Uint256,
+    pub delisting: Option<DelistingSchedule>,
+    pub category_id: Option<u8>,
+    pub category_exit: Option<CategoryExitSchedule>,
+    pub swap_route: Option<SwapRoute>,
+}
+
+/// Router and operations swapping a collateral to the stable token,
+/// the first operation offers the collateral and the last one asks
+/// for the stable token
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapRoute {
+    pub router_contract: HumanAddr,
+    pub operations: Vec<SwapOperation>,
+}
+
+/// Max LTV ramps down linearly from the whitelisted max_ltv to
+/// final_ltv between start_time and end_time, the liquidation
+/// threshold ramps down proportionally
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DelistingSchedule {
+    pub start_time: u64,
+    pub end_time: u64,
+    pub final_ltv: Decimal256,
+}
+
+/// LTV ratios of the borrowers of category_id ramp down linearly
+/// from the category ratios to the collateral ratios between
+/// start_time and end_time, after which they are no longer eligible
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CategoryExitSchedule {
+    pub category_id: u8,
+    pub start_time: u64,
+    pub end_time: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSource {
+    Oracle,
+    /// Market aToken, priced at the market exchange rate
+    ExchangeRate,
 }
 
 // We define a custom struct for each query response
@@ -195,6 +451,38 @@ pub struct WhitelistResponse {
     pub elems: Vec<WhitelistResponseElem>,
 }
 
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CategoryResponse {
+    pub category_id: u8,
+    pub name: String,
+    pub max_ltv: Decimal256,
+    pub liquidation_threshold: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CategoriesResponse {
+    pub categories: Vec<CategoryResponse>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BorrowerCategoryResponse {
+    pub borrower: String,
+    pub category_id: Option<u8>,
+    /// Whether all the collaterals of the borrower belong to the category
+    pub eligible: bool,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DebtCeilingsResponse {
+    pub borrower: String,
+    pub loan_amount: Uint256,
+    pub within_ceilings: bool,
+}
+
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct CollateralsResponse {
@@ -213,3 +501,56 @@ pub struct BorrowLimitResponse {
     pub borrower: String,
     pub borrow_limit: Uint256,
 }
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BorrowerHealthResponse {
+    pub borrower: String,
+    pub collaterals: Vec<CollateralHealthElem>,
+    pub borrow_limit: Uint256,
+    pub liquidation_limit: Uint256,
+    /// Loan amount with interest accrued to the current block,
+    /// term loans included
+    pub loan_amount: Uint256,
+    /// liquidation_limit / loan_amount, the position is
+    /// liquidatable below 1. None without a loan
+    pub health_factor: Option<Decimal256>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidatablePositionsResponse {
+    pub positions: Vec<LiquidatablePositionElem>,
+    /// Last borrower scanned, start_after of the next page
+    pub last_scanned: Option<String>,
+    /// Borrowers of the page whose position could not be evaluated,
+    /// e.g. on a missing or stale collateral price
+    pub failed: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidatablePositionElem {
+    pub borrower: String,
+    pub loan_amount: Uint256,
+    pub borrow_limit: Uint256,
+    pub liquidation_limit: Uint256,
+    /// loan_amount - liquidation_limit
+    pub shortfall: Uint256,
+    /// Collaterals the liquidation queue would seize
+    pub collaterals_to_seize: TokensHuman,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralHealthElem {
+    pub collateral_token: String,
+    pub amount: Uint256,
+    pub price: Decimal256,
+    pub value: Uint256,
+    /// Contribution of the collateral to the borrow limit
+    pub borrow_limit: Uint256,
+    /// Price at which the position becomes liquidatable, other
+    /// collaterals being constant and the aToken share cap applied.
+    /// None if the price of the collateral cannot change whether
+    /// the position is liquidatable
+    pub liquidation_price: Option<Decimal256>,
+}
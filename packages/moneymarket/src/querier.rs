@@ -1,9 +1,10 @@
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    to_binary, AllBalanceResponse, BankQuery, Coin, Deps, HumanAddr, QueryRequest,
+    to_binary, AllBalanceResponse, BankQuery, Coin, CosmosMsg, Deps, HumanAddr, QueryRequest,
     StdError, StdResult, Uint128, WasmQuery,
 };
 use cw20::{Cw20QueryMsg, TokenInfoResponse, BalanceResponse};
+use oraiswap::asset::{Asset, AssetInfo};
 use oraiswap::oracle::OracleContract;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -37,6 +38,24 @@ pub fn query_balance(
     Ok(balance.balance.into())
 }
 
+/// Query the balance of either a native denom or a cw20 token
+pub fn query_asset_balance(
+    deps: Deps,
+    account_addr: HumanAddr,
+    asset_info: &AssetInfo,
+) -> StdResult<Uint256> {
+    match asset_info {
+        AssetInfo::Token { contract_addr } => {
+            query_balance(deps, account_addr, contract_addr.clone())
+        }
+        AssetInfo::NativeToken { denom } => Ok(deps
+            .querier
+            .query_balance(account_addr, denom)?
+            .amount
+            .into()),
+    }
+}
+
 pub fn query_token_balance(
     deps: Deps,
     contract_addr: HumanAddr,
@@ -128,6 +147,35 @@ pub fn deduct_tax(_deps: Deps, amount: Uint128) -> StdResult<Uint128> {
     Ok((Uint256::from(amount) - tax_amount).into())
 }
 
+/// cw20 transfers are not taxed, only native denoms go through deduct_tax
+pub fn deduct_asset_tax(
+    deps: Deps,
+    asset_info: &AssetInfo,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    match asset_info {
+        AssetInfo::Token { .. } => Ok(amount),
+        AssetInfo::NativeToken { .. } => deduct_tax(deps, amount),
+    }
+}
+
+/// Build the message transferring `amount` of either a native denom
+/// or a cw20 token from `sender` to `recipient`, taxed if native
+pub fn asset_transfer_msg(
+    deps: Deps,
+    asset_info: &AssetInfo,
+    sender: HumanAddr,
+    recipient: HumanAddr,
+    amount: Uint256,
+) -> StdResult<CosmosMsg> {
+    let amount = deduct_asset_tax(deps, asset_info, amount.into())?;
+    Asset {
+        info: asset_info.clone(),
+        amount,
+    }
+    .into_msg(None, &deps.querier, sender, recipient)
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct TimeConstraints {
     pub block_time: u64,